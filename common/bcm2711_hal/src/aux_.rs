@@ -0,0 +1,178 @@
+//! Auxiliary peripherals (Mini UART, SPI1, and SPI2)
+//!
+//! The auxiliary peripherals share a register block (which enables them
+//! through `aux_enables`) and an interrupt line ([`irq::AUX`]). [`Demux`]
+//! services the line by checking `aux_irq` and calling the drivers of the
+//! peripherals with pending interrupts. With the `solid` feature,
+//! `&'static Demux` can be used as the handler of a
+//! `solid::interrupt::Handler`:
+//!
+//! ```rust,ignore
+//! use bcm2711_hal::{aux::{mini_uart::{Config, MiniUart}, Demux}, identity_mapped};
+//! use bcm2711_pac::{aux, irq};
+//! use solid::{interrupt, singleton::pin_singleton};
+//!
+//! static UART1: MiniUart = unsafe { MiniUart::new(identity_mapped(aux::BASE)) };
+//! static AUX: Demux =
+//!     unsafe { Demux::new(identity_mapped(aux::BASE)) }.with_mini_uart(&UART1);
+//!
+//! UART1.init(&Config::default()).unwrap();
+//!
+//! let handler = pin_singleton!(: Handler<_> = interrupt::Handler::new(&AUX)).unwrap();
+//! handler.register_static(&irq::AUX.handler_options(10)).unwrap();
+//! irq::AUX.number().enable().unwrap();
+//! ```
+//!
+//! [`irq::AUX`]: bcm2711_pac::irq::AUX
+use bcm2711_pac::aux::{Registers, AUX_ENABLES, AUX_IRQ};
+use core::fmt;
+use tock_registers::{
+    fields::FieldValue,
+    interfaces::{ReadWriteable, Readable},
+};
+
+use crate::sync::with_lock;
+
+#[path = "aux_/mini_uart.rs"]
+pub mod mini_uart;
+#[path = "aux_/spi.rs"]
+pub mod spi;
+
+/// A driver of an auxiliary peripheral serviced by [`Demux`]
+trait Service: Sync {
+    fn service(&self);
+}
+
+/// Dispatches the shared interrupt of the auxiliary peripherals to their
+/// drivers
+pub struct Demux {
+    regs: *const Registers,
+    mini_uart: Option<&'static dyn Service>,
+    spi1: Option<&'static dyn Service>,
+    spi2: Option<&'static dyn Service>,
+}
+
+// Safety: `aux_irq` is only read, and the drivers are `Sync`.
+unsafe impl Send for Demux {}
+unsafe impl Sync for Demux {}
+
+impl Demux {
+    /// Construct a `Demux` dispatching to no drivers.
+    ///
+    /// # Safety
+    ///
+    /// `regs` must point to [the auxiliary peripheral register
+    /// block](Registers) and remain valid for the lifetime of the constructed
+    /// `Demux`.
+    #[inline]
+    pub const unsafe fn new(regs: *const Registers) -> Self {
+        Self {
+            regs,
+            mini_uart: None,
+            spi1: None,
+            spi2: None,
+        }
+    }
+
+    /// Dispatch the Mini UART interrupt to `uart`.
+    #[inline]
+    pub const fn with_mini_uart<const RX: usize, const TX: usize>(
+        self,
+        uart: &'static mini_uart::MiniUart<RX, TX>,
+    ) -> Self {
+        Self {
+            mini_uart: Some(uart),
+            ..self
+        }
+    }
+
+    /// Dispatch the interrupt of the SPI master driven by `spi` (SPI1 or SPI2)
+    /// to `spi`.
+    #[inline]
+    pub const fn with_spi(self, spi: &'static spi::AuxSpi) -> Self {
+        match spi.instance() {
+            spi::Instance::Spi1 => Self {
+                spi1: Some(spi),
+                ..self
+            },
+            spi::Instance::Spi2 => Self {
+                spi2: Some(spi),
+                ..self
+            },
+        }
+    }
+
+    /// Service the interrupt: call the drivers of the peripherals indicated by
+    /// `aux_irq`.
+    pub fn service(&self) {
+        // Safety: Upheld by the caller of `Self::new`
+        let irq = unsafe { &*self.regs }.aux_irq.extract();
+        for (pending, driver) in [
+            (AUX_IRQ::MINI_UART_IRQ, self.mini_uart),
+            (AUX_IRQ::SPI1_IRQ, self.spi1),
+            (AUX_IRQ::SPI2_IRQ, self.spi2),
+        ] {
+            if irq.is_set(pending) {
+                if let Some(driver) = driver {
+                    driver.service();
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Demux {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Demux")
+            .field("regs", &self.regs)
+            .field("mini_uart", &self.mini_uart.is_some())
+            .field("spi1", &self.spi1.is_some())
+            .field("spi2", &self.spi2.is_some())
+            .finish()
+    }
+}
+
+#[cfg(feature = "solid")]
+impl<'a> solid::closure::FuncMut<(solid::thread::CpuCx<'a>,)> for &'static Demux {
+    type Output = ();
+
+    #[inline]
+    fn call(&mut self, _: (solid::thread::CpuCx<'a>,)) {
+        self.service();
+    }
+}
+
+/// Set or clear the enable bits of the peripherals in `aux_enables`.
+fn set_enables(regs: &Registers, enables: FieldValue<u32, AUX_ENABLES::Register>) {
+    with_lock(|| regs.aux_enables.modify(enables));
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use bcm2711_pac::sim;
+    use mini_uart::{Config, MiniUart};
+    use std::boxed::Box;
+
+    #[test]
+    fn dispatch() {
+        let device = sim::aux::Aux::new();
+        let uart: &'static MiniUart<8, 8> =
+            Box::leak(Box::new(unsafe { MiniUart::new(device.regs()) }));
+        let demux = unsafe { Demux::new(device.regs()) }.with_mini_uart(uart);
+        uart.init(&Config::default()).unwrap();
+
+        device.push_rx(b"abc");
+        demux.service();
+        assert_eq!(device.rx_len(), 0);
+        let mut buf = [0; 4];
+        assert_eq!(uart.try_read(&mut buf), Ok(3));
+
+        // Nothing is pending
+        let empty = unsafe { Demux::new(device.regs()) };
+        device.push_rx(b"d");
+        empty.service();
+        assert_eq!(device.rx_len(), 1);
+    }
+}
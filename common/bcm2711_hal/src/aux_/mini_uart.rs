@@ -0,0 +1,492 @@
+//! Interrupt-driven Mini UART (UART1) driver
+//!
+//! The Mini UART derives its baud rate from the VPU core clock, whose
+//! frequency may be changed by the firmware (e.g., on thermal throttling).
+//! Call [`MiniUart::set_baud_rate`] with the new core clock frequency when it
+//! changes. The current frequency can be queried through the mailbox property
+//! interface (clock ID `CORE`).
+//!
+//! [`MiniUart::service`] must be called on the shared interrupt of the
+//! auxiliary peripherals, usually through [`Demux`](super::Demux).
+use bcm2711_pac::aux::{
+    MiniUartRegisters, Registers, AUX_ENABLES, AUX_MU_BAUD_REG, AUX_MU_CNTL_REG, AUX_MU_IER_REG,
+    AUX_MU_IIR_REG, AUX_MU_IO_REG, AUX_MU_LCR_REG, AUX_MU_LSR_REG, AUX_MU_STAT_REG,
+};
+use core::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
+
+use crate::buffered::{Buffered, Fifos};
+
+/// The core clock frequency configured by the Raspberry Pi 4 firmware by
+/// default (`core_freq=500`)
+pub const DEFAULT_CORE_CLOCK_HZ: u32 = 500_000_000;
+
+/// The maximum difference between the achieved and requested baud rates
+/// accepted by [`baud_divisor`], in percent
+pub const MAX_BAUD_RATE_ERROR_PERCENT: u32 = 2;
+
+/// The number of data bits in a character
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum DataBits {
+    /// 7 bits
+    Seven,
+    /// 8 bits
+    Eight,
+}
+
+/// The hardware flow control mode
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum FlowControl {
+    /// No flow control
+    None,
+    /// RTS is deasserted while the receive FIFO is almost full, and
+    /// transmission is suspended while CTS is deasserted.
+    RtsCts,
+}
+
+/// The configuration for [`MiniUart::init`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Config {
+    /// The VPU core clock frequency
+    pub core_clock_hz: u32,
+    /// The baud rate
+    pub baud_rate: u32,
+    /// The number of data bits. The Mini UART doesn't support parity bits
+    /// and always uses one stop bit.
+    pub data_bits: DataBits,
+    /// The hardware flow control mode
+    pub flow_control: FlowControl,
+}
+
+impl Default for Config {
+    /// 115200 baud, 8 data bits, and no flow control
+    #[inline]
+    fn default() -> Self {
+        Self {
+            core_clock_hz: DEFAULT_CORE_CLOCK_HZ,
+            baud_rate: 115_200,
+            data_bits: DataBits::Eight,
+            flow_control: FlowControl::None,
+        }
+    }
+}
+
+/// The error type for [`MiniUart::init`] and [`MiniUart::set_baud_rate`]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum ConfigError {
+    /// The baud rate is out of the range supported with the core clock
+    /// frequency.
+    UnsupportedBaudRate,
+    /// The closest achievable baud rate differs from the requested one by
+    /// more than [`MAX_BAUD_RATE_ERROR_PERCENT`]. Contains the closest
+    /// achievable baud rate.
+    InaccurateBaudRate(u32),
+}
+
+/// A receive error reported by [`MiniUart::read`] and
+/// [`MiniUart::try_read`]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Error {
+    /// Characters were lost because the receive FIFO was full.
+    Overrun,
+}
+
+/// Calculate the value of `baud_reg` for the specified core clock frequency
+/// and baud rate (`baud_rate = core_clock_hz / (8 * (baud_reg + 1))`).
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_hal::aux::mini_uart::{baud_divisor, ConfigError};
+/// assert_eq!(baud_divisor(500_000_000, 115_200), Ok(542));
+/// assert_eq!(baud_divisor(250_000_000, 115_200), Ok(270));
+/// assert_eq!(
+///     baud_divisor(250_000_000, 4_000_000),
+///     Err(ConfigError::InaccurateBaudRate(3_906_250))
+/// );
+/// ```
+pub const fn baud_divisor(core_clock_hz: u32, baud_rate: u32) -> Result<u16, ConfigError> {
+    if baud_rate == 0 {
+        return Err(ConfigError::UnsupportedBaudRate);
+    }
+    let (clock, baud) = (core_clock_hz as u64, baud_rate as u64);
+    let div = (clock + baud * 4) / (baud * 8);
+    if div == 0 || div > 0x1_0000 {
+        return Err(ConfigError::UnsupportedBaudRate);
+    }
+    let actual = clock / (div * 8);
+    if actual.abs_diff(baud) * 100 > baud * MAX_BAUD_RATE_ERROR_PERCENT as u64 {
+        return Err(ConfigError::InaccurateBaudRate(actual as u32));
+    }
+    Ok((div - 1) as u16)
+}
+
+/// An interrupt-driven Mini UART with an `RX`-byte receive buffer and a
+/// `TX`-byte transmit buffer
+///
+/// All methods take `&self` so that a `MiniUart` can be placed in a `static`
+/// and shared between the interrupt handler and tasks.
+pub struct MiniUart<const RX: usize = 256, const TX: usize = 256> {
+    regs: *const Registers,
+    buffered: Buffered<RX, TX>,
+    /// An overrun was detected but not reported yet.
+    overrun: AtomicBool,
+}
+
+// Safety: `buffered` accesses its buffers with the lock held, and the
+// read-modify-write operations on the registers are also done with the lock
+// held.
+unsafe impl<const RX: usize, const TX: usize> Send for MiniUart<RX, TX> {}
+unsafe impl<const RX: usize, const TX: usize> Sync for MiniUart<RX, TX> {}
+
+impl<const RX: usize, const TX: usize> MiniUart<RX, TX> {
+    /// Construct a `MiniUart`. The Mini UART is left untouched until
+    /// [`Self::init`] is called.
+    ///
+    /// # Safety
+    ///
+    /// `regs` must point to [the auxiliary peripheral register
+    /// block](Registers) and remain valid for the lifetime of the constructed
+    /// `MiniUart`. The Mini UART registers must not be accessed by other
+    /// means.
+    #[inline]
+    pub const unsafe fn new(regs: *const Registers) -> Self {
+        Self {
+            regs,
+            buffered: Buffered::new(),
+            overrun: AtomicBool::new(false),
+        }
+    }
+
+    #[inline]
+    fn regs(&self) -> &MiniUartRegisters {
+        // Safety: Upheld by the caller of `Self::new`
+        unsafe { &(*self.regs).aux_mu }
+    }
+
+    /// Enable and configure the Mini UART, discarding the buffered characters
+    /// and the pending errors, and enable the receive interrupt.
+    pub fn init(&self, config: &Config) -> Result<(), ConfigError> {
+        let divisor = baud_divisor(config.core_clock_hz, config.baud_rate)?;
+
+        // Safety: Upheld by the caller of `Self::new`
+        super::set_enables(unsafe { &*self.regs }, AUX_ENABLES::MINI_UART_ENABLE::SET);
+
+        let regs = self.regs();
+        regs.cntl_reg.set(0);
+        regs.ier_reg.set(0);
+        regs.iir_reg.write(
+            AUX_MU_IIR_REG::TX_INT_PENDING_FIFO_CLEAR::SET
+                + AUX_MU_IIR_REG::RX_INT_PENDING_FIFO_CLEAR::SET,
+        );
+        let data_bits = match config.data_bits {
+            DataBits::Seven => AUX_MU_LCR_REG::DATA_SIZE::SevenBits,
+            DataBits::Eight => AUX_MU_LCR_REG::DATA_SIZE::EightBits,
+        };
+        regs.lcr_reg.write(data_bits + AUX_MU_LCR_REG::DLAB::Normal);
+        regs.mcr_reg.set(0);
+        regs.baud_reg
+            .write(AUX_MU_BAUD_REG::BAUDRATE.val(divisor.into()));
+
+        self.buffered.clear();
+        // Clear the overrun flag of `lsr_reg`
+        regs.lsr_reg.get();
+        self.overrun.store(false, Ordering::Relaxed);
+
+        regs.ier_reg.write(
+            AUX_MU_IER_REG::RX_INT_ENABLE::SET + AUX_MU_IER_REG::RX_INT_ENABLE_EXTRA.val(0b11),
+        );
+        let flow_control = match config.flow_control {
+            FlowControl::None => AUX_MU_CNTL_REG::RTS_AFC::CLEAR + AUX_MU_CNTL_REG::CTS_AFC::CLEAR,
+            FlowControl::RtsCts => AUX_MU_CNTL_REG::RTS_AFC::SET + AUX_MU_CNTL_REG::CTS_AFC::SET,
+        };
+        regs.cntl_reg.write(
+            AUX_MU_CNTL_REG::RX_ENABLE::SET + AUX_MU_CNTL_REG::TX_ENABLE::SET + flow_control,
+        );
+        Ok(())
+    }
+
+    /// Change the baud rate, e.g., after the core clock frequency was
+    /// changed. Characters being transmitted or received may be corrupted.
+    pub fn set_baud_rate(&self, core_clock_hz: u32, baud_rate: u32) -> Result<(), ConfigError> {
+        let divisor = baud_divisor(core_clock_hz, baud_rate)?;
+        self.regs()
+            .baud_reg
+            .write(AUX_MU_BAUD_REG::BAUDRATE.val(divisor.into()));
+        Ok(())
+    }
+
+    /// Disable the Mini UART through `aux_enables`. [`Self::init`] enables it
+    /// again.
+    pub fn disable(&self) {
+        self.regs().ier_reg.set(0);
+        // Safety: Upheld by the caller of `Self::new`
+        super::set_enables(unsafe { &*self.regs }, AUX_ENABLES::MINI_UART_ENABLE::CLEAR);
+    }
+
+    /// Service the interrupt: move the received characters to the receive
+    /// buffer and the buffered characters to the transmit FIFO.
+    ///
+    /// When the receive buffer is full, the remaining characters are left in
+    /// the receive FIFO (which deasserts RTS with [`FlowControl::RtsCts`])
+    /// until [`Self::read`] makes room.
+    pub fn service(&self) {
+        // Reading `lsr_reg` clears the overrun flag
+        if self.regs().lsr_reg.is_set(AUX_MU_LSR_REG::RX_OVERRUN) {
+            self.overrun.store(true, Ordering::Relaxed);
+        }
+        self.buffered.service(self);
+    }
+
+    /// Read the received characters into `buf` without blocking. Returns the
+    /// number of characters read, which is zero if none are buffered.
+    ///
+    /// A pending receive error is returned (and cleared) first.
+    #[inline]
+    pub fn try_read(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.buffered.try_read(self, buf)
+    }
+
+    /// Read the received characters into `buf`, busy-waiting until at least
+    /// one is available. See [`Self::try_read`].
+    #[inline]
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.buffered.read(self, buf)
+    }
+
+    /// Queue as many characters from `bytes` as possible for transmission
+    /// without blocking. Returns the number of queued characters, which is
+    /// zero if the transmit buffer is full.
+    #[inline]
+    pub fn try_write(&self, bytes: &[u8]) -> usize {
+        self.buffered.try_write(self, bytes)
+    }
+
+    /// Queue all of `bytes` for transmission, busy-waiting while the transmit
+    /// buffer is full.
+    #[inline]
+    pub fn write(&self, bytes: &[u8]) {
+        self.buffered.write(self, bytes);
+    }
+
+    /// Busy-wait until all queued characters are transmitted.
+    #[inline]
+    pub fn flush(&self) {
+        self.buffered.flush(self);
+    }
+}
+
+impl<const RX: usize, const TX: usize> Fifos for MiniUart<RX, TX> {
+    type Error = Error;
+
+    #[inline]
+    fn take_error(&self) -> Result<(), Error> {
+        if self.overrun.swap(false, Ordering::Relaxed) {
+            return Err(Error::Overrun);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn rx_ready(&self) -> bool {
+        self.regs().stat_reg.is_set(AUX_MU_STAT_REG::RX_NOT_EMPTY)
+    }
+
+    #[inline]
+    fn pop_rx(&self) -> Option<u8> {
+        Some(self.regs().io_reg.read(AUX_MU_IO_REG::RX_DATA) as u8)
+    }
+
+    #[inline]
+    fn set_rx_interrupt(&self, enable: bool) {
+        self.regs()
+            .ier_reg
+            .modify(AUX_MU_IER_REG::RX_INT_ENABLE.val(enable as u32));
+    }
+
+    #[inline]
+    fn tx_ready(&self) -> bool {
+        self.regs().stat_reg.is_set(AUX_MU_STAT_REG::TX_NOT_FULL)
+    }
+
+    #[inline]
+    fn push_tx(&self, byte: u8) {
+        self.regs()
+            .io_reg
+            .write(AUX_MU_IO_REG::TX_DATA.val(byte.into()));
+    }
+
+    #[inline]
+    fn set_tx_interrupt(&self, enable: bool) {
+        self.regs()
+            .ier_reg
+            .modify(AUX_MU_IER_REG::TX_INT_ENABLE.val(enable as u32));
+    }
+
+    #[inline]
+    fn tx_done(&self) -> bool {
+        self.regs().stat_reg.is_set(AUX_MU_STAT_REG::TX_DONE)
+    }
+}
+
+impl<const RX: usize, const TX: usize> super::Service for MiniUart<RX, TX> {
+    #[inline]
+    fn service(&self) {
+        MiniUart::service(self);
+    }
+}
+
+impl<const RX: usize, const TX: usize> fmt::Debug for MiniUart<RX, TX> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MiniUart")
+            .field("regs", &self.regs)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use bcm2711_pac::{aux::AUX_IRQ, sim};
+    use std::vec::Vec;
+
+    fn uart<const RX: usize, const TX: usize>(
+        device: &sim::aux::Aux,
+        config: &Config,
+    ) -> MiniUart<RX, TX> {
+        let uart = unsafe { MiniUart::new(device.regs()) };
+        uart.init(config).unwrap();
+        uart
+    }
+
+    #[test]
+    fn divisor() {
+        assert_eq!(baud_divisor(500_000_000, 9_600), Ok(6509));
+        assert_eq!(baud_divisor(500_000_000, 31_250_000), Ok(1));
+        assert_eq!(baud_divisor(500_000_000, 62_500_000), Ok(0));
+        // 2.5% off
+        assert_eq!(
+            baud_divisor(500_000_000, 30_500_000),
+            Err(ConfigError::InaccurateBaudRate(31_250_000))
+        );
+        assert_eq!(
+            baud_divisor(500_000_000, 100),
+            Err(ConfigError::UnsupportedBaudRate)
+        );
+        assert_eq!(
+            baud_divisor(500_000_000, 0),
+            Err(ConfigError::UnsupportedBaudRate)
+        );
+    }
+
+    #[test]
+    fn init() {
+        let device = sim::aux::Aux::new();
+        let uart = uart::<8, 8>(
+            &device,
+            &Config {
+                core_clock_hz: 250_000_000,
+                data_bits: DataBits::Seven,
+                flow_control: FlowControl::RtsCts,
+                ..Config::default()
+            },
+        );
+        let regs = device.regs();
+        assert!(regs.aux_enables.is_set(AUX_ENABLES::MINI_UART_ENABLE));
+        assert_eq!(regs.aux_mu.baud_reg.get(), 270);
+        assert!(regs
+            .aux_mu
+            .lcr_reg
+            .matches_all(AUX_MU_LCR_REG::DATA_SIZE::SevenBits));
+        assert!(regs.aux_mu.cntl_reg.matches_all(
+            AUX_MU_CNTL_REG::RX_ENABLE::SET
+                + AUX_MU_CNTL_REG::TX_ENABLE::SET
+                + AUX_MU_CNTL_REG::RTS_AFC::SET
+                + AUX_MU_CNTL_REG::CTS_AFC::SET
+        ));
+        assert!(regs.aux_mu.ier_reg.matches_all(
+            AUX_MU_IER_REG::RX_INT_ENABLE::SET + AUX_MU_IER_REG::TX_INT_ENABLE::CLEAR
+        ));
+
+        // Throttled core clock
+        uart.set_baud_rate(200_000_000, 115_200).unwrap();
+        assert_eq!(regs.aux_mu.baud_reg.get(), 216);
+
+        uart.disable();
+        assert!(!regs.aux_enables.is_set(AUX_ENABLES::MINI_UART_ENABLE));
+    }
+
+    #[test]
+    fn rx() {
+        let device = sim::aux::Aux::new();
+        let uart = uart::<4, 8>(&device, &Config::default());
+        let mut buf = [0; 8];
+
+        device.push_rx(b"hello");
+        assert!(device.regs().aux_irq.is_set(AUX_IRQ::MINI_UART_IRQ));
+        uart.service();
+        // The buffer is full; the rest is left in the FIFO
+        assert_eq!(device.rx_len(), 1);
+        assert!(!device.regs().aux_irq.is_set(AUX_IRQ::MINI_UART_IRQ));
+        assert_eq!(uart.read(&mut buf), Ok(4));
+        assert_eq!(&buf[..4], b"hell");
+
+        uart.service();
+        assert_eq!(uart.read(&mut buf), Ok(1));
+        assert_eq!(buf[0], b'o');
+
+        // Overrun
+        device.push_rx(b"0123456789");
+        uart.service();
+        assert_eq!(uart.try_read(&mut buf), Err(Error::Overrun));
+        assert_eq!(uart.try_read(&mut buf), Ok(4));
+        assert_eq!(&buf[..4], b"0123");
+    }
+
+    #[test]
+    fn tx_flow_control() {
+        let device = sim::aux::Aux::new();
+        let uart = uart::<8, 16>(
+            &device,
+            &Config {
+                flow_control: FlowControl::RtsCts,
+                ..Config::default()
+            },
+        );
+        let data: Vec<u8> = (0..64).collect();
+
+        // The FIFO (8 characters) and the buffer (16 characters) are filled
+        device.set_cts(false);
+        assert_eq!(uart.try_write(&data), 24);
+        assert_eq!(uart.try_write(&data[24..]), 0);
+        assert!(device
+            .regs()
+            .aux_mu
+            .ier_reg
+            .is_set(AUX_MU_IER_REG::TX_INT_ENABLE));
+        assert!(!device.regs().aux_irq.is_set(AUX_IRQ::MINI_UART_IRQ));
+
+        device.set_cts(true);
+        let mut sent = Vec::new();
+        let mut remaining = &data[24..];
+        while sent.len() < data.len() {
+            if device.regs().aux_irq.is_set(AUX_IRQ::MINI_UART_IRQ) {
+                uart.service();
+            }
+            sent.extend(device.take_tx());
+            remaining = &remaining[uart.try_write(remaining)..];
+        }
+        assert!(remaining.is_empty());
+        assert_eq!(sent, data);
+        uart.flush();
+        assert!(!device
+            .regs()
+            .aux_mu
+            .ier_reg
+            .is_set(AUX_MU_IER_REG::TX_INT_ENABLE));
+    }
+}
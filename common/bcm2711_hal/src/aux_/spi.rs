@@ -0,0 +1,869 @@
+//! Interrupt-driven auxiliary SPI master (SPI1 and SPI2) driver
+//!
+//! Unlike the SPI0-compatible masters ([`crate::spi`]), the auxiliary SPI
+//! masters shift words of 1–32 bits, MSB or LSB first, and can hold the chip
+//! select line asserted between words: a word written to `txhold_reg[a-d]`
+//! leaves CS asserted after it's shifted out, while a word written to
+//! `io_reg[a-d]` deasserts it. [`AuxSpi::transaction`] uses this to stream any
+//! number of words (and [`Operation`]s) within one assertion of CS.
+//!
+//! The words are moved between the buffers and the four-word FIFOs by
+//! [`AuxSpi::service`], which must be called on the shared interrupt of the
+//! auxiliary peripherals, usually through [`Demux`](super::Demux). The
+//! calling task repeatedly calls the wait function (see [`AuxSpi::with_wait`])
+//! until the transaction completes.
+//!
+//! ```rust,ignore
+//! use bcm2711_hal::{
+//!     aux::{spi::{AuxSpi, Config, Instance, Operation}, Demux},
+//!     identity_mapped,
+//!     spi::ChipSelect,
+//! };
+//! use bcm2711_pac::{aux, irq};
+//! use solid::{interrupt, singleton::pin_singleton};
+//!
+//! static SPI1: AuxSpi = unsafe { AuxSpi::new(identity_mapped(aux::BASE), Instance::Spi1) };
+//! static AUX: Demux = unsafe { Demux::new(identity_mapped(aux::BASE)) }.with_spi(&SPI1);
+//!
+//! let handler = pin_singleton!(: Handler<_> = interrupt::Handler::new(&AUX)).unwrap();
+//! handler.register_static(&irq::AUX.handler_options(10)).unwrap();
+//! irq::AUX.number().enable().unwrap();
+//!
+//! SPI1.init(&Config::default()).unwrap();
+//!
+//! // A 9-bit command followed by 24-bit data words, all within one CS
+//! // assertion
+//! let mut data = [0; 64];
+//! SPI1.transaction(
+//!     ChipSelect::Cs0,
+//!     9,
+//!     &mut [
+//!         Operation::Write(&[0x1a5]),
+//!         Operation::Width(24),
+//!         Operation::Read(&mut data),
+//!     ],
+//! )
+//! .unwrap();
+//! ```
+//!
+//! The pins must be switched to the appropriate alternate functions
+//! separately (e.g., GPIO16–21 to `ALT4` for SPI1).
+use bcm2711_pac::aux::{
+    Registers, SpiRegisters, AUX_ENABLES, AUX_SPI_CNTL0_REG, AUX_SPI_CNTL1_REG, AUX_SPI_STAT_REG,
+};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tock_registers::{
+    fields::Field,
+    interfaces::{ReadWriteable, Readable, Writeable},
+};
+
+use crate::{spi::ChipSelect, sync::with_lock};
+
+/// The core clock frequency configured by the Raspberry Pi 4 firmware by
+/// default (`core_freq=500`)
+pub const DEFAULT_CORE_CLOCK_HZ: u32 = 500_000_000;
+
+/// The depth of the FIFOs, in words
+const FIFO_LEN: usize = 4;
+
+/// An auxiliary SPI master
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Instance {
+    /// SPI1
+    Spi1,
+    /// SPI2
+    Spi2,
+}
+
+/// The level of the clock line between words
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum ClockPolarity {
+    /// The clock idles low.
+    IdleLow,
+    /// The clock idles high.
+    IdleHigh,
+}
+
+/// A clock edge
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Edge {
+    /// The rising edge
+    Rising,
+    /// The falling edge
+    Falling,
+}
+
+/// The order in which the bits of a word are shifted
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum BitOrder {
+    /// The most significant bit first
+    MsbFirst,
+    /// The least significant bit first
+    LsbFirst,
+}
+
+/// The configuration for [`AuxSpi::init`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Config {
+    /// The VPU core clock frequency
+    pub core_clock_hz: u32,
+    /// The maximum clock frequency. The actual frequency is the fastest one
+    /// not exceeding this, `core_clock_hz / (2 * n)` for some `n` in
+    /// `1..=4096`.
+    pub clock_hz: u32,
+    /// The level of the clock line between words
+    pub clock_polarity: ClockPolarity,
+    /// The clock edge on which the data is driven
+    pub out_edge: Edge,
+    /// The clock edge on which the data is sampled
+    pub in_edge: Edge,
+    /// The order in which the bits are shifted out and in
+    pub bit_order: BitOrder,
+    /// The number of additional bit periods (0–7) for which CS is deasserted
+    /// between transactions
+    pub cs_high_time: u8,
+}
+
+impl Default for Config {
+    /// 1 MHz, SPI mode 0 (the clock idles low, and data is sampled on the
+    /// rising edges), MSB first
+    #[inline]
+    fn default() -> Self {
+        Self {
+            core_clock_hz: DEFAULT_CORE_CLOCK_HZ,
+            clock_hz: 1_000_000,
+            clock_polarity: ClockPolarity::IdleLow,
+            out_edge: Edge::Falling,
+            in_edge: Edge::Rising,
+            bit_order: BitOrder::MsbFirst,
+            cs_high_time: 0,
+        }
+    }
+}
+
+/// The error type for [`AuxSpi::init`]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum ConfigError {
+    /// The clock frequency is too low for the core clock frequency.
+    UnsupportedClock,
+    /// [`Config::cs_high_time`] is out of range.
+    UnsupportedCsHighTime,
+    /// A transaction is in progress.
+    Busy,
+}
+
+/// The error type for [`AuxSpi::transaction`]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Error {
+    /// Another transaction is in progress.
+    Busy,
+    /// A word width is out of the range `1..=32`.
+    UnsupportedWidth,
+}
+
+/// A part of a transaction performed by [`AuxSpi::transaction`]
+///
+/// The low bits of each word (as many as the current width) are shifted out,
+/// and the received bits are stored in the low bits of each word.
+#[derive(PartialEq, Eq, Debug, Hash)]
+pub enum Operation<'a> {
+    /// Transmit the words, discarding the received words.
+    Write(&'a [u32]),
+    /// Receive into the buffer, transmitting zeros.
+    Read(&'a mut [u32]),
+    /// Receive into the first buffer while transmitting the second. The
+    /// operation is as long as the longer buffer; zeros are transmitted after
+    /// the second buffer, and the words received after the first buffer is
+    /// filled are discarded.
+    Transfer(&'a mut [u32], &'a [u32]),
+    /// Transmit the words while replacing them with the received words.
+    TransferInPlace(&'a mut [u32]),
+    /// Change the width of the following words to the specified number of
+    /// bits (1–32). This waits until the preceding words are shifted out, so
+    /// the clock pauses while CS stays asserted.
+    Width(u8),
+}
+
+impl Operation<'_> {
+    /// The number of words shifted by the operation
+    #[inline]
+    fn len(&self) -> usize {
+        match self {
+            Self::Write(tx) => tx.len(),
+            Self::Read(rx) => rx.len(),
+            Self::Transfer(rx, tx) => rx.len().max(tx.len()),
+            Self::TransferInPlace(buf) => buf.len(),
+            Self::Width(_) => 0,
+        }
+    }
+
+    /// The word to transmit at `pos`
+    #[inline]
+    fn tx_word(&self, pos: usize) -> u32 {
+        match self {
+            Self::Write(tx) | Self::Transfer(_, tx) => tx.get(pos).copied().unwrap_or(0),
+            Self::TransferInPlace(buf) => buf[pos],
+            Self::Read(_) | Self::Width(_) => 0,
+        }
+    }
+
+    /// Store the word received at `pos`.
+    #[inline]
+    fn set_rx_word(&mut self, pos: usize, word: u32) {
+        match self {
+            Self::Read(rx) | Self::Transfer(rx, _) | Self::TransferInPlace(rx) => {
+                if let Some(slot) = rx.get_mut(pos) {
+                    *slot = word;
+                }
+            }
+            Self::Write(_) | Self::Width(_) => {}
+        }
+    }
+}
+
+/// Calculate `cntl0_reg.SPEED` for the fastest clock frequency not exceeding
+/// `clock_hz` (`clock_hz = core_clock_hz / (2 * (speed + 1))`).
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_hal::aux::spi::{clock_divider, ConfigError};
+/// assert_eq!(clock_divider(500_000_000, 1_000_000), Ok(249));
+/// assert_eq!(clock_divider(500_000_000, 300_000_000), Ok(0));
+/// assert_eq!(clock_divider(500_000_000, 60_000), Err(ConfigError::UnsupportedClock));
+/// ```
+pub const fn clock_divider(core_clock_hz: u32, clock_hz: u32) -> Result<u16, ConfigError> {
+    if clock_hz == 0 {
+        return Err(ConfigError::UnsupportedClock);
+    }
+    let (core, clock) = (core_clock_hz as u64, clock_hz as u64 * 2);
+    let mut div = core / clock;
+    if div * clock != core {
+        div += 1;
+    }
+    if div > 0x1000 {
+        return Err(ConfigError::UnsupportedClock);
+    }
+    Ok(if div == 0 { 0 } else { div - 1 } as u16)
+}
+
+/// Align the low `width` bits of `word` for the data registers.
+#[inline]
+fn encode(word: u32, width: u32, bit_order: BitOrder) -> u32 {
+    match bit_order {
+        // Shifted out from bit 31
+        BitOrder::MsbFirst => word << (32 - width),
+        // Shifted out from bit 0
+        BitOrder::LsbFirst => word,
+    }
+}
+
+/// Extract the `width` bits shifted in from a value read from the data
+/// registers.
+#[inline]
+fn decode(value: u32, width: u32, bit_order: BitOrder) -> u32 {
+    match bit_order {
+        // Shifted in at bit 0
+        BitOrder::MsbFirst => value & (u32::MAX >> (32 - width)),
+        // Shifted in at bit 31
+        BitOrder::LsbFirst => value >> (32 - width),
+    }
+}
+
+/// The position of a word in the operations of a transaction
+#[derive(Clone, Copy, Debug)]
+struct Cursor {
+    op: usize,
+    pos: usize,
+}
+
+/// A transaction serviced by [`pump`]
+struct Job {
+    /// The operations, which outlive the job. The lifetime is erased.
+    ops: *mut [Operation<'static>],
+    /// The total number of words
+    len: usize,
+    /// The width of the words being shifted
+    width: u32,
+    bit_order: BitOrder,
+    /// The next word to write to the transmit FIFO
+    tx: Cursor,
+    /// The next word to read from the receive FIFO
+    rx: Cursor,
+    /// The number of words written to the transmit FIFO
+    tx_count: usize,
+    /// The number of words written but not read yet
+    in_flight: usize,
+    /// Waiting for the words in flight to change the width
+    blocked: bool,
+}
+
+/// The state of [`AuxSpi`] protected by `with_lock`
+struct State {
+    /// `cntl0_reg` between transactions
+    cntl0: u32,
+    /// `cntl1_reg` without the interrupt enable bits
+    cntl1: u32,
+    bit_order: BitOrder,
+    /// The transaction in progress (only while it's serviced by
+    /// [`AuxSpi::service`])
+    job: Option<Job>,
+}
+
+/// An interrupt-driven auxiliary SPI master
+///
+/// All methods take `&self` so that an `AuxSpi` can be placed in a `static`
+/// and shared between the interrupt handler and tasks. Only one transaction
+/// can be in progress at a time; the others fail with [`Error::Busy`].
+pub struct AuxSpi {
+    regs: *const Registers,
+    instance: Instance,
+    wait: fn(),
+    /// Protected by `with_lock`
+    state: UnsafeCell<State>,
+    /// Set while a transaction (or [`AuxSpi::init`]) is in progress
+    busy: AtomicBool,
+}
+
+// Safety: `state` is only accessed with the lock held.
+unsafe impl Send for AuxSpi {}
+unsafe impl Sync for AuxSpi {}
+
+impl AuxSpi {
+    /// Construct an `AuxSpi` for `instance`, busy-waiting for the completion
+    /// of transactions. The SPI master is left untouched until [`Self::init`]
+    /// is called.
+    ///
+    /// # Safety
+    ///
+    /// `regs` must point to [the auxiliary peripheral register
+    /// block](Registers) and remain valid for the lifetime of the constructed
+    /// `AuxSpi`. The registers of `instance` must not be accessed by other
+    /// means.
+    #[inline]
+    pub const unsafe fn new(regs: *const Registers, instance: Instance) -> Self {
+        Self {
+            regs,
+            instance,
+            wait: core::hint::spin_loop,
+            state: UnsafeCell::new(State {
+                cntl0: 0,
+                cntl1: 0,
+                bit_order: BitOrder::MsbFirst,
+                job: None,
+            }),
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    /// Call `wait` repeatedly while waiting for the completion of
+    /// transactions, instead of [`core::hint::spin_loop`].
+    #[inline]
+    pub const fn with_wait(self, wait: fn()) -> Self {
+        Self { wait, ..self }
+    }
+
+    /// Get the SPI master driven by `self`.
+    #[inline]
+    pub const fn instance(&self) -> Instance {
+        self.instance
+    }
+
+    #[inline]
+    fn regs(&self) -> &SpiRegisters {
+        // Safety: Upheld by the caller of `Self::new`
+        let regs = unsafe { &*self.regs };
+        match self.instance {
+            Instance::Spi1 => &regs.aux_spi1,
+            Instance::Spi2 => &regs.aux_spi2,
+        }
+    }
+
+    /// Call `f` with the lock held.
+    #[inline]
+    fn with_state<R>(&self, f: impl FnOnce(&SpiRegisters, &mut State) -> R) -> R {
+        // Safety: We are holding the lock
+        with_lock(|| f(self.regs(), unsafe { &mut *self.state.get() }))
+    }
+
+    /// Acquire `busy`, which is released (aborting the transaction in
+    /// progress) when the returned guard is dropped.
+    fn lock(&self) -> Option<Busy<'_>> {
+        if self.busy.swap(true, Ordering::Acquire) {
+            None
+        } else {
+            Some(Busy(self))
+        }
+    }
+
+    #[inline]
+    fn enable_bit(&self) -> Field<u32, AUX_ENABLES::Register> {
+        match self.instance {
+            Instance::Spi1 => AUX_ENABLES::SPI1_ENABLE,
+            Instance::Spi2 => AUX_ENABLES::SPI2_ENABLE,
+        }
+    }
+
+    /// Enable and configure the SPI master, which drives the clock and chip
+    /// select lines to their idle levels.
+    pub fn init(&self, config: &Config) -> Result<(), ConfigError> {
+        let speed = clock_divider(config.core_clock_hz, config.clock_hz)?;
+        if config.cs_high_time > 7 {
+            return Err(ConfigError::UnsupportedCsHighTime);
+        }
+        let _busy = self.lock().ok_or(ConfigError::Busy)?;
+
+        // Safety: Upheld by the caller of `Self::new`
+        super::set_enables(unsafe { &*self.regs }, self.enable_bit().val(1));
+        self.regs()
+            .cntl0_reg
+            .write(AUX_SPI_CNTL0_REG::CLEAR_FIFO::SET);
+
+        let cntl0 = AUX_SPI_CNTL0_REG::SPEED.val(speed.into())
+            + AUX_SPI_CNTL0_REG::ENABLE::SET
+            + AUX_SPI_CNTL0_REG::CS.val(0b111)
+            + AUX_SPI_CNTL0_REG::SHIFT_LEN.val(8)
+            + match config.clock_polarity {
+                ClockPolarity::IdleLow => AUX_SPI_CNTL0_REG::CLK_POLARITY::IdleLow,
+                ClockPolarity::IdleHigh => AUX_SPI_CNTL0_REG::CLK_POLARITY::IdleHigh,
+            }
+            + match config.out_edge {
+                Edge::Rising => AUX_SPI_CNTL0_REG::OUT_EDGE::RisingEdge,
+                Edge::Falling => AUX_SPI_CNTL0_REG::OUT_EDGE::FallingEdge,
+            }
+            + match config.in_edge {
+                Edge::Rising => AUX_SPI_CNTL0_REG::IN_EDGE::RisingEdge,
+                Edge::Falling => AUX_SPI_CNTL0_REG::IN_EDGE::FallingEdge,
+            };
+        let (cntl0, cntl1) = match config.bit_order {
+            BitOrder::MsbFirst => (
+                cntl0 + AUX_SPI_CNTL0_REG::SHIFT_OUT_DIR::MsbFirst,
+                AUX_SPI_CNTL1_REG::SHIFT_IN_DIR::MsbFirst,
+            ),
+            BitOrder::LsbFirst => (
+                cntl0 + AUX_SPI_CNTL0_REG::SHIFT_OUT_DIR::LsbFirst,
+                AUX_SPI_CNTL1_REG::SHIFT_IN_DIR::LsbFirst,
+            ),
+        };
+        let cntl1 = cntl1 + AUX_SPI_CNTL1_REG::CS_HIGH_TIME.val(config.cs_high_time.into());
+
+        self.with_state(|_, state| {
+            state.cntl0 = cntl0.value;
+            state.cntl1 = cntl1.value;
+            state.bit_order = config.bit_order;
+        });
+        // `_busy` writes `state.cntl0` and `state.cntl1`
+        Ok(())
+    }
+
+    /// Disable the SPI master through `aux_enables`. [`Self::init`] enables
+    /// it again.
+    pub fn disable(&self) {
+        self.regs().cntl1_reg.set(0);
+        // Safety: Upheld by the caller of `Self::new`
+        super::set_enables(unsafe { &*self.regs }, self.enable_bit().val(0));
+    }
+
+    /// Perform `ops` in order on the chip select line `cs`, which is held
+    /// asserted from the first word to the last. The words are `width` bits
+    /// wide (1–32) until changed by [`Operation::Width`].
+    ///
+    /// This waits until [`Self::service`] finishes the transaction.
+    pub fn transaction(
+        &self,
+        cs: ChipSelect,
+        width: u8,
+        ops: &mut [Operation<'_>],
+    ) -> Result<(), Error> {
+        let valid = |width: u8| (1..=32).contains(&width);
+        if !valid(width)
+            || ops
+                .iter()
+                .any(|op| matches!(*op, Operation::Width(width) if !valid(width)))
+        {
+            return Err(Error::UnsupportedWidth);
+        }
+        let busy = self.lock().ok_or(Error::Busy)?;
+        let len = ops.iter().map(Operation::len).sum();
+        if len == 0 {
+            return Ok(());
+        }
+        let cs = match cs {
+            ChipSelect::Cs0 => 0b110,
+            ChipSelect::Cs1 => 0b101,
+            ChipSelect::Cs2 => 0b011,
+        };
+
+        self.with_state(|regs, state| {
+            let cntl0 =
+                AUX_SPI_CNTL0_REG::CS.val(cs) + AUX_SPI_CNTL0_REG::SHIFT_LEN.val(width.into());
+            regs.cntl0_reg.set(cntl0.modify(state.cntl0));
+            let mut job = Job {
+                ops: core::ptr::slice_from_raw_parts_mut(ops.as_mut_ptr().cast(), ops.len()),
+                len,
+                width: width.into(),
+                bit_order: state.bit_order,
+                tx: Cursor { op: 0, pos: 0 },
+                rx: Cursor { op: 0, pos: 0 },
+                tx_count: 0,
+                in_flight: 0,
+                blocked: false,
+            };
+            // Safety: `ops` outlives `job`, which is removed before returning
+            // (by `busy` if `wait` panics)
+            unsafe { pump(regs, &mut job) };
+            regs.cntl1_reg.set(state.cntl1 | interrupts(&job));
+            state.job = Some(job);
+        });
+        while self.with_state(|_, state| state.job.is_some()) {
+            (self.wait)();
+        }
+        drop(busy);
+        Ok(())
+    }
+
+    /// Service the interrupt: move words between the transaction in progress
+    /// and the FIFOs.
+    pub fn service(&self) {
+        self.with_state(|regs, state| {
+            if let Some(job) = &mut state.job {
+                // Safety: `Self::transaction` keeps the operations alive until
+                // `job` is removed
+                if unsafe { pump(regs, job) } {
+                    regs.cntl1_reg.set(state.cntl1);
+                    state.job = None;
+                } else {
+                    regs.cntl1_reg.set(state.cntl1 | interrupts(job));
+                }
+            }
+        });
+    }
+
+    /// Stop the transaction in progress (if any) and return the SPI master to
+    /// the idle state.
+    fn abort(&self) {
+        self.with_state(|regs, state| {
+            if state.job.take().is_some() {
+                // Disabling the SPI master deasserts CS
+                regs.cntl0_reg
+                    .write(AUX_SPI_CNTL0_REG::CLEAR_FIFO::SET + AUX_SPI_CNTL0_REG::ENABLE::CLEAR);
+            }
+            regs.cntl0_reg.set(state.cntl0);
+            regs.cntl1_reg.set(state.cntl1);
+        });
+    }
+}
+
+/// The guard returned by [`AuxSpi::lock`]
+struct Busy<'a>(&'a AuxSpi);
+
+impl Drop for Busy<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.abort();
+        self.0.busy.store(false, Ordering::Release);
+    }
+}
+
+/// The interrupts needed by the unfinished `job`
+#[inline]
+fn interrupts(job: &Job) -> u32 {
+    if job.tx_count < job.len && !job.blocked {
+        AUX_SPI_CNTL1_REG::TX_EMPTY_IRQ::SET.value
+    } else {
+        AUX_SPI_CNTL1_REG::DONE_IRQ::SET.value
+    }
+}
+
+/// Move words between `job` and the FIFOs until neither can make progress.
+/// Returns `true` if all words have been received.
+///
+/// All words but the last are written to `txhold_rega` so that CS stays
+/// asserted. At most [`FIFO_LEN`] words are in flight so that the receive
+/// FIFO never overflows.
+///
+/// # Safety
+///
+/// `job.ops` must be valid.
+unsafe fn pump(regs: &SpiRegisters, job: &mut Job) -> bool {
+    let ops = &mut *job.ops;
+    while job.in_flight > 0 && !regs.stat_reg.is_set(AUX_SPI_STAT_REG::RX_EMPTY) {
+        let word = decode(regs.io_rega.get(), job.width, job.bit_order);
+        while job.rx.pos == ops[job.rx.op].len() {
+            job.rx = Cursor {
+                op: job.rx.op + 1,
+                pos: 0,
+            };
+        }
+        ops[job.rx.op].set_rx_word(job.rx.pos, word);
+        job.rx.pos += 1;
+        job.in_flight -= 1;
+    }
+
+    job.blocked = false;
+    'tx: while job.tx_count < job.len && job.in_flight < FIFO_LEN {
+        while job.tx.pos == ops[job.tx.op].len() {
+            if let Operation::Width(width) = ops[job.tx.op] {
+                // The width applies to the words being shifted
+                if job.in_flight > 0 {
+                    job.blocked = true;
+                    break 'tx;
+                }
+                job.width = width.into();
+                regs.cntl0_reg
+                    .modify(AUX_SPI_CNTL0_REG::SHIFT_LEN.val(job.width));
+            }
+            job.tx = Cursor {
+                op: job.tx.op + 1,
+                pos: 0,
+            };
+        }
+        let word = encode(ops[job.tx.op].tx_word(job.tx.pos), job.width, job.bit_order);
+        job.tx.pos += 1;
+        job.tx_count += 1;
+        job.in_flight += 1;
+        if job.tx_count == job.len {
+            regs.io_rega.set(word);
+        } else {
+            regs.txhold_rega.set(word);
+        }
+    }
+
+    job.tx_count == job.len && job.in_flight == 0
+}
+
+impl super::Service for AuxSpi {
+    #[inline]
+    fn service(&self) {
+        AuxSpi::service(self);
+    }
+}
+
+impl fmt::Debug for AuxSpi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuxSpi")
+            .field("regs", &self.regs)
+            .field("instance", &self.instance)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use bcm2711_pac::{aux::AUX_IRQ, sim};
+    use std::{vec, vec::Vec};
+
+    fn spi(device: &sim::aux::Aux, instance: Instance, config: &Config) -> AuxSpi {
+        let spi = unsafe { AuxSpi::new(device.regs(), instance) };
+        spi.init(config).unwrap();
+        spi
+    }
+
+    /// Perform `ops` while servicing `spi` on another thread.
+    fn serviced(
+        spi: &AuxSpi,
+        cs: ChipSelect,
+        width: u8,
+        ops: &mut [Operation<'_>],
+    ) -> Result<(), Error> {
+        let done = AtomicBool::new(false);
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    spi.service();
+                    std::thread::yield_now();
+                }
+            });
+            let result = spi.transaction(cs, width, ops);
+            done.store(true, Ordering::Relaxed);
+            result
+        })
+    }
+
+    #[test]
+    fn divider() {
+        assert_eq!(clock_divider(500_000_000, 250_000_000), Ok(0));
+        assert_eq!(clock_divider(500_000_000, 125_000_000), Ok(1));
+        assert_eq!(clock_divider(500_000_000, 124_999_999), Ok(2));
+        assert_eq!(clock_divider(500_000_000, 61_036), Ok(4095));
+        assert!(clock_divider(500_000_000, 61_035).is_err());
+        assert!(clock_divider(500_000_000, 0).is_err());
+    }
+
+    #[test]
+    fn init() {
+        let device = sim::aux::Aux::new();
+        let spi = spi(
+            &device,
+            Instance::Spi2,
+            &Config {
+                clock_polarity: ClockPolarity::IdleHigh,
+                out_edge: Edge::Rising,
+                in_edge: Edge::Falling,
+                bit_order: BitOrder::LsbFirst,
+                cs_high_time: 3,
+                ..Config::default()
+            },
+        );
+        let regs = device.regs();
+        assert!(regs.aux_enables.is_set(AUX_ENABLES::SPI2_ENABLE));
+        assert!(!regs.aux_enables.is_set(AUX_ENABLES::SPI1_ENABLE));
+        assert!(regs.aux_spi2.cntl0_reg.matches_all(
+            AUX_SPI_CNTL0_REG::SPEED.val(249)
+                + AUX_SPI_CNTL0_REG::ENABLE::SET
+                + AUX_SPI_CNTL0_REG::CS.val(0b111)
+                + AUX_SPI_CNTL0_REG::CLK_POLARITY::IdleHigh
+                + AUX_SPI_CNTL0_REG::OUT_EDGE::RisingEdge
+                + AUX_SPI_CNTL0_REG::IN_EDGE::FallingEdge
+                + AUX_SPI_CNTL0_REG::SHIFT_OUT_DIR::LsbFirst
+        ));
+        assert_eq!(
+            regs.aux_spi2.cntl1_reg.get(),
+            (AUX_SPI_CNTL1_REG::SHIFT_IN_DIR::LsbFirst + AUX_SPI_CNTL1_REG::CS_HIGH_TIME.val(3))
+                .value
+        );
+        assert_eq!(regs.aux_spi1.cntl0_reg.get(), 0);
+
+        assert_eq!(
+            spi.init(&Config {
+                cs_high_time: 8,
+                ..Config::default()
+            }),
+            Err(ConfigError::UnsupportedCsHighTime)
+        );
+
+        spi.disable();
+        assert!(!regs.aux_enables.is_set(AUX_ENABLES::SPI2_ENABLE));
+    }
+
+    #[test]
+    fn stream() {
+        let device = sim::aux::Aux::new();
+        let spi = spi(&device, Instance::Spi1, &Config::default());
+        let tx: Vec<u32> = (0..100).map(|i| i * 3).collect();
+        let response: Vec<u32> = (0..100).map(|i| i + 0x100).collect();
+        device.set_spi_response(1, &response);
+        let mut rx = vec![0; 100];
+
+        // Nothing happens outside transactions
+        spi.service();
+        assert!(device.take_spi_tx(1).is_empty());
+
+        serviced(
+            &spi,
+            ChipSelect::Cs1,
+            12,
+            &mut [Operation::Transfer(&mut rx, &tx)],
+        )
+        .unwrap();
+        let words = device.take_spi_tx(1);
+        assert_eq!(words.len(), 100);
+        for (i, word) in words.iter().enumerate() {
+            // Left-aligned
+            assert_eq!(word.value, tx[i] << 20);
+            // Only the last word deasserts CS
+            assert_eq!(word.hold, i < 99);
+            let cntl0 = word.cntl0;
+            assert_eq!(cntl0 >> 17 & 0b111, 0b101);
+            assert_eq!(cntl0 & 0x3f, 12);
+        }
+        // Truncated to 12 bits
+        assert_eq!(rx, response.iter().map(|&w| w & 0xfff).collect::<Vec<_>>());
+
+        // The controller is idle
+        let regs = &device.regs().aux_spi1;
+        assert!(regs.cntl0_reg.matches_all(AUX_SPI_CNTL0_REG::CS.val(0b111)));
+        assert!(!device.regs().aux_irq.is_set(AUX_IRQ::SPI1_IRQ));
+        assert_eq!(device.spi_rx_len(1), 0);
+    }
+
+    #[test]
+    fn operations() {
+        let device = sim::aux::Aux::new();
+        let spi = spi(
+            &device,
+            Instance::Spi2,
+            &Config {
+                bit_order: BitOrder::LsbFirst,
+                ..Config::default()
+            },
+        );
+        // Shifted in at bit 31
+        device.set_spi_response(2, &[0, 0, 0xab00_0000, 0x8000_0000, 0, 0, 0x8765_4321]);
+        let mut rx = [0; 2];
+        let mut buf = [0x1234_5678];
+
+        serviced(
+            &spi,
+            ChipSelect::Cs0,
+            3,
+            &mut [
+                Operation::Write(&[0b101, 0b1111]),
+                Operation::Width(8),
+                Operation::Transfer(&mut rx, &[0x5a]),
+                Operation::Width(1),
+                Operation::Read(&mut []),
+                Operation::Write(&[1]),
+                Operation::Width(8),
+                Operation::Read(&mut [0]),
+                Operation::Width(32),
+                Operation::TransferInPlace(&mut buf),
+                Operation::Width(4),
+            ],
+        )
+        .unwrap();
+        let words: Vec<_> = device
+            .take_spi_tx(2)
+            .into_iter()
+            .map(|word| (word.value, word.hold, word.cntl0 & 0x3f))
+            .collect();
+        assert_eq!(
+            words,
+            [
+                (0b101, true, 3),
+                (0b1111, true, 3),
+                (0x5a, true, 8),
+                (0, true, 8),
+                (1, true, 1),
+                (0, true, 8),
+                (0x1234_5678, false, 32),
+            ]
+        );
+        assert_eq!(rx, [0xab, 0x80]);
+        assert_eq!(buf, [0x8765_4321]);
+    }
+
+    #[test]
+    fn errors() {
+        let device = sim::aux::Aux::new();
+        let spi = spi(&device, Instance::Spi1, &Config::default());
+        for (width, ops) in [
+            (0, &mut [][..]),
+            (33, &mut [][..]),
+            (8, &mut [Operation::Width(0)][..]),
+        ] {
+            assert_eq!(
+                spi.transaction(ChipSelect::Cs0, width, ops),
+                Err(Error::UnsupportedWidth)
+            );
+        }
+        // Empty transactions don't assert CS
+        spi.transaction(ChipSelect::Cs0, 8, &mut [Operation::Write(&[])])
+            .unwrap();
+        assert!(device.take_spi_tx(1).is_empty());
+        assert_eq!(spi.regs().cntl0_reg.read(AUX_SPI_CNTL0_REG::CS), 0b111);
+
+        let _busy = spi.lock().unwrap();
+        assert_eq!(
+            spi.transaction(ChipSelect::Cs0, 8, &mut [Operation::Write(&[1])]),
+            Err(Error::Busy)
+        );
+        assert_eq!(spi.init(&Config::default()), Err(ConfigError::Busy));
+    }
+}
@@ -0,0 +1,669 @@
+//! BSC (I²C) master driver (BSC0, BSC1, BSC3–6)
+//!
+//! [`I2c`] performs 7-bit-addressed transfers by polling the controller,
+//! calling the wait function (see [`I2c::with_wait`]) while the FIFO can't
+//! make progress. The controller stretches the clock while its FIFO is empty
+//! (writes) or full (reads), so a wait function yielding the processor
+//! doesn't lose data.
+//!
+//! [`I2c::write_read`] joins a write and a read with a repeated start,
+//! which the controller doesn't directly support: the read is queued (by
+//! setting `c.ST` again) as soon as the write becomes active, so the
+//! controller starts it without a stop condition once the write drains the
+//! FIFO.
+//!
+//! ```rust,ignore
+//! use bcm2711_hal::{bsc::{Config, I2c}, identity_mapped};
+//! use bcm2711_pac::bsc::BASE_BSC1;
+//!
+//! let i2c = unsafe { I2c::new(identity_mapped(BASE_BSC1)) };
+//! i2c.init(&Config {
+//!     clock_hz: 400_000,
+//!     ..Config::default()
+//! })
+//! .unwrap();
+//!
+//! for addr in i2c.scan().unwrap().iter() {
+//!     println!("found a device at {addr:#04x}");
+//! }
+//!
+//! // Read two bytes from register 0x0f
+//! let mut buf = [0; 2];
+//! i2c.write_read(0x68, &[0x0f], &mut buf).unwrap();
+//! ```
+//!
+//! The pins must be switched to the appropriate alternate functions
+//! separately (e.g., GPIO2–3 to `ALT0` for BSC1).
+use bcm2711_pac::bsc::{Registers, A, C, CLKT, DEL, DIV, DLEN, S};
+use core::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    LocalRegisterCopy,
+};
+
+use crate::sync::with_lock;
+
+/// The core clock frequency configured by the Raspberry Pi 4 firmware, from
+/// which the bus clock is derived. The actual value can be queried through
+/// the mailbox property interface (clock ID `CORE`).
+pub const DEFAULT_CORE_CLOCK_HZ: u32 = 500_000_000;
+
+/// The depth of the FIFO
+const FIFO_LEN: usize = 16;
+
+/// The maximum length of a transfer (limited by `dlen`)
+const MAX_LEN: usize = 0xffff;
+
+/// The number of times [`I2c::write_read`] polls `s` with the lock held for
+/// the write to start before releasing the lock and calling the wait function
+const START_SPINS: usize = 100;
+
+/// The clock stretch timeout applied by [`timing`], in milliseconds (the
+/// SMBus limit)
+const CLOCK_STRETCH_TIMEOUT_MS: u32 = 35;
+
+/// The configuration for [`I2c::init`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Config {
+    /// The VPU core clock frequency
+    pub core_clock_hz: u32,
+    /// The maximum bus clock frequency. See [`timing`].
+    pub clock_hz: u32,
+}
+
+impl Default for Config {
+    /// 100 kHz (the standard mode)
+    #[inline]
+    fn default() -> Self {
+        Self {
+            core_clock_hz: DEFAULT_CORE_CLOCK_HZ,
+            clock_hz: 100_000,
+        }
+    }
+}
+
+/// The values of the timing registers calculated by [`timing`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Timing {
+    /// `div.CDIV`
+    pub cdiv: u16,
+    /// `del.FEDL`
+    pub fedl: u16,
+    /// `del.REDL`
+    pub redl: u16,
+    /// `clkt.TOUT`
+    pub tout: u16,
+}
+
+/// The error type for [`I2c::init`]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum ConfigError {
+    /// The clock frequency is out of the range supported with the core clock
+    /// frequency.
+    UnsupportedClock,
+    /// A transfer is in progress.
+    Busy,
+}
+
+/// The error type for transfers
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Error {
+    /// Another transfer is in progress.
+    Busy,
+    /// The address doesn't fit in 7 bits.
+    InvalidAddress,
+    /// A transfer is longer than 65535 bytes, or the write part of
+    /// [`I2c::write_read`] is longer than the FIFO (16 bytes).
+    TooLong,
+    /// The slave didn't acknowledge the address or a written byte
+    /// (`s.ERR`).
+    Nack,
+    /// The slave stretched the clock for longer than the timeout
+    /// (`s.CLKT`).
+    ClockStretchTimeout,
+}
+
+/// Calculate the timing registers for the fastest bus clock frequency not
+/// exceeding `clock_hz` (`clock_hz = core_clock_hz / cdiv`, where `cdiv` is
+/// even).
+///
+/// The data is driven `cdiv / 16` core clocks after the falling edges and
+/// sampled `cdiv / 4` core clocks after the rising edges of SCL. The clock
+/// stretch timeout is 35 ms (the SMBus limit) or the maximum value, whichever
+/// is shorter.
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_hal::bsc::{timing, ConfigError, Timing};
+/// assert_eq!(
+///     timing(500_000_000, 100_000),
+///     Ok(Timing { cdiv: 5000, fedl: 312, redl: 1250, tout: 3500 })
+/// );
+/// assert_eq!(timing(500_000_000, 3_000), Err(ConfigError::UnsupportedClock));
+/// ```
+pub const fn timing(core_clock_hz: u32, clock_hz: u32) -> Result<Timing, ConfigError> {
+    if clock_hz == 0 {
+        return Err(ConfigError::UnsupportedClock);
+    }
+    let (core, clock) = (core_clock_hz, clock_hz);
+    let mut cdiv = core / clock;
+    if cdiv * clock != core {
+        cdiv += 1;
+    }
+    // `cdiv` is rounded down to an even number by the controller
+    if cdiv & 1 != 0 {
+        cdiv += 1;
+    }
+    if cdiv < 2 {
+        cdiv = 2;
+    }
+    if cdiv > 0xfffe {
+        return Err(ConfigError::UnsupportedClock);
+    }
+    let fedl = if cdiv / 16 > 1 { cdiv / 16 } else { 1 };
+    let redl = if cdiv / 4 > 1 { cdiv / 4 } else { 1 };
+    let actual = (core / cdiv) as u64;
+    let tout = actual * CLOCK_STRETCH_TIMEOUT_MS as u64 / 1000;
+    let tout = if tout > 0xffff {
+        0xffff
+    } else if tout == 0 {
+        // Zero disables the timeout
+        1
+    } else {
+        tout
+    };
+    Ok(Timing {
+        cdiv: cdiv as u16,
+        fedl: fedl as u16,
+        redl: redl as u16,
+        tout: tout as u16,
+    })
+}
+
+/// A set of 7-bit addresses returned by [`I2c::scan`]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct AddressSet(u128);
+
+impl AddressSet {
+    /// The empty set
+    pub const EMPTY: Self = Self(0);
+
+    /// Check if the set contains `addr`.
+    #[inline]
+    pub const fn contains(&self, addr: u8) -> bool {
+        addr < 0x80 && self.0 & (1 << addr) != 0
+    }
+
+    /// Add `addr` (`0..=0x7f`) to the set.
+    #[inline]
+    pub fn insert(&mut self, addr: u8) {
+        assert!(addr < 0x80, "not a 7-bit address");
+        self.0 |= 1 << addr;
+    }
+
+    /// Get the number of addresses in the set.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Check if the set is empty.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterate over the addresses in ascending order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..0x80).filter(|&addr| self.contains(addr))
+    }
+}
+
+impl fmt::Debug for AddressSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// A BSC master
+///
+/// All methods take `&self` so that an `I2c` can be placed in a `static` and
+/// shared between tasks. Only one transfer can be in progress at a time; the
+/// others fail with [`Error::Busy`].
+pub struct I2c {
+    regs: *const Registers,
+    wait: fn(),
+    /// Set while a transfer (or [`I2c::init`]) is in progress
+    busy: AtomicBool,
+}
+
+// Safety: The registers are only accessed by the holder of `busy`.
+unsafe impl Send for I2c {}
+unsafe impl Sync for I2c {}
+
+impl I2c {
+    /// Construct an `I2c`, busy-waiting for the completion of transfers. The
+    /// controller is left untouched until [`Self::init`] is called.
+    ///
+    /// # Safety
+    ///
+    /// `regs` must point to [a BSC register block](Registers) and remain
+    /// valid for the lifetime of the constructed `I2c`. The register block
+    /// must not be accessed by other means.
+    #[inline]
+    pub const unsafe fn new(regs: *const Registers) -> Self {
+        Self {
+            regs,
+            wait: core::hint::spin_loop,
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    /// Call `wait` repeatedly while waiting for the FIFO, instead of
+    /// [`core::hint::spin_loop`].
+    #[inline]
+    pub const fn with_wait(self, wait: fn()) -> Self {
+        Self { wait, ..self }
+    }
+
+    #[inline]
+    fn regs(&self) -> &Registers {
+        // Safety: Upheld by the caller of `Self::new`
+        unsafe { &*self.regs }
+    }
+
+    /// Acquire `busy`, which is released (aborting the transfer in progress)
+    /// when the returned guard is dropped.
+    fn lock(&self) -> Option<Busy<'_>> {
+        if self.busy.swap(true, Ordering::Acquire) {
+            None
+        } else {
+            Some(Busy(self))
+        }
+    }
+
+    /// Configure the bus timing.
+    pub fn init(&self, config: &Config) -> Result<(), ConfigError> {
+        let timing = timing(config.core_clock_hz, config.clock_hz)?;
+        let _busy = self.lock().ok_or(ConfigError::Busy)?;
+        let regs = self.regs();
+        regs.div.write(DIV::CDIV.val(timing.cdiv.into()));
+        regs.del
+            .write(DEL::FEDL.val(timing.fedl.into()) + DEL::REDL.val(timing.redl.into()));
+        regs.clkt.write(CLKT::TOUT.val(timing.tout.into()));
+        // `_busy` resets the controller
+        Ok(())
+    }
+
+    /// Write `bytes` to the slave at `addr`.
+    pub fn write(&self, addr: u8, bytes: &[u8]) -> Result<(), Error> {
+        let _busy = self.begin(addr, bytes.len())?;
+        let regs = self.regs();
+        regs.dlen.write(DLEN::DLEN.val(bytes.len() as u32));
+        let queued = fill_fifo(regs, bytes);
+        regs.c.write(C::I2CEN::SET + C::ST::SET);
+        self.complete(&bytes[queued..], &mut [])
+    }
+
+    /// Read into `buf` from the slave at `addr`.
+    pub fn read(&self, addr: u8, buf: &mut [u8]) -> Result<(), Error> {
+        let _busy = self.begin(addr, buf.len())?;
+        let regs = self.regs();
+        regs.dlen.write(DLEN::DLEN.val(buf.len() as u32));
+        regs.c.write(C::I2CEN::SET + C::ST::SET + C::READ::Read);
+        self.complete(&[], buf)
+    }
+
+    /// Write `bytes` to the slave at `addr`, and then read into `buf` after a
+    /// repeated start. `bytes` must fit in the FIFO (16 bytes).
+    pub fn write_read(&self, addr: u8, bytes: &[u8], buf: &mut [u8]) -> Result<(), Error> {
+        if bytes.len() > FIFO_LEN {
+            return Err(Error::TooLong);
+        }
+        let _busy = self.begin(addr, buf.len())?;
+        let regs = self.regs();
+        regs.dlen.write(DLEN::DLEN.val(bytes.len() as u32));
+        fill_fifo(regs, bytes);
+
+        // The read must be queued before the write finishes, which takes
+        // at least the nine clock cycles of the address. The lock is released
+        // between the bounded polls in case the write is slow to start.
+        let mut start = true;
+        loop {
+            let queued = with_lock(|| {
+                if core::mem::take(&mut start) {
+                    regs.c.write(C::I2CEN::SET + C::ST::SET);
+                }
+                for _ in 0..START_SPINS {
+                    let s = regs.s.extract();
+                    if s.is_set(S::TA) || s.is_set(S::DONE) {
+                        check(s)?;
+                        regs.dlen.write(DLEN::DLEN.val(buf.len() as u32));
+                        regs.c.write(C::I2CEN::SET + C::ST::SET + C::READ::Read);
+                        return Ok(true);
+                    }
+                    core::hint::spin_loop();
+                }
+                Ok(false)
+            })?;
+            if queued {
+                break;
+            }
+            (self.wait)();
+        }
+        self.complete(&[], buf)
+    }
+
+    /// Scan the bus for slaves by reading a byte from each non-reserved
+    /// address (`0x08..=0x77`). Returns the acknowledged addresses.
+    ///
+    /// Reading is harmless to most devices, but it may pop data from some
+    /// (e.g., the receive FIFO of a bridge chip).
+    pub fn scan(&self) -> Result<AddressSet, Error> {
+        let mut found = AddressSet::EMPTY;
+        for addr in 0x08..=0x77 {
+            match self.read(addr, &mut [0]) {
+                Ok(()) => found.insert(addr),
+                Err(Error::Nack) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(found)
+    }
+
+    /// Acquire `busy` and prepare a transfer of `len` bytes to `addr`.
+    fn begin(&self, addr: u8, len: usize) -> Result<Busy<'_>, Error> {
+        if addr > 0x7f {
+            return Err(Error::InvalidAddress);
+        }
+        if len > MAX_LEN {
+            return Err(Error::TooLong);
+        }
+        let busy = self.lock().ok_or(Error::Busy)?;
+        let regs = self.regs();
+        regs.c.write(C::I2CEN::SET + C::CLEAR::Clear);
+        regs.s.write(S::DONE::SET + S::ERR::SET + S::CLKT::SET);
+        regs.a.write(A::ADDR.val(addr.into()));
+        Ok(busy)
+    }
+
+    /// Move the rest of `tx` to the FIFO and the received bytes to `rx` until
+    /// the started transfer (the last of the queued ones) completes.
+    fn complete(&self, mut tx: &[u8], rx: &mut [u8]) -> Result<(), Error> {
+        let regs = self.regs();
+        let mut rx_pos = 0;
+        loop {
+            tx = &tx[fill_fifo(regs, tx)..];
+            while rx_pos < rx.len() && regs.s.is_set(S::RXD) {
+                rx[rx_pos] = regs.fifo.get() as u8;
+                rx_pos += 1;
+            }
+            let s = regs.s.extract();
+            check(s)?;
+            // `DONE` may be left by the write of `write_read`
+            if tx.is_empty() && rx_pos == rx.len() && s.is_set(S::DONE) && !s.is_set(S::TA) {
+                return Ok(());
+            }
+            (self.wait)();
+        }
+    }
+
+    /// Stop the transfer in progress (if any) and return the controller to
+    /// the idle state.
+    fn abort(&self) {
+        let regs = self.regs();
+        // Clearing `I2CEN` aborts the transfer
+        regs.c.write(C::CLEAR::Clear);
+        regs.s.write(S::DONE::SET + S::ERR::SET + S::CLKT::SET);
+    }
+}
+
+/// The guard returned by [`I2c::lock`]
+struct Busy<'a>(&'a I2c);
+
+impl Drop for Busy<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.abort();
+        self.0.busy.store(false, Ordering::Release);
+    }
+}
+
+/// Move bytes from `bytes` to the FIFO until either is exhausted. Returns the
+/// number of moved bytes.
+fn fill_fifo(regs: &Registers, bytes: &[u8]) -> usize {
+    let mut count = 0;
+    while count < bytes.len() && regs.s.is_set(S::TXD) {
+        regs.fifo.set(bytes[count].into());
+        count += 1;
+    }
+    count
+}
+
+/// Convert the error flags of `s` to an error.
+fn check(s: LocalRegisterCopy<u32, S::Register>) -> Result<(), Error> {
+    if s.is_set(S::CLKT) {
+        Err(Error::ClockStretchTimeout)
+    } else if s.is_set(S::ERR) {
+        Err(Error::Nack)
+    } else {
+        Ok(())
+    }
+}
+
+impl fmt::Debug for I2c {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("I2c")
+            .field("regs", &self.regs)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use bcm2711_pac::sim::{self, bsc::Transfer};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::{vec, vec::Vec};
+
+    fn i2c(device: &sim::bsc::Bsc) -> I2c {
+        let i2c = unsafe { I2c::new(device.regs()) };
+        i2c.init(&Config::default()).unwrap();
+        i2c
+    }
+
+    #[test]
+    fn timings() {
+        assert_eq!(
+            timing(500_000_000, 400_000),
+            Ok(Timing {
+                cdiv: 1250,
+                fedl: 78,
+                redl: 312,
+                tout: 14000,
+            })
+        );
+        // Rounded up to an even divider
+        assert_eq!(timing(500_000_000, 1_000_001).unwrap().cdiv, 500);
+        assert_eq!(timing(500_000_000, 166_000_000).unwrap().cdiv, 4);
+        assert_eq!(
+            timing(500_000_000, 500_000_000),
+            Ok(Timing {
+                cdiv: 2,
+                fedl: 1,
+                redl: 1,
+                tout: 0xffff,
+            })
+        );
+        assert_eq!(timing(65_534_000, 1_000).unwrap().cdiv, 0xfffe);
+        assert!(timing(65_535_000, 1_000).is_err());
+        assert!(timing(500_000_000, 0).is_err());
+        assert_eq!(timing(20_000, 10).unwrap().tout, 1);
+    }
+
+    #[test]
+    fn init() {
+        let device = sim::bsc::Bsc::new();
+        let i2c = i2c(&device);
+        let regs = device.regs();
+        assert_eq!(regs.div.get(), 5000);
+        assert!(regs
+            .del
+            .matches_all(DEL::FEDL.val(312) + DEL::REDL.val(1250)));
+        assert_eq!(regs.clkt.get(), 3500);
+        assert!(!regs.c.is_set(C::I2CEN));
+
+        assert_eq!(
+            i2c.init(&Config {
+                clock_hz: 1_000,
+                ..Config::default()
+            }),
+            Err(ConfigError::UnsupportedClock)
+        );
+    }
+
+    #[test]
+    fn write_and_read() {
+        let device = sim::bsc::Bsc::new();
+        let i2c = i2c(&device);
+        let data: Vec<u8> = (0..100).collect();
+        device.attach(0x50);
+        i2c.write(0x50, &data).unwrap();
+
+        device.set_response(0x50, &data[..40]);
+        let mut buf = vec![0; 40];
+        i2c.read(0x50, &mut buf).unwrap();
+        assert_eq!(buf, &data[..40]);
+
+        i2c.write(0x50, &[]).unwrap();
+        assert_eq!(
+            device.take_transfers(),
+            [
+                Transfer {
+                    addr: 0x50,
+                    read: false,
+                    data: data.clone(),
+                    repeated_start: false,
+                },
+                Transfer {
+                    addr: 0x50,
+                    read: true,
+                    data: data[..40].to_vec(),
+                    repeated_start: false,
+                },
+                Transfer {
+                    addr: 0x50,
+                    read: false,
+                    data: vec![],
+                    repeated_start: false,
+                },
+            ]
+        );
+        assert!(!device.regs().c.is_set(C::I2CEN));
+    }
+
+    #[test]
+    fn write_read() {
+        let device = sim::bsc::Bsc::new();
+        let i2c = i2c(&device);
+        device.set_response(0x68, &[0x12, 0x34, 0x56]);
+        let mut buf = [0; 20];
+        i2c.write_read(0x68, &[0x0f, 0x00], &mut buf).unwrap();
+        assert_eq!(&buf[..4], [0x12, 0x34, 0x56, 0xff]);
+        let transfers = device.take_transfers();
+        assert_eq!(transfers.len(), 2);
+        assert_eq!(transfers[0].data, [0x0f, 0x00]);
+        assert!(!transfers[0].repeated_start);
+        assert!(transfers[1].read);
+        assert_eq!(transfers[1].data.len(), 20);
+        assert!(transfers[1].repeated_start);
+
+        assert_eq!(
+            i2c.write_read(0x68, &[0; 17], &mut buf),
+            Err(Error::TooLong)
+        );
+        assert_eq!(i2c.write_read(0x69, &[0], &mut buf), Err(Error::Nack));
+        assert!(device.take_transfers().is_empty());
+    }
+
+    #[test]
+    fn write_read_slow_start() {
+        static WAITS: AtomicUsize = AtomicUsize::new(0);
+
+        let device = sim::bsc::Bsc::new();
+        let i2c = i2c(&device).with_wait(|| {
+            WAITS.fetch_add(1, Ordering::Relaxed);
+        });
+        device.set_response(0x68, &[0x12]);
+
+        // Hide the write from the first polls, keeping `DONE` if it finishes
+        let mut hidden = 2 * START_SPINS;
+        let mut done = 0;
+        let s = device.offset(&device.regs().s);
+        device.on_read(s, move |state| {
+            if hidden > 0 {
+                hidden -= 1;
+                done |= state.get(s) & S::DONE::SET.value;
+                state.clear_bits(s, (S::TA::SET + S::DONE::SET).value);
+                if hidden == 0 {
+                    state.set_bits(s, done);
+                }
+            }
+        });
+
+        let mut buf = [0];
+        i2c.write_read(0x68, &[0x0f], &mut buf).unwrap();
+        assert_eq!(buf, [0x12]);
+        // The lock was released in between
+        assert!(WAITS.load(Ordering::Relaxed) >= 2);
+    }
+
+    #[test]
+    fn errors() {
+        let device = sim::bsc::Bsc::new();
+        let i2c = i2c(&device);
+        assert_eq!(i2c.write(0x20, &[1]), Err(Error::Nack));
+        assert_eq!(i2c.read(0x80, &mut [0]), Err(Error::InvalidAddress));
+        assert_eq!(i2c.write(0x20, &vec![0; 0x10000]), Err(Error::TooLong));
+
+        device.set_stretching(0x20, true);
+        assert_eq!(i2c.read(0x20, &mut [0]), Err(Error::ClockStretchTimeout));
+        // The error flags are cleared, and the next transfer succeeds
+        device.set_stretching(0x20, false);
+        i2c.read(0x20, &mut [0]).unwrap();
+        assert!(!device.regs().s.is_set(S::CLKT));
+        assert_eq!(device.fifo_len(), 0);
+
+        let _busy = i2c.lock().unwrap();
+        assert_eq!(i2c.write(0x20, &[1]), Err(Error::Busy));
+        assert_eq!(i2c.init(&Config::default()), Err(ConfigError::Busy));
+    }
+
+    #[test]
+    fn scan() {
+        let device = sim::bsc::Bsc::new();
+        let i2c = i2c(&device);
+        assert!(i2c.scan().unwrap().is_empty());
+
+        // Reserved addresses are skipped
+        for addr in [0x03, 0x08, 0x3c, 0x77, 0x78] {
+            device.attach(addr);
+        }
+        let found = i2c.scan().unwrap();
+        assert_eq!(found.iter().collect::<Vec<_>>(), [0x08, 0x3c, 0x77]);
+        assert_eq!(found.len(), 3);
+        assert!(found.contains(0x3c));
+        assert!(!found.contains(0x03));
+        assert!(!found.contains(0xff));
+        assert_eq!(std::format!("{found:?}"), "{8, 60, 119}");
+
+        device.set_stretching(0x3c, true);
+        assert_eq!(i2c.scan(), Err(Error::ClockStretchTimeout));
+    }
+}
@@ -0,0 +1,183 @@
+//! The ring-buffered receive and transmit paths shared by the
+//! interrupt-driven UART drivers
+use core::cell::UnsafeCell;
+
+use crate::{ring::Ring, sync::with_lock};
+
+/// The hardware FIFOs of a UART driven by [`Buffered`]
+///
+/// The methods other than [`Self::take_error`] and [`Self::tx_done`] are
+/// called with the lock held.
+pub(crate) trait Fifos {
+    /// A receive error
+    type Error;
+
+    /// Take one of the pending receive errors.
+    fn take_error(&self) -> Result<(), Self::Error>;
+
+    /// Check if the receive FIFO has a character.
+    fn rx_ready(&self) -> bool;
+
+    /// Pop a character from the receive FIFO. Returns `None` if the character
+    /// was discarded because of a receive error.
+    fn pop_rx(&self) -> Option<u8>;
+
+    /// Enable or disable the receive interrupts.
+    fn set_rx_interrupt(&self, enable: bool);
+
+    /// Check if the transmit FIFO has room for a character.
+    fn tx_ready(&self) -> bool;
+
+    /// Push a character to the transmit FIFO.
+    fn push_tx(&self, byte: u8);
+
+    /// Enable or disable the transmit interrupt.
+    fn set_tx_interrupt(&self, enable: bool);
+
+    /// Check if the transmit FIFO is empty and the last character was
+    /// transmitted.
+    fn tx_done(&self) -> bool;
+}
+
+/// The ring buffers of [`Buffered`]
+struct Rings<const RX: usize, const TX: usize> {
+    rx: Ring<RX>,
+    tx: Ring<TX>,
+}
+
+/// An `RX`-byte receive buffer and a `TX`-byte transmit buffer in front of
+/// the hardware FIFOs of a UART
+pub(crate) struct Buffered<const RX: usize, const TX: usize> {
+    /// Protected by `with_lock`
+    rings: UnsafeCell<Rings<RX, TX>>,
+}
+
+impl<const RX: usize, const TX: usize> Buffered<RX, TX> {
+    /// Construct empty buffers.
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self {
+            rings: UnsafeCell::new(Rings {
+                rx: Ring::EMPTY,
+                tx: Ring::EMPTY,
+            }),
+        }
+    }
+
+    /// Call `f` with the lock held.
+    #[inline]
+    fn with_rings<R>(&self, f: impl FnOnce(&mut Rings<RX, TX>) -> R) -> R {
+        // Safety: We are holding the lock
+        with_lock(|| f(unsafe { &mut *self.rings.get() }))
+    }
+
+    /// Discard the buffered characters.
+    pub(crate) fn clear(&self) {
+        self.with_rings(|rings| {
+            rings.rx.clear();
+            rings.tx.clear();
+        });
+    }
+
+    /// Move the received characters to the receive buffer and the buffered
+    /// characters to the transmit FIFO.
+    ///
+    /// When the receive buffer is full, the remaining characters are left in
+    /// the receive FIFO, and the receive interrupts are disabled until
+    /// [`Self::try_read`] makes room.
+    pub(crate) fn service(&self, fifos: &impl Fifos) {
+        self.with_rings(|rings| {
+            while !rings.rx.is_full() && fifos.rx_ready() {
+                if let Some(byte) = fifos.pop_rx() {
+                    rings.rx.push(byte);
+                }
+            }
+            if rings.rx.is_full() {
+                fifos.set_rx_interrupt(false);
+            }
+
+            fill_tx_fifo(fifos, &mut rings.tx);
+        });
+    }
+
+    /// Read the received characters into `buf` without blocking. Returns the
+    /// number of characters read, which is zero if none are buffered.
+    ///
+    /// A pending receive error is returned (and cleared) first.
+    pub(crate) fn try_read<F: Fifos>(&self, fifos: &F, buf: &mut [u8]) -> Result<usize, F::Error> {
+        fifos.take_error()?;
+        Ok(self.with_rings(|rings| {
+            let count = rings.rx.pop_slice(buf);
+            if count > 0 {
+                // `service` may have stopped receiving
+                fifos.set_rx_interrupt(true);
+            }
+            count
+        }))
+    }
+
+    /// Read the received characters into `buf`, busy-waiting until at least
+    /// one is available. See [`Self::try_read`].
+    pub(crate) fn read<F: Fifos>(&self, fifos: &F, buf: &mut [u8]) -> Result<usize, F::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match self.try_read(fifos, buf)? {
+                0 => core::hint::spin_loop(),
+                count => return Ok(count),
+            }
+        }
+    }
+
+    /// Queue as many characters from `bytes` as possible for transmission
+    /// without blocking. Returns the number of queued characters, which is
+    /// zero if the transmit buffer is full.
+    pub(crate) fn try_write(&self, fifos: &impl Fifos, bytes: &[u8]) -> usize {
+        self.with_rings(|rings| {
+            let mut count = 0;
+            loop {
+                count += rings.tx.push_slice(&bytes[count..]);
+                fill_tx_fifo(fifos, &mut rings.tx);
+                // Repeat if the transmit FIFO made room in the buffer
+                if count == bytes.len() || rings.tx.is_full() {
+                    break count;
+                }
+            }
+        })
+    }
+
+    /// Queue all of `bytes` for transmission, busy-waiting while the transmit
+    /// buffer is full.
+    pub(crate) fn write(&self, fifos: &impl Fifos, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            match self.try_write(fifos, bytes) {
+                0 => core::hint::spin_loop(),
+                count => bytes = &bytes[count..],
+            }
+        }
+    }
+
+    /// Busy-wait until all queued characters are transmitted.
+    pub(crate) fn flush(&self, fifos: &impl Fifos) {
+        while !self.with_rings(|rings| rings.tx.is_empty()) {
+            core::hint::spin_loop();
+        }
+        while !fifos.tx_done() {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// Move characters from `tx` to the transmit FIFO until either is exhausted,
+/// and enable the transmit interrupt if `tx` still has characters. Must be
+/// called with the lock held.
+fn fill_tx_fifo<const TX: usize>(fifos: &impl Fifos, tx: &mut Ring<TX>) {
+    while fifos.tx_ready() {
+        match tx.pop() {
+            Some(byte) => fifos.push_tx(byte),
+            None => break,
+        }
+    }
+    fifos.set_tx_interrupt(!tx.is_empty());
+}
@@ -0,0 +1,47 @@
+//! Data cache maintenance for buffers shared with DMA engines
+//!
+//! The operations are no-ops on other architectures than AArch64 (e.g., in
+//! host-side tests).
+
+/// The data cache line size of Cortex-A72
+pub(crate) const LINE_SIZE: usize = 64;
+
+/// Write back the cache lines overlapping `ptr..ptr + len` so that DMA
+/// engines can read the up-to-date contents.
+#[inline]
+pub(crate) fn clean(ptr: *const u8, len: usize) {
+    // Safety: Cleaning cache lines doesn't change the memory contents
+    #[cfg(target_arch = "aarch64")]
+    for_each_line(ptr, len, |line| unsafe {
+        core::arch::asm!("dc cvac, {}", in(reg) line, options(nostack, preserves_flags));
+    });
+    #[cfg(not(target_arch = "aarch64"))]
+    let _ = (ptr, len);
+}
+
+/// Write back and invalidate the cache lines overlapping `ptr..ptr + len` so
+/// that the contents written by DMA engines aren't hidden or overwritten by
+/// stale cache lines. Must be called both before and after a DMA engine
+/// writes the range.
+#[inline]
+pub(crate) fn clean_invalidate(ptr: *const u8, len: usize) {
+    // Safety: Cleaning cache lines doesn't change the memory contents
+    #[cfg(target_arch = "aarch64")]
+    for_each_line(ptr, len, |line| unsafe {
+        core::arch::asm!("dc civac, {}", in(reg) line, options(nostack, preserves_flags));
+    });
+    #[cfg(not(target_arch = "aarch64"))]
+    let _ = (ptr, len);
+}
+
+/// Call `f` with the address of each cache line overlapping `ptr..ptr + len`
+/// and wait for the operations to complete.
+#[cfg(target_arch = "aarch64")]
+#[inline]
+fn for_each_line(ptr: *const u8, len: usize, f: impl FnMut(usize)) {
+    let start = ptr as usize & !(LINE_SIZE - 1);
+    let end = ptr as usize + len;
+    (start..end).step_by(LINE_SIZE).for_each(f);
+    // Safety: `dsb` has no effect other than ordering memory accesses
+    unsafe { core::arch::asm!("dsb sy", options(nostack, preserves_flags)) };
+}
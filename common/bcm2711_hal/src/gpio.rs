@@ -0,0 +1,392 @@
+//! Typed, owned GPIO pins
+//!
+//! [`Gpio::take`] splits [the GPIO register block](gpio::Registers) into 58
+//! [`Pin`]s, each of which can only be configured through its owner. The mode
+//! of a pin is a part of its type, and a mode change consumes the pin and
+//! returns it in the new mode:
+//!
+//! ```rust,no_run
+//! use bcm2711_hal::gpio::{Bias, Gpio};
+//!
+//! let gpio = Gpio::take().unwrap();
+//! let mut led = gpio.p42.into_output_low();
+//! let button = gpio.p4.into_input().with_bias(Bias::PullUp);
+//! let _txd = gpio.p14.into_alt::<0>();
+//!
+//! loop {
+//!     led.set_level(button.is_low());
+//! }
+//! ```
+//!
+//! A pin can't be used after its mode is changed:
+//!
+//! ```rust,compile_fail
+//! use bcm2711_hal::gpio::Gpio;
+//!
+//! let gpio = Gpio::take().unwrap();
+//! let mut led = gpio.p42.into_output();
+//! let input = gpio.p42.into_input();
+//! led.set_high();
+//! ```
+//!
+//! Output levels are changed atomically through `gpset`/`gpclr`. Mode and
+//! bias changes modify registers shared with other pins and are serialized
+//! internally.
+//!
+//! Edge and level events can be dispatched to per-pin handlers by
+//! [`event::Dispatcher`].
+use bcm2711_pac::gpio::{self, alt::Signal, Function, Registers};
+use core::{
+    fmt,
+    marker::PhantomData,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tock_registers::interfaces::{Readable, Writeable};
+
+use crate::{identity_mapped, sync::with_lock};
+
+pub mod event;
+
+pub use bcm2711_pac::gpio::{Bias, NUM_PINS};
+
+/// The mode of a pin that hasn't been configured since [`Gpio::take`]. The
+/// firmware may have assigned any function to it.
+pub struct Unknown;
+
+/// Input mode
+pub struct Input;
+
+/// Output mode
+pub struct Output;
+
+/// Alternate function `A` (`0..6`)
+pub struct Alt<const A: usize>;
+
+impl<const A: usize> Alt<A> {
+    const FUNCTION: Function = match Function::alt(A) {
+        Some(function) => function,
+        None => panic!("alternate function index out of range"),
+    };
+}
+
+/// A GPIO pin `N` in mode `M`
+pub struct Pin<const N: usize, M> {
+    regs: *const Registers,
+    _mode: PhantomData<M>,
+}
+
+// Safety: The pin's state is in the registers. Every operation is a single
+// read or write of a register specific to the pin, or a read-modify-write
+// operation serialized by `with_lock`.
+unsafe impl<const N: usize, M> Send for Pin<N, M> {}
+unsafe impl<const N: usize, M> Sync for Pin<N, M> {}
+
+impl<const N: usize, M> Pin<N, M> {
+    /// The index of the `gpset`/`gpclr`/`gplev` register containing this pin
+    const BANK: usize = N / gpio::GPLEV::PINS_PER_REGISTER;
+    /// The bit representing this pin in `gpset`/`gpclr`/`gplev`
+    const BIT: usize = N % gpio::GPLEV::PINS_PER_REGISTER;
+
+    #[inline]
+    const fn new(regs: *const Registers) -> Self {
+        Self {
+            regs,
+            _mode: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn regs(&self) -> &Registers {
+        // Safety: Upheld by the caller of `Gpio::new`
+        unsafe { &*self.regs }
+    }
+
+    #[inline]
+    fn into_mode<M2>(self, function: Function) -> Pin<N, M2> {
+        with_lock(|| self.regs().set_function(N, function));
+        Pin::new(self.regs)
+    }
+
+    /// Get the pin number.
+    #[inline]
+    pub const fn number(&self) -> usize {
+        N
+    }
+
+    /// Check if the pin is high. Valid in all modes.
+    #[inline]
+    pub fn is_high(&self) -> bool {
+        self.regs().gplev[Self::BANK].is_set(gpio::GPLEV::pin(Self::BIT))
+    }
+
+    /// Check if the pin is low. Valid in all modes.
+    #[inline]
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+
+    /// Set the pull resistor configuration.
+    #[inline]
+    pub fn set_bias(&mut self, bias: Bias) {
+        with_lock(|| self.regs().set_bias(N, bias));
+    }
+
+    /// Set the pull resistor configuration and return `self`.
+    #[inline]
+    pub fn with_bias(mut self, bias: Bias) -> Self {
+        self.set_bias(bias);
+        self
+    }
+
+    /// Get the pull resistor configuration. Returns `None` if the register
+    /// contains the reserved value.
+    #[inline]
+    pub fn bias(&self) -> Option<Bias> {
+        self.regs().bias(N)
+    }
+
+    /// Configure the pin as an input.
+    #[inline]
+    pub fn into_input(self) -> Pin<N, Input> {
+        self.into_mode(Function::Input)
+    }
+
+    /// Configure the pin as an output. The pin starts driving the level
+    /// last set for it (which is retained while it's not an output).
+    #[inline]
+    pub fn into_output(self) -> Pin<N, Output> {
+        self.into_mode(Function::Output)
+    }
+
+    /// Configure the pin as an output driving low.
+    #[inline]
+    pub fn into_output_low(self) -> Pin<N, Output> {
+        // Set the level first so that the pin doesn't glitch
+        self.write_level(false);
+        self.into_output()
+    }
+
+    /// Configure the pin as an output driving high.
+    #[inline]
+    pub fn into_output_high(self) -> Pin<N, Output> {
+        self.write_level(true);
+        self.into_output()
+    }
+
+    /// Assign the alternate function `A` to the pin. [`alt::signal`] tells
+    /// which signal it carries.
+    ///
+    /// Referencing a nonexistent alternate function fails to compile:
+    ///
+    /// ```rust,compile_fail
+    /// # let gpio = bcm2711_hal::gpio::Gpio::take().unwrap();
+    /// let pin = gpio.p14.into_alt::<6>();
+    /// ```
+    ///
+    /// [`alt::signal`]: gpio::alt::signal()
+    #[inline]
+    pub fn into_alt<const A: usize>(self) -> Pin<N, Alt<A>> {
+        self.into_mode(Alt::<A>::FUNCTION)
+    }
+
+    #[inline]
+    fn write_level(&self, high: bool) {
+        let regs = self.regs();
+        if high {
+            regs.gpset[Self::BANK].write(gpio::GPSET::set(Self::BIT));
+        } else {
+            regs.gpclr[Self::BANK].write(gpio::GPCLR::clear(Self::BIT));
+        }
+    }
+}
+
+impl<const N: usize> Pin<N, Output> {
+    /// Drive the pin high.
+    #[inline]
+    pub fn set_high(&mut self) {
+        self.write_level(true);
+    }
+
+    /// Drive the pin low.
+    #[inline]
+    pub fn set_low(&mut self) {
+        self.write_level(false);
+    }
+
+    /// Drive the pin high if `high` is `true` or low otherwise.
+    #[inline]
+    pub fn set_level(&mut self, high: bool) {
+        self.write_level(high);
+    }
+
+    /// Invert the level of the pin.
+    #[inline]
+    pub fn toggle(&mut self) {
+        let high = self.is_high();
+        self.write_level(!high);
+    }
+}
+
+impl<const N: usize, const A: usize> Pin<N, Alt<A>> {
+    /// Get the peripheral signal carried by the pin. Returns `None` if the
+    /// alternate function is reserved or not covered by
+    /// [the alternate function table](gpio::alt).
+    #[inline]
+    pub fn signal(&self) -> Option<Signal> {
+        gpio::alt::signal(N, Alt::<A>::FUNCTION)
+    }
+}
+
+impl<const N: usize, M> fmt::Debug for Pin<N, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pin<{}, {}>", N, core::any::type_name::<M>())
+    }
+}
+
+/// Set by [`Gpio::take`]
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+macro_rules! pins {
+    ( $( $field:ident: $n:literal ),* $(,)? ) => {
+        /// All GPIO pins, each owned by a field
+        #[derive(Debug)]
+        pub struct Gpio {
+            $(
+                #[doc = concat!("GPIO", stringify!($n))]
+                pub $field: Pin<$n, Unknown>,
+            )*
+        }
+
+        impl Gpio {
+            /// Construct a `Gpio` for the specified register block.
+            ///
+            /// # Safety
+            ///
+            /// `regs` must point to [the GPIO register block](Registers) and
+            /// remain valid for the lifetime of the constructed `Gpio` and
+            /// its pins. No other `Gpio` may exist for the same register
+            /// block, and the pins must not be configured by other means.
+            #[inline]
+            pub const unsafe fn new(regs: *const Registers) -> Self {
+                Self {
+                    $( $field: Pin::new(regs), )*
+                }
+            }
+        }
+    };
+}
+
+pins! {
+    p0: 0, p1: 1, p2: 2, p3: 3, p4: 4, p5: 5, p6: 6, p7: 7, p8: 8, p9: 9,
+    p10: 10, p11: 11, p12: 12, p13: 13, p14: 14, p15: 15, p16: 16, p17: 17,
+    p18: 18, p19: 19, p20: 20, p21: 21, p22: 22, p23: 23, p24: 24, p25: 25,
+    p26: 26, p27: 27, p28: 28, p29: 29, p30: 30, p31: 31, p32: 32, p33: 33,
+    p34: 34, p35: 35, p36: 36, p37: 37, p38: 38, p39: 39, p40: 40, p41: 41,
+    p42: 42, p43: 43, p44: 44, p45: 45, p46: 46, p47: 47, p48: 48, p49: 49,
+    p50: 50, p51: 51, p52: 52, p53: 53, p54: 54, p55: 55, p56: 56, p57: 57,
+}
+
+impl Gpio {
+    /// Take the GPIO pins. Returns `None` if called more than once.
+    ///
+    /// This assumes that the GPIO register block is identity-mapped, as is
+    /// the case with SOLID for Raspberry Pi 4.
+    pub fn take() -> Option<Self> {
+        if TAKEN.swap(true, Ordering::Relaxed) {
+            return None;
+        }
+        // Safety: The register block is identity-mapped, and `TAKEN` ensures
+        // that this is the only `Gpio` created by this method
+        Some(unsafe { Self::new(identity_mapped(gpio::BASE)) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use bcm2711_pac::sim::{self, Access};
+
+    const GPFSEL1: usize = 0x04;
+    const GPSET0: usize = 0x1c;
+
+    #[test]
+    fn output() {
+        let device = sim::gpio::Gpio::new();
+        let gpio = unsafe { Gpio::new(device.regs()) };
+
+        let mut pin = gpio.p17.into_output();
+        assert_eq!(device.regs().function(17), Function::Output);
+        assert!(pin.is_low());
+        pin.set_high();
+        assert!(device.level(17));
+        pin.toggle();
+        assert!(!device.level(17));
+        pin.set_level(true);
+        assert!(pin.is_high());
+        pin.set_low();
+        assert!(pin.is_low());
+
+        // The level is set before the function
+        device.take_trace();
+        let _pin = gpio.p18.into_output_high();
+        let trace = device.take_trace();
+        assert_eq!(
+            trace[0],
+            Access::Write {
+                offset: GPSET0,
+                value: 1 << 18
+            }
+        );
+        assert!(matches!(
+            trace.last(),
+            Some(Access::Write {
+                offset: GPFSEL1,
+                ..
+            })
+        ));
+        assert!(device.level(18));
+    }
+
+    #[test]
+    fn input() {
+        let device = sim::gpio::Gpio::new();
+        let gpio = unsafe { Gpio::new(device.regs()) };
+
+        let pin = gpio.p40.into_input().with_bias(Bias::PullDown);
+        assert_eq!(pin.number(), 40);
+        assert_eq!(pin.bias(), Some(Bias::PullDown));
+        assert_eq!(device.regs().function(40), Function::Input);
+        assert!(pin.is_low());
+        device.set_input(40, true);
+        assert!(pin.is_high());
+
+        // Reconfiguring a pin keeps the others in the same register
+        let out = gpio.p41.into_output();
+        let _pin = pin.into_output().into_input();
+        assert_eq!(device.regs().function(41), Function::Output);
+        assert!(out.is_low());
+    }
+
+    #[test]
+    fn alt() {
+        let device = sim::gpio::Gpio::new();
+        let gpio = unsafe { Gpio::new(device.regs()) };
+
+        let txd = gpio.p14.into_alt::<0>();
+        assert_eq!(device.regs().function(14), Function::Alt0);
+        assert_eq!(txd.signal(), Some(Signal::Uart0Txd));
+        let rxd = gpio.p15.into_alt::<4>();
+        assert_eq!(device.regs().function(15), Function::Alt4);
+        assert_eq!(
+            std::format!("{:?}", rxd),
+            "Pin<15, bcm2711_hal::gpio::Alt<4>>"
+        );
+    }
+
+    #[test]
+    fn take() {
+        assert!(Gpio::take().is_some());
+        assert!(Gpio::take().is_none());
+    }
+}
@@ -0,0 +1,540 @@
+//! GPIO event interrupts
+//!
+//! [`Dispatcher`] services the GPIO interrupt lines ([`irq::GPIO`]`[0..3]`,
+//! one for each [bank](BANK_PINS)). It reads and clears `gpeds` and calls the
+//! handlers registered for the pins by [`Dispatcher::listen`]. Each [`Event`]
+//! carries a [`TickCount`] timestamp taken when the interrupt was serviced.
+//!
+//! With the `solid` feature, `Dispatcher::register` registers a
+//! `solid::interrupt::Handler` for each bank:
+//!
+//! ```rust,ignore
+//! use bcm2711_hal::{
+//!     gpio::{event::{Dispatcher, Event, TickCount, Trigger}, Bias, Gpio},
+//!     identity_mapped,
+//! };
+//!
+//! static DISPATCHER: Dispatcher =
+//!     unsafe { Dispatcher::new(identity_mapped(bcm2711_pac::gpio::BASE), TickCount::now) };
+//!
+//! DISPATCHER.register(10).unwrap();
+//!
+//! let gpio = Gpio::take().unwrap();
+//! let mut button = gpio.p4.into_input().with_bias(Bias::PullUp);
+//! DISPATCHER.listen(&mut button, Trigger::Falling, &|event: Event| {
+//!     println!("pressed at {:?}", event.timestamp);
+//! });
+//! ```
+//!
+//! Level-triggered events ([`Trigger::High`], [`Trigger::Low`]) would be
+//! raised continuously while the level persists, so the detection is disabled
+//! when one is dispatched. Call [`Dispatcher::rearm`] to enable it again.
+//!
+//! [`irq::GPIO`]: bcm2711_pac::irq::GPIO
+use bcm2711_pac::gpio::{Registers, GPAFEN, GPAREN, GPFEN, GPHEN, GPLEN, GPREN};
+use core::{cell::UnsafeCell, fmt, ops::Range};
+use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
+
+use super::{Pin, NUM_PINS};
+use crate::sync::with_lock;
+
+#[cfg(feature = "solid")]
+pub use solid::timer::TickCount;
+
+/// A SOLID-OS tick count. This is a stand-in for `solid::timer::TickCount`
+/// used without the `solid` feature (e.g., in host-side tests).
+#[cfg(not(feature = "solid"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TickCount(pub u64);
+
+/// The number of GPIO banks, each of which has an interrupt line
+pub const NUM_BANKS: usize = 3;
+
+/// The pins of each bank
+pub const BANK_PINS: [Range<usize>; NUM_BANKS] = [0..28, 28..46, 46..NUM_PINS];
+
+/// Get the bank containing the specified pin.
+///
+/// # Panic
+///
+/// Panics if `pin` is outside the range `0..`[`NUM_PINS`].
+#[inline]
+pub const fn bank(pin: usize) -> usize {
+    assert!(pin < NUM_PINS);
+    match pin {
+        0..=27 => 0,
+        28..=45 => 1,
+        _ => 2,
+    }
+}
+
+/// The condition raising an [`Event`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Trigger {
+    /// A rising edge, sampled by the system clock (`gpren`)
+    Rising,
+    /// A falling edge, sampled by the system clock (`gpfen`)
+    Falling,
+    /// Both edges, sampled by the system clock
+    Both,
+    /// A high level (`gphen`). One-shot; see [`Dispatcher::rearm`].
+    High,
+    /// A low level (`gplen`). One-shot; see [`Dispatcher::rearm`].
+    Low,
+    /// A rising edge, detected asynchronously (`gparen`). Catches pulses
+    /// shorter than a system clock cycle.
+    AsyncRising,
+    /// A falling edge, detected asynchronously (`gpafen`)
+    AsyncFalling,
+    /// Both edges, detected asynchronously
+    AsyncBoth,
+}
+
+impl Trigger {
+    /// Check if this is [`Self::High`] or [`Self::Low`].
+    #[inline]
+    pub const fn is_level(self) -> bool {
+        matches!(self, Self::High | Self::Low)
+    }
+
+    /// Get the detection enable bits (`gpren`, `gpfen`, `gphen`, `gplen`,
+    /// `gparen`, `gpafen`) for this trigger.
+    #[inline]
+    const fn enables(self) -> [bool; 6] {
+        match self {
+            Self::Rising => [true, false, false, false, false, false],
+            Self::Falling => [false, true, false, false, false, false],
+            Self::Both => [true, true, false, false, false, false],
+            Self::High => [false, false, true, false, false, false],
+            Self::Low => [false, false, false, true, false, false],
+            Self::AsyncRising => [false, false, false, false, true, false],
+            Self::AsyncFalling => [false, false, false, false, false, true],
+            Self::AsyncBoth => [false, false, false, false, true, true],
+        }
+    }
+}
+
+/// A GPIO event passed to a handler
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Event {
+    /// The pin number
+    pub pin: usize,
+    /// The level of the pin when the event was serviced
+    pub level: bool,
+    /// The time the interrupt was serviced
+    pub timestamp: TickCount,
+}
+
+/// A handler called for each [`Event`] of a pin
+pub type EventHandler = &'static (dyn Fn(Event) + Sync);
+
+#[derive(Clone, Copy)]
+struct Slot {
+    trigger: Trigger,
+    handler: EventHandler,
+}
+
+/// Dispatches GPIO events to per-pin handlers
+pub struct Dispatcher {
+    regs: *const Registers,
+    clock: fn() -> TickCount,
+    /// Protected by `with_lock`
+    slots: UnsafeCell<[Option<Slot>; NUM_PINS]>,
+    #[cfg(feature = "solid")]
+    handlers: [UnsafeCell<solid::interrupt::Handler<BankHandler>>; NUM_BANKS],
+    #[cfg(feature = "solid")]
+    registered: core::sync::atomic::AtomicBool,
+}
+
+// Safety: `slots` is only accessed with the lock held. `handlers` is only
+// accessed by `register` while it owns `registered`. The rest are register
+// accesses (see `Pin`).
+unsafe impl Send for Dispatcher {}
+unsafe impl Sync for Dispatcher {}
+
+impl Dispatcher {
+    /// Construct a `Dispatcher`. `clock` provides the timestamps of events
+    /// (e.g., `TickCount::now`).
+    ///
+    /// # Safety
+    ///
+    /// `regs` must point to [the GPIO register block](Registers) and remain
+    /// valid for the lifetime of the constructed `Dispatcher`. The event
+    /// detection registers (`gpeds`, `gpren`, etc.) must not be modified by
+    /// other means.
+    #[inline]
+    pub const unsafe fn new(regs: *const Registers, clock: fn() -> TickCount) -> Self {
+        Self {
+            regs,
+            clock,
+            slots: UnsafeCell::new([None; NUM_PINS]),
+            #[cfg(feature = "solid")]
+            handlers: [
+                UnsafeCell::new(solid::interrupt::Handler::new(BankHandler::UNSET)),
+                UnsafeCell::new(solid::interrupt::Handler::new(BankHandler::UNSET)),
+                UnsafeCell::new(solid::interrupt::Handler::new(BankHandler::UNSET)),
+            ],
+            #[cfg(feature = "solid")]
+            registered: core::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    #[inline]
+    fn regs(&self) -> &Registers {
+        // Safety: Upheld by the caller of `Self::new`
+        unsafe { &*self.regs }
+    }
+
+    /// Call `handler` when `trigger` occurs on `pin`, replacing the previous
+    /// handler of the pin. Pending events of the pin are discarded.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `pin` belongs to another register block.
+    pub fn listen<const N: usize, M>(
+        &self,
+        pin: &mut Pin<N, M>,
+        trigger: Trigger,
+        handler: EventHandler,
+    ) {
+        assert_eq!(
+            pin.regs, self.regs,
+            "the pin belongs to another register block"
+        );
+        with_lock(|| {
+            self.set_enables(N, [false; 6]);
+            self.regs().gpeds[N / 32].set(1 << (N % 32));
+            // Safety: We are holding the lock
+            unsafe { (*self.slots.get())[N] = Some(Slot { trigger, handler }) };
+            self.set_enables(N, trigger.enables());
+        });
+    }
+
+    /// Stop listening to the events of `pin`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `pin` belongs to another register block.
+    pub fn unlisten<const N: usize, M>(&self, pin: &mut Pin<N, M>) {
+        assert_eq!(
+            pin.regs, self.regs,
+            "the pin belongs to another register block"
+        );
+        with_lock(|| {
+            self.set_enables(N, [false; 6]);
+            self.regs().gpeds[N / 32].set(1 << (N % 32));
+            // Safety: We are holding the lock
+            unsafe { (*self.slots.get())[N] = None };
+        });
+    }
+
+    /// Enable the detection of a level-triggered event after it was
+    /// dispatched. Does nothing if no handler is registered for `pin`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `pin` is outside the range `0..`[`NUM_PINS`].
+    pub fn rearm(&self, pin: usize) {
+        assert!(pin < NUM_PINS);
+        with_lock(|| {
+            // Safety: We are holding the lock
+            if let Some(slot) = unsafe { (*self.slots.get())[pin] } {
+                self.set_enables(pin, slot.trigger.enables());
+            }
+        });
+    }
+
+    /// Set the detection enable bits of `pin`. Must be called with the lock
+    /// held.
+    fn set_enables(&self, pin: usize, enables: [bool; 6]) {
+        let regs = self.regs();
+        let (i, bit) = (pin / 32, pin % 32);
+        regs.gpren[i].modify(GPREN::pin(bit).val(enables[0] as u32));
+        regs.gpfen[i].modify(GPFEN::pin(bit).val(enables[1] as u32));
+        regs.gphen[i].modify(GPHEN::pin(bit).val(enables[2] as u32));
+        regs.gplen[i].modify(GPLEN::pin(bit).val(enables[3] as u32));
+        regs.gparen[i].modify(GPAREN::pin(bit).val(enables[4] as u32));
+        regs.gpafen[i].modify(GPAFEN::pin(bit).val(enables[5] as u32));
+    }
+
+    /// Service the interrupt of `bank`: clear the pending events of its pins
+    /// and call their handlers in the ascending order of pin numbers.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `bank` is outside the range `0..`[`NUM_BANKS`].
+    pub fn service(&self, bank: usize) {
+        let timestamp = (self.clock)();
+        let regs = self.regs();
+        let pins = BANK_PINS[bank].clone();
+
+        for i in pins.start / 32..=(pins.end - 1) / 32 {
+            // The bits of `gpeds[i]` in this bank
+            let first = pins.start.max(i * 32) - i * 32;
+            let end = pins.end.min(i * 32 + 32) - i * 32;
+            let mask = (u32::MAX >> (32 - (end - first))) << first;
+
+            let status = regs.gpeds[i].get() & mask;
+            if status == 0 {
+                continue;
+            }
+
+            let mut handlers: [Option<EventHandler>; 32] = [None; 32];
+            with_lock(|| {
+                for (bit, handler) in handlers.iter_mut().enumerate() {
+                    if status & (1 << bit) == 0 {
+                        continue;
+                    }
+                    // Safety: We are holding the lock
+                    if let Some(slot) = unsafe { (*self.slots.get())[i * 32 + bit] } {
+                        if slot.trigger.is_level() {
+                            // Disable it first, or it would be set again
+                            self.set_enables(i * 32 + bit, [false; 6]);
+                        }
+                        *handler = Some(slot.handler);
+                    }
+                }
+            });
+            regs.gpeds[i].set(status);
+
+            let level = regs.gplev[i].get();
+            for (bit, handler) in handlers.iter().enumerate() {
+                if let Some(handler) = handler {
+                    handler(Event {
+                        pin: i * 32 + bit,
+                        level: level & (1 << bit) != 0,
+                        timestamp,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Register and enable the interrupt handlers of all banks with the
+    /// specified priority. This can be retried if it fails.
+    #[cfg(feature = "solid")]
+    pub fn register(&'static self, priority: i32) -> Result<(), RegisterError> {
+        use bcm2711_pac::irq;
+        use core::sync::atomic::Ordering;
+
+        if self.registered.swap(true, Ordering::Acquire) {
+            return Err(RegisterError::AlreadyRegistered);
+        }
+
+        let result = (|| {
+            for (bank, handler) in self.handlers.iter().enumerate() {
+                let handler = handler.get();
+                // Safety: `registered` ensures that we have exclusive access
+                // to `handler`. The overwritten value, which is unregistered
+                // and doesn't own resources, doesn't need to be dropped. A
+                // handler attached by a previous failed call is left in place.
+                // `self` is `'static`, so `handler` is pinned.
+                let handler = unsafe {
+                    if !(*handler).is_registered() {
+                        handler.write(solid::interrupt::Handler::new(BankHandler {
+                            dispatcher: self,
+                            bank,
+                        }));
+                    }
+                    core::pin::Pin::new_unchecked(&mut *handler)
+                };
+                handler
+                    .register_static(&irq::GPIO[bank].handler_options(priority))
+                    .map_err(RegisterError::Register)?;
+                irq::GPIO[bank]
+                    .number()
+                    .enable()
+                    .map_err(RegisterError::Enable)?;
+            }
+            Ok(())
+        })();
+
+        // Allow retrying if any of the handlers couldn't be attached
+        if result.is_err() {
+            self.registered.store(false, Ordering::Release);
+        }
+        result
+    }
+}
+
+impl fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dispatcher")
+            .field("regs", &self.regs)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The error type for [`Dispatcher::register`]
+#[cfg(feature = "solid")]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum RegisterError {
+    /// [`Dispatcher::register`] already succeeded or is in progress.
+    AlreadyRegistered,
+    /// Registering an interrupt handler failed.
+    Register(solid::interrupt::RegisterError),
+    /// Enabling an interrupt line failed.
+    Enable(solid::interrupt::EnableError),
+}
+
+/// The interrupt handler of a bank
+#[cfg(feature = "solid")]
+struct BankHandler {
+    dispatcher: *const Dispatcher,
+    bank: usize,
+}
+
+#[cfg(feature = "solid")]
+impl BankHandler {
+    /// The placeholder value before [`Dispatcher::register`]
+    const UNSET: Self = Self {
+        dispatcher: core::ptr::null(),
+        bank: 0,
+    };
+}
+
+// Safety: `Dispatcher` is `Sync`
+#[cfg(feature = "solid")]
+unsafe impl Send for BankHandler {}
+
+#[cfg(feature = "solid")]
+impl<'a> solid::closure::FuncMut<(solid::thread::CpuCx<'a>,)> for BankHandler {
+    type Output = ();
+
+    #[inline]
+    fn call(&mut self, _: (solid::thread::CpuCx<'a>,)) {
+        // Safety: Set to a `&'static Dispatcher` before the registration
+        unsafe { &*self.dispatcher }.service(self.bank);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use crate::gpio::Gpio;
+    use bcm2711_pac::sim;
+    use core::sync::atomic::{AtomicU64, Ordering};
+    use std::{sync::Mutex, vec::Vec};
+
+    static NOW: AtomicU64 = AtomicU64::new(0);
+
+    fn clock() -> TickCount {
+        TickCount(NOW.load(Ordering::Relaxed))
+    }
+
+    #[test]
+    fn bank() {
+        assert_eq!(super::bank(0), 0);
+        assert_eq!(super::bank(27), 0);
+        assert_eq!(super::bank(28), 1);
+        assert_eq!(super::bank(45), 1);
+        assert_eq!(super::bank(46), 2);
+        assert_eq!(super::bank(57), 2);
+        for (bank, pins) in BANK_PINS.iter().enumerate() {
+            assert!(pins.clone().all(|pin| super::bank(pin) == bank));
+        }
+    }
+
+    #[test]
+    fn edges() {
+        static EVENTS: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+        let push = &|event| EVENTS.lock().unwrap().push(event);
+
+        let device = sim::gpio::Gpio::new();
+        let gpio = unsafe { Gpio::new(device.regs()) };
+        let dispatcher = unsafe { Dispatcher::new(device.regs(), clock) };
+        let mut p5 = gpio.p5.into_input();
+        let mut p30 = gpio.p30.into_input();
+        let mut p33 = gpio.p33.into_input();
+        dispatcher.listen(&mut p5, Trigger::Rising, push);
+        dispatcher.listen(&mut p30, Trigger::Both, push);
+        dispatcher.listen(&mut p33, Trigger::AsyncFalling, push);
+
+        NOW.store(100, Ordering::Relaxed);
+        device.set_input(5, true);
+        device.set_input(30, true);
+        device.set_input(33, true);
+        device.set_input(33, false);
+
+        // Bank 0 doesn't include pin 30
+        dispatcher.service(0);
+        assert_eq!(
+            *EVENTS.lock().unwrap(),
+            [Event {
+                pin: 5,
+                level: true,
+                timestamp: TickCount(100)
+            }]
+        );
+        assert_eq!(device.regs().gpeds[0].get(), 1 << 30);
+
+        dispatcher.service(1);
+        assert_eq!(device.regs().gpeds[0].get(), 0);
+        assert_eq!(device.regs().gpeds[1].get(), 0);
+        let pins: Vec<_> = EVENTS.lock().unwrap().iter().map(|e| e.pin).collect();
+        assert_eq!(pins, [5, 30, 33]);
+
+        // Falling edges
+        EVENTS.lock().unwrap().clear();
+        device.set_input(5, false);
+        device.set_input(30, false);
+        dispatcher.service(0);
+        dispatcher.service(1);
+        assert_eq!(
+            *EVENTS.lock().unwrap(),
+            [Event {
+                pin: 30,
+                level: false,
+                timestamp: TickCount(100)
+            }]
+        );
+
+        // No more events after `unlisten`
+        EVENTS.lock().unwrap().clear();
+        dispatcher.unlisten(&mut p30);
+        device.set_input(30, true);
+        dispatcher.service(1);
+        assert!(EVENTS.lock().unwrap().is_empty());
+        assert_eq!(device.regs().gpren[0].get(), 1 << 5);
+    }
+
+    #[test]
+    fn level() {
+        static COUNT: AtomicU64 = AtomicU64::new(0);
+
+        let device = sim::gpio::Gpio::new();
+        let gpio = unsafe { Gpio::new(device.regs()) };
+        let dispatcher = unsafe { Dispatcher::new(device.regs(), clock) };
+        let mut pin = gpio.p50.into_input();
+        dispatcher.listen(&mut pin, Trigger::High, &|event| {
+            assert_eq!(event.pin, 50);
+            COUNT.fetch_add(1, Ordering::Relaxed);
+        });
+
+        device.set_input(50, true);
+        dispatcher.service(2);
+        dispatcher.service(2);
+        assert_eq!(COUNT.load(Ordering::Relaxed), 1);
+
+        // Still high
+        dispatcher.rearm(50);
+        dispatcher.service(2);
+        assert_eq!(COUNT.load(Ordering::Relaxed), 2);
+
+        device.set_input(50, false);
+        dispatcher.rearm(50);
+        dispatcher.service(2);
+        assert_eq!(COUNT.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "the pin belongs to another register block")]
+    fn foreign_pin() {
+        let device1 = sim::gpio::Gpio::new();
+        let device2 = sim::gpio::Gpio::new();
+        let gpio = unsafe { Gpio::new(device1.regs()) };
+        let dispatcher = unsafe { Dispatcher::new(device2.regs(), clock) };
+        dispatcher.listen(&mut gpio.p0.into_input(), Trigger::Rising, &|_| {});
+    }
+}
@@ -0,0 +1,39 @@
+#![doc = include_str!("../README.md")]
+#![no_std]
+use bcm2711_pac::{ram_to_legacy_dma, Vpa};
+
+mod buffered;
+mod cache;
+mod ring;
+mod sync;
+
+// `aux.rs` breaks some tools on Windows
+// https://msdn.microsoft.com/en-us/library/aa365247(v=vs.85).aspx#file_and_directory_names
+#[path = "aux_.rs"]
+pub mod aux;
+pub mod bsc;
+pub mod gpio;
+pub mod pl011;
+pub mod spi;
+
+/// Get a pointer to the register block `T` located at `base`, assuming the
+/// identity mapping provided by SOLID for Raspberry Pi 4.
+///
+/// # Panic
+///
+/// Panics if `base` isn't in the low-peripheral address range.
+#[inline]
+pub const fn identity_mapped<T>(base: Vpa) -> *const T {
+    match base.to_arm_pa() {
+        Some(arm_pa) => arm_pa as usize as *const T,
+        None => panic!("not a low-peripheral address"),
+    }
+}
+
+/// Get the address of `ptr` as seen by the legacy DMA engines (DMA0–10),
+/// assuming the identity mapping provided by SOLID for Raspberry Pi 4.
+/// Returns `None` if `ptr` is outside the first 1 GiB of SDRAM.
+#[inline]
+pub fn identity_mapped_to_legacy_dma(ptr: *const u8) -> Option<u32> {
+    ram_to_legacy_dma(ptr as usize as u64)
+}
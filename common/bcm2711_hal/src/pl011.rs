@@ -0,0 +1,612 @@
+//! Interrupt-driven PL011 UART driver (UART0, UART2–5)
+//!
+//! [`Uart`] buffers the received and transmitted characters in ring buffers.
+//! [`Uart::service`] moves them between the ring buffers and the hardware
+//! FIFOs and must be called from the handler of [`irq::PL011`], which is
+//! shared by all PL011 instances. With the `solid` feature, `&'static Uart`
+//! can be used as the handler of a `solid::interrupt::Handler`:
+//!
+//! ```rust,ignore
+//! use bcm2711_hal::{identity_mapped, pl011::{Config, Uart}};
+//! use bcm2711_pac::{irq, pl011};
+//! use solid::{interrupt, singleton::pin_singleton};
+//!
+//! static UART0: Uart = unsafe { Uart::new(identity_mapped(pl011::BASE_UART0)) };
+//!
+//! UART0.init(&Config::default()).unwrap();
+//!
+//! let handler = pin_singleton!(: Handler<_> = interrupt::Handler::new(&UART0)).unwrap();
+//! handler.register_static(&irq::PL011.handler_options(10)).unwrap();
+//! irq::PL011.number().enable().unwrap();
+//!
+//! UART0.write(b"hello\r\n");
+//! ```
+//!
+//! To use several instances, register a closure servicing all of them (e.g.,
+//! `|_: CpuCx<'_>| { UART0.service(); UART2.service(); }`) instead.
+//!
+//! The TXD/RXD (and, with [`FlowControl::RtsCts`], CTS/RTS) pins must be
+//! switched to the appropriate alternate functions separately (e.g., GPIO14
+//! and GPIO15 to `ALT0` for UART0).
+//!
+//! [`irq::PL011`]: bcm2711_pac::irq::PL011
+use bcm2711_pac::pl011::{Registers, CR, DR, FBRD, FR, IBRD, ICR, IFLS, IMSC, LCRH};
+use core::{
+    fmt,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
+
+use crate::buffered::{Buffered, Fifos};
+
+/// The UART reference clock frequency configured by the Raspberry Pi 4
+/// firmware. The actual value can be queried through the mailbox property
+/// interface (clock ID `UART`).
+pub const DEFAULT_CLOCK_HZ: u32 = 48_000_000;
+
+/// The number of data bits in a character
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum DataBits {
+    /// 5 bits
+    Five,
+    /// 6 bits
+    Six,
+    /// 7 bits
+    Seven,
+    /// 8 bits
+    Eight,
+}
+
+/// The parity bit of a character
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Parity {
+    /// No parity bit
+    None,
+    /// Even parity
+    Even,
+    /// Odd parity
+    Odd,
+    /// The parity bit is always 1 (stick parity).
+    Mark,
+    /// The parity bit is always 0 (stick parity).
+    Space,
+}
+
+/// The number of stop bits in a character
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum StopBits {
+    /// 1 bit
+    One,
+    /// 2 bits
+    Two,
+}
+
+/// The hardware flow control mode
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum FlowControl {
+    /// No flow control
+    None,
+    /// RTS is deasserted while the receive FIFO is full, and transmission is
+    /// suspended while CTS is deasserted.
+    RtsCts,
+}
+
+/// A FIFO level triggering an interrupt
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum FifoLevel {
+    /// 1/8 full (4 characters)
+    OneEighth,
+    /// 1/4 full (8 characters)
+    OneQuarter,
+    /// 1/2 full (16 characters)
+    OneHalf,
+    /// 3/4 full (24 characters)
+    ThreeQuarters,
+    /// 7/8 full (28 characters)
+    SevenEighths,
+}
+
+/// The configuration for [`Uart::init`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Config {
+    /// The UART reference clock frequency (`UARTCLK`)
+    pub clock_hz: u32,
+    /// The baud rate
+    pub baud_rate: u32,
+    /// The number of data bits
+    pub data_bits: DataBits,
+    /// The parity bit
+    pub parity: Parity,
+    /// The number of stop bits
+    pub stop_bits: StopBits,
+    /// The hardware flow control mode
+    pub flow_control: FlowControl,
+    /// The receive FIFO level raising an interrupt. A partially filled FIFO
+    /// is also serviced after a receive timeout.
+    pub rx_level: FifoLevel,
+    /// The transmit FIFO level raising an interrupt
+    pub tx_level: FifoLevel,
+}
+
+impl Default for Config {
+    /// 115200 baud, 8 data bits, no parity, 1 stop bit, and no flow control
+    #[inline]
+    fn default() -> Self {
+        Self {
+            clock_hz: DEFAULT_CLOCK_HZ,
+            baud_rate: 115_200,
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+            rx_level: FifoLevel::OneHalf,
+            tx_level: FifoLevel::OneEighth,
+        }
+    }
+}
+
+/// The error type for [`Uart::init`]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum ConfigError {
+    /// The baud rate can't be generated from the reference clock.
+    UnsupportedBaudRate,
+}
+
+/// A receive error reported by [`Uart::read`] and [`Uart::try_read`]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Error {
+    /// A character was lost because the receive FIFO was full.
+    Overrun,
+    /// The input was held low for longer than a character (a break
+    /// condition).
+    Break,
+    /// A character didn't have a valid stop bit. The character was
+    /// discarded.
+    Framing,
+    /// A character had a wrong parity bit. The character was discarded.
+    Parity,
+}
+
+/// The error bits of `dr`, shifted to bit 0
+const ERROR_FE: u32 = 1 << 0;
+const ERROR_PE: u32 = 1 << 1;
+const ERROR_BE: u32 = 1 << 2;
+const ERROR_OE: u32 = 1 << 3;
+
+/// Calculate the integer and fractional baud rate divisors (`ibrd`, `fbrd`)
+/// for the specified reference clock frequency and baud rate.
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_hal::pl011::baud_divisor;
+/// assert_eq!(baud_divisor(48_000_000, 115_200), Ok((26, 3)));
+/// assert!(baud_divisor(48_000_000, 4_000_000).is_err());
+/// ```
+pub const fn baud_divisor(clock_hz: u32, baud_rate: u32) -> Result<(u16, u8), ConfigError> {
+    if baud_rate == 0 {
+        return Err(ConfigError::UnsupportedBaudRate);
+    }
+    // The divisor is `clock_hz / (16 * baud_rate)` in units of 1/64
+    let div = (clock_hz as u64 * 4 + baud_rate as u64 / 2) / baud_rate as u64;
+    let (integer, fraction) = (div >> 6, div & 63);
+    if integer == 0 || integer > 0xffff || (integer == 0xffff && fraction != 0) {
+        return Err(ConfigError::UnsupportedBaudRate);
+    }
+    Ok((integer as u16, fraction as u8))
+}
+
+/// An interrupt-driven PL011 UART with an `RX`-byte receive buffer and a
+/// `TX`-byte transmit buffer
+///
+/// All methods take `&self` so that a `Uart` can be placed in a `static` and
+/// shared between the interrupt handler and tasks.
+pub struct Uart<const RX: usize = 256, const TX: usize = 256> {
+    regs: *const Registers,
+    buffered: Buffered<RX, TX>,
+    /// The pending receive errors (`ERROR_*`)
+    errors: AtomicU32,
+}
+
+// Safety: `buffered` accesses its buffers with the lock held, and the
+// read-modify-write operations on `imsc` are also done with the lock held.
+unsafe impl<const RX: usize, const TX: usize> Send for Uart<RX, TX> {}
+unsafe impl<const RX: usize, const TX: usize> Sync for Uart<RX, TX> {}
+
+impl<const RX: usize, const TX: usize> Uart<RX, TX> {
+    /// Construct a `Uart`. The UART is left untouched until [`Self::init`]
+    /// is called.
+    ///
+    /// # Safety
+    ///
+    /// `regs` must point to [a PL011 UART register block](Registers) and
+    /// remain valid for the lifetime of the constructed `Uart`. The register
+    /// block must not be accessed by other means.
+    #[inline]
+    pub const unsafe fn new(regs: *const Registers) -> Self {
+        Self {
+            regs,
+            buffered: Buffered::new(),
+            errors: AtomicU32::new(0),
+        }
+    }
+
+    #[inline]
+    fn regs(&self) -> &Registers {
+        // Safety: Upheld by the caller of `Self::new`
+        unsafe { &*self.regs }
+    }
+
+    /// Reset and configure the UART, discarding the buffered characters
+    /// and the pending errors, and enable the receive interrupts.
+    pub fn init(&self, config: &Config) -> Result<(), ConfigError> {
+        let (ibrd, fbrd) = baud_divisor(config.clock_hz, config.baud_rate)?;
+        let regs = self.regs();
+
+        // Disable the UART after the current character
+        regs.imsc.set(0);
+        regs.cr.set(0);
+        while regs.fr.is_set(FR::BUSY) {
+            core::hint::spin_loop();
+        }
+        // Flush the FIFOs
+        regs.lcrh.set(0);
+
+        self.buffered.clear();
+        self.errors.store(0, Ordering::Relaxed);
+
+        regs.ibrd.write(IBRD::IBRD.val(ibrd.into()));
+        regs.fbrd.write(FBRD::FBRD.val(fbrd.into()));
+        // Writing `lcrh` also latches `ibrd` and `fbrd`
+        let data_bits = match config.data_bits {
+            DataBits::Five => LCRH::WLEN::FiveBits,
+            DataBits::Six => LCRH::WLEN::SixBits,
+            DataBits::Seven => LCRH::WLEN::SevenBits,
+            DataBits::Eight => LCRH::WLEN::EightBits,
+        };
+        let parity = match config.parity {
+            Parity::None => LCRH::PEN::CLEAR,
+            Parity::Even => LCRH::PEN::SET + LCRH::EPS::Even,
+            Parity::Odd => LCRH::PEN::SET + LCRH::EPS::Odd,
+            Parity::Mark => LCRH::PEN::SET + LCRH::SPS::SET + LCRH::EPS::Odd,
+            Parity::Space => LCRH::PEN::SET + LCRH::SPS::SET + LCRH::EPS::Even,
+        };
+        let stop_bits = match config.stop_bits {
+            StopBits::One => LCRH::STP2::CLEAR,
+            StopBits::Two => LCRH::STP2::SET,
+        };
+        regs.lcrh
+            .write(LCRH::FEN::SET + data_bits + parity + stop_bits);
+        regs.ifls.write(
+            IFLS::RXIFLSEL.val(config.rx_level as u32) + IFLS::TXIFLSEL.val(config.tx_level as u32),
+        );
+
+        regs.icr.write(
+            ICR::RXIC::SET
+                + ICR::TXIC::SET
+                + ICR::RTIC::SET
+                + ICR::FEIC::SET
+                + ICR::PEIC::SET
+                + ICR::BEIC::SET
+                + ICR::OEIC::SET,
+        );
+        regs.imsc.write(
+            IMSC::RXIM::SET
+                + IMSC::RTIM::SET
+                + IMSC::FEIM::SET
+                + IMSC::PEIM::SET
+                + IMSC::BEIM::SET
+                + IMSC::OEIM::SET,
+        );
+
+        let flow_control = match config.flow_control {
+            FlowControl::None => CR::RTSEN::CLEAR + CR::CTSEN::CLEAR,
+            FlowControl::RtsCts => CR::RTSEN::SET + CR::CTSEN::SET,
+        };
+        regs.cr
+            .write(CR::UARTEN::SET + CR::TXE::SET + CR::RXE::SET + flow_control);
+        Ok(())
+    }
+
+    /// Service the interrupt: move the received characters to the receive
+    /// buffer and the buffered characters to the transmit FIFO.
+    ///
+    /// When the receive buffer is full, the remaining characters are left in
+    /// the receive FIFO (which deasserts RTS with [`FlowControl::RtsCts`])
+    /// until [`Self::read`] makes room.
+    pub fn service(&self) {
+        // Clear the interrupts first so that ones raised while servicing
+        // aren't lost
+        self.regs().icr.write(
+            ICR::RXIC::SET
+                + ICR::TXIC::SET
+                + ICR::RTIC::SET
+                + ICR::FEIC::SET
+                + ICR::PEIC::SET
+                + ICR::BEIC::SET
+                + ICR::OEIC::SET,
+        );
+        self.buffered.service(self);
+    }
+
+    /// Read the received characters into `buf` without blocking. Returns the
+    /// number of characters read, which is zero if none are buffered.
+    ///
+    /// A pending receive error is returned (and cleared) first. Errors are
+    /// reported in the order of [`Error::Overrun`], [`Error::Break`],
+    /// [`Error::Framing`], and [`Error::Parity`], not in the order of
+    /// occurrence.
+    #[inline]
+    pub fn try_read(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.buffered.try_read(self, buf)
+    }
+
+    /// Read the received characters into `buf`, busy-waiting until at least
+    /// one is available. See [`Self::try_read`].
+    #[inline]
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.buffered.read(self, buf)
+    }
+
+    /// Queue as many characters from `bytes` as possible for transmission
+    /// without blocking. Returns the number of queued characters, which is
+    /// zero if the transmit buffer is full.
+    #[inline]
+    pub fn try_write(&self, bytes: &[u8]) -> usize {
+        self.buffered.try_write(self, bytes)
+    }
+
+    /// Queue all of `bytes` for transmission, busy-waiting while the transmit
+    /// buffer is full.
+    #[inline]
+    pub fn write(&self, bytes: &[u8]) {
+        self.buffered.write(self, bytes);
+    }
+
+    /// Busy-wait until all queued characters are transmitted.
+    #[inline]
+    pub fn flush(&self) {
+        self.buffered.flush(self);
+    }
+}
+
+impl<const RX: usize, const TX: usize> Fifos for Uart<RX, TX> {
+    type Error = Error;
+
+    fn take_error(&self) -> Result<(), Error> {
+        let errors = self.errors.load(Ordering::Relaxed);
+        let (error, bits) = if errors == 0 {
+            return Ok(());
+        } else if errors & ERROR_OE != 0 {
+            (Error::Overrun, ERROR_OE)
+        } else if errors & ERROR_BE != 0 {
+            (Error::Break, ERROR_BE)
+        } else if errors & ERROR_FE != 0 {
+            (Error::Framing, ERROR_FE)
+        } else {
+            (Error::Parity, ERROR_PE)
+        };
+        self.errors.fetch_and(!bits, Ordering::Relaxed);
+        Err(error)
+    }
+
+    #[inline]
+    fn rx_ready(&self) -> bool {
+        !self.regs().fr.is_set(FR::RXFE)
+    }
+
+    fn pop_rx(&self) -> Option<u8> {
+        let dr = self.regs().dr.extract();
+        let mut error = dr.get() >> 8 & 0b1111;
+        // A break is also reported as a framing error by the hardware
+        if error & ERROR_BE != 0 {
+            error &= !ERROR_FE;
+        }
+        if error != 0 {
+            self.errors.fetch_or(error, Ordering::Relaxed);
+        }
+        // The data of an overrun character is valid
+        (error & !ERROR_OE == 0).then(|| dr.read(DR::DATA) as u8)
+    }
+
+    #[inline]
+    fn set_rx_interrupt(&self, enable: bool) {
+        let enable = enable as u32;
+        self.regs()
+            .imsc
+            .modify(IMSC::RXIM.val(enable) + IMSC::RTIM.val(enable));
+    }
+
+    #[inline]
+    fn tx_ready(&self) -> bool {
+        !self.regs().fr.is_set(FR::TXFF)
+    }
+
+    #[inline]
+    fn push_tx(&self, byte: u8) {
+        self.regs().dr.set(byte.into());
+    }
+
+    #[inline]
+    fn set_tx_interrupt(&self, enable: bool) {
+        self.regs().imsc.modify(IMSC::TXIM.val(enable as u32));
+    }
+
+    #[inline]
+    fn tx_done(&self) -> bool {
+        let regs = self.regs();
+        regs.fr.is_set(FR::TXFE) && !regs.fr.is_set(FR::BUSY)
+    }
+}
+
+impl<const RX: usize, const TX: usize> fmt::Debug for Uart<RX, TX> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Uart")
+            .field("regs", &self.regs)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "solid")]
+impl<'a, const RX: usize, const TX: usize> solid::closure::FuncMut<(solid::thread::CpuCx<'a>,)>
+    for &'static Uart<RX, TX>
+{
+    type Output = ();
+
+    #[inline]
+    fn call(&mut self, _: (solid::thread::CpuCx<'a>,)) {
+        self.service();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use bcm2711_pac::{pl011::RIS, sim};
+    use std::vec::Vec;
+
+    fn uart<const RX: usize, const TX: usize>(
+        device: &sim::pl011::Uart,
+        config: &Config,
+    ) -> Uart<RX, TX> {
+        let uart = unsafe { Uart::new(device.regs()) };
+        uart.init(config).unwrap();
+        uart
+    }
+
+    #[test]
+    fn divisor() {
+        assert_eq!(baud_divisor(48_000_000, 9_600), Ok((312, 32)));
+        assert_eq!(baud_divisor(48_000_000, 3_000_000), Ok((1, 0)));
+        assert!(baud_divisor(48_000_000, 3_000_001).is_ok());
+        assert!(baud_divisor(48_000_000, 3_100_000).is_err());
+        assert!(baud_divisor(48_000_000, 0).is_err());
+        assert!(baud_divisor(48_000_000, 45).is_err());
+    }
+
+    #[test]
+    fn init() {
+        let device = sim::pl011::Uart::new();
+        let _uart = uart::<16, 16>(
+            &device,
+            &Config {
+                baud_rate: 9_600,
+                data_bits: DataBits::Seven,
+                parity: Parity::Mark,
+                stop_bits: StopBits::Two,
+                flow_control: FlowControl::RtsCts,
+                ..Config::default()
+            },
+        );
+        let regs = device.regs();
+        assert_eq!(regs.ibrd.get(), 312);
+        assert_eq!(regs.fbrd.get(), 32);
+        assert!(regs.lcrh.matches_all(
+            LCRH::FEN::SET
+                + LCRH::WLEN::SevenBits
+                + LCRH::PEN::SET
+                + LCRH::SPS::SET
+                + LCRH::EPS::Odd
+                + LCRH::STP2::SET
+        ));
+        assert!(regs
+            .ifls
+            .matches_all(IFLS::RXIFLSEL::OneHalf + IFLS::TXIFLSEL::OneEighth));
+        assert!(regs.cr.matches_all(
+            CR::UARTEN::SET + CR::TXE::SET + CR::RXE::SET + CR::RTSEN::SET + CR::CTSEN::SET
+        ));
+        assert!(regs
+            .imsc
+            .matches_all(IMSC::RXIM::SET + IMSC::RTIM::SET + IMSC::TXIM::CLEAR));
+    }
+
+    #[test]
+    fn rx() {
+        let device = sim::pl011::Uart::new();
+        let uart = uart::<8, 8>(&device, &Config::default());
+        let mut buf = [0; 16];
+        assert_eq!(uart.try_read(&mut buf), Ok(0));
+
+        device.push_rx(b"hello, world");
+        assert!(device.regs().mis.is_set(bcm2711_pac::pl011::MIS::RTMIS));
+        uart.service();
+        // The buffer is full; the rest is left in the FIFO
+        assert_eq!(device.rx_len(), 4);
+        assert!(!device.regs().imsc.is_set(IMSC::RXIM));
+        assert_eq!(uart.read(&mut buf[..5]), Ok(5));
+        assert_eq!(&buf[..5], b"hello");
+        assert!(device.regs().imsc.is_set(IMSC::RXIM));
+
+        uart.service();
+        assert_eq!(device.rx_len(), 0);
+        assert_eq!(uart.read(&mut buf), Ok(7));
+        assert_eq!(&buf[..7], b", world");
+        assert_eq!(uart.try_read(&mut buf), Ok(0));
+    }
+
+    #[test]
+    fn rx_errors() {
+        let device = sim::pl011::Uart::new();
+        let uart = uart::<8, 8>(&device, &Config::default());
+        device.push_rx(b"a");
+        device.push_rx_raw(DR::PE::SET.value | u32::from(b'b'));
+        device.push_rx_raw(DR::BE::SET.value | DR::FE::SET.value);
+        device.push_rx_raw(DR::OE::SET.value | u32::from(b'c'));
+        device.push_rx_raw(DR::FE::SET.value | u32::from(b'd'));
+        uart.service();
+        assert!(!device.regs().ris.is_set(RIS::OERIS));
+
+        let mut buf = [0; 8];
+        assert_eq!(uart.try_read(&mut buf), Err(Error::Overrun));
+        assert_eq!(uart.try_read(&mut buf), Err(Error::Break));
+        // The framing error of 'd' is reported separately from the break
+        assert_eq!(uart.try_read(&mut buf), Err(Error::Framing));
+        assert_eq!(uart.try_read(&mut buf), Err(Error::Parity));
+        assert_eq!(uart.try_read(&mut buf), Ok(2));
+        assert_eq!(&buf[..2], b"ac");
+    }
+
+    #[test]
+    fn tx() {
+        let device = sim::pl011::Uart::new();
+        let uart = uart::<8, 64>(&device, &Config::default());
+        uart.write(b"hello");
+        uart.flush();
+        assert_eq!(device.take_tx(), b"hello");
+        assert!(!device.regs().imsc.is_set(IMSC::TXIM));
+    }
+
+    #[test]
+    fn tx_flow_control() {
+        let device = sim::pl011::Uart::new();
+        let uart = uart::<8, 64>(
+            &device,
+            &Config {
+                flow_control: FlowControl::RtsCts,
+                ..Config::default()
+            },
+        );
+        let data: Vec<u8> = (0..128).collect();
+
+        // The FIFO (32 characters) and the buffer (64 characters) are filled
+        device.set_cts(false);
+        assert_eq!(uart.try_write(&data), 96);
+        assert_eq!(uart.try_write(&data[96..]), 0);
+        assert!(device.regs().imsc.is_set(IMSC::TXIM));
+
+        device.set_cts(true);
+        let mut sent = Vec::new();
+        let mut remaining = &data[96..];
+        while sent.len() < data.len() {
+            if device.regs().mis.is_set(bcm2711_pac::pl011::MIS::TXMIS) {
+                uart.service();
+            }
+            sent.extend(device.take_tx());
+            remaining = &remaining[uart.try_write(remaining)..];
+        }
+        assert!(remaining.is_empty());
+        assert_eq!(sent, data);
+        assert!(!device.regs().imsc.is_set(IMSC::TXIM));
+    }
+}
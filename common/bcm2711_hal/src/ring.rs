@@ -0,0 +1,98 @@
+//! Fixed-capacity byte ring buffers shared by the interrupt-driven drivers
+
+/// A FIFO queue of up to `N` bytes
+pub(crate) struct Ring<const N: usize> {
+    buf: [u8; N],
+    /// The index of the oldest byte
+    head: usize,
+    len: usize,
+}
+
+impl<const N: usize> Ring<N> {
+    /// An empty `Ring`
+    pub(crate) const EMPTY: Self = {
+        assert!(N > 0, "zero-capacity ring buffer");
+        Self {
+            buf: [0; N],
+            head: 0,
+            len: 0,
+        }
+    };
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub(crate) fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+
+    /// Append `byte`. Returns `false` if the buffer is full.
+    #[inline]
+    pub(crate) fn push(&mut self, byte: u8) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        self.buf[(self.head + self.len) % N] = byte;
+        self.len += 1;
+        true
+    }
+
+    /// Remove the oldest byte.
+    #[inline]
+    pub(crate) fn pop(&mut self) -> Option<u8> {
+        if self.is_empty() {
+            return None;
+        }
+        let byte = self.buf[self.head];
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        Some(byte)
+    }
+
+    /// Append as many bytes from `bytes` as possible. Returns the number of
+    /// appended bytes.
+    pub(crate) fn push_slice(&mut self, bytes: &[u8]) -> usize {
+        bytes.iter().take_while(|&&b| self.push(b)).count()
+    }
+
+    /// Remove as many bytes as possible into `buf`. Returns the number of
+    /// removed bytes.
+    pub(crate) fn pop_slice(&mut self, buf: &mut [u8]) -> usize {
+        let mut count = 0;
+        for (slot, byte) in buf.iter_mut().zip(core::iter::from_fn(|| self.pop())) {
+            *slot = byte;
+            count += 1;
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_around() {
+        let mut ring = Ring::<4>::EMPTY;
+        assert_eq!(ring.push_slice(b"abc"), 3);
+        assert_eq!(ring.pop(), Some(b'a'));
+        assert_eq!(ring.push_slice(b"def"), 2);
+        assert!(ring.is_full());
+        assert!(!ring.push(b'g'));
+
+        let mut buf = [0; 8];
+        assert_eq!(ring.pop_slice(&mut buf), 4);
+        assert_eq!(&buf[..4], b"bcde");
+        assert!(ring.is_empty());
+        assert_eq!(ring.pop(), None);
+    }
+}
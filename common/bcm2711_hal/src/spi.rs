@@ -0,0 +1,1251 @@
+//! SPI master driver (SPI0, SPI3–6)
+//!
+//! [`Spi`] performs full-duplex transfers in one of three
+//! [`TransferMode`]s:
+//!
+//!  - [`TransferMode::Polled`]: The calling processor moves every byte
+//!    between the buffers and the FIFOs.
+//!  - [`TransferMode::Interrupt`]: [`Spi::service`] moves the bytes when the
+//!    transmit FIFO is empty or the receive FIFO needs reading. It must be
+//!    called from the handler of [`irq::SPI`], which is shared by all SPI
+//!    master instances.
+//!  - [`TransferMode::Dma`]: Two DMA engines move the bytes, paced by the
+//!    `DREQ` signals of the controller (see [`Dma`]).
+//!
+//! In the latter two modes, the calling task repeatedly calls the wait
+//! function (see [`Spi::with_wait`]) until the transfer completes. A wait
+//! function yielding the processor keeps it available to other tasks during
+//! long transfers.
+//!
+//! ```rust,ignore
+//! use bcm2711_hal::{
+//!     identity_mapped, identity_mapped_to_legacy_dma,
+//!     spi::{ChipSelect, Config, Dma, DmaThresholds, Spi, TransferMode, DREQ_SPI0_RX, DREQ_SPI0_TX},
+//! };
+//! use bcm2711_pac::{dmac, spi::BASE_SPI0};
+//!
+//! let dmac: &dmac::Dma0Registers = unsafe { &*identity_mapped(dmac::BASE_DMA0) };
+//! let spi = unsafe {
+//!     Spi::new(identity_mapped(BASE_SPI0)).with_dma(Dma {
+//!         tx: dmac.dma4(),
+//!         rx: dmac.dma5(),
+//!         tx_dreq: DREQ_SPI0_TX,
+//!         rx_dreq: DREQ_SPI0_RX,
+//!         base: BASE_SPI0,
+//!         thresholds: DmaThresholds::default(),
+//!         to_bus: identity_mapped_to_legacy_dma,
+//!     })
+//! };
+//! spi.init(&Config {
+//!     clock_hz: 10_000_000,
+//!     transfer_mode: TransferMode::Dma,
+//!     ..Config::default()
+//! })
+//! .unwrap();
+//!
+//! // The receive buffer must start on a cache line
+//! #[repr(align(64))]
+//! struct Buffer([u8; 4096]);
+//!
+//! let mut rx = Buffer([0; 4096]);
+//! spi.transfer(ChipSelect::Cs0, &[0x03, 0, 0, 0], &mut rx.0).unwrap();
+//! ```
+//!
+//! With [`TransferMode::Interrupt`], the `Spi` must be placed in a `static`
+//! and serviced by the interrupt handler. With the `solid` feature,
+//! `&'static Spi` can be used as the handler of a
+//! `solid::interrupt::Handler`:
+//!
+//! ```rust,ignore
+//! static SPI0: Spi = unsafe { Spi::new(identity_mapped(BASE_SPI0)) };
+//!
+//! let handler = pin_singleton!(: Handler<_> = interrupt::Handler::new(&SPI0)).unwrap();
+//! handler.register_static(&irq::SPI.handler_options(10)).unwrap();
+//! irq::SPI.number().enable().unwrap();
+//! ```
+//!
+//! The pins must be switched to the appropriate alternate functions
+//! separately (e.g., GPIO7–11 to `ALT0` for SPI0).
+//!
+//! [`irq::SPI`]: bcm2711_pac::irq::SPI
+use bcm2711_pac::{
+    dmac::{DmaCb, DmaRegisters, DMA_CS, DMA_TI, DMA_TXFR_LEN},
+    spi::{Registers, CLK, CS, DC, DLEN},
+    MemoryField, Vpa,
+};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tock_registers::{
+    fields::FieldValue,
+    interfaces::{Readable, Writeable},
+};
+
+use crate::{cache, sync::with_lock};
+
+/// The core clock frequency configured by the Raspberry Pi 4 firmware, from
+/// which the SPI clock is derived. The actual value can be queried through
+/// the mailbox property interface (clock ID `CORE`).
+pub const DEFAULT_CORE_CLOCK_HZ: u32 = 500_000_000;
+
+/// The peripheral number (`DREQ`) of the SPI0 transmit FIFO
+pub const DREQ_SPI0_TX: u8 = 6;
+/// The peripheral number (`DREQ`) of the SPI0 receive FIFO
+pub const DREQ_SPI0_RX: u8 = 7;
+
+/// The required alignment of a receive buffer in [`TransferMode::Dma`]. The
+/// cache maintenance on the buffer would otherwise corrupt the data sharing
+/// its first cache line.
+pub const DMA_RX_ALIGN: usize = cache::LINE_SIZE;
+
+/// The maximum length of a DMA transfer (limited by `dlen`). Longer transfers
+/// are split into pieces of this length, which is a multiple of the cache
+/// line size so that each piece of the receive buffer starts on a cache line.
+const MAX_DMA_LEN: usize = 0xffc0;
+
+/// The clock polarity and phase
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Mode {
+    /// CPOL = 0, CPHA = 0: The clock idles low, and data is sampled on the
+    /// rising edges.
+    Mode0,
+    /// CPOL = 0, CPHA = 1: The clock idles low, and data is sampled on the
+    /// falling edges.
+    Mode1,
+    /// CPOL = 1, CPHA = 0: The clock idles high, and data is sampled on the
+    /// falling edges.
+    Mode2,
+    /// CPOL = 1, CPHA = 1: The clock idles high, and data is sampled on the
+    /// rising edges.
+    Mode3,
+}
+
+/// A chip select line
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum ChipSelect {
+    /// CS0
+    Cs0,
+    /// CS1
+    Cs1,
+    /// CS2
+    Cs2,
+}
+
+/// The active level of a chip select line
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Polarity {
+    /// The line is driven low during transfers.
+    ActiveLow,
+    /// The line is driven high during transfers.
+    ActiveHigh,
+}
+
+/// The way the FIFOs are serviced during transfers
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum TransferMode {
+    /// The calling processor busy-waits on the FIFOs.
+    Polled,
+    /// [`Spi::service`] services the FIFOs on interrupts.
+    Interrupt,
+    /// The DMA engines specified by [`Spi::with_dma`] service the FIFOs.
+    /// The receive buffers must be aligned to [`DMA_RX_ALIGN`] bytes.
+    Dma,
+}
+
+/// The configuration for [`Spi::init`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Config {
+    /// The core clock frequency
+    pub core_clock_hz: u32,
+    /// The maximum SPI clock frequency. The actual frequency is the core
+    /// clock frequency divided by an even number.
+    pub clock_hz: u32,
+    /// The clock polarity and phase
+    pub mode: Mode,
+    /// The active levels of CS0–CS2
+    pub cs_polarity: [Polarity; 3],
+    /// The way the FIFOs are serviced
+    pub transfer_mode: TransferMode,
+}
+
+impl Default for Config {
+    /// 1 MHz, mode 0, active-low chip selects, and polled transfers
+    #[inline]
+    fn default() -> Self {
+        Self {
+            core_clock_hz: DEFAULT_CORE_CLOCK_HZ,
+            clock_hz: 1_000_000,
+            mode: Mode::Mode0,
+            cs_polarity: [Polarity::ActiveLow; 3],
+            transfer_mode: TransferMode::Polled,
+        }
+    }
+}
+
+/// The FIFO levels at which the controller requests service from the DMA
+/// engines (`dc`)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct DmaThresholds {
+    /// The transmit FIFO level at or below which `DREQ` is asserted
+    pub tx_dreq: u8,
+    /// The transmit FIFO level at or below which `PANIC` is asserted
+    pub tx_panic: u8,
+    /// The receive FIFO level above which `DREQ` is asserted
+    pub rx_dreq: u8,
+    /// The receive FIFO level above which `PANIC` is asserted
+    pub rx_panic: u8,
+}
+
+impl Default for DmaThresholds {
+    /// The reset values of `dc`
+    #[inline]
+    fn default() -> Self {
+        Self {
+            tx_dreq: 0x20,
+            tx_panic: 0x10,
+            rx_dreq: 0x20,
+            rx_panic: 0x30,
+        }
+    }
+}
+
+/// The DMA engines and addresses used by [`TransferMode::Dma`]
+#[derive(Clone, Copy, Debug)]
+pub struct Dma {
+    /// The DMA engine (one of DMA0–6) writing the transmit FIFO
+    pub tx: *const DmaRegisters,
+    /// The DMA engine (one of DMA0–6) reading the receive FIFO
+    pub rx: *const DmaRegisters,
+    /// The peripheral number of the transmit FIFO (e.g., [`DREQ_SPI0_TX`])
+    pub tx_dreq: u8,
+    /// The peripheral number of the receive FIFO (e.g., [`DREQ_SPI0_RX`])
+    pub rx_dreq: u8,
+    /// The address of the register block (e.g.,
+    /// [`BASE_SPI0`](bcm2711_pac::spi::BASE_SPI0)), from which the address of
+    /// `fifo` seen by the DMA engines is derived
+    pub base: Vpa,
+    /// The FIFO levels requesting service
+    pub thresholds: DmaThresholds,
+    /// Get the address of a buffer seen by the DMA engines, or `None` if the
+    /// buffer isn't accessible by them (e.g.,
+    /// [`identity_mapped_to_legacy_dma`](crate::identity_mapped_to_legacy_dma)).
+    pub to_bus: fn(*const u8) -> Option<u32>,
+}
+
+/// The error type for [`Spi::init`]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum ConfigError {
+    /// The clock frequency can't be generated from the core clock.
+    UnsupportedClock,
+    /// [`TransferMode::Dma`] was specified without DMA engines.
+    NoDma,
+    /// A transfer is in progress.
+    Busy,
+}
+
+/// The error type for transfers
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Error {
+    /// Another transfer is in progress.
+    Busy,
+    /// A buffer isn't accessible by the DMA engines.
+    DmaAddress,
+    /// The receive buffer isn't aligned to [`DMA_RX_ALIGN`] bytes.
+    DmaAlignment,
+    /// A DMA engine reported an error. The transfer was aborted.
+    Dma,
+}
+
+/// Calculate the clock divider (`clk.CDIV`) generating the fastest SPI clock
+/// not exceeding `clock_hz` from the core clock. Returns the divisor
+/// (`2..=65536`), which is written as zero if it's 65536.
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_hal::spi::clock_divider;
+/// assert_eq!(clock_divider(500_000_000, 10_000_000), Ok(50));
+/// assert_eq!(clock_divider(500_000_000, 3_000_000), Ok(168));
+/// assert!(clock_divider(500_000_000, 1_000).is_err());
+/// ```
+pub const fn clock_divider(core_clock_hz: u32, clock_hz: u32) -> Result<u32, ConfigError> {
+    if clock_hz == 0 {
+        return Err(ConfigError::UnsupportedClock);
+    }
+    // Round up to an even number; odd divisors are rounded down by the
+    // hardware
+    let mut div = core_clock_hz / clock_hz;
+    if div * clock_hz != core_clock_hz {
+        div += 1;
+    }
+    if div > 0x10000 {
+        return Err(ConfigError::UnsupportedClock);
+    }
+    let div = (div + 1) & !1;
+    Ok(if div < 2 { 2 } else { div })
+}
+
+/// A transfer of `len` bytes serviced by [`pump`]. Zeros are transmitted
+/// after `tx_len` bytes, and the bytes received after `rx_len` bytes are
+/// discarded.
+struct Job {
+    tx: *const u8,
+    tx_len: usize,
+    rx: *mut u8,
+    rx_len: usize,
+    len: usize,
+    /// The number of bytes written to the transmit FIFO
+    tx_pos: usize,
+    /// The number of bytes read from the receive FIFO
+    rx_pos: usize,
+}
+
+impl Job {
+    #[inline]
+    fn new(tx: *const u8, tx_len: usize, rx: *mut u8, rx_len: usize) -> Self {
+        Self {
+            tx,
+            tx_len,
+            rx,
+            rx_len,
+            len: tx_len.max(rx_len),
+            tx_pos: 0,
+            rx_pos: 0,
+        }
+    }
+}
+
+/// The state of [`Spi`] protected by `with_lock`
+struct State {
+    /// `cs` without the transfer-specific bits (the mode and `CSPOL0`–`2`)
+    cs: u32,
+    transfer_mode: TransferMode,
+    /// The transfer serviced by [`Spi::service`]
+    job: Option<Job>,
+}
+
+/// The control blocks of [`TransferMode::Dma`] and the words used past the
+/// ends of the buffers. Aligned to the cache lines so that no other data
+/// shares them.
+///
+/// The engines move whole words through the FIFO, so the transmit buffer is
+/// moved in three parts: the whole words, the partial last word (through
+/// `tx_tail`), and the rest of the transfer (through `zero`). The receive
+/// buffer is moved in whole cache lines instead, so that the cache
+/// maintenance doesn't affect the data following it, with the partial last
+/// line received through `rx_tail` and the rest through `discard`.
+#[repr(C, align(64))]
+struct DmaBlocks {
+    /// The control blocks of each engine, used for the three parts
+    tx: [DmaCb; 3],
+    rx: [DmaCb; 3],
+    /// The partial last word of the transmit buffer, padded with zeros
+    tx_tail: u32,
+    /// The partial last cache line of the receive buffer
+    rx_tail: [u8; cache::LINE_SIZE],
+    /// The source of the zeros transmitted after the transmit buffer
+    zero: u32,
+    /// The destination of the bytes received after the receive buffer
+    discard: u32,
+}
+
+/// An SPI master
+///
+/// All methods take `&self` so that a `Spi` can be placed in a `static` and
+/// shared between the interrupt handler and tasks. Only one transfer can be
+/// in progress at a time; the others fail with [`Error::Busy`].
+pub struct Spi {
+    regs: *const Registers,
+    dma: Option<Dma>,
+    wait: fn(),
+    /// Protected by `with_lock`
+    state: UnsafeCell<State>,
+    /// Owned by the holder of `busy`
+    dma_blocks: UnsafeCell<DmaBlocks>,
+    /// Set while a transfer (or [`Spi::init`]) is in progress
+    busy: AtomicBool,
+}
+
+// Safety: `state` is only accessed with the lock held, and `dma_blocks` and
+// the DMA engines are only accessed by the holder of `busy`.
+unsafe impl Send for Spi {}
+unsafe impl Sync for Spi {}
+
+impl Spi {
+    /// Construct a `Spi` without DMA engines, busy-waiting for the
+    /// completion of transfers. The controller is left untouched until
+    /// [`Self::init`] is called.
+    ///
+    /// # Safety
+    ///
+    /// `regs` must point to [an SPI master register block](Registers) and
+    /// remain valid for the lifetime of the constructed `Spi`. The register
+    /// block must not be accessed by other means.
+    #[inline]
+    pub const unsafe fn new(regs: *const Registers) -> Self {
+        Self {
+            regs,
+            dma: None,
+            wait: core::hint::spin_loop,
+            state: UnsafeCell::new(State {
+                cs: 0,
+                transfer_mode: TransferMode::Polled,
+                job: None,
+            }),
+            dma_blocks: UnsafeCell::new(DmaBlocks {
+                tx: [DMA_CB_ZERO; 3],
+                rx: [DMA_CB_ZERO; 3],
+                tx_tail: 0,
+                rx_tail: [0; cache::LINE_SIZE],
+                zero: 0,
+                discard: 0,
+            }),
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    /// Use the specified DMA engines for [`TransferMode::Dma`].
+    ///
+    /// # Safety
+    ///
+    /// `dma.tx` and `dma.rx` must point to distinct DMA engine register
+    /// blocks and remain valid for the lifetime of the `Spi`. The engines
+    /// must be enabled and must not be used by other means. `dma.base` must
+    /// be the address of the register block passed to [`Self::new`].
+    #[inline]
+    pub const unsafe fn with_dma(self, dma: Dma) -> Self {
+        Self {
+            dma: Some(dma),
+            ..self
+        }
+    }
+
+    /// Call `wait` repeatedly while waiting for the completion of
+    /// interrupt-driven and DMA transfers, instead of
+    /// [`core::hint::spin_loop`].
+    #[inline]
+    pub const fn with_wait(self, wait: fn()) -> Self {
+        Self { wait, ..self }
+    }
+
+    #[inline]
+    fn regs(&self) -> &Registers {
+        // Safety: Upheld by the caller of `Self::new`
+        unsafe { &*self.regs }
+    }
+
+    /// Call `f` with the lock held.
+    #[inline]
+    fn with_state<R>(&self, f: impl FnOnce(&Registers, &mut State) -> R) -> R {
+        // Safety: We are holding the lock
+        with_lock(|| f(self.regs(), unsafe { &mut *self.state.get() }))
+    }
+
+    /// Acquire `busy`, which is released (aborting the transfer in progress)
+    /// when the returned guard is dropped.
+    fn lock(&self) -> Option<Busy<'_>> {
+        if self.busy.swap(true, Ordering::Acquire) {
+            None
+        } else {
+            Some(Busy(self))
+        }
+    }
+
+    /// Configure the controller, which drives the clock and chip select lines
+    /// to their idle levels.
+    pub fn init(&self, config: &Config) -> Result<(), ConfigError> {
+        let cdiv = clock_divider(config.core_clock_hz, config.clock_hz)?;
+        if config.transfer_mode == TransferMode::Dma && self.dma.is_none() {
+            return Err(ConfigError::NoDma);
+        }
+        let _busy = self.lock().ok_or(ConfigError::Busy)?;
+
+        let mode = match config.mode {
+            Mode::Mode0 => {
+                CS::CPOL::RestStateIsLow + CS::CPHA::FirstSclkTransitionAtMiddleOfDataBit
+            }
+            Mode::Mode1 => {
+                CS::CPOL::RestStateIsLow + CS::CPHA::FirstSclkTransitionAtBeginningOFDataBit
+            }
+            Mode::Mode2 => {
+                CS::CPOL::RestStateIsHigh + CS::CPHA::FirstSclkTransitionAtMiddleOfDataBit
+            }
+            Mode::Mode3 => {
+                CS::CPOL::RestStateIsHigh + CS::CPHA::FirstSclkTransitionAtBeginningOFDataBit
+            }
+        };
+        let cspol = [CS::CSPOL0::SET, CS::CSPOL1::SET, CS::CSPOL2::SET]
+            .into_iter()
+            .zip(config.cs_polarity)
+            .filter(|&(_, polarity)| polarity == Polarity::ActiveHigh)
+            .fold(mode, |cs, (cspol, _)| cs + cspol);
+
+        let regs = self.regs();
+        regs.cs.write(CS::CLEAR_TX::SET + CS::CLEAR_RX::SET);
+        regs.clk.write(CLK::CDIV.val(cdiv & 0xffff));
+        if let Some(dma) = &self.dma {
+            let t = &dma.thresholds;
+            regs.dc.write(
+                DC::TDREQ.val(t.tx_dreq.into())
+                    + DC::TPANIC.val(t.tx_panic.into())
+                    + DC::RDREQ.val(t.rx_dreq.into())
+                    + DC::RPANIC.val(t.rx_panic.into()),
+            );
+        }
+        self.with_state(|_, state| {
+            state.cs = cspol.value;
+            state.transfer_mode = config.transfer_mode;
+        });
+        // `_busy` writes `state.cs` to `cs`
+        Ok(())
+    }
+
+    /// Transmit `tx` while receiving into `rx` on the chip select line `cs`.
+    /// The transfer is as long as the longer buffer; zeros are transmitted
+    /// after `tx`, and the bytes received after `rx` is filled are
+    /// discarded.
+    pub fn transfer(&self, cs: ChipSelect, tx: &[u8], rx: &mut [u8]) -> Result<(), Error> {
+        self.run(
+            cs,
+            Job::new(tx.as_ptr(), tx.len(), rx.as_mut_ptr(), rx.len()),
+        )
+    }
+
+    /// Transmit `buf` while replacing it with the received bytes on the chip
+    /// select line `cs`.
+    pub fn transfer_in_place(&self, cs: ChipSelect, buf: &mut [u8]) -> Result<(), Error> {
+        // Each byte is transmitted before the byte replacing it is received
+        let ptr = buf.as_mut_ptr();
+        self.run(cs, Job::new(ptr, buf.len(), ptr, buf.len()))
+    }
+
+    /// Transmit `tx` on the chip select line `cs`, discarding the received
+    /// bytes.
+    pub fn write(&self, cs: ChipSelect, tx: &[u8]) -> Result<(), Error> {
+        self.run(
+            cs,
+            Job::new(tx.as_ptr(), tx.len(), core::ptr::null_mut(), 0),
+        )
+    }
+
+    /// Receive into `rx` on the chip select line `cs`, transmitting zeros.
+    pub fn read(&self, cs: ChipSelect, rx: &mut [u8]) -> Result<(), Error> {
+        self.run(
+            cs,
+            Job::new(core::ptr::null(), 0, rx.as_mut_ptr(), rx.len()),
+        )
+    }
+
+    /// Perform `job`, whose buffers must be valid.
+    fn run(&self, cs: ChipSelect, mut job: Job) -> Result<(), Error> {
+        let busy = self.lock().ok_or(Error::Busy)?;
+        if job.len == 0 {
+            return Ok(());
+        }
+        let (cs_value, transfer_mode) = self.with_state(|_, state| {
+            let cs_value = match cs {
+                ChipSelect::Cs0 => CS::CS::ChipSelect0 + CS::CSPOL.val(state.cs >> 21 & 1),
+                ChipSelect::Cs1 => CS::CS::ChipSelect1 + CS::CSPOL.val(state.cs >> 22 & 1),
+                ChipSelect::Cs2 => CS::CS::ChipSelect2 + CS::CSPOL.val(state.cs >> 23 & 1),
+            };
+            (state.cs | cs_value.value, state.transfer_mode)
+        });
+        let regs = self.regs();
+        regs.cs
+            .set(cs_value | (CS::CLEAR_TX::SET + CS::CLEAR_RX::SET).value);
+
+        match transfer_mode {
+            TransferMode::Polled => {
+                regs.cs.set(cs_value | CS::TA::SET.value);
+                // Safety: The buffers of `job` are valid
+                while !unsafe { pump(regs, &mut job) } {
+                    core::hint::spin_loop();
+                }
+            }
+            TransferMode::Interrupt => {
+                self.with_state(|regs, state| {
+                    state.job = Some(job);
+                    // `DONE` raises an interrupt right away
+                    regs.cs
+                        .set(cs_value | (CS::TA::SET + CS::INTD::SET + CS::INTR::SET).value);
+                });
+                // `busy` aborts the transfer if `wait` panics
+                while self.with_state(|_, state| state.job.is_some()) {
+                    (self.wait)();
+                }
+            }
+            TransferMode::Dma => self.run_dma(cs_value, &job)?,
+        }
+        drop(busy);
+        Ok(())
+    }
+
+    /// Perform `job` with the DMA engines.
+    fn run_dma(&self, cs_value: u32, job: &Job) -> Result<(), Error> {
+        // `init` doesn't allow `TransferMode::Dma` without DMA engines
+        let dma = self.dma.as_ref().unwrap();
+        // Safety: Upheld by the caller of `Self::with_dma`
+        let (tx_engine, rx_engine) = unsafe { (&*dma.tx, &*dma.rx) };
+        let regs = self.regs();
+        let fifo = dma.base.to_legacy_bus().ok_or(Error::DmaAddress)? + 4;
+        if job.rx_len > 0 && job.rx as usize & (DMA_RX_ALIGN - 1) != 0 {
+            return Err(Error::DmaAlignment);
+        }
+        let to_bus = |ptr: *const u8| (dma.to_bus)(ptr).ok_or(Error::DmaAddress);
+
+        // Safety: We are holding `busy`
+        let blocks = unsafe { &mut *self.dma_blocks.get() };
+        let base = blocks as *const DmaBlocks as usize;
+        let blocks_bus = to_bus(base as *const u8)?;
+        let bus = |field: *const ()| blocks_bus + (field as usize - base) as u32;
+        let tx_cbs = [0, 1, 2].map(|i| bus(&blocks.tx[i] as *const DmaCb as *const ()));
+        let rx_cbs = [0, 1, 2].map(|i| bus(&blocks.rx[i] as *const DmaCb as *const ()));
+        let [tx_tail, zero, discard] = [&blocks.tx_tail, &blocks.zero, &blocks.discard]
+            .map(|word| bus(word as *const u32 as *const ()));
+        let rx_tail = bus(blocks.rx_tail.as_ptr() as *const ());
+
+        let tx_ti = DMA_TI::PERMAP.val(dma.tx_dreq.into())
+            + DMA_TI::DEST_DREQ::SET
+            + DMA_TI::WAIT_RESP::SET;
+        let rx_ti = DMA_TI::PERMAP.val(dma.rx_dreq.into()) + DMA_TI::SRC_DREQ::SET;
+
+        let mut pos = 0;
+        while pos < job.len {
+            // `pos` stays aligned to the cache lines
+            let len = (job.len - pos).min(MAX_DMA_LEN);
+            let ([tx_words, tx_tail_len, tx_rest], tx_partial) = split(job.tx_len, pos, len, 4);
+            let ([rx_lines, rx_tail_len, rx_rest], rx_partial) =
+                split(job.rx_len, pos, len, cache::LINE_SIZE);
+
+            let mut tx_source = 0;
+            if tx_words > 0 {
+                // Safety: `pos` is in bounds
+                let tx = unsafe { job.tx.add(pos) };
+                cache::clean(tx, tx_words);
+                tx_source = to_bus(tx)?;
+            }
+            if tx_partial > 0 {
+                let mut tail = [0; 4];
+                // Safety: `job.tx` is valid for `job.tx_len` bytes
+                tail[..tx_partial].copy_from_slice(unsafe {
+                    core::slice::from_raw_parts(job.tx.add(pos + tx_words), tx_partial)
+                });
+                blocks.tx_tail = u32::from_le_bytes(tail);
+            }
+            let tx_start = chain(
+                &mut blocks.tx,
+                tx_cbs,
+                [
+                    (tx_ti + DMA_TI::SRC_INC::SET, tx_source, fifo, tx_words),
+                    (tx_ti, tx_tail, fifo, tx_tail_len),
+                    (tx_ti, zero, fifo, tx_rest),
+                ],
+            );
+
+            let mut rx_dest = 0;
+            if rx_lines > 0 {
+                // Safety: `pos` is in bounds
+                let rx = unsafe { job.rx.add(pos) };
+                cache::clean_invalidate(rx, rx_lines);
+                rx_dest = to_bus(rx)?;
+            }
+            let rx_start = chain(
+                &mut blocks.rx,
+                rx_cbs,
+                [
+                    (rx_ti + DMA_TI::DEST_INC::SET, fifo, rx_dest, rx_lines),
+                    (rx_ti + DMA_TI::DEST_INC::SET, fifo, rx_tail, rx_tail_len),
+                    (rx_ti, fifo, discard, rx_rest),
+                ],
+            );
+
+            blocks.zero = 0;
+            cache::clean(base as *const u8, core::mem::size_of::<DmaBlocks>());
+
+            regs.dlen.write(DLEN::LEN.val(len as u32));
+            regs.cs.set(cs_value | (CS::TA::SET + CS::DMAEN::SET).value);
+            for (engine, start) in [(rx_engine, rx_start), (tx_engine, tx_start)] {
+                engine.conblk_ad.set(start);
+                engine.cs.write(
+                    DMA_CS::ACTIVE::SET
+                        + DMA_CS::END::SET
+                        + DMA_CS::WAIT_FOR_OUTSTANDING_WRITES::SET,
+                );
+            }
+
+            // The receive engine finishes last. The caller's `Busy` aborts the
+            // transfer on error or if `wait` panics.
+            loop {
+                if tx_engine.cs.is_set(DMA_CS::ERROR) || rx_engine.cs.is_set(DMA_CS::ERROR) {
+                    return Err(Error::Dma);
+                }
+                if rx_engine.cs.is_set(DMA_CS::END) {
+                    break;
+                }
+                (self.wait)();
+            }
+            tx_engine.cs.write(DMA_CS::END::SET);
+            rx_engine.cs.write(DMA_CS::END::SET);
+
+            if rx_lines > 0 {
+                // Safety: `pos` is in bounds
+                cache::clean_invalidate(unsafe { job.rx.add(pos) }, rx_lines);
+            }
+            if rx_partial > 0 {
+                cache::clean_invalidate(base as *const u8, core::mem::size_of::<DmaBlocks>());
+                // Safety: `job.rx` is valid for `job.rx_len` bytes
+                unsafe { core::slice::from_raw_parts_mut(job.rx.add(pos + rx_lines), rx_partial) }
+                    .copy_from_slice(&blocks.rx_tail[..rx_partial]);
+            }
+            pos += len;
+        }
+        Ok(())
+    }
+
+    /// Service the interrupt: move bytes between the buffers of the
+    /// interrupt-driven transfer in progress and the FIFOs, and complete the
+    /// transfer when all bytes have been received. Does nothing if there's no
+    /// such transfer.
+    pub fn service(&self) {
+        self.with_state(|regs, state| {
+            if let Some(job) = &mut state.job {
+                // Safety: `Self::run` keeps the buffers alive until `job` is
+                // removed
+                if unsafe { pump(regs, job) } {
+                    regs.cs.set(state.cs);
+                    state.job = None;
+                }
+            }
+        });
+    }
+
+    /// Stop the transfer in progress (if any) and return the controller to
+    /// the idle state.
+    fn abort(&self) {
+        self.with_state(|regs, state| {
+            state.job = None;
+            regs.cs.set(state.cs);
+        });
+        if let Some(dma) = &self.dma {
+            // Safety: Upheld by the caller of `Self::with_dma`
+            for engine in unsafe { [&*dma.tx, &*dma.rx] } {
+                let cs = engine.cs.extract();
+                if cs.is_set(DMA_CS::ACTIVE) || cs.is_set(DMA_CS::ERROR) {
+                    engine.cs.write(DMA_CS::RESET::SET);
+                }
+            }
+        }
+    }
+}
+
+/// A control block with all fields zero
+const DMA_CB_ZERO: DmaCb = DmaCb {
+    ti: MemoryField::new(0),
+    source_ad: 0,
+    dest_ad: 0,
+    txfr_len: MemoryField::new(0),
+    stride: MemoryField::new(0),
+    nextconbk: 0,
+    reserved: [0; 2],
+};
+
+/// Split the part `pos..pos + len` of a transfer covered by a buffer of
+/// `buf_len` bytes into three parts: the whole `unit`s, the partial last
+/// `unit`, and the rest. Returns the lengths of the parts and the number of
+/// the buffer bytes in the second part. `unit` must be a power of two, and
+/// `pos` must be a multiple of `unit`.
+fn split(buf_len: usize, pos: usize, len: usize, unit: usize) -> ([usize; 3], usize) {
+    let covered = buf_len.saturating_sub(pos).min(len);
+    let whole = covered & !(unit - 1);
+    let partial = covered - whole;
+    let tail = if partial > 0 {
+        (len - whole).min(unit)
+    } else {
+        0
+    };
+    ([whole, tail, len - whole - tail], partial)
+}
+
+/// Fill `cbs` (located at `cbs_bus`) with a chain of control blocks moving
+/// the non-empty segments `(ti, source_ad, dest_ad, len)`. Returns the
+/// address of the first control block.
+fn chain(
+    cbs: &mut [DmaCb; 3],
+    cbs_bus: [u32; 3],
+    segments: [(FieldValue<u32, DMA_TI::Register>, u32, u32, usize); 3],
+) -> u32 {
+    let mut next = 0;
+    for i in (0..3).rev() {
+        let (ti, source_ad, dest_ad, len) = segments[i];
+        if len > 0 {
+            cbs[i] = control_block(ti, source_ad, dest_ad, len, next);
+            next = cbs_bus[i];
+        }
+    }
+    next
+}
+
+/// Construct a control block moving `len` bytes.
+fn control_block(
+    ti: FieldValue<u32, DMA_TI::Register>,
+    source_ad: u32,
+    dest_ad: u32,
+    len: usize,
+    nextconbk: u32,
+) -> DmaCb {
+    DmaCb {
+        ti: ti.into(),
+        source_ad,
+        dest_ad,
+        txfr_len: DMA_TXFR_LEN::LENGTH.val(len as u32).into(),
+        nextconbk,
+        ..DMA_CB_ZERO
+    }
+}
+
+/// The guard returned by [`Spi::lock`]
+struct Busy<'a>(&'a Spi);
+
+impl Drop for Busy<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.abort();
+        self.0.busy.store(false, Ordering::Release);
+    }
+}
+
+/// Move bytes between `job` and the FIFOs until neither can make progress.
+/// Returns `true` if all bytes have been received.
+///
+/// # Safety
+///
+/// `job.tx` and `job.rx` must be valid for `job.tx_len` and `job.rx_len`
+/// bytes, respectively.
+unsafe fn pump(regs: &Registers, job: &mut Job) -> bool {
+    while job.tx_pos < job.len && regs.cs.is_set(CS::TXD) {
+        let byte = if job.tx_pos < job.tx_len {
+            *job.tx.add(job.tx_pos)
+        } else {
+            0
+        };
+        regs.fifo.set(byte.into());
+        job.tx_pos += 1;
+    }
+    while job.rx_pos < job.len && regs.cs.is_set(CS::RXD) {
+        let byte = regs.fifo.get() as u8;
+        if job.rx_pos < job.rx_len {
+            *job.rx.add(job.rx_pos) = byte;
+        }
+        job.rx_pos += 1;
+    }
+    job.rx_pos == job.len
+}
+
+impl fmt::Debug for Spi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spi")
+            .field("regs", &self.regs)
+            .field("dma", &self.dma)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "solid")]
+impl<'a> solid::closure::FuncMut<(solid::thread::CpuCx<'a>,)> for &'static Spi {
+    type Output = ();
+
+    #[inline]
+    fn call(&mut self, _: (solid::thread::CpuCx<'a>,)) {
+        self.service();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use bcm2711_pac::{
+        sim::{self, Access, Device, Semantics},
+        spi::BASE_SPI0,
+    };
+    use std::{sync::atomic::AtomicBool, vec, vec::Vec};
+
+    fn spi(device: &sim::spi::Spi, config: &Config) -> Spi {
+        let spi = unsafe { Spi::new(device.regs()) };
+        spi.init(config).unwrap();
+        spi
+    }
+
+    /// The values written to `cs` since the last call
+    fn cs_writes(device: &sim::spi::Spi) -> Vec<u32> {
+        device
+            .take_trace()
+            .into_iter()
+            .filter_map(|access| match access {
+                Access::Write { offset: 0, value } => Some(value),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn divider() {
+        assert_eq!(clock_divider(500_000_000, 250_000_000), Ok(2));
+        assert_eq!(clock_divider(500_000_000, 1_000_000_000), Ok(2));
+        assert_eq!(clock_divider(500_000_000, 124_999_999), Ok(6));
+        assert_eq!(clock_divider(65_536_000, 1_000), Ok(65536));
+        assert!(clock_divider(65_536_000, 999).is_err());
+        assert!(clock_divider(500_000_000, 7_629).is_err());
+        assert!(clock_divider(500_000_000, 0).is_err());
+    }
+
+    #[test]
+    fn init() {
+        let device = sim::spi::Spi::new();
+        let spi = spi(
+            &device,
+            &Config {
+                core_clock_hz: 65_536_000,
+                clock_hz: 1_000,
+                mode: Mode::Mode3,
+                cs_polarity: [
+                    Polarity::ActiveLow,
+                    Polarity::ActiveHigh,
+                    Polarity::ActiveHigh,
+                ],
+                ..Config::default()
+            },
+        );
+        let regs = device.regs();
+        assert_eq!(regs.clk.read(CLK::CDIV), 0);
+        let cs = regs.cs.extract();
+        assert!(cs.matches_all(
+            CS::CPOL::RestStateIsHigh
+                + CS::CPHA::FirstSclkTransitionAtBeginningOFDataBit
+                + CS::CSPOL0::ActiveLow
+                + CS::CSPOL1::ActiveHigh
+                + CS::CSPOL2::ActiveHigh
+                + CS::TA::CLEAR
+        ));
+
+        assert_eq!(
+            spi.init(&Config {
+                transfer_mode: TransferMode::Dma,
+                ..Config::default()
+            }),
+            Err(ConfigError::NoDma)
+        );
+    }
+
+    #[test]
+    fn polled() {
+        let device = sim::spi::Spi::new();
+        let spi = spi(
+            &device,
+            &Config {
+                cs_polarity: [
+                    Polarity::ActiveLow,
+                    Polarity::ActiveHigh,
+                    Polarity::ActiveLow,
+                ],
+                ..Config::default()
+            },
+        );
+        device.take_trace();
+
+        // Longer than the FIFO
+        let tx: Vec<u8> = (0..100).collect();
+        let response: Vec<u8> = (0..100).map(|i| !i).collect();
+        device.set_response(&response);
+        let mut rx = [0; 100];
+        spi.transfer(ChipSelect::Cs1, &tx, &mut rx).unwrap();
+        assert_eq!(device.take_tx(), tx);
+        assert_eq!(rx[..], response[..]);
+
+        let writes = cs_writes(&device);
+        let active =
+            (CS::CS::ChipSelect1 + CS::CSPOL::ActiveHigh + CS::CSPOL1::ActiveHigh + CS::TA::SET)
+                .value;
+        assert!(writes.contains(&active));
+        assert!(!device.regs().cs.is_set(CS::TA));
+
+        // The shorter buffer is padded
+        device.set_response(b"abcd");
+        let mut rx = [0; 2];
+        spi.transfer(ChipSelect::Cs0, b"xyz", &mut rx).unwrap();
+        assert_eq!(device.take_tx(), b"xyz");
+        assert_eq!(&rx, b"ab");
+
+        device.set_response(b"abcd");
+        let mut rx = [0; 3];
+        spi.transfer(ChipSelect::Cs0, b"x", &mut rx).unwrap();
+        assert_eq!(device.take_tx(), b"x\0\0");
+        assert_eq!(&rx, b"abc");
+        assert_eq!(device.rx_len(), 0);
+
+        let mut buf = *b"12";
+        device.set_response(b"34");
+        spi.transfer_in_place(ChipSelect::Cs2, &mut buf).unwrap();
+        assert_eq!(device.take_tx(), b"12");
+        assert_eq!(&buf, b"34");
+
+        spi.write(ChipSelect::Cs0, b"w").unwrap();
+        let mut rx = [0xff; 2];
+        spi.read(ChipSelect::Cs0, &mut rx).unwrap();
+        assert_eq!(device.take_tx(), b"w\0\0");
+        assert_eq!(rx, [0; 2]);
+    }
+
+    #[test]
+    fn interrupt() {
+        let device = sim::spi::Spi::new();
+        let spi = spi(
+            &device,
+            &Config {
+                transfer_mode: TransferMode::Interrupt,
+                ..Config::default()
+            },
+        );
+        let tx: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        let response: Vec<u8> = tx.iter().map(|&b| b ^ 0x55).collect();
+        device.set_response(&response);
+        let mut rx = vec![0; 200];
+
+        // Nothing happens outside transfers
+        spi.service();
+        assert!(device.take_tx().is_empty());
+
+        let done = AtomicBool::new(false);
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    spi.service();
+                    std::thread::yield_now();
+                }
+            });
+            spi.transfer(ChipSelect::Cs0, &tx, &mut rx).unwrap();
+            done.store(true, Ordering::Relaxed);
+        });
+        assert_eq!(device.take_tx(), tx);
+        assert_eq!(rx, response);
+        let cs = device.regs().cs.extract();
+        assert!(!cs.is_set(CS::TA));
+        assert!(!cs.is_set(CS::INTD));
+        assert!(!cs.is_set(CS::INTR));
+    }
+
+    /// A simulated DMA engine that finishes (or fails) instantly
+    fn dma_engine(error: bool) -> Device<DmaRegisters> {
+        let device = Device::new();
+        device.set_semantics(0, Semantics::ReadOnly);
+        device.on_write(0, move |state, value| {
+            if value & DMA_CS::RESET::SET.value != 0 {
+                state.set(0, 0);
+            } else if value & DMA_CS::ACTIVE::SET.value == 0 {
+                state.clear_bits(0, value & DMA_CS::END::SET.value);
+            } else if error {
+                state.set(0, (DMA_CS::ACTIVE::SET + DMA_CS::ERROR::SET).value);
+            } else {
+                state.set(0, DMA_CS::END::SET.value);
+            }
+        });
+        device
+    }
+
+    /// A receive buffer suitable for DMA
+    #[repr(align(64))]
+    struct Aligned<const N: usize>([u8; N]);
+
+    fn dma_spi(
+        device: &sim::spi::Spi,
+        tx: &Device<DmaRegisters>,
+        rx: &Device<DmaRegisters>,
+    ) -> Spi {
+        let spi = unsafe {
+            Spi::new(device.regs()).with_dma(Dma {
+                tx: tx.regs(),
+                rx: rx.regs(),
+                tx_dreq: DREQ_SPI0_TX,
+                rx_dreq: DREQ_SPI0_RX,
+                base: BASE_SPI0,
+                thresholds: DmaThresholds {
+                    tx_dreq: 1,
+                    tx_panic: 2,
+                    rx_dreq: 3,
+                    rx_panic: 4,
+                },
+                to_bus: |ptr| Some(ptr as usize as u32),
+            })
+        };
+        spi.init(&Config {
+            transfer_mode: TransferMode::Dma,
+            ..Config::default()
+        })
+        .unwrap();
+        spi
+    }
+
+    #[test]
+    fn dma() {
+        let device = sim::spi::Spi::new();
+        let (tx_engine, rx_engine) = (dma_engine(false), dma_engine(false));
+        let spi = dma_spi(&device, &tx_engine, &rx_engine);
+        assert_eq!(device.regs().dc.get(), 0x0403_0201);
+
+        let tx = vec![0u8; 70_000];
+        let mut rx = Aligned([0u8; 4]);
+        device.take_trace();
+        spi.transfer(ChipSelect::Cs0, &tx, &mut rx.0).unwrap();
+
+        // Split into two pieces
+        let dlen: Vec<u32> = device
+            .trace()
+            .into_iter()
+            .filter_map(|access| match access {
+                Access::Write {
+                    offset: 0x0c,
+                    value,
+                } => Some(value),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(dlen, [0xffc0, 70_000 - 0xffc0]);
+        assert!(cs_writes(&device).contains(&(CS::TA::SET + CS::DMAEN::SET).value));
+        assert!(!device.regs().cs.is_set(CS::TA));
+
+        // The control blocks of the last piece
+        let blocks = unsafe { &*spi.dma_blocks.get() };
+        let fifo = 0x7e20_4004;
+        let bus = |ptr: *const u8| ptr as usize as u32;
+        assert_eq!(
+            tx_engine.regs().conblk_ad.get(),
+            bus(&blocks.tx[0] as *const _ as _)
+        );
+        assert_eq!(blocks.tx[0].source_ad, bus(tx[0xffc0..].as_ptr()));
+        assert_eq!(blocks.tx[0].dest_ad, fifo);
+        assert_eq!(blocks.tx[0].txfr_len.get(), 70_000 - 0xffc0);
+        assert_eq!(blocks.tx[0].nextconbk, 0);
+        assert!(blocks.tx[0]
+            .ti
+            .matches_all(DMA_TI::PERMAP.val(6) + DMA_TI::DEST_DREQ::SET + DMA_TI::SRC_INC::SET));
+
+        // `rx` is exhausted in the first piece
+        assert_eq!(
+            rx_engine.regs().conblk_ad.get(),
+            bus(&blocks.rx[2] as *const _ as _)
+        );
+        assert_eq!(blocks.rx[2].source_ad, fifo);
+        assert_eq!(
+            blocks.rx[2].dest_ad,
+            bus(&blocks.discard as *const u32 as _)
+        );
+        assert_eq!(blocks.rx[2].txfr_len.get(), 70_000 - 0xffc0);
+        assert!(blocks.rx[2]
+            .ti
+            .matches_all(DMA_TI::PERMAP.val(7) + DMA_TI::SRC_DREQ::SET + DMA_TI::DEST_INC::CLEAR));
+    }
+
+    #[test]
+    fn dma_chained() {
+        let device = sim::spi::Spi::new();
+        let (tx_engine, rx_engine) = (dma_engine(false), dma_engine(false));
+        let spi = dma_spi(&device, &tx_engine, &rx_engine);
+        let bus = |ptr: *const DmaCb| ptr as usize as u32;
+
+        let rx_tail = |blocks: &DmaBlocks| blocks.rx_tail.as_ptr() as usize as u32;
+
+        // The partial last word of `tx` is padded with zeros, and the partial
+        // last cache line of `rx` is received through `rx_tail`
+        let mut rx = Aligned([0u8; 16]);
+        spi.transfer(ChipSelect::Cs0, b"command", &mut rx.0)
+            .unwrap();
+        let blocks = unsafe { &*spi.dma_blocks.get() };
+        assert_eq!(tx_engine.regs().conblk_ad.get(), bus(&blocks.tx[0]));
+        assert_eq!(blocks.tx[0].txfr_len.get(), 4);
+        assert_eq!(blocks.tx[0].nextconbk, bus(&blocks.tx[1]));
+        assert_eq!(
+            blocks.tx[1].source_ad,
+            &blocks.tx_tail as *const u32 as usize as u32
+        );
+        assert_eq!(blocks.tx_tail.to_le_bytes(), *b"and\0");
+        assert_eq!(blocks.tx[1].txfr_len.get(), 4);
+        assert_eq!(blocks.tx[1].nextconbk, bus(&blocks.tx[2]));
+        assert_eq!(
+            blocks.tx[2].source_ad,
+            &blocks.zero as *const u32 as usize as u32
+        );
+        assert_eq!(blocks.tx[2].txfr_len.get(), 8);
+        assert_eq!(blocks.tx[2].nextconbk, 0);
+        assert!(blocks.tx[2].ti.is_set(DMA_TI::DEST_DREQ));
+        assert!(!blocks.tx[2].ti.is_set(DMA_TI::SRC_INC));
+        assert_eq!(rx_engine.regs().conblk_ad.get(), bus(&blocks.rx[1]));
+        assert_eq!(blocks.rx[1].dest_ad, rx_tail(blocks));
+        assert_eq!(blocks.rx[1].txfr_len.get(), 16);
+        assert_eq!(blocks.rx[1].nextconbk, 0);
+        assert!(blocks.rx[1].ti.is_set(DMA_TI::DEST_INC));
+
+        // The whole cache lines of `rx` are received directly
+        let mut rx = Aligned([0u8; 70]);
+        spi.read(ChipSelect::Cs0, &mut rx.0).unwrap();
+        let blocks = unsafe { &*spi.dma_blocks.get() };
+        assert_eq!(tx_engine.regs().conblk_ad.get(), bus(&blocks.tx[2]));
+        assert_eq!(blocks.tx[2].txfr_len.get(), 70);
+        assert_eq!(rx_engine.regs().conblk_ad.get(), bus(&blocks.rx[0]));
+        assert_eq!(blocks.rx[0].dest_ad, rx.0.as_ptr() as usize as u32);
+        assert_eq!(blocks.rx[0].txfr_len.get(), 64);
+        assert_eq!(blocks.rx[0].nextconbk, bus(&blocks.rx[1]));
+        assert_eq!(blocks.rx[1].dest_ad, rx_tail(blocks));
+        assert_eq!(blocks.rx[1].txfr_len.get(), 6);
+        assert_eq!(blocks.rx[1].nextconbk, 0);
+
+        // Simulate the tail written by the engine
+        let blocks = unsafe { &mut *spi.dma_blocks.get() };
+        blocks.rx_tail[..8].copy_from_slice(b"uvwxyz??");
+        spi.read(ChipSelect::Cs0, &mut rx.0).unwrap();
+        assert_eq!(&rx.0[64..], b"uvwxyz");
+    }
+
+    #[test]
+    fn dma_alignment() {
+        let device = sim::spi::Spi::new();
+        let (tx_engine, rx_engine) = (dma_engine(false), dma_engine(false));
+        let spi = dma_spi(&device, &tx_engine, &rx_engine);
+
+        let mut rx = Aligned([0u8; 65]);
+        assert_eq!(
+            spi.read(ChipSelect::Cs0, &mut rx.0[1..]),
+            Err(Error::DmaAlignment)
+        );
+        assert!(!device.regs().cs.is_set(CS::TA));
+        // Transmitting doesn't depend on the alignment
+        spi.write(ChipSelect::Cs0, &rx.0[1..]).unwrap();
+    }
+
+    #[test]
+    fn dma_error() {
+        let device = sim::spi::Spi::new();
+        let (tx_engine, rx_engine) = (dma_engine(true), dma_engine(false));
+        let spi = dma_spi(&device, &tx_engine, &rx_engine);
+
+        assert_eq!(spi.write(ChipSelect::Cs0, b"data"), Err(Error::Dma));
+        // The failed engine was reset
+        assert_eq!(tx_engine.regs().cs.get(), 0);
+        assert!(!device.regs().cs.is_set(CS::TA));
+
+        // The address isn't accessible
+        let spi = unsafe {
+            Spi::new(device.regs()).with_dma(Dma {
+                to_bus: |_| None,
+                ..spi.dma.unwrap()
+            })
+        };
+        spi.init(&Config {
+            transfer_mode: TransferMode::Dma,
+            ..Config::default()
+        })
+        .unwrap();
+        assert_eq!(spi.write(ChipSelect::Cs0, b"data"), Err(Error::DmaAddress));
+    }
+}
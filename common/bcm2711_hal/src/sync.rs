@@ -0,0 +1,45 @@
+//! Serialization of read-modify-write operations on registers shared by
+//! multiple owners (e.g., `gpfsel`, which holds the functions of ten pins)
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// The lock protecting all shared registers
+static LOCK: AtomicBool = AtomicBool::new(false);
+
+/// Call `f` while holding the global register lock.
+///
+/// With the `solid` feature, interrupts are disabled while the lock is held,
+/// so this function can be called from an interrupt handler. Without it, an
+/// interrupt handler calling this function may deadlock if it preempts
+/// another call on the same processor.
+#[inline]
+pub(crate) fn with_lock<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "solid")]
+    {
+        solid::interrupt::free(|_| with_spin_lock(f))
+    }
+    #[cfg(not(feature = "solid"))]
+    {
+        with_spin_lock(f)
+    }
+}
+
+#[inline]
+fn with_spin_lock<R>(f: impl FnOnce() -> R) -> R {
+    struct Guard;
+
+    impl Drop for Guard {
+        #[inline]
+        fn drop(&mut self) {
+            LOCK.store(false, Ordering::Release);
+        }
+    }
+
+    while LOCK
+        .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        core::hint::spin_loop();
+    }
+    let _guard = Guard;
+    f()
+}
@@ -1,13 +1,15 @@
 //! [BCM2711 ARM timer][1] (**based** on ARM SP804)
 //!
 //! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A162%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
-use tock_registers::{
-    register_bitfields, register_structs,
+use tock_registers::register_structs;
+
+use crate::{
+    describe::{Instance, Peripheral},
     registers::{ReadOnly, ReadWrite, WriteOnly},
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
 };
 
-use crate::Vpa;
-
 /// The base address of [the ARM timer register block](Registers).
 pub const BASE: Vpa = Vpa(0x4_7e00_b400);
 
@@ -35,6 +37,31 @@ register_structs! {
     }
 }
 
+impl_snapshot! {
+    Registers {
+        load,
+        value,
+        control,
+        irqcntl,
+        rawirq,
+        mskirq,
+        reload,
+        prediv,
+        freecnt,
+    }
+}
+
+/// Describe [the ARM timer register block](Registers) and its instance. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "AP804",
+        "ARM timer",
+        &[Instance::vpa("ARM_TIMER", BASE)],
+    )];
+    PERIPHERALS
+}
+
 register_bitfields! {u32,
     pub CONTROL [
         _32BIT OFFSET(1) NUMBITS(1) [
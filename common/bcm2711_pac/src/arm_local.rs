@@ -0,0 +1,298 @@
+//! [BCM2711 ARM local peripherals][1]
+//!
+//! This register block includes [the ARM Mailboxes](crate::mbox).
+//!
+//! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A166%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
+use tock_registers::{fields::Field, register_structs, RegisterLongName};
+
+use crate::{
+    describe::{Instance, Peripheral},
+    mbox,
+    registers::{ReadOnly, ReadWrite, WriteOnly},
+    snapshot::{impl_snapshot, register_bitfields, FieldInfo, RegisterInfo},
+};
+
+/// The low-peripheral ARM physical address of [the ARM local peripheral
+/// register block](Registers).
+pub const BASE_ARM_PA: u64 = 0xff80_0000;
+
+/// The number of processor cores served by this register block.
+pub const CORE_COUNT: usize = 4;
+
+register_structs! {
+    pub Registers {
+        /// ARM control
+        (0x00 => pub arm_control: ReadWrite<u32, ARM_CONTROL::Register>),
+        (0x04 => _pad0),
+        /// VideoCore interrupt control
+        (0x0c => pub core_irq_control: ReadWrite<u32, CORE_IRQ_CONTROL::Register>),
+        /// PMU interrupt routing set (W1S)
+        (0x10 => pub pmu_control_set: ReadWrite<u32, PMU_CONTROL::Register>),
+        /// PMU interrupt routing clear (W1C)
+        (0x14 => pub pmu_control_clr: ReadWrite<u32, PMU_CONTROL::Register>),
+        (0x18 => _pad1),
+        /// Peripheral interrupt routing (local timer)
+        (0x24 => pub peri_irq_route0: ReadWrite<u32, PERI_IRQ_ROUTE0::Register>),
+        (0x28 => _pad2),
+        /// AXI outstanding transaction time-out
+        (0x30 => pub axi_quiet_time: ReadWrite<u32, AXI_QUIET_TIME::Register>),
+        /// Local timer control
+        (0x34 => pub local_timer_control: ReadWrite<u32, LOCAL_TIMER_CONTROL::Register>),
+        /// Local timer interrupt clear and reload (WO)
+        (0x38 => pub local_timer_irq: WriteOnly<u32, LOCAL_TIMER_IRQ::Register>),
+        (0x3c => _pad3),
+        /// Core timer interrupt control for each core
+        (0x40 => pub timer_cntrl: [ReadWrite<u32, TIMER_CNTRL::Register>; CORE_COUNT]),
+        /// Mailbox interrupt control for each core
+        (0x50 => pub mailbox_cntrl: [ReadWrite<u32, MAILBOX_CNTRL::Register>; CORE_COUNT]),
+        /// IRQ source for each core (RO)
+        (0x60 => pub irq_source: [ReadOnly<u32, IRQ_SOURCE::Register>; CORE_COUNT]),
+        /// FIQ source for each core (RO)
+        (0x70 => pub fiq_source: [ReadOnly<u32, FIQ_SOURCE::Register>; CORE_COUNT]),
+        /// ARM mailboxes
+        (0x80 => pub mbox: mbox::Registers),
+        (0x100 => @END),
+    }
+}
+
+impl_snapshot! {
+    Registers {
+        arm_control,
+        core_irq_control,
+        pmu_control_set,
+        pmu_control_clr,
+        peri_irq_route0,
+        axi_quiet_time,
+        local_timer_control,
+        local_timer_irq,
+        timer_cntrl,
+        mailbox_cntrl,
+        irq_source,
+        fiq_source,
+        mbox,
+    }
+}
+
+/// Describe [the ARM local peripheral register block](Registers) and its
+/// instance. See [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "ARM_LOCAL",
+        "ARM local peripherals",
+        &[Instance::new("ARM_LOCAL", BASE_ARM_PA)],
+    )];
+    PERIPHERALS
+}
+
+register_bitfields! {u32,
+    pub ARM_CONTROL [
+        /// Core timer clock source
+        PROC_CLK_TIMER OFFSET(8) NUMBITS(1) [
+            Crystal = 0,
+            Apb = 1,
+        ],
+        /// Core timer increment per clock
+        TIMER_INCREMENT OFFSET(9) NUMBITS(1) [
+            One = 0,
+            Two = 1,
+        ],
+    ]
+}
+
+register_bitfields! {u32,
+    pub CORE_IRQ_CONTROL [
+        /// The core to which the AXI error interrupt is routed
+        AXI_ERR_CORE OFFSET(4) NUMBITS(3) [],
+    ]
+}
+
+#[allow(non_snake_case)]
+pub mod PMU_CONTROL {
+    use super::*;
+    pub struct Register;
+    impl RegisterLongName for Register {}
+
+    impl RegisterInfo for Register {
+        const NAME: &'static str = "PMU_CONTROL";
+        const FIELDS: &'static [FieldInfo] = &[
+            FieldInfo::array("IRQ", 0, 1, CORE_COUNT as u32, &[]),
+            FieldInfo::array("FIQ", 4, 1, CORE_COUNT as u32, &[]),
+        ];
+    }
+
+    /// Construct a [`Field`] representing the bit routing the PMU interrupt of
+    /// the specified core to the core's IRQ.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `i` is outside the range `0..`[`CORE_COUNT`].
+    #[inline]
+    pub const fn IRQ(i: usize) -> Field<u32, Register> {
+        assert!(i < CORE_COUNT);
+        Field::new(0b1, i)
+    }
+
+    /// Construct a [`Field`] representing the bit routing the PMU interrupt of
+    /// the specified core to the core's FIQ.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `i` is outside the range `0..`[`CORE_COUNT`].
+    #[inline]
+    pub const fn FIQ(i: usize) -> Field<u32, Register> {
+        assert!(i < CORE_COUNT);
+        Field::new(0b1, 4 + i)
+    }
+}
+
+register_bitfields! {u32,
+    pub PERI_IRQ_ROUTE0 [
+        /// Local timer interrupt routing
+        LOCAL_TIMER_IRQ OFFSET(0) NUMBITS(3) [
+            Core0Irq = 0b000,
+            Core1Irq = 0b001,
+            Core2Irq = 0b010,
+            Core3Irq = 0b011,
+            Core0Fiq = 0b100,
+            Core1Fiq = 0b101,
+            Core2Fiq = 0b110,
+            Core3Fiq = 0b111,
+        ],
+        /// Write mask. Only the fields whose corresponding bits are set in
+        /// this field are updated by a write.
+        WRITE_MASKS OFFSET(24) NUMBITS(8) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub AXI_QUIET_TIME [
+        /// The time-out value in the units of 2¹⁰ crystal clock cycles
+        AXI_QUIET_TIME OFFSET(0) NUMBITS(20) [],
+        /// Enable the AXI outstanding transaction time-out interrupt
+        AXI_QUIET_IRQ_ENB OFFSET(20) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub LOCAL_TIMER_CONTROL [
+        /// Reload value. The timer counts down from this value and is
+        /// automatically reloaded upon reaching zero.
+        RELOAD OFFSET(0) NUMBITS(28) [],
+        /// Timer enable
+        TIMER_EN OFFSET(28) NUMBITS(1) [],
+        /// Interrupt enable
+        INT_EN OFFSET(29) NUMBITS(1) [],
+        /// Interrupt flag (RO)
+        INT_FLAG OFFSET(31) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub LOCAL_TIMER_IRQ [
+        /// Reload the timer without generating an interrupt (W1SC)
+        RELOAD OFFSET(30) NUMBITS(1) [],
+        /// Clear the interrupt flag (W1SC)
+        INT_FLAG_CLEAR OFFSET(31) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub TIMER_CNTRL [
+        /// Route the secure physical timer interrupt to IRQ
+        CNT_PS_IRQ OFFSET(0) NUMBITS(1) [],
+        /// Route the non-secure physical timer interrupt to IRQ
+        CNT_PNS_IRQ OFFSET(1) NUMBITS(1) [],
+        /// Route the hypervisor timer interrupt to IRQ
+        CNT_HP_IRQ OFFSET(2) NUMBITS(1) [],
+        /// Route the virtual timer interrupt to IRQ
+        CNT_V_IRQ OFFSET(3) NUMBITS(1) [],
+        /// Route the secure physical timer interrupt to FIQ
+        CNT_PS_FIQ OFFSET(4) NUMBITS(1) [],
+        /// Route the non-secure physical timer interrupt to FIQ
+        CNT_PNS_FIQ OFFSET(5) NUMBITS(1) [],
+        /// Route the hypervisor timer interrupt to FIQ
+        CNT_HP_FIQ OFFSET(6) NUMBITS(1) [],
+        /// Route the virtual timer interrupt to FIQ
+        CNT_V_FIQ OFFSET(7) NUMBITS(1) [],
+    ]
+}
+
+#[allow(non_snake_case)]
+pub mod MAILBOX_CNTRL {
+    use super::*;
+    pub struct Register;
+    impl RegisterLongName for Register {}
+
+    impl RegisterInfo for Register {
+        const NAME: &'static str = "MAILBOX_CNTRL";
+        const FIELDS: &'static [FieldInfo] = &[
+            FieldInfo::array("MBOX_IRQ", 0, 1, 4, &[]),
+            FieldInfo::array("MBOX_FIQ", 4, 1, 4, &[]),
+        ];
+    }
+
+    /// Construct a [`Field`] representing the bit routing the specified
+    /// mailbox's interrupt to IRQ.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `i` is outside the range `0..4`.
+    #[inline]
+    pub const fn MBOX_IRQ(i: usize) -> Field<u32, Register> {
+        assert!(i < 4);
+        Field::new(0b1, i)
+    }
+
+    /// Construct a [`Field`] representing the bit routing the specified
+    /// mailbox's interrupt to FIQ.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `i` is outside the range `0..4`.
+    #[inline]
+    pub const fn MBOX_FIQ(i: usize) -> Field<u32, Register> {
+        assert!(i < 4);
+        Field::new(0b1, 4 + i)
+    }
+}
+
+register_bitfields! {u32,
+    pub IRQ_SOURCE [
+        /// Secure physical timer interrupt
+        CNT_PS_IRQ OFFSET(0) NUMBITS(1) [],
+        /// Non-secure physical timer interrupt
+        CNT_PNS_IRQ OFFSET(1) NUMBITS(1) [],
+        /// Hypervisor timer interrupt
+        CNT_HP_IRQ OFFSET(2) NUMBITS(1) [],
+        /// Virtual timer interrupt
+        CNT_V_IRQ OFFSET(3) NUMBITS(1) [],
+        /// Mailbox `0..4` interrupts (one bit per mailbox)
+        MAILBOX_IRQ OFFSET(4) NUMBITS(4) [],
+        /// VideoCore interrupt
+        CORE_IRQ OFFSET(8) NUMBITS(1) [],
+        /// PMU interrupt
+        PMU_IRQ OFFSET(9) NUMBITS(1) [],
+        /// AXI outstanding transaction time-out interrupt (core 0 only)
+        AXI_QUIET OFFSET(10) NUMBITS(1) [],
+        /// Local timer interrupt
+        LOCAL_TIMER_IRQ OFFSET(11) NUMBITS(1) [],
+    ],
+    pub FIQ_SOURCE [
+        /// Secure physical timer interrupt
+        CNT_PS_FIQ OFFSET(0) NUMBITS(1) [],
+        /// Non-secure physical timer interrupt
+        CNT_PNS_FIQ OFFSET(1) NUMBITS(1) [],
+        /// Hypervisor timer interrupt
+        CNT_HP_FIQ OFFSET(2) NUMBITS(1) [],
+        /// Virtual timer interrupt
+        CNT_V_FIQ OFFSET(3) NUMBITS(1) [],
+        /// Mailbox `0..4` interrupts (one bit per mailbox)
+        MAILBOX_FIQ OFFSET(4) NUMBITS(4) [],
+        /// VideoCore interrupt
+        CORE_FIQ OFFSET(8) NUMBITS(1) [],
+        /// PMU interrupt
+        PMU_FIQ OFFSET(9) NUMBITS(1) [],
+        /// Local timer interrupt
+        LOCAL_TIMER_FIQ OFFSET(11) NUMBITS(1) [],
+    ]
+}
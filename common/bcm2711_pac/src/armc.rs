@@ -0,0 +1,462 @@
+//! [BCM2711 ARMC legacy interrupt controller][1]
+//!
+//! The GIC-400 receives each VideoCore peripheral interrupt on a separate
+//! line (see [`irq`](crate::irq)), but the legacy interrupt controller is
+//! still present and reports the same sources in its pending registers. This
+//! is useful to find which peripheral raised a shared line, and its software
+//! interrupts ([`Registers::swirq_set`]) can be used to trigger
+//! [`irq::SWIRQ`](crate::irq::SWIRQ) for testing.
+//!
+//! Each core has an IRQ bank ([`Registers::irq`]) and an FIQ bank
+//! ([`Registers::fiq`]) consisting of three pending registers and the
+//! corresponding enable set/clear registers:
+//!
+//! | Register      | Layout        | Sources                           |
+//! | ------------- | ------------- | --------------------------------- |
+//! | `*0`          | [`VC_IRQ0`]   | VideoCore peripheral IRQs 0–31    |
+//! | `*1`          | [`VC_IRQ1`]   | VideoCore peripheral IRQs 32–63   |
+//! | `*2`          | [`ARMC_IRQ`]  | ARMC interrupts and summary bits  |
+//!
+//! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A105%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    register_structs,
+};
+
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::{ReadOnly, ReadWrite},
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
+};
+
+/// The base address of [the ARMC interrupt register block](Registers).
+pub const BASE: Vpa = Vpa(0x4_7e00_b200);
+
+/// The number of processor cores served by this register block.
+pub const CORE_COUNT: usize = 4;
+
+register_structs! {
+    pub Registers {
+        /// Core 0 IRQ bank
+        (0x000 => pub irq0: BankRegisters),
+        (0x02c => _pad0),
+        /// Interrupt line bits 31:0 (RO). Reflects the raw state of the
+        /// sources regardless of the enable registers.
+        (0x030 => pub irq_status0: ReadOnly<u32, VC_IRQ0::Register>),
+        /// Interrupt line bits 63:32 (RO)
+        (0x034 => pub irq_status1: ReadOnly<u32, VC_IRQ1::Register>),
+        /// Interrupt line bits 79:64 (RO)
+        (0x038 => pub irq_status2: ReadOnly<u32, ARMC_IRQ::Register>),
+        (0x03c => _pad1),
+        /// Core 1 IRQ bank
+        (0x040 => pub irq1: BankRegisters),
+        (0x06c => _pad2),
+        /// Core 2 IRQ bank
+        (0x080 => pub irq2: BankRegisters),
+        (0x0ac => _pad3),
+        /// Core 3 IRQ bank
+        (0x0c0 => pub irq3: BankRegisters),
+        (0x0ec => _pad4),
+        /// Software interrupt set (W1S). Reads the current state.
+        (0x0f0 => pub swirq_set: ReadWrite<u32, SWIRQ::Register>),
+        /// Software interrupt clear (W1C). Reads the current state.
+        (0x0f4 => pub swirq_clear: ReadWrite<u32, SWIRQ::Register>),
+        (0x0f8 => _pad5),
+        /// Core 0 FIQ bank
+        (0x100 => pub fiq0: BankRegisters),
+        (0x12c => _pad6),
+        /// Core 1 FIQ bank
+        (0x140 => pub fiq1: BankRegisters),
+        (0x16c => _pad7),
+        /// Core 2 FIQ bank
+        (0x180 => pub fiq2: BankRegisters),
+        (0x1ac => _pad8),
+        /// Core 3 FIQ bank
+        (0x1c0 => pub fiq3: BankRegisters),
+        (0x1ec => _pad9),
+        (0x200 => @END),
+    },
+
+    /// The per-core IRQ or FIQ bank
+    pub BankRegisters {
+        /// Pending VideoCore peripheral IRQs 0–31 (RO)
+        (0x00 => pub pending0: ReadOnly<u32, VC_IRQ0::Register>),
+        /// Pending VideoCore peripheral IRQs 32–63 (RO)
+        (0x04 => pub pending1: ReadOnly<u32, VC_IRQ1::Register>),
+        /// Pending ARMC interrupts and summary bits (RO)
+        (0x08 => pub pending2: ReadOnly<u32, ARMC_IRQ::Register>),
+        (0x0c => _pad0),
+        /// Enable VideoCore peripheral IRQs 0–31 (W1S). Reads the current
+        /// enable state.
+        (0x10 => pub set_en_0: ReadWrite<u32, VC_IRQ0::Register>),
+        /// Enable VideoCore peripheral IRQs 32–63 (W1S)
+        (0x14 => pub set_en_1: ReadWrite<u32, VC_IRQ1::Register>),
+        /// Enable ARMC interrupts (W1S)
+        (0x18 => pub set_en_2: ReadWrite<u32, ARMC_IRQ::Register>),
+        (0x1c => _pad1),
+        /// Disable VideoCore peripheral IRQs 0–31 (W1C). Reads the current
+        /// enable state.
+        (0x20 => pub clr_en_0: ReadWrite<u32, VC_IRQ0::Register>),
+        /// Disable VideoCore peripheral IRQs 32–63 (W1C)
+        (0x24 => pub clr_en_1: ReadWrite<u32, VC_IRQ1::Register>),
+        /// Disable ARMC interrupts (W1C)
+        (0x28 => pub clr_en_2: ReadWrite<u32, ARMC_IRQ::Register>),
+        (0x2c => @END),
+    }
+}
+
+impl_snapshot! {
+    Registers {
+        irq0,
+        irq_status0,
+        irq_status1,
+        irq_status2,
+        irq1,
+        irq2,
+        irq3,
+        swirq_set,
+        swirq_clear,
+        fiq0,
+        fiq1,
+        fiq2,
+        fiq3,
+    }
+    BankRegisters {
+        pending0,
+        pending1,
+        pending2,
+        set_en_0,
+        set_en_1,
+        set_en_2,
+        clr_en_0,
+        clr_en_1,
+        clr_en_2,
+    }
+}
+
+/// Describe [the ARMC register block](Registers) and its instance. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "ARMC",
+        "ARMC legacy interrupt controller",
+        &[Instance::vpa("ARMC", BASE)],
+    )];
+    PERIPHERALS
+}
+
+register_bitfields! {u32,
+    /// VideoCore peripheral interrupts 0–31
+    pub VC_IRQ0 [
+        /// System timer compare channel 0
+        TIMER0 OFFSET(0) NUMBITS(1) [],
+        /// System timer compare channel 1
+        TIMER1 OFFSET(1) NUMBITS(1) [],
+        /// System timer compare channel 2
+        TIMER2 OFFSET(2) NUMBITS(1) [],
+        /// System timer compare channel 3
+        TIMER3 OFFSET(3) NUMBITS(1) [],
+        /// H.264 0
+        H264_0 OFFSET(4) NUMBITS(1) [],
+        /// H.264 1
+        H264_1 OFFSET(5) NUMBITS(1) [],
+        /// H.264 2
+        H264_2 OFFSET(6) NUMBITS(1) [],
+        /// JPEG
+        JPEG OFFSET(7) NUMBITS(1) [],
+        /// ISP
+        ISP OFFSET(8) NUMBITS(1) [],
+        /// USB
+        USB OFFSET(9) NUMBITS(1) [],
+        /// V3D
+        V3D OFFSET(10) NUMBITS(1) [],
+        /// Transposer
+        TRANSPOSER OFFSET(11) NUMBITS(1) [],
+        /// Multicore sync 0
+        MULTICORESYNC0 OFFSET(12) NUMBITS(1) [],
+        /// Multicore sync 1
+        MULTICORESYNC1 OFFSET(13) NUMBITS(1) [],
+        /// Multicore sync 2
+        MULTICORESYNC2 OFFSET(14) NUMBITS(1) [],
+        /// Multicore sync 3
+        MULTICORESYNC3 OFFSET(15) NUMBITS(1) [],
+        /// DMA channel 0
+        DMA0 OFFSET(16) NUMBITS(1) [],
+        /// DMA channel 1
+        DMA1 OFFSET(17) NUMBITS(1) [],
+        /// DMA channel 2
+        DMA2 OFFSET(18) NUMBITS(1) [],
+        /// DMA channel 3
+        DMA3 OFFSET(19) NUMBITS(1) [],
+        /// DMA channel 4
+        DMA4 OFFSET(20) NUMBITS(1) [],
+        /// DMA channel 5
+        DMA5 OFFSET(21) NUMBITS(1) [],
+        /// DMA channel 6
+        DMA6 OFFSET(22) NUMBITS(1) [],
+        /// DMA channels 7 and 8
+        DMA7_8 OFFSET(23) NUMBITS(1) [],
+        /// DMA channels 9 and 10
+        DMA9_10 OFFSET(24) NUMBITS(1) [],
+        /// DMA channel 11
+        DMA11 OFFSET(25) NUMBITS(1) [],
+        /// DMA channel 12
+        DMA12 OFFSET(26) NUMBITS(1) [],
+        /// DMA channel 13
+        DMA13 OFFSET(27) NUMBITS(1) [],
+        /// DMA channel 14
+        DMA14 OFFSET(28) NUMBITS(1) [],
+        /// Auxiliary peripherals (Mini UART, SPI1, SPI2)
+        AUX OFFSET(29) NUMBITS(1) [],
+        /// ARM
+        ARM OFFSET(30) NUMBITS(1) [],
+        /// DMA channel 15
+        DMA15 OFFSET(31) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    /// VideoCore peripheral interrupts 32–63
+    pub VC_IRQ1 [
+        /// HDMI CEC
+        HDMI_CEC OFFSET(0) NUMBITS(1) [],
+        /// HVS
+        HVS OFFSET(1) NUMBITS(1) [],
+        /// RPIVID
+        RPIVID OFFSET(2) NUMBITS(1) [],
+        /// SDC
+        SDC OFFSET(3) NUMBITS(1) [],
+        /// DSI0
+        DSI0 OFFSET(4) NUMBITS(1) [],
+        /// Pixel valve 2
+        PIXELVALVE2 OFFSET(5) NUMBITS(1) [],
+        /// Camera 0
+        CAMERA0 OFFSET(6) NUMBITS(1) [],
+        /// Camera 1
+        CAMERA1 OFFSET(7) NUMBITS(1) [],
+        /// HDMI0
+        HDMI0 OFFSET(8) NUMBITS(1) [],
+        /// HDMI1
+        HDMI1 OFFSET(9) NUMBITS(1) [],
+        /// Pixel valve 3
+        PIXELVALVE3 OFFSET(10) NUMBITS(1) [],
+        /// BSC/SPI slave
+        SPI_BSC_SLAVE OFFSET(11) NUMBITS(1) [],
+        /// DSI1
+        DSI1 OFFSET(12) NUMBITS(1) [],
+        /// Pixel valve 0
+        PIXELVALVE0 OFFSET(13) NUMBITS(1) [],
+        /// Pixel valves 1 and 4
+        PIXELVALVE1_4 OFFSET(14) NUMBITS(1) [],
+        /// CPR
+        CPR OFFSET(15) NUMBITS(1) [],
+        /// SMI
+        SMI OFFSET(16) NUMBITS(1) [],
+        /// GPIO bank 0
+        GPIO0 OFFSET(17) NUMBITS(1) [],
+        /// GPIO bank 1
+        GPIO1 OFFSET(18) NUMBITS(1) [],
+        /// GPIO bank 2
+        GPIO2 OFFSET(19) NUMBITS(1) [],
+        /// Any GPIO bank
+        GPIO3 OFFSET(20) NUMBITS(1) [],
+        /// BSC master controllers (all instances)
+        I2C OFFSET(21) NUMBITS(1) [],
+        /// SPI master controllers (all instances)
+        SPI OFFSET(22) NUMBITS(1) [],
+        /// PCM/I2S
+        PCM_I2S OFFSET(23) NUMBITS(1) [],
+        /// SDHOST
+        SDHOST OFFSET(24) NUMBITS(1) [],
+        /// PL011 UARTs (all instances)
+        PL011_UART OFFSET(25) NUMBITS(1) [],
+        /// Ethernet/PCIe L2
+        ETH_PCIE_L2 OFFSET(26) NUMBITS(1) [],
+        /// VEC
+        VEC OFFSET(27) NUMBITS(1) [],
+        /// CPG
+        CPG OFFSET(28) NUMBITS(1) [],
+        /// RNG
+        RNG OFFSET(29) NUMBITS(1) [],
+        /// EMMC and EMMC2
+        EMMC OFFSET(30) NUMBITS(1) [],
+        /// Ethernet/PCIe secure
+        ETH_PCIE_SECURE OFFSET(31) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    /// ARMC interrupts. The summary bits ([`INT31_0`], [`INT63_32`], and
+    /// [`IRQ`]) are only present in the pending and status registers.
+    pub ARMC_IRQ [
+        /// ARM timer (AP804)
+        TIMER_IRQ OFFSET(0) NUMBITS(1) [],
+        /// ARM mailbox
+        MAILBOX_IRQ0 OFFSET(1) NUMBITS(1) [],
+        /// ARM doorbell 0
+        BELL_IRQ0 OFFSET(2) NUMBITS(1) [],
+        /// ARM doorbell 1
+        BELL_IRQ1 OFFSET(3) NUMBITS(1) [],
+        /// VPU core 0 halted
+        VPU_C0_C1_HALT OFFSET(4) NUMBITS(1) [],
+        /// VPU core 1 halted
+        VPU_C1_HALT OFFSET(5) NUMBITS(1) [],
+        /// ARM address error
+        ARM_ADDR_ERROR OFFSET(6) NUMBITS(1) [],
+        /// ARM AXI error
+        ARM_AXI_ERROR OFFSET(7) NUMBITS(1) [],
+        /// Software interrupts 0–7 (see [`Registers::swirq_set`])
+        SW_TRIG_INT OFFSET(8) NUMBITS(8) [],
+        /// Some interrupt in [`BankRegisters::pending0`] is pending
+        INT31_0 OFFSET(24) NUMBITS(1) [],
+        /// Some interrupt in [`BankRegisters::pending1`] is pending
+        INT63_32 OFFSET(25) NUMBITS(1) [],
+        /// Some interrupt is pending
+        IRQ OFFSET(31) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub SWIRQ [
+        /// Software interrupts 0–7. Bit `i` corresponds to
+        /// [`irq::SWIRQ`](crate::irq::SWIRQ)`[i]`.
+        SW_INT OFFSET(0) NUMBITS(8) [],
+    ]
+}
+
+impl Registers {
+    /// Get the IRQ bank of the specified core.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `core` is outside the range `0..`[`CORE_COUNT`].
+    #[inline]
+    pub fn irq(&self, core: usize) -> &BankRegisters {
+        match core {
+            0 => &self.irq0,
+            1 => &self.irq1,
+            2 => &self.irq2,
+            3 => &self.irq3,
+            _ => panic!("core index out of range"),
+        }
+    }
+
+    /// Get the FIQ bank of the specified core.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `core` is outside the range `0..`[`CORE_COUNT`].
+    #[inline]
+    pub fn fiq(&self, core: usize) -> &BankRegisters {
+        match core {
+            0 => &self.fiq0,
+            1 => &self.fiq1,
+            2 => &self.fiq2,
+            3 => &self.fiq3,
+            _ => panic!("core index out of range"),
+        }
+    }
+
+    /// Get the raw state of all VideoCore peripheral interrupts. Bit `n`
+    /// corresponds to VideoCore peripheral interrupt `n`
+    /// ([`irq::Irq::vc`](crate::irq::Irq::vc)`(n)`).
+    #[inline]
+    pub fn vc_status(&self) -> u64 {
+        self.irq_status0.get() as u64 | (self.irq_status1.get() as u64) << 32
+    }
+
+    /// Raise software interrupt `i`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `i` is outside the range `0..8`.
+    #[inline]
+    pub fn trigger_swirq(&self, i: usize) {
+        assert!(i < 8);
+        self.swirq_set.write(SWIRQ::SW_INT.val(1 << i));
+    }
+
+    /// Lower software interrupt `i`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `i` is outside the range `0..8`.
+    #[inline]
+    pub fn clear_swirq(&self, i: usize) {
+        assert!(i < 8);
+        self.swirq_clear.write(SWIRQ::SW_INT.val(1 << i));
+    }
+}
+
+impl BankRegisters {
+    /// Get the pending VideoCore peripheral interrupts routed to this bank.
+    /// Bit `n` corresponds to VideoCore peripheral interrupt `n`.
+    #[inline]
+    pub fn pending_vc(&self) -> u64 {
+        self.pending0.get() as u64 | (self.pending1.get() as u64) << 32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{mem::MaybeUninit, ptr::addr_of};
+
+    #[test]
+    fn offsets() {
+        let regs = MaybeUninit::<Registers>::uninit();
+        let regs = regs.as_ptr();
+        let offset = |p: *const u32| p as usize - regs as usize;
+
+        // The register addresses listed in the datasheet, which are relative
+        // to `BASE - 0x200`
+        // Safety: `addr_of!` doesn't read the (uninitialized) registers
+        let actual = unsafe {
+            [
+                offset(addr_of!((*regs).irq0.pending0).cast()),
+                offset(addr_of!((*regs).irq0.set_en_0).cast()),
+                offset(addr_of!((*regs).irq0.clr_en_2).cast()),
+                offset(addr_of!((*regs).irq_status0).cast()),
+                offset(addr_of!((*regs).irq_status1).cast()),
+                offset(addr_of!((*regs).irq_status2).cast()),
+                offset(addr_of!((*regs).irq1.pending0).cast()),
+                offset(addr_of!((*regs).irq2.pending0).cast()),
+                offset(addr_of!((*regs).irq3.pending0).cast()),
+                offset(addr_of!((*regs).irq3.clr_en_2).cast()),
+                offset(addr_of!((*regs).swirq_set).cast()),
+                offset(addr_of!((*regs).swirq_clear).cast()),
+                offset(addr_of!((*regs).fiq0.pending0).cast()),
+                offset(addr_of!((*regs).fiq1.pending0).cast()),
+                offset(addr_of!((*regs).fiq2.pending0).cast()),
+                offset(addr_of!((*regs).fiq3.pending0).cast()),
+                offset(addr_of!((*regs).fiq3.clr_en_2).cast()),
+            ]
+        };
+        let expected = [
+            0x200, // IRQ0_PENDING0
+            0x210, // IRQ0_SET_EN_0
+            0x228, // IRQ0_CLR_EN_2
+            0x230, // IRQ_STATUS0
+            0x234, // IRQ_STATUS1
+            0x238, // IRQ_STATUS2
+            0x240, // IRQ1_PENDING0
+            0x280, // IRQ2_PENDING0
+            0x2c0, // IRQ3_PENDING0
+            0x2e8, // IRQ3_CLR_EN_2
+            0x2f0, // SWIRQ_SET
+            0x2f4, // SWIRQ_CLEAR
+            0x300, // FIQ0_PENDING0
+            0x340, // FIQ1_PENDING0
+            0x380, // FIQ2_PENDING0
+            0x3c0, // FIQ3_PENDING0
+            0x3e8, // FIQ3_CLR_EN_2
+        ]
+        .map(|x| x - 0x200);
+        assert_eq!(actual, expected);
+
+        // The block must not overlap with the next one
+        assert_eq!(core::mem::size_of::<Registers>(), 0x200);
+        assert_eq!(BASE.0 + 0x200, crate::ap804::BASE.0);
+    }
+}
@@ -1,13 +1,15 @@
 //! [Auxiliaries][1]
 //!
 //! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A11%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
-use tock_registers::{
-    register_bitfields, register_structs,
+use tock_registers::register_structs;
+
+use crate::{
+    describe::{Instance, Peripheral},
     registers::{ReadOnly, ReadWrite},
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
 };
 
-use crate::Vpa;
-
 /// The base address of [the AUX Mini UART register block](Registers).
 pub const BASE: Vpa = Vpa(0x4_7e21_5000);
 
@@ -56,6 +58,57 @@ register_structs! {
     }
 }
 
+impl_snapshot! {
+    Registers {
+        aux_irq,
+        aux_enables,
+        aux_mu,
+        aux_spi1,
+        aux_spi2,
+    }
+    MiniUartRegisters {
+        // Reading `AUX_MU_IO_REG` pops the receive FIFO
+        io_reg: skip,
+        ier_reg,
+        iir_reg,
+        lcr_reg,
+        mcr_reg,
+        // Reading `AUX_MU_LSR_REG` clears the receiver overrun flag
+        lsr_reg: skip,
+        msr_reg,
+        scratch,
+        cntl_reg,
+        stat_reg,
+        baud_reg,
+    }
+    SpiRegisters {
+        cntl0_reg,
+        cntl1_reg,
+        stat_reg,
+        peek_reg,
+        // Reading `AUX_SPI_IO_REG` or `AUX_SPI_TXHOLD_REG` pops the receive FIFO
+        io_rega: skip,
+        io_regb: skip,
+        io_regc: skip,
+        io_regd: skip,
+        txhold_rega: skip,
+        txhold_regb: skip,
+        txhold_regc: skip,
+        txhold_regd: skip,
+    }
+}
+
+/// Describe [the auxiliary peripheral register block](Registers) and its
+/// instance. See [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "AUX",
+        "Auxiliaries (Mini UART, SPI1, and SPI2)",
+        &[Instance::vpa("AUX", BASE)],
+    )];
+    PERIPHERALS
+}
+
 register_bitfields! {u32,
     pub AUX_IRQ [
         /// If set the mini UART has an interrupt pending.
@@ -83,18 +136,22 @@ register_bitfields! {u32,
         /// Receive data read, `DLAB` = 0
         RX_DATA OFFSET(0) NUMBITS(8) [],
         /// Transmit data write, `DLAB` = 0
-        TX_DATA OFFSET(8) NUMBITS(8) [],
+        TX_DATA OFFSET(0) NUMBITS(8) [],
         /// LS 8 bits baudrate, `DLAB` = 1
-        BAUDRATE_LO OFFSET(16) NUMBITS(8) [],
+        BAUDRATE_LO OFFSET(0) NUMBITS(8) [],
     ]
 }
 
 register_bitfields! {u32,
     pub AUX_MU_IER_REG [
+        /// Enable receive interrupt, `DLAB` = 0. The datasheet swaps this
+        /// bit and [`TX_INT_ENABLE`](const@AUX_MU_IER_REG::TX_INT_ENABLE).
+        RX_INT_ENABLE OFFSET(0) NUMBITS(1) [],
         /// Enable transmit interrupt, `DLAB` = 0
-        TX_INT_ENABLE OFFSET(0) NUMBITS(1) [],
-        /// Enable receive interrupt, `DLAB` = 0
-        RX_INT_ENABLE OFFSET(1) NUMBITS(1) [],
+        TX_INT_ENABLE OFFSET(1) NUMBITS(1) [],
+        /// Undocumented, `DLAB` = 0. Must be set to `0b11` for the receive
+        /// interrupt to be raised.
+        RX_INT_ENABLE_EXTRA OFFSET(2) NUMBITS(2) [],
         /// MS 8 bits baudrate, `DLAB` = 1
         BAUDRATE_HI OFFSET(0) NUMBITS(8) [],
     ]
@@ -113,10 +170,11 @@ register_bitfields! {u32,
 
 register_bitfields! {u32,
     pub AUX_MU_LCR_REG [
-        /// Data size
-        DATA_SIZE OFFSET(0) NUMBITS(1) [
-            SevenBits = 7,
-            EightBits = 8,
+        /// Data size. The datasheet documents only bit 0, but bit 1 must be
+        /// set as well to select the 8-bit mode.
+        DATA_SIZE OFFSET(0) NUMBITS(2) [
+            SevenBits = 0b00,
+            EightBits = 0b11,
         ],
         /// Break
         BREAK OFFSET(6) NUMBITS(1) [],
@@ -318,6 +376,9 @@ register_bitfields! {u32,
 
 register_bitfields! {u32,
     pub AUX_SPI_DATA [
-        DATA OFFSET(0) NUMBITS(16) [],
+        /// Data. The shift register shifts out from bit 31 (MSB first; bit 23 in
+        /// the variable width mode) or bit 0 (LSB first) and shifts in at bit 0
+        /// (MSB first) or bit 31 (LSB first).
+        DATA OFFSET(0) NUMBITS(32) [],
     ]
 }
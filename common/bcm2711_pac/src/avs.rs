@@ -0,0 +1,254 @@
+//! BCM2711 AVS monitor (SoC temperature sensor)
+//!
+//! The AVS monitor is not documented in the BCM2711 datasheet. The register
+//! definitions and the conversion formula in this module are based on the
+//! Linux `bcm2711_thermal` driver and the Raspberry Pi 4 device tree.
+//!
+//! [`Thermometer`] samples the sensor and maintains a smoothed reading.
+use core::sync::atomic::{AtomicI32, Ordering};
+use tock_registers::{interfaces::Readable, register_structs, LocalRegisterCopy};
+
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::ReadOnly,
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
+};
+
+/// The base address of [the AVS monitor register block](Registers).
+pub const BASE: Vpa = Vpa(0x4_7d5d_2000);
+
+/// The low-peripheral ARM physical address of [`BASE`], usable in constant
+/// contexts.
+pub const BASE_ARM_PA: u64 = 0xfd5d_2000;
+
+/// The slope of the conversion from [`RO_TEMP_STATUS::DATA`](const@RO_TEMP_STATUS::DATA)
+/// to temperature, in millidegrees Celsius per LSB.
+pub const TEMP_SLOPE: i32 = -487;
+
+/// The offset of the conversion from [`RO_TEMP_STATUS::DATA`](const@RO_TEMP_STATUS::DATA)
+/// to temperature, in millidegrees Celsius.
+pub const TEMP_OFFSET: i32 = 410_040;
+
+register_structs! {
+    pub Registers {
+        (0x000 => _pad0),
+        /// Temperature status (RO)
+        (0x200 => pub ro_temp_status: ReadOnly<u32, RO_TEMP_STATUS::Register>),
+        (0x204 => @END),
+    }
+}
+
+impl_snapshot! {
+    Registers {
+        ro_temp_status,
+    }
+}
+
+/// Describe [the AVS monitor register block](Registers) and its instance. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "AVS",
+        "AVS monitor (SoC temperature sensor)",
+        &[Instance::vpa("AVS", BASE)],
+    )];
+    PERIPHERALS
+}
+
+register_bitfields! {u32,
+    pub RO_TEMP_STATUS [
+        /// Raw temperature code
+        DATA OFFSET(0) NUMBITS(10) [],
+        /// Valid flag 1. Both this and [`VALID2`] must be set for [`DATA`] to
+        /// be valid.
+        VALID1 OFFSET(10) NUMBITS(1) [],
+        /// Valid flag 2
+        VALID2 OFFSET(16) NUMBITS(1) [],
+    ]
+}
+
+/// Convert a raw temperature code to millidegrees Celsius.
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_pac::avs::raw_to_millicelsius;
+/// assert_eq!(raw_to_millicelsius(742), 48_686);
+/// ```
+#[inline]
+pub const fn raw_to_millicelsius(raw: u32) -> i32 {
+    TEMP_SLOPE * (raw & 0x3ff) as i32 + TEMP_OFFSET
+}
+
+/// Decode the value of [`Registers::ro_temp_status`]. Returns the temperature
+/// in millidegrees Celsius or `None` if the value is not valid.
+#[inline]
+pub fn decode_status(status: LocalRegisterCopy<u32, RO_TEMP_STATUS::Register>) -> Option<i32> {
+    if status.is_set(RO_TEMP_STATUS::VALID1) && status.is_set(RO_TEMP_STATUS::VALID2) {
+        Some(raw_to_millicelsius(status.read(RO_TEMP_STATUS::DATA)))
+    } else {
+        None
+    }
+}
+
+/// Apply one step of an exponential moving average with weight
+/// `1 / 2^shift` given to `sample`.
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_pac::avs::smooth;
+/// assert_eq!(smooth(40_000, 48_000, 2), 42_000);
+/// assert_eq!(smooth(40_000, 48_000, 0), 48_000);
+/// ```
+#[inline]
+pub const fn smooth(prev: i32, sample: i32, shift: u32) -> i32 {
+    prev + (sample - prev) / (1 << shift)
+}
+
+/// The value of [`Thermometer::smoothed`] indicating no valid sample
+const NO_READING: i32 = i32::MIN;
+
+/// Samples the SoC temperature sensor and maintains an exponential moving
+/// average of the readings.
+///
+/// All methods take `&self` so that a `Thermometer` can be placed in a
+/// `static`, sampled from a timer handler, and read from tasks. With the
+/// `solid` feature, `&'static Thermometer` can be used directly as the handler
+/// of a `solid::timer::Timer`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use bcm2711_pac::avs;
+///
+/// static THERMOMETER: avs::Thermometer =
+///     unsafe { avs::Thermometer::new(avs::BASE_ARM_PA as usize as *const avs::Registers, 3) };
+///
+/// // Call this periodically
+/// THERMOMETER.sample();
+///
+/// if let Some(t) = THERMOMETER.millicelsius() {
+///     println!("{}.{:03} °C", t / 1000, t % 1000);
+/// }
+/// ```
+pub struct Thermometer {
+    regs: *const Registers,
+    /// The smoothed reading in millidegrees Celsius or [`NO_READING`]
+    smoothed: AtomicI32,
+    /// The smoothing factor passed to [`smooth`]
+    shift: u32,
+}
+
+// Safety: The register block is only read, and the shared state is atomic.
+unsafe impl Send for Thermometer {}
+unsafe impl Sync for Thermometer {}
+
+impl Thermometer {
+    /// Construct a `Thermometer`. Each new sample is given the weight
+    /// `1 / 2^shift` in the moving average; pass `0` to disable smoothing.
+    ///
+    /// # Safety
+    ///
+    /// `regs` must point to [the AVS monitor register block](Registers) and
+    /// remain valid for the lifetime of the constructed `Thermometer`.
+    #[inline]
+    pub const unsafe fn new(regs: *const Registers, shift: u32) -> Self {
+        assert!(shift < 16, "smoothing factor too large");
+        Self {
+            regs,
+            smoothed: AtomicI32::new(NO_READING),
+            shift,
+        }
+    }
+
+    /// Read the sensor without updating the moving average. Returns the
+    /// temperature in millidegrees Celsius or `None` if the sensor has no
+    /// valid reading.
+    #[inline]
+    pub fn read(&self) -> Option<i32> {
+        // Safety: Upheld by the caller of `Self::new`
+        let regs = unsafe { &*self.regs };
+        decode_status(regs.ro_temp_status.extract())
+    }
+
+    /// Read the sensor and update the moving average. Returns the
+    /// instantaneous temperature in millidegrees Celsius.
+    ///
+    /// This method should be called from one context at a time (e.g., a
+    /// single timer handler).
+    pub fn sample(&self) -> Option<i32> {
+        let sample = self.read()?;
+        let prev = self.smoothed.load(Ordering::Relaxed);
+        let new = if prev == NO_READING {
+            sample
+        } else {
+            smooth(prev, sample, self.shift)
+        };
+        self.smoothed.store(new, Ordering::Relaxed);
+        Some(sample)
+    }
+
+    /// Get the smoothed temperature in millidegrees Celsius. Returns `None`
+    /// if [`Self::sample`] has never got a valid reading.
+    #[inline]
+    pub fn millicelsius(&self) -> Option<i32> {
+        match self.smoothed.load(Ordering::Relaxed) {
+            NO_READING => None,
+            x => Some(x),
+        }
+    }
+}
+
+#[cfg(feature = "solid")]
+impl<'a> solid::closure::FuncMut<(solid::thread::CpuCx<'a>,)> for &'static Thermometer {
+    type Output = ();
+
+    #[inline]
+    fn call(&mut self, _: (solid::thread::CpuCx<'a>,)) {
+        self.sample();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversion() {
+        // Reference values computed from the device tree coefficients
+        assert_eq!(raw_to_millicelsius(0), 410_040);
+        assert_eq!(raw_to_millicelsius(742), 48_686);
+        assert_eq!(raw_to_millicelsius(800), 20_440);
+        assert_eq!(raw_to_millicelsius(0x3ff), -88_161);
+        // Bits outside `DATA` are ignored
+        assert_eq!(raw_to_millicelsius(0x1_0400 | 742), 48_686);
+    }
+
+    #[test]
+    fn decode() {
+        let status = |x| LocalRegisterCopy::<u32, RO_TEMP_STATUS::Register>::new(x);
+        assert_eq!(decode_status(status(0x1_0400 | 742)), Some(48_686));
+        assert_eq!(decode_status(status(0x0_0400 | 742)), None);
+        assert_eq!(decode_status(status(0x1_0000 | 742)), None);
+        assert_eq!(decode_status(status(0)), None);
+    }
+
+    #[test]
+    fn smoothing() {
+        let mut t = 40_000;
+        for _ in 0..100 {
+            t = smooth(t, 50_000, 3);
+        }
+        assert!((49_990..=50_000).contains(&t));
+
+        let mut t = 50_000;
+        for _ in 0..100 {
+            t = smooth(t, 40_000, 3);
+        }
+        assert!((40_000..=40_010).contains(&t));
+
+        assert_eq!(smooth(45_000, 45_000, 5), 45_000);
+    }
+}
@@ -0,0 +1,41 @@
+//! Write a machine-readable description of the register blocks of
+//! `bcm2711_pac`. See `bcm2711_pac::describe` for the output formats.
+//!
+//! ```text
+//! Usage: describe <svd|json> [OUTPUT]
+//! ```
+//!
+//! The output is written to the standard output if `OUTPUT` is omitted.
+use bcm2711_pac::describe;
+use std::{env, fs, io::Write, process::exit};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (format, output) = match &args[..] {
+        [format] => (format.as_str(), None),
+        [format, output] => (format.as_str(), Some(output.as_str())),
+        _ => usage(),
+    };
+
+    let mut text = String::new();
+    match format {
+        "svd" => describe::write_svd(&mut text, describe::peripherals()),
+        "json" => describe::write_json(&mut text, describe::peripherals()),
+        _ => usage(),
+    }
+    .expect("formatting failed");
+
+    let result = match output {
+        Some(path) => fs::write(path, text),
+        None => std::io::stdout().write_all(text.as_bytes()),
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        exit(1);
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: describe <svd|json> [OUTPUT]");
+    exit(2);
+}
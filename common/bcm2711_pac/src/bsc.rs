@@ -1,9 +1,14 @@
 //! [Broadcom Serial Control controllers][1]
 //!
 //! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A27%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
-use tock_registers::{register_bitfields, register_structs, registers::ReadWrite};
+use tock_registers::register_structs;
 
-use crate::Vpa;
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::ReadWrite,
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
+};
 
 /// The base address of the BSC0 instance of [the BSC register block](Registers).
 pub const BASE_BSC0: Vpa = Vpa(0x4_7e20_5000);
@@ -40,6 +45,38 @@ register_structs! {
     }
 }
 
+impl_snapshot! {
+    Registers {
+        c,
+        s,
+        dlen,
+        a,
+        // Reading `FIFO` pops the receive FIFO
+        fifo: skip,
+        div,
+        del,
+        clkt,
+    }
+}
+
+/// Describe [the BSC register block](Registers) and its instances. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "BSC",
+        "Broadcom Serial Control controller",
+        &[
+            Instance::vpa("BSC0", BASE_BSC0),
+            Instance::vpa("BSC1", BASE_BSC1),
+            Instance::vpa("BSC3", BASE_BSC3),
+            Instance::vpa("BSC4", BASE_BSC4),
+            Instance::vpa("BSC5", BASE_BSC5),
+            Instance::vpa("BSC6", BASE_BSC6),
+        ],
+    )];
+    PERIPHERALS
+}
+
 register_bitfields! {u32,
     pub C [
         /// Read transfer
@@ -0,0 +1,469 @@
+//! [BSC/SPI slave controller][1]
+//!
+//! [`BscSlave`] is an interrupt-driven I²C slave driver exposing a register
+//! file (an array of byte-wide registers addressed by an 8-bit pointer) to the
+//! bus master.
+//!
+//! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A33%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
+use tock_registers::{
+    interfaces::{ReadWriteable, Readable, Writeable},
+    register_structs,
+};
+
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::{ReadOnly, ReadWrite, WriteOnly},
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
+};
+
+/// The base address of [the BSC/SPI slave register block](Registers).
+pub const BASE: Vpa = Vpa(0x4_7e21_4000);
+
+/// The low-peripheral ARM physical address of [`BASE`], usable in constant
+/// contexts.
+pub const BASE_ARM_PA: u64 = 0xfe21_4000;
+
+register_structs! {
+    pub Registers {
+        /// Data and status
+        (0x00 => pub dr: ReadWrite<u32, DR::Register>),
+        /// Operation status and error clear
+        (0x04 => pub rsr: ReadWrite<u32, RSR::Register>),
+        /// I2C slave address
+        (0x08 => pub slv: ReadWrite<u32, SLV::Register>),
+        /// Control
+        (0x0c => pub cr: ReadWrite<u32, CR::Register>),
+        /// Flag (RO)
+        (0x10 => pub fr: ReadOnly<u32, FR::Register>),
+        /// Interrupt FIFO level select
+        (0x14 => pub ifls: ReadWrite<u32, IFLS::Register>),
+        /// Interrupt mask set/clear
+        (0x18 => pub imsc: ReadWrite<u32, INT::Register>),
+        /// Raw interrupt status (RO)
+        (0x1c => pub ris: ReadOnly<u32, INT::Register>),
+        /// Masked interrupt status (RO)
+        (0x20 => pub mis: ReadOnly<u32, INT::Register>),
+        /// Interrupt clear (WO)
+        (0x24 => pub icr: WriteOnly<u32, INT::Register>),
+        /// DMA control
+        (0x28 => pub dmacr: ReadWrite<u32>),
+        /// FIFO test data
+        (0x2c => pub tdr: ReadWrite<u32>),
+        /// GPU status
+        (0x30 => pub gpustat: ReadWrite<u32>),
+        /// Host control
+        (0x34 => pub hctrl: ReadWrite<u32>),
+        /// I2C debug
+        (0x38 => pub debug1: ReadWrite<u32>),
+        /// SPI debug
+        (0x3c => pub debug2: ReadWrite<u32>),
+        (0x40 => @END),
+    }
+}
+
+impl_snapshot! {
+    Registers {
+        // Reading `DR` pops the receive FIFO
+        dr: skip,
+        rsr,
+        slv,
+        cr,
+        fr,
+        ifls,
+        imsc,
+        ris,
+        mis,
+        icr,
+        dmacr,
+        tdr,
+        gpustat,
+        hctrl,
+        debug1,
+        debug2,
+    }
+}
+
+/// Describe [the BSC/SPI slave register block](Registers) and its instance. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "BSC_SLAVE",
+        "BSC/SPI slave controller",
+        &[Instance::vpa("BSC_SLAVE", BASE)],
+    )];
+    PERIPHERALS
+}
+
+register_bitfields! {u32,
+    pub DR [
+        /// Received/transmitted data. Reading pops a byte from the RX FIFO;
+        /// writing pushes a byte to the TX FIFO.
+        DATA OFFSET(0) NUMBITS(8) [],
+        /// RX overrun error
+        OE OFFSET(8) NUMBITS(1) [],
+        /// TX underrun error
+        UE OFFSET(9) NUMBITS(1) [],
+        /// Transmit operation in progress
+        TXBUSY OFFSET(16) NUMBITS(1) [],
+        /// RX FIFO empty
+        RXFE OFFSET(17) NUMBITS(1) [],
+        /// TX FIFO full
+        TXFF OFFSET(18) NUMBITS(1) [],
+        /// RX FIFO full
+        RXFF OFFSET(19) NUMBITS(1) [],
+        /// TX FIFO empty
+        TXFE OFFSET(20) NUMBITS(1) [],
+        /// Receive operation in progress
+        RXBUSY OFFSET(21) NUMBITS(1) [],
+        /// The number of bytes in the TX FIFO
+        TXFLEVEL OFFSET(22) NUMBITS(5) [],
+        /// The number of bytes in the RX FIFO
+        RXFLEVEL OFFSET(27) NUMBITS(5) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub RSR [
+        /// RX overrun error. Write `0` to clear.
+        OE OFFSET(0) NUMBITS(1) [],
+        /// TX underrun error. Write `0` to clear.
+        UE OFFSET(1) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub SLV [
+        /// I2C slave address
+        ADDR OFFSET(0) NUMBITS(7) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub CR [
+        /// Enable device
+        EN OFFSET(0) NUMBITS(1) [],
+        /// Enable SPI mode
+        SPI OFFSET(1) NUMBITS(1) [],
+        /// Enable I2C mode
+        I2C OFFSET(2) NUMBITS(1) [],
+        /// SPI clock phase
+        CPHA OFFSET(3) NUMBITS(1) [],
+        /// SPI clock polarity
+        CPOL OFFSET(4) NUMBITS(1) [],
+        /// Enable the status register (I2C only)
+        ENSTAT OFFSET(5) NUMBITS(1) [],
+        /// Enable the control register (I2C only)
+        ENCTRL OFFSET(6) NUMBITS(1) [],
+        /// Stop the current operation and clear the FIFOs
+        BRK OFFSET(7) NUMBITS(1) [],
+        /// Enable transmit
+        TXE OFFSET(8) NUMBITS(1) [],
+        /// Enable receive
+        RXE OFFSET(9) NUMBITS(1) [],
+        /// Inverse RX status flags
+        INV_RXF OFFSET(10) NUMBITS(1) [],
+        /// Enable test FIFO
+        TESTFIFO OFFSET(11) NUMBITS(1) [],
+        /// Enable host control
+        HOSTCTRLEN OFFSET(12) NUMBITS(1) [],
+        /// Inverse TX status flags
+        INV_TXF OFFSET(13) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub FR [
+        /// Transmit operation in progress
+        TXBUSY OFFSET(0) NUMBITS(1) [],
+        /// RX FIFO empty
+        RXFE OFFSET(1) NUMBITS(1) [],
+        /// TX FIFO full
+        TXFF OFFSET(2) NUMBITS(1) [],
+        /// RX FIFO full
+        RXFF OFFSET(3) NUMBITS(1) [],
+        /// TX FIFO empty
+        TXFE OFFSET(4) NUMBITS(1) [],
+        /// Receive operation in progress
+        RXBUSY OFFSET(5) NUMBITS(1) [],
+        /// The number of bytes in the TX FIFO
+        TXFLEVEL OFFSET(6) NUMBITS(5) [],
+        /// The number of bytes in the RX FIFO
+        RXFLEVEL OFFSET(11) NUMBITS(5) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub IFLS [
+        /// TX interrupt FIFO level select. The TX interrupt is asserted when
+        /// the TX FIFO is at or below the selected level.
+        TXIFLSEL OFFSET(0) NUMBITS(3) [
+            OneEighth = 0b000,
+            OneQuarter = 0b001,
+            OneHalf = 0b010,
+            ThreeQuarters = 0b011,
+            SevenEighths = 0b100,
+        ],
+        /// RX interrupt FIFO level select. The RX interrupt is asserted when
+        /// the RX FIFO is at or above the selected level.
+        RXIFLSEL OFFSET(3) NUMBITS(3) [
+            OneEighth = 0b000,
+            OneQuarter = 0b001,
+            OneHalf = 0b010,
+            ThreeQuarters = 0b011,
+            SevenEighths = 0b100,
+        ],
+    ]
+}
+
+register_bitfields! {u32,
+    /// The layout of [`Registers::imsc`], [`Registers::ris`],
+    /// [`Registers::mis`], and [`Registers::icr`]
+    pub INT [
+        /// Receive interrupt
+        RX OFFSET(0) NUMBITS(1) [],
+        /// Transmit interrupt
+        TX OFFSET(1) NUMBITS(1) [],
+        /// Break error interrupt
+        BE OFFSET(2) NUMBITS(1) [],
+        /// Overrun error interrupt
+        OE OFFSET(3) NUMBITS(1) [],
+    ]
+}
+
+/// A register file exposed by [`BscSlave`].
+///
+/// The bus master selects a register by writing its address as the first
+/// byte of a write transaction. Subsequent bytes are written to consecutive
+/// addresses, and read transactions read from consecutive addresses.
+pub trait RegisterFile {
+    /// Read the register at `addr`.
+    ///
+    /// This method is called ahead of time to fill the TX FIFO, so the
+    /// returned value may never reach the master. It should not have side
+    /// effects.
+    fn read(&mut self, addr: u8) -> u8;
+
+    /// Write `value` to the register at `addr`.
+    fn write(&mut self, addr: u8, value: u8);
+}
+
+/// A plain memory-backed register file. Addresses wrap around at `N`.
+impl<const N: usize> RegisterFile for [u8; N] {
+    #[inline]
+    fn read(&mut self, addr: u8) -> u8 {
+        self[addr as usize % N]
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u8, value: u8) {
+        self[addr as usize % N] = value;
+    }
+}
+
+/// The receiver state of [`BscSlave`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+enum RxState {
+    /// The next byte is a register address.
+    Address,
+    /// The next byte is written to the register file.
+    Data,
+}
+
+/// An interrupt-driven I²C slave driver exposing a [`RegisterFile`].
+///
+/// Call [`Self::service`] from the interrupt handler of the BSC/SPI slave
+/// controller. With the `solid` feature, `BscSlave` can be used directly as
+/// the handler of a `solid::interrupt::Handler`.
+///
+/// The controller can't stretch the clock, so the TX FIFO is filled in
+/// advance from the current register address. After a write transaction
+/// that changes the register address, the master should wait for the next
+/// interrupt to be serviced (e.g., issue a STOP condition and a short delay)
+/// before starting a read transaction. The RX interrupt is only raised when
+/// the RX FIFO holds at least two bytes, so a write transaction consisting of
+/// a register address alone is not noticed until the next interrupt; call
+/// `service` periodically as well (e.g., from a timer) if the master does
+/// this.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use bcm2711_pac::bsc_slave;
+///
+/// let mut slave = unsafe {
+///     bsc_slave::BscSlave::new(
+///         bsc_slave::BASE_ARM_PA as usize as *const bsc_slave::Registers,
+///         [0u8; 16],
+///     )
+/// };
+/// slave.init(0x42);
+///
+/// // In the interrupt handler
+/// slave.service();
+/// ```
+pub struct BscSlave<F> {
+    regs: *const Registers,
+    file: F,
+    rx_state: RxState,
+    /// The register address of the next byte written by the master
+    rx_addr: u8,
+    /// The register address of the next byte pushed to the TX FIFO
+    tx_addr: u8,
+    overruns: u32,
+    underruns: u32,
+}
+
+// Safety: The register block is only accessed through `&mut self`.
+unsafe impl<F: Send> Send for BscSlave<F> {}
+
+impl<F: RegisterFile> BscSlave<F> {
+    /// Construct a `BscSlave`. Call [`Self::init`] before using it.
+    ///
+    /// # Safety
+    ///
+    /// `regs` must point to [the BSC/SPI slave register block](Registers) and
+    /// remain valid for the lifetime of the constructed `BscSlave`. The
+    /// register block must not be accessed by other code while the
+    /// constructed `BscSlave` is in use.
+    #[inline]
+    pub const unsafe fn new(regs: *const Registers, file: F) -> Self {
+        Self {
+            regs,
+            file,
+            rx_state: RxState::Address,
+            rx_addr: 0,
+            tx_addr: 0,
+            overruns: 0,
+            underruns: 0,
+        }
+    }
+
+    #[inline]
+    fn regs(&self) -> &Registers {
+        // Safety: Upheld by the caller of `Self::new`
+        unsafe { &*self.regs }
+    }
+
+    /// Get a reference to the register file.
+    #[inline]
+    pub fn file(&self) -> &F {
+        &self.file
+    }
+
+    /// Get a mutable reference to the register file.
+    #[inline]
+    pub fn file_mut(&mut self) -> &mut F {
+        &mut self.file
+    }
+
+    /// Get the number of RX FIFO overruns observed so far.
+    #[inline]
+    pub fn overruns(&self) -> u32 {
+        self.overruns
+    }
+
+    /// Get the number of TX FIFO underruns observed so far.
+    #[inline]
+    pub fn underruns(&self) -> u32 {
+        self.underruns
+    }
+
+    /// Configure the controller as an I²C slave at the 7-bit address `addr`
+    /// and enable its interrupts.
+    pub fn init(&mut self, addr: u8) {
+        let regs = self.regs();
+        regs.cr.set(0);
+        regs.slv.write(SLV::ADDR.val(addr as u32));
+        regs.ifls
+            .write(IFLS::TXIFLSEL::OneEighth + IFLS::RXIFLSEL::OneEighth);
+        regs.icr.set(u32::MAX);
+        regs.rsr.set(0);
+        regs.imsc
+            .write(INT::RX::SET + INT::TX::SET + INT::BE::SET + INT::OE::SET);
+        regs.cr
+            .write(CR::EN::SET + CR::I2C::SET + CR::TXE::SET + CR::RXE::SET);
+
+        self.rx_state = RxState::Address;
+        self.rx_addr = 0;
+        self.restart_tx();
+    }
+
+    /// Disable the controller and its interrupts.
+    pub fn disable(&mut self) {
+        let regs = self.regs();
+        regs.imsc.set(0);
+        regs.cr.set(0);
+        regs.icr.set(u32::MAX);
+    }
+
+    /// Service the controller: process received bytes, refill the TX FIFO,
+    /// and clear the interrupt flags.
+    pub fn service(&mut self) {
+        self.regs().icr.set(u32::MAX);
+
+        let rsr = self.regs().rsr.extract();
+        if rsr.is_set(RSR::OE) {
+            self.overruns = self.overruns.wrapping_add(1);
+        }
+        if rsr.is_set(RSR::UE) {
+            self.underruns = self.underruns.wrapping_add(1);
+        }
+        if rsr.get() != 0 {
+            self.regs().rsr.set(0);
+        }
+
+        // Drain the RX FIFO
+        while !self.regs().fr.is_set(FR::RXFE) {
+            let byte = self.regs().dr.read(DR::DATA) as u8;
+            match self.rx_state {
+                RxState::Address => {
+                    self.rx_addr = byte;
+                    self.rx_state = RxState::Data;
+                }
+                RxState::Data => {
+                    self.file.write(self.rx_addr, byte);
+                    self.rx_addr = self.rx_addr.wrapping_add(1);
+                }
+            }
+        }
+
+        if self.rx_state == RxState::Data && !self.regs().fr.is_set(FR::RXBUSY) {
+            // The write transaction has ended. The TX FIFO contains data for
+            // the previous register address; replace it.
+            self.rx_state = RxState::Address;
+            self.restart_tx();
+        } else {
+            self.fill_tx();
+        }
+    }
+
+    /// Discard the contents of the TX FIFO and refill it starting from the
+    /// current register address.
+    fn restart_tx(&mut self) {
+        let regs = self.regs();
+        regs.cr.modify(CR::BRK::SET);
+        regs.cr.modify(CR::BRK::CLEAR);
+        self.tx_addr = self.rx_addr;
+        self.fill_tx();
+    }
+
+    /// Fill the TX FIFO with consecutive registers.
+    fn fill_tx(&mut self) {
+        while !self.regs().fr.is_set(FR::TXFF) {
+            let byte = self.file.read(self.tx_addr);
+            self.regs().dr.write(DR::DATA.val(byte as u32));
+            self.tx_addr = self.tx_addr.wrapping_add(1);
+        }
+    }
+}
+
+#[cfg(feature = "solid")]
+impl<'a, F: RegisterFile> solid::closure::FuncMut<(solid::thread::CpuCx<'a>,)> for BscSlave<F> {
+    type Output = ();
+
+    #[inline]
+    fn call(&mut self, _: (solid::thread::CpuCx<'a>,)) {
+        self.service();
+    }
+}
@@ -2,6 +2,29 @@
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Vpa(pub u64);
 
+/// The range of VC addresses occupied by the main peripherals
+const PERIPHERAL_VPA_START: u64 = 0x4_7c00_0000;
+const PERIPHERAL_VPA_END: u64 = 0x4_7fff_ffff;
+
+/// The low-peripheral ARM physical address of [`PERIPHERAL_VPA_START`]
+const LOW_PERIPHERAL_ARM_PA_START: u64 = 0xfc00_0000;
+const LOW_PERIPHERAL_ARM_PA_END: u64 = 0xffff_ffff;
+
+/// The legacy bus address of [`PERIPHERAL_VPA_START`]
+const PERIPHERAL_LEGACY_BUS_START: u32 = 0x7c00_0000;
+const PERIPHERAL_LEGACY_BUS_END: u32 = 0x7fff_ffff;
+
+/// The base address of the uncached SDRAM alias in the legacy (32-bit) bus
+/// address space used by the DMA and DMA Lite engines
+pub const LEGACY_DMA_UNCACHED_BASE: u32 = 0xc000_0000;
+
+/// The size of SDRAM accessible through a legacy bus alias (1 GiB). Use
+/// [`ENABLE::PAGE`](const@crate::dmac::ENABLE::PAGE) to select other pages.
+pub const LEGACY_DMA_RAM_SIZE: u64 = 0x4000_0000;
+
+/// The size of the 35-bit address space seen by the DMA4 engines
+const DMA4_ADDR_LIMIT: u64 = 1 << 35;
+
 impl Vpa {
     /// Map a given VC address to a low-peripheral ARM physical address.
     ///
@@ -15,8 +38,206 @@ impl Vpa {
     #[inline]
     pub const fn to_arm_pa(self) -> Option<u64> {
         match self.0 {
-            0x4_7c00_0000..=0x4_7fff_ffff => Some(self.0 - 0x4_7c00_0000 + 0xfc00_0000),
+            PERIPHERAL_VPA_START..=PERIPHERAL_VPA_END => {
+                Some(self.0 - PERIPHERAL_VPA_START + LOW_PERIPHERAL_ARM_PA_START)
+            }
             _ => None,
         }
     }
+
+    /// Map a given VC address to an ARM physical address in the
+    /// high-peripheral mode (`arm_peri_high=1`), in which the main
+    /// peripherals appear at the same 35-bit addresses as in the VC address
+    /// space.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bcm2711_pac::Vpa;
+    /// let gpio = Vpa(0x4_7e20_0000);
+    /// assert_eq!(gpio.to_arm_pa_high(), Some(0x4_7e20_0000));
+    /// ```
+    #[inline]
+    pub const fn to_arm_pa_high(self) -> Option<u64> {
+        match self.0 {
+            PERIPHERAL_VPA_START..=PERIPHERAL_VPA_END => Some(self.0),
+            _ => None,
+        }
+    }
+
+    /// Map a given low-peripheral ARM physical address to a VC address. This
+    /// is the inverse of [`Self::to_arm_pa`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bcm2711_pac::Vpa;
+    /// assert_eq!(Vpa::from_arm_pa(0xfe20_0000), Some(Vpa(0x4_7e20_0000)));
+    /// assert_eq!(Vpa::from_arm_pa(0x8000), None);
+    /// ```
+    #[inline]
+    pub const fn from_arm_pa(arm_pa: u64) -> Option<Self> {
+        match arm_pa {
+            LOW_PERIPHERAL_ARM_PA_START..=LOW_PERIPHERAL_ARM_PA_END => Some(Self(
+                arm_pa - LOW_PERIPHERAL_ARM_PA_START + PERIPHERAL_VPA_START,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Map a given high-peripheral ARM physical address to a VC address. This
+    /// is the inverse of [`Self::to_arm_pa_high`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bcm2711_pac::Vpa;
+    /// assert_eq!(Vpa::from_arm_pa_high(0x4_7e20_0000), Some(Vpa(0x4_7e20_0000)));
+    /// assert_eq!(Vpa::from_arm_pa_high(0xfe20_0000), None);
+    /// ```
+    #[inline]
+    pub const fn from_arm_pa_high(arm_pa: u64) -> Option<Self> {
+        match arm_pa {
+            PERIPHERAL_VPA_START..=PERIPHERAL_VPA_END => Some(Self(arm_pa)),
+            _ => None,
+        }
+    }
+
+    /// Map a given VC address to a legacy (32-bit) bus address, which is
+    /// used by the DMA and DMA Lite engines ([`DmaCb`](crate::dmac::DmaCb)
+    /// and [`DmaLiteCb`](crate::dmac::DmaLiteCb)) to access peripherals.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bcm2711_pac::Vpa;
+    /// let gpio = Vpa(0x4_7e20_0000);
+    /// assert_eq!(gpio.to_legacy_bus(), Some(0x7e20_0000));
+    /// ```
+    #[inline]
+    pub const fn to_legacy_bus(self) -> Option<u32> {
+        match self.0 {
+            PERIPHERAL_VPA_START..=PERIPHERAL_VPA_END => {
+                Some((self.0 - PERIPHERAL_VPA_START) as u32 + PERIPHERAL_LEGACY_BUS_START)
+            }
+            _ => None,
+        }
+    }
+
+    /// Map a given legacy bus address to a VC address. This is the inverse
+    /// of [`Self::to_legacy_bus`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bcm2711_pac::Vpa;
+    /// assert_eq!(Vpa::from_legacy_bus(0x7e20_0000), Some(Vpa(0x4_7e20_0000)));
+    /// assert_eq!(Vpa::from_legacy_bus(0xc000_8000), None);
+    /// ```
+    #[inline]
+    pub const fn from_legacy_bus(bus: u32) -> Option<Self> {
+        match bus {
+            PERIPHERAL_LEGACY_BUS_START..=PERIPHERAL_LEGACY_BUS_END => Some(Self(
+                (bus - PERIPHERAL_LEGACY_BUS_START) as u64 + PERIPHERAL_VPA_START,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Get the address used by the DMA4 engines ([`Dma4Cb`](crate::dmac::Dma4Cb))
+    /// to access this peripheral address. The DMA4 engines see the full 35-bit
+    /// VC address space. Use [`split_dma4_addr`] to get the values of the
+    /// control block fields.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bcm2711_pac::Vpa;
+    /// let gpio = Vpa(0x4_7e20_0000);
+    /// assert_eq!(gpio.to_dma4(), Some(0x4_7e20_0000));
+    /// ```
+    #[inline]
+    pub const fn to_dma4(self) -> Option<u64> {
+        match self.0 {
+            PERIPHERAL_VPA_START..=PERIPHERAL_VPA_END => Some(self.0),
+            _ => None,
+        }
+    }
+}
+
+/// Map a given ARM physical address of SDRAM to the uncached legacy bus alias
+/// (`0xc000_0000`), which is used by the DMA and DMA Lite engines. Returns
+/// `None` if the address is outside the first 1 GiB of SDRAM.
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_pac::ram_to_legacy_dma;
+/// assert_eq!(ram_to_legacy_dma(0x0010_0000), Some(0xc010_0000));
+/// assert_eq!(ram_to_legacy_dma(0x4000_0000), None);
+/// ```
+#[inline]
+pub const fn ram_to_legacy_dma(arm_pa: u64) -> Option<u32> {
+    if arm_pa < LEGACY_DMA_RAM_SIZE {
+        Some(arm_pa as u32 | LEGACY_DMA_UNCACHED_BASE)
+    } else {
+        None
+    }
+}
+
+/// Map a given legacy bus address of SDRAM to an ARM physical address. All
+/// four aliases (`0x0000_0000`, `0x4000_0000`, `0x8000_0000`, and
+/// `0xc000_0000`) are accepted. Returns `None` for the peripheral window
+/// (`0x7c00_0000..=0x7fff_ffff`).
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_pac::legacy_dma_to_ram;
+/// assert_eq!(legacy_dma_to_ram(0xc010_0000), Some(0x0010_0000));
+/// assert_eq!(legacy_dma_to_ram(0x4010_0000), Some(0x0010_0000));
+/// assert_eq!(legacy_dma_to_ram(0x7e20_0000), None);
+/// ```
+#[inline]
+pub const fn legacy_dma_to_ram(bus: u32) -> Option<u64> {
+    match bus {
+        PERIPHERAL_LEGACY_BUS_START..=PERIPHERAL_LEGACY_BUS_END => None,
+        _ => Some((bus as u64) & (LEGACY_DMA_RAM_SIZE - 1)),
+    }
+}
+
+/// Map a given ARM physical address of SDRAM to the address used by the DMA4
+/// engines. Returns `None` if the address is outside the 35-bit address space
+/// or falls in the low-peripheral window (`0xfc00_0000..=0xffff_ffff`).
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_pac::ram_to_dma4;
+/// assert_eq!(ram_to_dma4(0x1_2345_6780), Some(0x1_2345_6780));
+/// assert_eq!(ram_to_dma4(0xfe20_0000), None);
+/// ```
+#[inline]
+pub const fn ram_to_dma4(arm_pa: u64) -> Option<u64> {
+    match arm_pa {
+        LOW_PERIPHERAL_ARM_PA_START..=LOW_PERIPHERAL_ARM_PA_END => None,
+        _ if arm_pa >= DMA4_ADDR_LIMIT => None,
+        _ => Some(arm_pa),
+    }
+}
+
+/// Split a DMA4 address into the lower 32 bits and the upper 8 bits, which are
+/// stored in [`Dma4Cb::src`](crate::dmac::Dma4Cb::src) (or `dest`) and
+/// [`DMA4_SRCI::ADDR`](const@crate::dmac::DMA4_SRCI::ADDR) (or
+/// [`DMA4_DESTI::ADDR`](const@crate::dmac::DMA4_DESTI::ADDR)), respectively.
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_pac::split_dma4_addr;
+/// assert_eq!(split_dma4_addr(0x4_7e20_1000), (0x7e20_1000, 0x4));
+/// ```
+#[inline]
+pub const fn split_dma4_addr(addr: u64) -> (u32, u32) {
+    (addr as u32, (addr >> 32) as u32 & 0xff)
 }
@@ -0,0 +1,415 @@
+//! [BCM2711 Clock Manager][1] (general-purpose, PCM, and PWM clocks)
+//!
+//! Every clock generator has a pair of registers, `CTL` and `DIV`, which can
+//! only be written when the upper eight bits of the written value are
+//! [`PASSWD`]. [`ClockRegisters`] provides methods that take care of this.
+//!
+//! [`solve`] calculates the register values for a requested output
+//! frequency.
+//!
+//! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A85%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
+use tock_registers::{
+    fields::FieldValue,
+    interfaces::{Readable, Writeable},
+    register_structs,
+};
+
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::ReadWrite,
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
+};
+
+/// The base address of [the Clock Manager register block](Registers).
+pub const BASE: Vpa = Vpa(0x4_7e10_1000);
+
+/// The password that must be present in bits `24..32` of every value written
+/// to the Clock Manager registers.
+pub const PASSWD: u32 = 0x5a << 24;
+
+/// The frequency of the crystal oscillator.
+pub const OSCILLATOR_HZ: u32 = 54_000_000;
+
+/// The frequency of PLLD's PER output as configured by the firmware.
+pub const PLLD_PER_HZ: u32 = 750_000_000;
+
+/// The nominal frequency of PLLC's PER output as configured by the firmware.
+/// PLLC also drives the core clock, so this frequency changes when the
+/// firmware scales the core clock (e.g., on thermal throttling).
+pub const PLLC_PER_HZ: u32 = 1_000_000_000;
+
+/// The maximum value of [`DIV::DIVI`](const@DIV::DIVI).
+pub const DIVI_MAX: u16 = 0xfff;
+
+register_structs! {
+    pub Registers {
+        (0x00 => _pad0),
+        /// General-purpose clocks 0-2
+        (0x70 => pub gp: [ClockRegisters; 3]),
+        (0x88 => _pad1),
+        /// PCM clock
+        (0x98 => pub pcm: ClockRegisters),
+        /// PWM clock
+        (0xa0 => pub pwm: ClockRegisters),
+        (0xa8 => @END),
+    },
+
+    /// A clock generator register pair
+    pub ClockRegisters {
+        /// Clock control
+        (0x00 => pub ctl: ReadWrite<u32, CTL::Register>),
+        /// Clock divisor
+        (0x04 => pub div: ReadWrite<u32, DIV::Register>),
+        (0x08 => @END),
+    }
+}
+
+impl_snapshot! {
+    Registers {
+        gp,
+        pcm,
+        pwm,
+    }
+    ClockRegisters {
+        ctl,
+        div,
+    }
+}
+
+/// Describe [the Clock Manager register block](Registers) and its instance. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "CM",
+        "Clock Manager",
+        &[Instance::vpa("CM", BASE)],
+    )];
+    PERIPHERALS
+}
+
+register_bitfields! {u32,
+    pub CTL [
+        /// Clock source
+        SRC OFFSET(0) NUMBITS(4) [
+            Gnd = 0,
+            Oscillator = 1,
+            TestDebug0 = 2,
+            TestDebug1 = 3,
+            PllA = 4,
+            PllC = 5,
+            PllD = 6,
+            HdmiAux = 7,
+        ],
+        /// Enable the clock generator. Change [`SRC`] before setting this.
+        ENAB OFFSET(4) NUMBITS(1) [],
+        /// Stop and reset the clock generator (may cause glitches)
+        KILL OFFSET(5) NUMBITS(1) [],
+        /// The clock generator is running (RO)
+        BUSY OFFSET(7) NUMBITS(1) [],
+        /// Invert the clock generator output
+        FLIP OFFSET(8) NUMBITS(1) [],
+        /// MASH control. Change this only while [`BUSY`] is clear.
+        MASH OFFSET(9) NUMBITS(2) [
+            Integer = 0,
+            OneStage = 1,
+            TwoStage = 2,
+            ThreeStage = 3,
+        ],
+        /// Password
+        PASSWD OFFSET(24) NUMBITS(8) [
+            Passwd = 0x5a,
+        ],
+    ]
+}
+
+register_bitfields! {u32,
+    pub DIV [
+        /// Fractional part of the divisor (in the units of 1/4096). Ignored
+        /// when [`CTL::MASH`](const@CTL::MASH) is `Integer`.
+        DIVF OFFSET(0) NUMBITS(12) [],
+        /// Integer part of the divisor. Change this only while
+        /// [`CTL::BUSY`](const@CTL::BUSY) is clear.
+        DIVI OFFSET(12) NUMBITS(12) [],
+        /// Password
+        PASSWD OFFSET(24) NUMBITS(8) [
+            Passwd = 0x5a,
+        ],
+    ]
+}
+
+impl ClockRegisters {
+    /// Write `value` to [`Self::ctl`] with the password.
+    #[inline]
+    pub fn write_ctl(&self, value: FieldValue<u32, CTL::Register>) {
+        self.ctl.set((value.value & !(0xff << 24)) | PASSWD);
+    }
+
+    /// Modify [`Self::ctl`] with the password.
+    #[inline]
+    pub fn modify_ctl(&self, value: FieldValue<u32, CTL::Register>) {
+        let old = self.ctl.get();
+        self.ctl.set((value.modify(old) & !(0xff << 24)) | PASSWD);
+    }
+
+    /// Write `value` to [`Self::div`] with the password.
+    #[inline]
+    pub fn write_div(&self, value: FieldValue<u32, DIV::Register>) {
+        self.div.set((value.value & !(0xff << 24)) | PASSWD);
+    }
+
+    /// Stop the clock generator and wait until it stops running.
+    pub fn stop(&self) {
+        self.modify_ctl(CTL::ENAB::CLEAR);
+        while self.ctl.is_set(CTL::BUSY) {
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Stop the clock generator, reprogram it with `config`, and start it
+    /// again.
+    pub fn configure(&self, config: &ClockConfig) {
+        self.stop();
+        self.write_div(config.div());
+        self.write_ctl(config.ctl());
+        self.write_ctl(config.ctl() + CTL::ENAB::SET);
+        while !self.ctl.is_set(CTL::BUSY) {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// A clock source for [`solve`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Source {
+    /// The value of [`CTL::SRC`](const@CTL::SRC) selecting this source
+    pub src: u32,
+    /// The frequency of this source in hertz
+    pub freq_hz: u32,
+}
+
+impl Source {
+    /// The crystal oscillator
+    pub const OSCILLATOR: Self = Self {
+        src: CTL::SRC::Value::Oscillator as u32,
+        freq_hz: OSCILLATOR_HZ,
+    };
+
+    /// PLLD's PER output
+    pub const PLLD: Self = Self {
+        src: CTL::SRC::Value::PllD as u32,
+        freq_hz: PLLD_PER_HZ,
+    };
+
+    /// PLLC's PER output. See [`PLLC_PER_HZ`] for caveats.
+    pub const PLLC: Self = Self {
+        src: CTL::SRC::Value::PllC as u32,
+        freq_hz: PLLC_PER_HZ,
+    };
+}
+
+/// The sources considered by [`solve`] by default, in the order of
+/// preference. PLLC comes last because its frequency is not stable.
+pub const DEFAULT_SOURCES: [Source; 3] = [Source::OSCILLATOR, Source::PLLD, Source::PLLC];
+
+/// A MASH noise-shaping mode
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+pub enum Mash {
+    /// Integer division
+    Integer = 0,
+    /// 1-stage MASH
+    OneStage = 1,
+    /// 2-stage MASH
+    TwoStage = 2,
+    /// 3-stage MASH
+    ThreeStage = 3,
+}
+
+impl Mash {
+    /// The minimum value of [`DIV::DIVI`](const@DIV::DIVI) supported by this mode.
+    #[inline]
+    pub const fn min_divi(self) -> u16 {
+        match self {
+            Self::Integer => 1,
+            Self::OneStage => 2,
+            Self::TwoStage => 3,
+            Self::ThreeStage => 5,
+        }
+    }
+
+    /// The range of instantaneous divisors produced by this mode, relative
+    /// to [`DIV::DIVI`](const@DIV::DIVI). The output frequency swings between
+    /// `source / (DIVI + min)` and `source / (DIVI + max)`.
+    #[inline]
+    pub const fn divisor_swing(self) -> (i32, i32) {
+        match self {
+            Self::Integer => (0, 0),
+            Self::OneStage => (0, 1),
+            Self::TwoStage => (-1, 2),
+            Self::ThreeStage => (-3, 4),
+        }
+    }
+}
+
+/// A clock generator configuration
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct ClockConfig {
+    pub source: Source,
+    pub mash: Mash,
+    /// The integer part of the divisor
+    pub divi: u16,
+    /// The fractional part of the divisor in the units of 1/4096. Must be
+    /// zero if `mash` is [`Mash::Integer`].
+    pub divf: u16,
+}
+
+impl ClockConfig {
+    /// The value to write to [`ClockRegisters::ctl`] (without
+    /// [`CTL::ENAB`](const@CTL::ENAB)).
+    #[inline]
+    pub fn ctl(&self) -> FieldValue<u32, CTL::Register> {
+        CTL::SRC.val(self.source.src) + CTL::MASH.val(self.mash as u32)
+    }
+
+    /// The value to write to [`ClockRegisters::div`].
+    #[inline]
+    pub fn div(&self) -> FieldValue<u32, DIV::Register> {
+        DIV::DIVI.val(self.divi as u32) + DIV::DIVF.val(self.divf as u32)
+    }
+
+    /// The divisor in the units of 1/4096.
+    #[inline]
+    pub const fn divisor_4096(&self) -> u32 {
+        ((self.divi as u32) << 12) | self.divf as u32
+    }
+
+    /// The average output frequency in hertz, rounded to the nearest
+    /// integer.
+    #[inline]
+    pub const fn output_hz(&self) -> u32 {
+        let div = self.divisor_4096() as u64;
+        if div == 0 {
+            return 0;
+        }
+        ((((self.source.freq_hz as u64) << 12) + div / 2) / div) as u32
+    }
+}
+
+/// The result of [`solve`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Solution {
+    /// The clock generator configuration
+    pub config: ClockConfig,
+    /// The achieved average output frequency in hertz, rounded to the nearest
+    /// integer
+    pub achieved_hz: u32,
+    /// The error of the achieved frequency relative to the requested
+    /// frequency, in parts per billion. Positive if the achieved frequency is
+    /// higher.
+    pub error_ppb: i64,
+}
+
+/// The error type for [`solve`].
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum SolveError {
+    /// The requested frequency is zero.
+    ZeroFrequency,
+    /// None of the sources can produce the requested frequency within the
+    /// divisor range.
+    OutOfRange,
+}
+
+/// Find the clock generator configuration producing the frequency closest to
+/// `target_hz`.
+///
+/// An integer divisor is used whenever it produces the exact frequency
+/// because it is free of jitter. Otherwise, a fractional divisor with `mash`
+/// is used (unless `mash` is [`Mash::Integer`]). When multiple sources give
+/// the same error, the one appearing first in `sources` is chosen.
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_pac::cm::{solve, Mash, Source, DEFAULT_SOURCES};
+///
+/// // 9.6 MHz = 54 MHz / 5.625
+/// let sol = solve(9_600_000, &DEFAULT_SOURCES, Mash::OneStage).unwrap();
+/// assert_eq!(sol.config.source, Source::OSCILLATOR);
+/// assert_eq!((sol.config.mash, sol.config.divi, sol.config.divf), (Mash::OneStage, 5, 2560));
+/// assert_eq!((sol.achieved_hz, sol.error_ppb), (9_600_000, 0));
+///
+/// // 25 MHz = 750 MHz / 30
+/// let sol = solve(25_000_000, &DEFAULT_SOURCES, Mash::OneStage).unwrap();
+/// assert_eq!(sol.config.source, Source::PLLD);
+/// assert_eq!((sol.config.mash, sol.config.divi), (Mash::Integer, 30));
+///
+/// // 44.1 kHz × 64 with integer division only
+/// let sol = solve(2_822_400, &DEFAULT_SOURCES, Mash::Integer).unwrap();
+/// assert_eq!(sol.config.mash, Mash::Integer);
+/// assert!(sol.error_ppb.abs() < 1_000_000);
+/// ```
+pub fn solve(target_hz: u32, sources: &[Source], mash: Mash) -> Result<Solution, SolveError> {
+    if target_hz == 0 {
+        return Err(SolveError::ZeroFrequency);
+    }
+
+    let mut best: Option<Solution> = None;
+    let mut consider = |config: ClockConfig| {
+        let solution = Solution {
+            config,
+            achieved_hz: config.output_hz(),
+            error_ppb: error_ppb(&config, target_hz),
+        };
+        let better = match best {
+            None => true,
+            Some(best) => solution.error_ppb.unsigned_abs() < best.error_ppb.unsigned_abs(),
+        };
+        if better {
+            best = Some(solution);
+        }
+    };
+
+    for &source in sources {
+        let target = target_hz as u64;
+        let freq = source.freq_hz as u64;
+
+        // Integer division
+        let divi = (freq + target / 2) / target;
+        if (Mash::Integer.min_divi() as u64..=DIVI_MAX as u64).contains(&divi) {
+            consider(ClockConfig {
+                source,
+                mash: Mash::Integer,
+                divi: divi as u16,
+                divf: 0,
+            });
+        }
+
+        // Fractional division. Skip it if an integer divisor is exact.
+        if mash != Mash::Integer {
+            let div = ((freq << 12) + target / 2) / target;
+            let divi = div >> 12;
+            let divf = div & 0xfff;
+            if divf != 0 && (mash.min_divi() as u64..=DIVI_MAX as u64).contains(&divi) {
+                consider(ClockConfig {
+                    source,
+                    mash,
+                    divi: divi as u16,
+                    divf: divf as u16,
+                });
+            }
+        }
+    }
+
+    best.ok_or(SolveError::OutOfRange)
+}
+
+/// Calculate the error of `config`'s output frequency relative to
+/// `target_hz` in parts per billion.
+fn error_ppb(config: &ClockConfig, target_hz: u32) -> i64 {
+    // (freq / (div / 4096) - target) / target
+    //   = (freq * 4096 - target * div) / (target * div)
+    let num =
+        ((config.source.freq_hz as i128) << 12) - target_hz as i128 * config.divisor_4096() as i128;
+    let den = target_hz as i128 * config.divisor_4096() as i128;
+    (num * 1_000_000_000 / den) as i64
+}
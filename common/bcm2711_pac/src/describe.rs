@@ -0,0 +1,660 @@
+//! Machine-readable descriptions of the register blocks
+//!
+//! Every register module provides a `describe` function (e.g.,
+//! [`pl011::describe`](crate::pl011::describe)) returning [`Peripheral`]s,
+//! which enumerate the registers with their offsets, access types, bit
+//! fields, and named field values. They are generated from the same
+//! `register_structs!` and `register_bitfields!` definitions as the register
+//! types, so they can't get out of sync.
+//!
+//! [`write_svd`] and [`write_json`] output the descriptions of all
+//! [`peripherals`] as a CMSIS-SVD file and a JSON document, respectively.
+//! The `describe` binary of this package writes them to a file:
+//!
+//! ```text
+//! cargo run -p bcm2711_pac --bin describe -- svd bcm2711.svd
+//! cargo run -p bcm2711_pac --bin describe -- json bcm2711.json
+//! ```
+//!
+//! The base addresses are ARM physical addresses in the low-peripheral mode.
+//!
+//! # JSON format
+//!
+//! ```text
+//! {
+//!   "device": "BCM2711",
+//!   "peripherals": [{
+//!     "name": "PL011", "description": "PL011 UART", "size": 144,
+//!     "instances": [{ "name": "UART0", "base": 4263514112 }, …],
+//!     "registers": [
+//!       { "name": "dr", "offset": 0, "access": "read-write",
+//!         "readSideEffects": true, "type": "DR", "fields": […] },
+//!       …,
+//!       { "name": "cr", "offset": 48, "access": "read-write",
+//!         "readSideEffects": false, "type": "CR", "fields": [
+//!           { "name": "UARTEN", "offset": 0, "width": 1, "count": 1,
+//!             "values": [] },
+//!           …
+//!         ] },
+//!       …
+//!     ]
+//!   }, …]
+//! }
+//! ```
+//!
+//! Registers in nested register blocks and register arrays are named by
+//! their paths (e.g., `aux_mu.io_reg`, `gpfsel[1]`). `type` is the name of
+//! the bit field definition and is empty for registers without one. A field
+//! with `count > 1` has `count` instances, the `i`-th of which is located at
+//! bit position `offset + width * i`.
+use core::fmt::{self, Write};
+
+use crate::{
+    snapshot::{FieldInfo, RegisterInfo, Snap},
+    Vpa,
+};
+
+/// The access type of a register
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Access {
+    /// Readable and writable
+    ReadWrite,
+    /// Read-only
+    ReadOnly,
+    /// Write-only
+    WriteOnly,
+}
+
+impl Access {
+    /// Get the name of the access type used by CMSIS-SVD (e.g.,
+    /// `read-write`).
+    pub const fn svd_name(self) -> &'static str {
+        match self {
+            Self::ReadWrite => "read-write",
+            Self::ReadOnly => "read-only",
+            Self::WriteOnly => "write-only",
+        }
+    }
+}
+
+/// Describes a register. Produced by [`Peripheral::registers`].
+#[derive(Clone, Copy)]
+pub struct RegisterDesc<'a> {
+    /// The path to the register from the register block (e.g., `cr`,
+    /// `aux_mu.io_reg`, `gpfsel[1]`)
+    pub path: &'a dyn fmt::Display,
+    /// The offset from the base address of the register block
+    pub offset: usize,
+    /// The access type
+    pub access: Access,
+    /// Reading the register has side effects (e.g., popping a FIFO)
+    pub read_side_effects: bool,
+    /// The name of the bit field definition. Empty if the register doesn't
+    /// have one.
+    pub name: &'static str,
+    /// The bit fields
+    pub fields: &'static [FieldInfo],
+}
+
+impl<'a> RegisterDesc<'a> {
+    pub(crate) fn new<R: RegisterInfo>(
+        offset: usize,
+        path: &'a dyn fmt::Display,
+        access: Access,
+    ) -> Self {
+        Self {
+            path,
+            offset,
+            access,
+            read_side_effects: false,
+            name: R::NAME,
+            fields: R::FIELDS,
+        }
+    }
+}
+
+/// An instance of a register block
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Instance {
+    /// The instance name (e.g., `UART0`)
+    pub name: &'static str,
+    /// The low-peripheral ARM physical address
+    pub arm_pa: u64,
+}
+
+impl Instance {
+    /// Construct an `Instance` located at the specified low-peripheral ARM
+    /// physical address.
+    pub const fn new(name: &'static str, arm_pa: u64) -> Self {
+        Self { name, arm_pa }
+    }
+
+    /// Construct an `Instance` located at the specified VC address.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `vpa` is not mapped to the ARM physical address space.
+    pub const fn vpa(name: &'static str, vpa: Vpa) -> Self {
+        match vpa.to_arm_pa() {
+            Some(arm_pa) => Self::new(name, arm_pa),
+            None => panic!("not a peripheral address"),
+        }
+    }
+}
+
+/// Describes a register block and its instances
+#[derive(Clone, Copy)]
+pub struct Peripheral {
+    /// The name of the register block (e.g., `PL011`)
+    pub name: &'static str,
+    /// A short description
+    pub description: &'static str,
+    /// The instances
+    pub instances: &'static [Instance],
+    /// The size of the register block in bytes
+    pub size: usize,
+    registers: DescribeFn,
+}
+
+type DescribeFn = fn(&mut dyn FnMut(&RegisterDesc<'_>));
+
+impl Peripheral {
+    /// Construct a `Peripheral` describing the register block `B`.
+    pub const fn new<B: Snap>(
+        name: &'static str,
+        description: &'static str,
+        instances: &'static [Instance],
+    ) -> Self {
+        Self {
+            name,
+            description,
+            instances,
+            size: core::mem::size_of::<B>(),
+            registers: describe_block::<B>,
+        }
+    }
+
+    /// Call `f` for each register in offset order.
+    pub fn registers(&self, f: &mut dyn FnMut(&RegisterDesc<'_>)) {
+        (self.registers)(f)
+    }
+}
+
+impl fmt::Debug for Peripheral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Peripheral")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("instances", &self.instances)
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+fn describe_block<B: Snap>(f: &mut dyn FnMut(&RegisterDesc<'_>)) {
+    B::describe(0, None, f)
+}
+
+/// Get the descriptions of all register blocks of this crate.
+pub fn peripherals() -> impl Iterator<Item = &'static Peripheral> {
+    [
+        crate::ap804::describe(),
+        crate::arm_local::describe(),
+        crate::armc::describe(),
+        crate::aux::describe(),
+        crate::avs::describe(),
+        crate::bsc::describe(),
+        crate::bsc_slave::describe(),
+        crate::cm::describe(),
+        crate::dmac::describe(),
+        crate::emmc2::describe(),
+        crate::gic400::describe(),
+        crate::gpio::describe(),
+        crate::pcm::describe(),
+        crate::pl011::describe(),
+        crate::pm::describe(),
+        crate::pwm::describe(),
+        crate::rng200::describe(),
+        crate::spi::describe(),
+        crate::sys_timer::describe(),
+        crate::vcmbox::describe(),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// Write a CMSIS-SVD description of `peripherals`.
+///
+/// Each instance becomes a `<peripheral>` element; the second and later
+/// instances of a register block are derived from the first one. Registers
+/// in nested register blocks and register arrays, and multi-instance fields,
+/// are flattened (e.g., `aux_mu.io_reg` → `AUX_MU_IO_REG`, `gpfsel[1]` →
+/// `GPFSEL_1`, `FSEL` → `FSEL0`…`FSEL9`). Registers whose reads have side
+/// effects are marked with `<readAction>modify</readAction>`.
+pub fn write_svd<'a>(
+    w: &mut dyn Write,
+    peripherals: impl IntoIterator<Item = &'a Peripheral>,
+) -> fmt::Result {
+    writeln!(w, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+    writeln!(
+        w,
+        r#"<device schemaVersion="1.3" xmlns:xs="http://www.w3.org/2001/XMLSchema-instance" xs:noNamespaceSchemaLocation="CMSIS-SVD.xsd">"#
+    )?;
+    writeln!(w, "  <vendor>Broadcom</vendor>")?;
+    writeln!(w, "  <name>BCM2711</name>")?;
+    writeln!(w, "  <version>{}</version>", env!("CARGO_PKG_VERSION"))?;
+    writeln!(
+        w,
+        "  <description>BCM2711 peripherals (low-peripheral mode)</description>"
+    )?;
+    writeln!(w, "  <addressUnitBits>8</addressUnitBits>")?;
+    writeln!(w, "  <width>32</width>")?;
+    writeln!(w, "  <size>32</size>")?;
+    writeln!(w, "  <peripherals>")?;
+
+    for peripheral in peripherals {
+        let (first, rest) = match peripheral.instances.split_first() {
+            Some(x) => x,
+            None => continue,
+        };
+
+        writeln!(w, "    <peripheral>")?;
+        writeln!(w, "      <name>{}</name>", Xml(first.name))?;
+        writeln!(
+            w,
+            "      <description>{}</description>",
+            Xml(peripheral.description)
+        )?;
+        writeln!(w, "      <groupName>{}</groupName>", Xml(peripheral.name))?;
+        writeln!(w, "      <baseAddress>{:#x}</baseAddress>", first.arm_pa)?;
+        writeln!(w, "      <addressBlock>")?;
+        writeln!(w, "        <offset>0x0</offset>")?;
+        writeln!(w, "        <size>{:#x}</size>", peripheral.size)?;
+        writeln!(w, "        <usage>registers</usage>")?;
+        writeln!(w, "      </addressBlock>")?;
+        writeln!(w, "      <registers>")?;
+
+        let mut result = Ok(());
+        peripheral.registers(&mut |reg| {
+            if result.is_ok() {
+                result = write_svd_register(w, reg);
+            }
+        });
+        result?;
+
+        writeln!(w, "      </registers>")?;
+        writeln!(w, "    </peripheral>")?;
+
+        for instance in rest {
+            writeln!(w, r#"    <peripheral derivedFrom="{}">"#, Xml(first.name))?;
+            writeln!(w, "      <name>{}</name>", Xml(instance.name))?;
+            writeln!(w, "      <baseAddress>{:#x}</baseAddress>", instance.arm_pa)?;
+            writeln!(w, "    </peripheral>")?;
+        }
+    }
+
+    writeln!(w, "  </peripherals>")?;
+    writeln!(w, "</device>")
+}
+
+fn write_svd_register(w: &mut dyn Write, reg: &RegisterDesc<'_>) -> fmt::Result {
+    writeln!(w, "        <register>")?;
+    writeln!(w, "          <name>{}</name>", SvdName(reg.path))?;
+    if !reg.name.is_empty() {
+        writeln!(w, "          <description>{}</description>", Xml(reg.name))?;
+    }
+    writeln!(
+        w,
+        "          <addressOffset>{:#x}</addressOffset>",
+        reg.offset
+    )?;
+    writeln!(w, "          <size>32</size>")?;
+    writeln!(w, "          <access>{}</access>", reg.access.svd_name())?;
+    if reg.read_side_effects {
+        writeln!(w, "          <readAction>modify</readAction>")?;
+    }
+
+    if !reg.fields.is_empty() {
+        writeln!(w, "          <fields>")?;
+        for field in reg.fields {
+            for i in 0..field.count {
+                writeln!(w, "            <field>")?;
+                if field.count == 1 {
+                    writeln!(w, "              <name>{}</name>", Xml(field.name))?;
+                } else {
+                    writeln!(w, "              <name>{}{}</name>", Xml(field.name), i)?;
+                }
+                writeln!(
+                    w,
+                    "              <bitOffset>{}</bitOffset>",
+                    field.offset + field.width * i
+                )?;
+                writeln!(w, "              <bitWidth>{}</bitWidth>", field.width)?;
+                if !field.values.is_empty() {
+                    writeln!(w, "              <enumeratedValues>")?;
+                    for &(name, value) in field.values {
+                        writeln!(w, "                <enumeratedValue>")?;
+                        writeln!(w, "                  <name>{}</name>", Xml(name))?;
+                        writeln!(w, "                  <value>{:#x}</value>", value)?;
+                        writeln!(w, "                </enumeratedValue>")?;
+                    }
+                    writeln!(w, "              </enumeratedValues>")?;
+                }
+                writeln!(w, "            </field>")?;
+            }
+        }
+        writeln!(w, "          </fields>")?;
+    }
+
+    writeln!(w, "        </register>")
+}
+
+/// Write a JSON description of `peripherals`. See [the module-level
+/// documentation](self) for the format.
+pub fn write_json<'a>(
+    w: &mut dyn Write,
+    peripherals: impl IntoIterator<Item = &'a Peripheral>,
+) -> fmt::Result {
+    writeln!(w, r#"{{"device": "BCM2711", "peripherals": ["#)?;
+
+    for (i, peripheral) in peripherals.into_iter().enumerate() {
+        if i > 0 {
+            writeln!(w, ",")?;
+        }
+        write!(
+            w,
+            r#"{{"name": {}, "description": {}, "size": {}, "instances": ["#,
+            Json(peripheral.name),
+            Json(peripheral.description),
+            peripheral.size
+        )?;
+        for (k, instance) in peripheral.instances.iter().enumerate() {
+            write!(
+                w,
+                r#"{}{{"name": {}, "base": {}}}"#,
+                if k > 0 { ", " } else { "" },
+                Json(instance.name),
+                instance.arm_pa
+            )?;
+        }
+        writeln!(w, r#"], "registers": ["#)?;
+
+        let mut result = Ok(());
+        let mut first = true;
+        peripheral.registers(&mut |reg| {
+            if result.is_ok() {
+                result = write_json_register(w, reg, first);
+                first = false;
+            }
+        });
+        result?;
+
+        write!(w, "\n]}}")?;
+    }
+
+    writeln!(w, "\n]}}")
+}
+
+fn write_json_register(w: &mut dyn Write, reg: &RegisterDesc<'_>, first: bool) -> fmt::Result {
+    if !first {
+        writeln!(w, ",")?;
+    }
+    write!(
+        w,
+        r#"{{"name": {}, "offset": {}, "access": "{}", "readSideEffects": {}, "type": {}, "fields": ["#,
+        Json(reg.path),
+        reg.offset,
+        reg.access.svd_name(),
+        reg.read_side_effects,
+        Json(reg.name)
+    )?;
+    for (i, field) in reg.fields.iter().enumerate() {
+        write!(
+            w,
+            r#"{}{{"name": {}, "offset": {}, "width": {}, "count": {}, "values": ["#,
+            if i > 0 { ", " } else { "" },
+            Json(field.name),
+            field.offset,
+            field.width,
+            field.count
+        )?;
+        for (k, &(name, value)) in field.values.iter().enumerate() {
+            write!(
+                w,
+                r#"{}{{"name": {}, "value": {}}}"#,
+                if k > 0 { ", " } else { "" },
+                Json(name),
+                value
+            )?;
+        }
+        write!(w, "]}}")?;
+    }
+    write!(w, "]}}")
+}
+
+/// Formats a string with the XML special characters escaped
+struct Xml<T>(T);
+
+impl<T: fmt::Display> fmt::Display for Xml<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Escape<'a, 'b>(&'a mut fmt::Formatter<'b>);
+        impl Write for Escape<'_, '_> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                for c in s.chars() {
+                    match c {
+                        '<' => self.0.write_str("&lt;")?,
+                        '>' => self.0.write_str("&gt;")?,
+                        '&' => self.0.write_str("&amp;")?,
+                        '"' => self.0.write_str("&quot;")?,
+                        _ => self.0.write_char(c)?,
+                    }
+                }
+                Ok(())
+            }
+        }
+        write!(Escape(f), "{}", self.0)
+    }
+}
+
+/// Formats a register path as a flat SVD register name
+/// (`aux_mu.io_reg` → `AUX_MU_IO_REG`, `gpfsel[1]` → `GPFSEL_1`)
+struct SvdName<'a>(&'a dyn fmt::Display);
+
+impl fmt::Display for SvdName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Flatten<'a, 'b>(&'a mut fmt::Formatter<'b>);
+        impl Write for Flatten<'_, '_> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                for c in s.chars() {
+                    match c {
+                        '.' | '[' => self.0.write_char('_')?,
+                        ']' => {}
+                        _ => self.0.write_char(c.to_ascii_uppercase())?,
+                    }
+                }
+                Ok(())
+            }
+        }
+        write!(Flatten(f), "{}", self.0)
+    }
+}
+
+/// Formats a string as a JSON string literal
+struct Json<T>(T);
+
+impl<T: fmt::Display> fmt::Display for Json<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Escape<'a, 'b>(&'a mut fmt::Formatter<'b>);
+        impl Write for Escape<'_, '_> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                for c in s.chars() {
+                    match c {
+                        '"' => self.0.write_str("\\\"")?,
+                        '\\' => self.0.write_str("\\\\")?,
+                        '\n' => self.0.write_str("\\n")?,
+                        c if (c as u32) < 0x20 => write!(self.0, "\\u{:04x}", c as u32)?,
+                        _ => self.0.write_char(c)?,
+                    }
+                }
+                Ok(())
+            }
+        }
+        f.write_char('"')?;
+        write!(Escape(f), "{}", self.0)?;
+        f.write_char('"')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+    use crate::{aux, gpio, pl011};
+    use std::{string::String, vec::Vec};
+
+    #[test]
+    fn consistency() {
+        let mut instance_names = Vec::new();
+        for peripheral in peripherals() {
+            assert!(!peripheral.instances.is_empty(), "{}", peripheral.name);
+            instance_names.extend(peripheral.instances.iter().map(|i| i.name));
+
+            let mut next_offset = 0;
+            peripheral.registers(&mut |reg| {
+                assert!(
+                    reg.offset >= next_offset && reg.offset + 4 <= peripheral.size,
+                    "{}: {}",
+                    peripheral.name,
+                    reg.path
+                );
+                next_offset = reg.offset + 4;
+
+                for field in reg.fields {
+                    assert!(
+                        field.offset + field.width * field.count <= 32,
+                        "{}: {}.{}",
+                        peripheral.name,
+                        reg.path,
+                        field.name
+                    );
+                    for &(name, value) in field.values {
+                        assert!(
+                            field.width >= 32 || value >> field.width == 0,
+                            "{}: {}.{}.{}",
+                            peripheral.name,
+                            reg.path,
+                            field.name,
+                            name
+                        );
+                    }
+                }
+            });
+        }
+
+        let count = instance_names.len();
+        instance_names.sort_unstable();
+        instance_names.dedup();
+        assert_eq!(instance_names.len(), count, "duplicate instance names");
+    }
+
+    #[test]
+    fn registers() {
+        let mut regs = Vec::new();
+        aux::describe()[0].registers(&mut |reg| {
+            regs.push((
+                std::format!("{}", reg.path),
+                reg.offset,
+                reg.access,
+                reg.read_side_effects,
+            ))
+        });
+        assert_eq!(regs[0], ("aux_irq".into(), 0x00, Access::ReadOnly, false));
+        assert!(regs.contains(&("aux_mu.io_reg".into(), 0x40, Access::ReadWrite, true)));
+        assert!(regs.contains(&("aux_mu.baud_reg".into(), 0x68, Access::ReadWrite, false)));
+        assert!(regs.contains(&("aux_spi2.io_rega".into(), 0xe0, Access::ReadWrite, true)));
+    }
+
+    #[test]
+    fn svd() {
+        let mut svd = String::new();
+        write_svd(&mut svd, pl011::describe().iter().chain(gpio::describe())).unwrap();
+
+        assert!(svd.contains(
+            "      <name>UART0</name>\n\
+            \x20     <description>PL011 UART</description>\n\
+            \x20     <groupName>PL011</groupName>\n\
+            \x20     <baseAddress>0xfe201000</baseAddress>\n"
+        ));
+        assert!(svd.contains(
+            "    <peripheral derivedFrom=\"UART0\">\n\
+            \x20     <name>UART5</name>\n\
+            \x20     <baseAddress>0xfe201a00</baseAddress>\n"
+        ));
+        assert!(svd.contains(
+            "          <name>DR</name>\n\
+            \x20         <description>DR</description>\n\
+            \x20         <addressOffset>0x0</addressOffset>\n\
+            \x20         <size>32</size>\n\
+            \x20         <access>read-write</access>\n\
+            \x20         <readAction>modify</readAction>\n"
+        ));
+        assert!(svd.contains(
+            "              <name>WLEN</name>\n\
+            \x20             <bitOffset>5</bitOffset>\n\
+            \x20             <bitWidth>2</bitWidth>\n\
+            \x20             <enumeratedValues>\n\
+            \x20               <enumeratedValue>\n\
+            \x20                 <name>FiveBits</name>\n\
+            \x20                 <value>0x0</value>\n"
+        ));
+        assert!(svd.contains(
+            "          <name>GPFSEL_1</name>\n\
+            \x20         <description>GPFSEL</description>\n\
+            \x20         <addressOffset>0x4</addressOffset>\n"
+        ));
+        assert!(svd.contains(
+            "              <name>FSEL9</name>\n\
+            \x20             <bitOffset>27</bitOffset>\n\
+            \x20             <bitWidth>3</bitWidth>\n"
+        ));
+        assert!(svd.ends_with("  </peripherals>\n</device>\n"));
+    }
+
+    #[test]
+    fn json() {
+        let mut json = String::new();
+        write_json(&mut json, pl011::describe()).unwrap();
+
+        assert!(json.starts_with(
+            "{\"device\": \"BCM2711\", \"peripherals\": [\n\
+            {\"name\": \"PL011\", \"description\": \"PL011 UART\", \"size\": 144, \
+            \"instances\": [{\"name\": \"UART0\", \"base\": 4263514112}, "
+        ));
+        assert!(json.contains(
+            "\n{\"name\": \"cr\", \"offset\": 48, \"access\": \"read-write\", \
+            \"readSideEffects\": false, \"type\": \"CR\", \"fields\": [\
+            {\"name\": \"UARTEN\", \"offset\": 0, \"width\": 1, \"count\": 1, \"values\": []}, "
+        ));
+        assert!(json.contains(
+            "{\"name\": \"WLEN\", \"offset\": 5, \"width\": 2, \"count\": 1, \"values\": [\
+            {\"name\": \"FiveBits\", \"value\": 0}, {\"name\": \"SixBits\", \"value\": 1}, "
+        ));
+        assert!(json.ends_with("\n]}\n]}\n"));
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(
+            std::format!("{}", Xml("a<b & \"c\">")),
+            "a&lt;b &amp; &quot;c&quot;&gt;"
+        );
+        assert_eq!(
+            std::format!("{}", Json("a\"b\\c\nd\x01")),
+            "\"a\\\"b\\\\c\\nd\\u0001\""
+        );
+        assert_eq!(
+            std::format!("{}", SvdName(&"dma0[3].conblk_ad")),
+            "DMA0_3_CONBLK_AD"
+        );
+    }
+}
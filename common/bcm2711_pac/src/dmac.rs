@@ -2,13 +2,17 @@
 //!
 //! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A34%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
 use tock_registers::{
-    register_bitfields, register_structs,register_bitmasks,
-    registers::{ReadOnly, ReadWrite},
+    register_structs,register_bitmasks,
     fields::Field,
     RegisterLongName,
 };
 
-use crate::{MemoryField, Vpa};
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::{ReadOnly, ReadWrite},
+    snapshot::{impl_snapshot, register_bitfields, FieldInfo, RegisterInfo},
+    MemoryField, Vpa,
+};
 
 /// The base address of [the DMA0-14 register block](Dma0Registers).
 pub const BASE_DMA0: Vpa = Vpa(0x4_7e00_7000);
@@ -111,6 +115,61 @@ register_structs! {
     }
 }
 
+impl_snapshot! {
+    Dma0Registers {
+        dma0,
+        dma7,
+        dma11,
+        int_status,
+        enable,
+    }
+    DmaRegisters {
+        cs,
+        conblk_ad,
+        ti,
+        source_ad,
+        dest_ad,
+        txfr_len,
+        stride,
+        nextconbk,
+        debug,
+    }
+    DmaLiteRegisters {
+        cs,
+        conblk_ad,
+        ti,
+        source_ad,
+        dest_ad,
+        txfr_len,
+        nextconbk,
+        debug,
+    }
+    Dma4Registers {
+        cs,
+        cb,
+        debug,
+        ti,
+        src,
+        srci,
+        dest,
+        desti,
+        len,
+        next_cb,
+        debug2,
+    }
+}
+
+/// Describe [the DMA controller register block](Dma0Registers) and its
+/// instance. See [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Dma0Registers>(
+        "DMA",
+        "DMA controller",
+        &[Instance::vpa("DMA", BASE_DMA0)],
+    )];
+    PERIPHERALS
+}
+
 impl Dma0Registers {
     /// DMA0
     #[inline]
@@ -271,6 +330,11 @@ pub mod INT_STATUS {
     pub struct Register;
     impl RegisterLongName for Register {}
 
+    impl RegisterInfo for Register {
+        const NAME: &'static str = "INT_STATUS";
+        const FIELDS: &'static [FieldInfo] = &[FieldInfo::array("INT", 0, 1, COUNT as u32, &[])];
+    }
+
     /// Construct a [`Field`] representing the `INT` bit (interrupt status, RO)
     /// corresponding to the specified DMA engine instance.
     ///
@@ -290,6 +354,15 @@ pub mod ENABLE {
     pub struct Register;
     impl RegisterLongName for Register {}
 
+    impl RegisterInfo for Register {
+        const NAME: &'static str = "ENABLE";
+        const FIELDS: &'static [FieldInfo] = &[
+            FieldInfo::array("EN", 0, 1, 14, &[]),
+            FieldInfo::new("PAGE", 24, 4, &[]),
+            FieldInfo::new("PAGELITE", 28, 4, &[]),
+        ];
+    }
+
     /// Construct a [`Field`] representing the `EN` bit (enable, RW)
     /// corresponding to the specified DMA engine instance.
     ///
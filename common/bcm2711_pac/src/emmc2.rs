@@ -0,0 +1,680 @@
+//! [BCM2711 EMMC2][1] (SDHCI 3.0 host controller)
+//!
+//! EMMC2 is the host controller connected to the microSD card slot of
+//! Raspberry Pi 4. Its register layout follows [the SD Host Controller
+//! Simplified Specification Version 3.00][2] and is compatible with the EMMC
+//! register block described in the BCM2711 datasheet.
+//!
+//! [`Sdhci`] is a polled [`sd::SdHost`] implementation for this register block.
+//! See [`sd`] for the card protocol and [`sd::BlockDevice`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use bcm2711_pac::emmc2::{self, sd::{self, BlockDevice}};
+//!
+//! let regs = unsafe {
+//!     &*(emmc2::BASE.to_arm_pa().unwrap() as usize as *const emmc2::Registers)
+//! };
+//! fn delay_us(us: u32) {
+//!     // ... e.g., `solid::timer::sleep_busy_ns`
+//! }
+//! // The base clock rate can be obtained by
+//! // `vcmbox::property::GetClockRate { clock: ClockId::EMMC2 }`
+//! let host = emmc2::Sdhci::new(regs, 100_000_000, delay_us);
+//!
+//! let mut card = sd::SdCard::init(host, &sd::Config::default()).unwrap();
+//! let mut block = [0u8; 512];
+//! card.read_blocks(0, &mut block).unwrap();
+//! ```
+//!
+//! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A60%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
+//! [2]: https://www.sdcard.org/downloads/pls/
+use tock_registers::{
+    fields::FieldValue,
+    interfaces::{ReadWriteable, Readable, Writeable},
+    register_structs, LocalRegisterCopy,
+};
+
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::{ReadOnly, ReadWrite},
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
+};
+
+pub mod sd;
+
+/// The base address of [the EMMC2 register block](Registers).
+pub const BASE: Vpa = Vpa(0x4_7e34_0000);
+
+register_structs! {
+    pub Registers {
+        /// Argument for ACMD23 command
+        (0x00 => pub arg2: ReadWrite<u32>),
+        /// Number and size in bytes for data block to be transferred
+        (0x04 => pub blksizecnt: ReadWrite<u32, BLKSIZECNT::Register>),
+        /// Argument for everything but ACMD23
+        (0x08 => pub arg1: ReadWrite<u32>),
+        /// Issue commands to the card
+        (0x0c => pub cmdtm: ReadWrite<u32, CMDTM::Register>),
+        /// Bits 31:0 of the response
+        (0x10 => pub resp0: ReadOnly<u32>),
+        /// Bits 63:32 of CMD2 and CMD10 responses
+        (0x14 => pub resp1: ReadOnly<u32>),
+        /// Bits 95:64 of CMD2 and CMD10 responses
+        (0x18 => pub resp2: ReadOnly<u32>),
+        /// Bits 127:96 of CMD2 and CMD10 responses
+        (0x1c => pub resp3: ReadOnly<u32>),
+        /// Data to/from the card
+        (0x20 => pub data: ReadWrite<u32>),
+        /// Status info for debugging (RO)
+        (0x24 => pub status: ReadOnly<u32, STATUS::Register>),
+        /// Host configuration
+        (0x28 => pub control0: ReadWrite<u32, CONTROL0::Register>),
+        /// Host configuration
+        (0x2c => pub control1: ReadWrite<u32, CONTROL1::Register>),
+        /// Interrupt flags (W1C)
+        (0x30 => pub interrupt: ReadWrite<u32, INTERRUPT::Register>),
+        /// Mask interrupts that change in [`Self::interrupt`]
+        (0x34 => pub irpt_mask: ReadWrite<u32, INTERRUPT::Register>),
+        /// Enable interrupts to the core
+        (0x38 => pub irpt_en: ReadWrite<u32, INTERRUPT::Register>),
+        /// Host configuration
+        (0x3c => pub control2: ReadWrite<u32, CONTROL2::Register>),
+        /// Capabilities (RO)
+        (0x40 => pub capabilities0: ReadOnly<u32, CAPABILITIES0::Register>),
+        /// Capabilities (RO)
+        (0x44 => pub capabilities1: ReadOnly<u32>),
+        (0x48 => _pad0),
+        /// Force an interrupt
+        (0x50 => pub force_irpt: ReadWrite<u32, INTERRUPT::Register>),
+        (0x54 => _pad1),
+        /// Number of SD clock cycles to wait for boot
+        (0x70 => pub boot_timeout: ReadWrite<u32>),
+        (0x74 => _pad2),
+        /// Version information and slot interrupt status (RO)
+        (0xfc => pub slotisr_ver: ReadOnly<u32, SLOTISR_VER::Register>),
+        (0x100 => @END),
+    }
+}
+
+impl_snapshot! {
+    Registers {
+        arg2,
+        blksizecnt,
+        arg1,
+        cmdtm,
+        resp0,
+        resp1,
+        resp2,
+        resp3,
+        // Reading `DATA` consumes the data buffer
+        data: skip,
+        status,
+        control0,
+        control1,
+        interrupt,
+        irpt_mask,
+        irpt_en,
+        control2,
+        capabilities0,
+        capabilities1,
+        force_irpt,
+        boot_timeout,
+        slotisr_ver,
+    }
+}
+
+/// Describe [the EMMC2 register block](Registers) and its instance. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "EMMC2",
+        "EMMC2 (SDHCI 3.0 host controller)",
+        &[Instance::vpa("EMMC2", BASE)],
+    )];
+    PERIPHERALS
+}
+
+register_bitfields! {u32,
+    pub BLKSIZECNT [
+        /// Block size in bytes
+        BLKSIZE OFFSET(0) NUMBITS(10) [],
+        /// Number of blocks to be transferred
+        BLKCNT OFFSET(16) NUMBITS(16) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub CMDTM [
+        /// Enable DMA
+        TM_DMA_EN OFFSET(0) NUMBITS(1) [],
+        /// Enable block counter
+        TM_BLKCNT_EN OFFSET(1) NUMBITS(1) [],
+        /// Command after completion
+        TM_AUTO_CMD_EN OFFSET(2) NUMBITS(2) [
+            None = 0b00,
+            Cmd12 = 0b01,
+            Cmd23 = 0b10,
+        ],
+        /// Direction of data transfer
+        TM_DAT_DIR OFFSET(4) NUMBITS(1) [
+            HostToCard = 0,
+            CardToHost = 1,
+        ],
+        /// Type of data transfer
+        TM_MULTI_BLOCK OFFSET(5) NUMBITS(1) [
+            Single = 0,
+            Multiple = 1,
+        ],
+        /// Type of expected response
+        CMD_RSPNS_TYPE OFFSET(16) NUMBITS(2) [
+            None = 0b00,
+            _136Bits = 0b01,
+            _48Bits = 0b10,
+            _48BitsUsingBusy = 0b11,
+        ],
+        /// Check the response's CRC
+        CMD_CRCCHK_EN OFFSET(19) NUMBITS(1) [],
+        /// Check that the response has the same command index
+        CMD_IXCHK_EN OFFSET(20) NUMBITS(1) [],
+        /// Command involves data
+        CMD_ISDATA OFFSET(21) NUMBITS(1) [],
+        /// Type of command to be issued
+        CMD_TYPE OFFSET(22) NUMBITS(2) [
+            Normal = 0b00,
+            Suspend = 0b01,
+            Resume = 0b10,
+            Abort = 0b11,
+        ],
+        /// Command index to be issued
+        CMD_INDEX OFFSET(24) NUMBITS(6) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub STATUS [
+        /// Command line still in use
+        CMD_INHIBIT OFFSET(0) NUMBITS(1) [],
+        /// Data lines still in use
+        DAT_INHIBIT OFFSET(1) NUMBITS(1) [],
+        /// At least one data line is active
+        DAT_ACTIVE OFFSET(2) NUMBITS(1) [],
+        /// Write transfer is active
+        WRITE_TRANSFER OFFSET(8) NUMBITS(1) [],
+        /// Read transfer is active
+        READ_TRANSFER OFFSET(9) NUMBITS(1) [],
+        /// The buffer has space for new data
+        BUFFER_WRITE_ENABLE OFFSET(10) NUMBITS(1) [],
+        /// New data is available to read
+        BUFFER_READ_ENABLE OFFSET(11) NUMBITS(1) [],
+        /// A card is inserted
+        CARD_INSERTED OFFSET(16) NUMBITS(1) [],
+        /// Value of DAT[3:0]
+        DAT_LEVEL0 OFFSET(20) NUMBITS(4) [],
+        /// Value of CMD
+        CMD_LEVEL OFFSET(24) NUMBITS(1) [],
+        /// Value of DAT[7:4]
+        DAT_LEVEL1 OFFSET(25) NUMBITS(4) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub CONTROL0 [
+        /// Use 4 data lines
+        HCTL_DWIDTH OFFSET(1) NUMBITS(1) [],
+        /// Enable high speed mode
+        HCTL_HS_EN OFFSET(2) NUMBITS(1) [],
+        /// Use 8 data lines
+        HCTL_8BIT OFFSET(5) NUMBITS(1) [],
+        /// SD bus power
+        SD_BUS_POWER OFFSET(8) NUMBITS(1) [],
+        /// SD bus voltage
+        SD_BUS_VOLTAGE OFFSET(9) NUMBITS(3) [
+            V1_8 = 0b101,
+            V3_0 = 0b110,
+            V3_3 = 0b111,
+        ],
+        /// Stop the current transaction at the next block gap
+        GAP_STOP OFFSET(16) NUMBITS(1) [],
+        /// Restart a transaction stopped by [`GAP_STOP`]
+        GAP_RESTART OFFSET(17) NUMBITS(1) [],
+        /// Use DAT2 read/wait protocol
+        READWAIT_EN OFFSET(18) NUMBITS(1) [],
+        /// Enable interrupt on block gap
+        GAP_IEN OFFSET(19) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub CONTROL1 [
+        /// Enable internal clock
+        CLK_INTLEN OFFSET(0) NUMBITS(1) [],
+        /// SD clock stable (RO)
+        CLK_STABLE OFFSET(1) NUMBITS(1) [],
+        /// SD clock enable
+        CLK_EN OFFSET(2) NUMBITS(1) [],
+        /// Mode of clock generation
+        CLK_GENSEL OFFSET(5) NUMBITS(1) [
+            Divided = 0,
+            Programmable = 1,
+        ],
+        /// Clock base divider MSBs
+        CLK_FREQ_MS2 OFFSET(6) NUMBITS(2) [],
+        /// Clock base divider LSBs
+        CLK_FREQ8 OFFSET(8) NUMBITS(8) [],
+        /// Data timeout exponent (TMCLK × 2^(x + 13)). `0b1111` disables the
+        /// timeout.
+        DATA_TOUNIT OFFSET(16) NUMBITS(4) [],
+        /// Reset the complete host circuit (W1SC)
+        SRST_HC OFFSET(24) NUMBITS(1) [],
+        /// Reset the command handling circuit (W1SC)
+        SRST_CMD OFFSET(25) NUMBITS(1) [],
+        /// Reset the data handling circuit (W1SC)
+        SRST_DATA OFFSET(26) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub INTERRUPT [
+        /// Command has finished
+        CMD_DONE OFFSET(0) NUMBITS(1) [],
+        /// Data transfer has finished
+        DATA_DONE OFFSET(1) NUMBITS(1) [],
+        /// Data transfer has stopped at block gap
+        BLOCK_GAP OFFSET(2) NUMBITS(1) [],
+        /// [`Registers::data`] can be written to
+        WRITE_RDY OFFSET(4) NUMBITS(1) [],
+        /// [`Registers::data`] contains data to be read
+        READ_RDY OFFSET(5) NUMBITS(1) [],
+        /// Card inserted
+        CARD_INSERTION OFFSET(6) NUMBITS(1) [],
+        /// Card removed
+        CARD_REMOVAL OFFSET(7) NUMBITS(1) [],
+        /// Card made interrupt request
+        CARD OFFSET(8) NUMBITS(1) [],
+        /// Clock retune request
+        RETUNE OFFSET(12) NUMBITS(1) [],
+        /// Boot has been acknowledged
+        BOOTACK OFFSET(13) NUMBITS(1) [],
+        /// Boot operation has terminated
+        ENDBOOT OFFSET(14) NUMBITS(1) [],
+        /// An error has occurred (RO)
+        ERR OFFSET(15) NUMBITS(1) [],
+        /// Command timeout
+        CTO_ERR OFFSET(16) NUMBITS(1) [],
+        /// Command CRC error
+        CCRC_ERR OFFSET(17) NUMBITS(1) [],
+        /// Command end bit error (not 1)
+        CEND_ERR OFFSET(18) NUMBITS(1) [],
+        /// Incorrect response command index
+        CBAD_ERR OFFSET(19) NUMBITS(1) [],
+        /// Data timeout
+        DTO_ERR OFFSET(20) NUMBITS(1) [],
+        /// Data CRC error
+        DCRC_ERR OFFSET(21) NUMBITS(1) [],
+        /// Data end bit error (not 1)
+        DEND_ERR OFFSET(22) NUMBITS(1) [],
+        /// Auto command error
+        ACMD_ERR OFFSET(24) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub CONTROL2 [
+        /// Auto command not executed due to an error
+        ACNOX_ERR OFFSET(0) NUMBITS(1) [],
+        /// Auto command timeout
+        ACTO_ERR OFFSET(1) NUMBITS(1) [],
+        /// Command CRC error during auto command
+        ACCRC_ERR OFFSET(2) NUMBITS(1) [],
+        /// End bit is not 1 during auto command
+        ACEND_ERR OFFSET(3) NUMBITS(1) [],
+        /// Command index error during auto command
+        ACBAD_ERR OFFSET(4) NUMBITS(1) [],
+        /// Error during auto CMD12
+        NOTC12_ERR OFFSET(7) NUMBITS(1) [],
+        /// Select the speed of the SD card
+        UHSMODE OFFSET(16) NUMBITS(3) [
+            Sdr12 = 0b000,
+            Sdr25 = 0b001,
+            Sdr50 = 0b010,
+            Sdr104 = 0b011,
+            Ddr50 = 0b100,
+        ],
+        /// SD clock tune in progress
+        TUNEON OFFSET(22) NUMBITS(1) [],
+        /// Tuned clock is used for sampling data
+        TUNED OFFSET(23) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub CAPABILITIES0 [
+        /// Timeout clock frequency
+        TIMEOUT_CLOCK_FREQ OFFSET(0) NUMBITS(6) [],
+        /// Timeout clock unit
+        TIMEOUT_CLOCK_UNIT OFFSET(7) NUMBITS(1) [
+            KHz = 0,
+            MHz = 1,
+        ],
+        /// Base clock frequency for SD clock in MHz. Zero if unspecified.
+        BASE_CLOCK_FREQ OFFSET(8) NUMBITS(8) [],
+        /// Max block length
+        MAX_BLOCK_LENGTH OFFSET(16) NUMBITS(2) [],
+        /// 8-bit bus support
+        BUS_8BIT OFFSET(18) NUMBITS(1) [],
+        /// ADMA2 support
+        ADMA2 OFFSET(19) NUMBITS(1) [],
+        /// High speed support
+        HIGH_SPEED OFFSET(21) NUMBITS(1) [],
+        /// SDMA support
+        SDMA OFFSET(22) NUMBITS(1) [],
+        /// Voltage support 3.3V
+        VOLTAGE_3_3 OFFSET(24) NUMBITS(1) [],
+        /// Voltage support 3.0V
+        VOLTAGE_3_0 OFFSET(25) NUMBITS(1) [],
+        /// Voltage support 1.8V
+        VOLTAGE_1_8 OFFSET(26) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub SLOTISR_VER [
+        /// OR of interrupt and wakeup signals for each slot
+        SLOT_STATUS OFFSET(0) NUMBITS(8) [],
+        /// Host controller specification version
+        SDVERSION OFFSET(16) NUMBITS(8) [],
+        /// Vendor version number
+        VENDOR OFFSET(24) NUMBITS(8) [],
+    ]
+}
+
+/// The maximum value of the 10-bit SD clock divider
+const CLOCK_DIVIDER_MAX: u32 = 0x3ff;
+
+/// The number of polling iterations before a wait times out. Each iteration
+/// is followed by a 1µs delay.
+const POLL_LIMIT: u32 = 1_000_000;
+
+/// A polled [`sd::SdHost`] implementation for [the EMMC2 register
+/// block](Registers).
+pub struct Sdhci<'a> {
+    regs: &'a Registers,
+    base_clock_hz: u32,
+    delay_us: fn(u32),
+}
+
+impl<'a> Sdhci<'a> {
+    /// Construct an `Sdhci`.
+    ///
+    /// `base_clock_hz` is the frequency of the controller's base clock. If
+    /// it's zero, the value reported by
+    /// [`CAPABILITIES0::BASE_CLOCK_FREQ`](const@CAPABILITIES0::BASE_CLOCK_FREQ)
+    /// is used instead. `delay_us` is a function that blocks for the
+    /// specified number of microseconds.
+    pub fn new(regs: &'a Registers, base_clock_hz: u32, delay_us: fn(u32)) -> Self {
+        let base_clock_hz = if base_clock_hz == 0 {
+            regs.capabilities0.read(CAPABILITIES0::BASE_CLOCK_FREQ) * 1_000_000
+        } else {
+            base_clock_hz
+        };
+        Self {
+            regs,
+            base_clock_hz,
+            delay_us,
+        }
+    }
+
+    /// Get the underlying register block.
+    #[inline]
+    pub fn regs(&self) -> &'a Registers {
+        self.regs
+    }
+
+    /// Poll `f` until it returns `true`.
+    fn wait(&self, mut f: impl FnMut() -> bool) -> Result<(), sd::Error> {
+        for _ in 0..POLL_LIMIT {
+            if f() {
+                return Ok(());
+            }
+            (self.delay_us)(1);
+        }
+        Err(sd::Error::HostTimeout)
+    }
+
+    /// Reset the command and/or data handling circuits after an error.
+    fn reset_lines(&self, value: FieldValue<u32, CONTROL1::Register>) {
+        let regs = self.regs;
+        regs.control1.modify(value);
+        let _ = self.wait(|| regs.control1.get() & value.mask() == 0);
+    }
+
+    /// Wait for any of the interrupt flags in `mask` (or an error) and clear
+    /// them.
+    fn wait_interrupt(&self, mask: FieldValue<u32, INTERRUPT::Register>) -> Result<(), sd::Error> {
+        let regs = self.regs;
+        let mut status = LocalRegisterCopy::new(0);
+        let result = self.wait(|| {
+            status = regs.interrupt.extract();
+            status.get() & (mask.mask() | INTERRUPT::ERR::SET.mask()) != 0
+        });
+
+        if let Err(e) = result {
+            self.reset_lines(CONTROL1::SRST_CMD::SET + CONTROL1::SRST_DATA::SET);
+            return Err(e);
+        }
+
+        if status.is_set(INTERRUPT::ERR) {
+            // Clear all error flags
+            regs.interrupt.set(status.get() & 0xffff_0000);
+            self.reset_lines(CONTROL1::SRST_CMD::SET + CONTROL1::SRST_DATA::SET);
+            return Err(decode_error(status));
+        }
+
+        regs.interrupt.set(status.get() & mask.mask());
+        Ok(())
+    }
+}
+
+/// Convert the error flags of [`INTERRUPT`] to [`sd::Error`].
+fn decode_error(status: LocalRegisterCopy<u32, INTERRUPT::Register>) -> sd::Error {
+    if status.is_set(INTERRUPT::CTO_ERR) {
+        sd::Error::CommandTimeout
+    } else if status.is_set(INTERRUPT::CCRC_ERR) {
+        sd::Error::CommandCrc
+    } else if status.is_set(INTERRUPT::CEND_ERR) {
+        sd::Error::CommandEndBit
+    } else if status.is_set(INTERRUPT::CBAD_ERR) {
+        sd::Error::CommandIndex
+    } else if status.is_set(INTERRUPT::DTO_ERR) {
+        sd::Error::DataTimeout
+    } else if status.is_set(INTERRUPT::DCRC_ERR) {
+        sd::Error::DataCrc
+    } else if status.is_set(INTERRUPT::DEND_ERR) {
+        sd::Error::DataEndBit
+    } else if status.is_set(INTERRUPT::ACMD_ERR) {
+        sd::Error::AutoCommand
+    } else {
+        sd::Error::Host(status.get())
+    }
+}
+
+/// Encode the value of [`Registers::cmdtm`] for a command.
+fn encode_cmdtm(
+    cmd: &sd::Command,
+    data: &sd::Data<'_>,
+    block_count: u32,
+) -> FieldValue<u32, CMDTM::Register> {
+    use sd::ResponseType::*;
+    let response = match cmd.response {
+        None => CMDTM::CMD_RSPNS_TYPE::None,
+        R2 => CMDTM::CMD_RSPNS_TYPE::_136Bits + CMDTM::CMD_CRCCHK_EN::SET,
+        R3 => CMDTM::CMD_RSPNS_TYPE::_48Bits,
+        R1b => {
+            CMDTM::CMD_RSPNS_TYPE::_48BitsUsingBusy
+                + CMDTM::CMD_CRCCHK_EN::SET
+                + CMDTM::CMD_IXCHK_EN::SET
+        }
+        R1 | R6 | R7 => {
+            CMDTM::CMD_RSPNS_TYPE::_48Bits + CMDTM::CMD_CRCCHK_EN::SET + CMDTM::CMD_IXCHK_EN::SET
+        }
+    };
+
+    let transfer = match data {
+        sd::Data::None => CMDTM::CMD_ISDATA::CLEAR,
+        sd::Data::Read(_) => CMDTM::CMD_ISDATA::SET + CMDTM::TM_DAT_DIR::CardToHost,
+        sd::Data::Write(_) => CMDTM::CMD_ISDATA::SET + CMDTM::TM_DAT_DIR::HostToCard,
+    };
+
+    let multi = if block_count > 1 {
+        CMDTM::TM_MULTI_BLOCK::Multiple + CMDTM::TM_BLKCNT_EN::SET + CMDTM::TM_AUTO_CMD_EN::Cmd12
+    } else {
+        CMDTM::TM_MULTI_BLOCK::Single
+    };
+
+    CMDTM::CMD_INDEX.val(cmd.index as u32) + response + transfer + multi
+}
+
+impl sd::SdHost for Sdhci<'_> {
+    fn reset(&mut self) -> Result<(), sd::Error> {
+        let regs = self.regs;
+        regs.control1.write(CONTROL1::SRST_HC::SET);
+        self.wait(|| !regs.control1.is_set(CONTROL1::SRST_HC))?;
+
+        // Power up the bus
+        regs.control0
+            .write(CONTROL0::SD_BUS_POWER::SET + CONTROL0::SD_BUS_VOLTAGE::V3_3);
+
+        // Report all status flags in `interrupt` but don't signal interrupts
+        regs.irpt_mask.set(u32::MAX);
+        regs.irpt_en.set(0);
+        regs.interrupt.set(u32::MAX);
+        Ok(())
+    }
+
+    fn set_clock(&mut self, hz: u32) -> Result<u32, sd::Error> {
+        let regs = self.regs;
+        if hz == 0 || self.base_clock_hz == 0 {
+            return Err(sd::Error::UnsupportedClock);
+        }
+
+        // Stop the SD clock before changing the divider
+        self.wait(|| {
+            !regs.status.is_set(STATUS::CMD_INHIBIT) && !regs.status.is_set(STATUS::DAT_INHIBIT)
+        })?;
+        regs.control1.modify(CONTROL1::CLK_EN::CLEAR);
+
+        // 10-bit divided clock mode: f = base / (2 * n) (n = 0: f = base)
+        let n = if hz >= self.base_clock_hz {
+            0
+        } else {
+            // Round up so that the frequency doesn't exceed `hz`
+            let n = (self.base_clock_hz - 1) / (2 * hz) + 1;
+            if n > CLOCK_DIVIDER_MAX {
+                return Err(sd::Error::UnsupportedClock);
+            }
+            n
+        };
+        let actual_hz = if n == 0 {
+            self.base_clock_hz
+        } else {
+            self.base_clock_hz / (2 * n)
+        };
+
+        regs.control1.modify(
+            CONTROL1::CLK_GENSEL::Divided
+                + CONTROL1::CLK_FREQ8.val(n & 0xff)
+                + CONTROL1::CLK_FREQ_MS2.val(n >> 8)
+                + CONTROL1::DATA_TOUNIT.val(0xe)
+                + CONTROL1::CLK_INTLEN::SET,
+        );
+        self.wait(|| regs.control1.is_set(CONTROL1::CLK_STABLE))?;
+        regs.control1.modify(CONTROL1::CLK_EN::SET);
+
+        // Give the card time to see the new clock
+        (self.delay_us)(10);
+        Ok(actual_hz)
+    }
+
+    fn set_bus_width(&mut self, width: sd::BusWidth) -> Result<(), sd::Error> {
+        self.regs.control0.modify(match width {
+            sd::BusWidth::One => CONTROL0::HCTL_DWIDTH::CLEAR + CONTROL0::HCTL_8BIT::CLEAR,
+            sd::BusWidth::Four => CONTROL0::HCTL_DWIDTH::SET + CONTROL0::HCTL_8BIT::CLEAR,
+        });
+        Ok(())
+    }
+
+    fn command(
+        &mut self,
+        cmd: &sd::Command,
+        mut data: sd::Data<'_>,
+    ) -> Result<sd::Response, sd::Error> {
+        let regs = self.regs;
+
+        let (block_size, block_count) = data.geometry()?;
+        let uses_dat = !matches!(data, sd::Data::None) || cmd.response == sd::ResponseType::R1b;
+
+        self.wait(|| {
+            let status = regs.status.extract();
+            !(status.is_set(STATUS::CMD_INHIBIT)
+                || (uses_dat && status.is_set(STATUS::DAT_INHIBIT)))
+        })?;
+
+        regs.interrupt.set(u32::MAX);
+        regs.blksizecnt.write(
+            BLKSIZECNT::BLKSIZE.val(block_size as u32) + BLKSIZECNT::BLKCNT.val(block_count),
+        );
+        regs.arg1.set(cmd.arg);
+        regs.cmdtm.write(encode_cmdtm(cmd, &data, block_count));
+
+        self.wait_interrupt(INTERRUPT::CMD_DONE::SET)?;
+
+        let response = match cmd.response {
+            sd::ResponseType::None => sd::Response::default(),
+            // The controller strips the CRC byte of R2 responses; realign
+            // the bits to match the CID/CSD register layout
+            sd::ResponseType::R2 => {
+                let raw = regs.resp0.get() as u128
+                    | (regs.resp1.get() as u128) << 32
+                    | (regs.resp2.get() as u128) << 64
+                    | (regs.resp3.get() as u128) << 96;
+                sd::Response::from_u128(raw << 8)
+            }
+            _ => sd::Response::from_u32(regs.resp0.get()),
+        };
+
+        match &mut data {
+            sd::Data::None => {}
+            sd::Data::Read(buf) => {
+                for block in buf.chunks_mut(block_size) {
+                    self.wait_interrupt(INTERRUPT::READ_RDY::SET)?;
+                    for word in block.chunks_mut(4) {
+                        word.copy_from_slice(&regs.data.get().to_le_bytes()[..word.len()]);
+                    }
+                }
+            }
+            sd::Data::Write(buf) => {
+                for block in buf.chunks(block_size) {
+                    self.wait_interrupt(INTERRUPT::WRITE_RDY::SET)?;
+                    for word in block.chunks(4) {
+                        let mut bytes = [0; 4];
+                        bytes[..word.len()].copy_from_slice(word);
+                        regs.data.set(u32::from_le_bytes(bytes));
+                    }
+                }
+            }
+        }
+
+        if uses_dat {
+            self.wait_interrupt(INTERRUPT::DATA_DONE::SET)?;
+        }
+
+        Ok(response)
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        (self.delay_us)(us);
+    }
+}
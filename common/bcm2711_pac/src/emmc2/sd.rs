@@ -0,0 +1,999 @@
+//! SD memory card protocol
+//!
+//! This module implements the card identification sequence and block
+//! transfers described in [the SD Physical Layer Simplified Specification][1]
+//! on top of [`SdHost`], which abstracts the host controller. [`super::Sdhci`]
+//! implements `SdHost` for EMMC2; the protocol layer can also be driven by a
+//! simulated card for testing.
+//!
+//! [1]: https://www.sdcard.org/downloads/pls/
+
+/// The size of a data block in bytes. SDHC/SDXC cards only support this
+/// block size, and SDSC cards are configured to use it by [`SdCard::init`].
+pub const BLOCK_SIZE: usize = 512;
+
+/// The maximum number of blocks transferred by a single command
+/// ([`BLKSIZECNT::BLKCNT`](const@super::BLKSIZECNT::BLKCNT) is 16 bits wide)
+const MAX_BLOCKS_PER_COMMAND: usize = 0xffff;
+
+/// The number of ACMD41 attempts before giving up. The card must finish
+/// initialization within 1 second.
+const INIT_RETRIES: u32 = 1000;
+
+/// The number of CMD13 attempts while waiting for the card to finish
+/// programming.
+const BUSY_RETRIES: u32 = 1_000_000;
+
+/// The bits of the card status (R1) indicating an error
+const R1_ERRORS: u32 = 0xfdf9_8008;
+
+/// Card status bit: the card is ready to accept data
+const R1_READY_FOR_DATA: u32 = 1 << 8;
+
+/// The current state field of the card status
+const fn r1_current_state(status: u32) -> u32 {
+    (status >> 9) & 0xf
+}
+
+/// [`r1_current_state`] value of the transfer state
+const STATE_TRAN: u32 = 4;
+
+/// OCR bit: the card has finished power-up
+const OCR_BUSY: u32 = 1 << 31;
+/// OCR bit: Card Capacity Status (SDHC/SDXC)
+const OCR_CCS: u32 = 1 << 30;
+/// OCR bits: 2.7–3.6V
+const OCR_VOLTAGE_WINDOW: u32 = 0x00ff_8000;
+
+/// CMD8 argument: 2.7–3.6V, check pattern `0xaa`
+const CMD8_ARG: u32 = 0x1aa;
+
+/// An error reported by [`SdHost`] or [`SdCard`].
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Error {
+    /// The card did not respond to a command.
+    CommandTimeout,
+    /// The response had a CRC error.
+    CommandCrc,
+    /// The response had an end bit error.
+    CommandEndBit,
+    /// The response had an incorrect command index.
+    CommandIndex,
+    /// The card did not send or accept data in time.
+    DataTimeout,
+    /// A data block had a CRC error.
+    DataCrc,
+    /// A data block had an end bit error.
+    DataEndBit,
+    /// The automatically issued CMD12 failed.
+    AutoCommand,
+    /// The host controller reported an unknown error. Contains the raw
+    /// interrupt status.
+    Host(u32),
+    /// The host controller did not finish an operation in time.
+    HostTimeout,
+    /// The host controller can't generate the requested clock frequency.
+    UnsupportedClock,
+    /// The card is not a supported SD memory card (e.g., it rejected the
+    /// voltage range).
+    UnsupportedCard,
+    /// The card did not finish power-up in time.
+    InitTimeout,
+    /// The card reported an error. Contains the card status (R1).
+    CardStatus(u32),
+    /// The requested blocks are out of the card's range.
+    OutOfRange,
+    /// The buffer size is not a valid multiple of the block size.
+    BadBuffer,
+}
+
+/// The response type of a command
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum ResponseType {
+    /// No response
+    None,
+    /// Normal response (card status)
+    R1,
+    /// Normal response with busy signaling on DAT0
+    R1b,
+    /// CID or CSD register (136 bits)
+    R2,
+    /// OCR register (no CRC)
+    R3,
+    /// Published RCA
+    R6,
+    /// Card interface condition
+    R7,
+}
+
+/// A command
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Command {
+    /// The command index
+    pub index: u8,
+    /// The command argument
+    pub arg: u32,
+    /// The expected response type
+    pub response: ResponseType,
+}
+
+impl Command {
+    /// Construct a `Command`.
+    #[inline]
+    pub const fn new(index: u8, arg: u32, response: ResponseType) -> Self {
+        Self {
+            index,
+            arg,
+            response,
+        }
+    }
+}
+
+/// The data phase of a command
+#[derive(Debug)]
+pub enum Data<'a> {
+    /// The command has no data phase.
+    None,
+    /// Read data from the card.
+    Read(&'a mut [u8]),
+    /// Write data to the card.
+    Write(&'a [u8]),
+}
+
+impl Data<'_> {
+    /// Get the length of the data in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            Self::None => 0,
+            Self::Read(buf) => buf.len(),
+            Self::Write(buf) => buf.len(),
+        }
+    }
+
+    /// Check if there's no data phase.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the block size in bytes and the number of blocks. Transfers
+    /// shorter than [`BLOCK_SIZE`] (e.g., the SCR register) are sent as a
+    /// single block.
+    pub fn geometry(&self) -> Result<(usize, u32), Error> {
+        let len = self.len();
+        if len == 0 {
+            return Ok((0, 0));
+        }
+        let block_size = len.min(BLOCK_SIZE);
+        if len / block_size * block_size != len || len / block_size > MAX_BLOCKS_PER_COMMAND {
+            return Err(Error::BadBuffer);
+        }
+        Ok((block_size, (len / block_size) as u32))
+    }
+}
+
+/// A command response. `self.0[0]` contains bits 31:0 of the response
+/// content.
+///
+/// For 48-bit responses, `self.0[0]` contains the card status, OCR, etc. and
+/// the rest is zero. For 136-bit responses, the words contain bits 127:0 of
+/// the CID or CSD register.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default)]
+pub struct Response(pub [u32; 4]);
+
+impl Response {
+    /// Construct a 48-bit response.
+    #[inline]
+    pub const fn from_u32(x: u32) -> Self {
+        Self([x, 0, 0, 0])
+    }
+
+    /// Construct a 136-bit response.
+    #[inline]
+    pub const fn from_u128(x: u128) -> Self {
+        Self([
+            x as u32,
+            (x >> 32) as u32,
+            (x >> 64) as u32,
+            (x >> 96) as u32,
+        ])
+    }
+
+    /// Get bits 31:0 of the response content.
+    #[inline]
+    pub const fn as_u32(&self) -> u32 {
+        self.0[0]
+    }
+
+    /// Get bits 127:0 of the response content.
+    #[inline]
+    pub const fn as_u128(&self) -> u128 {
+        self.0[0] as u128
+            | (self.0[1] as u128) << 32
+            | (self.0[2] as u128) << 64
+            | (self.0[3] as u128) << 96
+    }
+
+    /// Extract the bits `lsb..lsb + width` of a 136-bit response.
+    #[inline]
+    const fn bits(&self, lsb: u32, width: u32) -> u32 {
+        ((self.as_u128() >> lsb) & ((1 << width) - 1)) as u32
+    }
+}
+
+/// The data bus width
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum BusWidth {
+    /// DAT0 only
+    One,
+    /// DAT0–DAT3
+    Four,
+}
+
+/// The host controller interface used by [`SdCard`].
+pub trait SdHost {
+    /// Reset the host controller and power up the card.
+    fn reset(&mut self) -> Result<(), Error>;
+
+    /// Set the SD clock frequency to `hz` or the highest supported frequency
+    /// not exceeding it. Returns the actual frequency.
+    fn set_clock(&mut self, hz: u32) -> Result<u32, Error>;
+
+    /// Set the data bus width of the host side. The card side must be
+    /// configured separately (ACMD6).
+    fn set_bus_width(&mut self, width: BusWidth) -> Result<(), Error>;
+
+    /// Issue a command and perform its data phase. If the data spans more
+    /// than one block, the host must stop the transmission (CMD12) after the
+    /// transfer.
+    fn command(&mut self, cmd: &Command, data: Data<'_>) -> Result<Response, Error>;
+
+    /// Block for the specified number of microseconds.
+    fn delay_us(&mut self, us: u32);
+}
+
+/// A block-addressed storage device.
+pub trait BlockDevice {
+    /// The error type
+    type Error;
+
+    /// Get the size of a block in bytes.
+    fn block_size(&self) -> usize;
+
+    /// Get the number of blocks.
+    fn block_count(&self) -> u64;
+
+    /// Read consecutive blocks starting at block `lba`. The length of `buf`
+    /// must be a multiple of [`Self::block_size`].
+    fn read_blocks(&mut self, lba: u64, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Write consecutive blocks starting at block `lba`. The length of `buf`
+    /// must be a multiple of [`Self::block_size`].
+    fn write_blocks(&mut self, lba: u64, buf: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// The configuration for [`SdCard::init`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Config {
+    /// The SD clock frequency used after identification. 25 MHz is the
+    /// maximum frequency in the default speed mode.
+    pub max_clock_hz: u32,
+    /// The data bus width used after identification
+    pub bus_width: BusWidth,
+}
+
+impl Default for Config {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            max_clock_hz: 25_000_000,
+            bus_width: BusWidth::Four,
+        }
+    }
+}
+
+/// The clock frequency used during card identification
+pub const IDENTIFICATION_CLOCK_HZ: u32 = 400_000;
+
+/// An initialized SD memory card.
+pub struct SdCard<H> {
+    host: H,
+    rca: u16,
+    high_capacity: bool,
+    block_count: u64,
+    cid: Response,
+    csd: Response,
+}
+
+impl<H: SdHost> SdCard<H> {
+    /// Reset the host controller and run the card identification sequence.
+    /// Leaves the card in the transfer state with the bus width and clock
+    /// frequency specified by `config`.
+    pub fn init(mut host: H, config: &Config) -> Result<Self, Error> {
+        host.reset()?;
+        host.set_bus_width(BusWidth::One)?;
+        host.set_clock(IDENTIFICATION_CLOCK_HZ)?;
+
+        // GO_IDLE_STATE
+        host.command(&Command::new(0, 0, ResponseType::None), Data::None)?;
+        host.delay_us(1000);
+
+        // SEND_IF_COND. Version 1.x cards don't respond to this command.
+        let v2 = match host.command(&Command::new(8, CMD8_ARG, ResponseType::R7), Data::None) {
+            Ok(resp) if resp.as_u32() & 0xfff == CMD8_ARG => true,
+            Ok(_) => return Err(Error::UnsupportedCard),
+            Err(Error::CommandTimeout) => false,
+            Err(e) => return Err(e),
+        };
+
+        // SD_SEND_OP_COND until the card finishes power-up
+        let hcs = if v2 { OCR_CCS } else { 0 };
+        let mut ocr = 0;
+        for _ in 0..INIT_RETRIES {
+            ocr = app_command(
+                &mut host,
+                0,
+                &Command::new(41, hcs | OCR_VOLTAGE_WINDOW, ResponseType::R3),
+                Data::None,
+            )?
+            .as_u32();
+            if ocr & OCR_BUSY != 0 {
+                break;
+            }
+            host.delay_us(1000);
+        }
+        if ocr & OCR_BUSY == 0 {
+            return Err(Error::InitTimeout);
+        }
+        if ocr & OCR_VOLTAGE_WINDOW == 0 {
+            return Err(Error::UnsupportedCard);
+        }
+        let high_capacity = ocr & OCR_CCS != 0;
+
+        // ALL_SEND_CID
+        let cid = host.command(&Command::new(2, 0, ResponseType::R2), Data::None)?;
+
+        // SEND_RELATIVE_ADDR
+        let resp = host.command(&Command::new(3, 0, ResponseType::R6), Data::None)?;
+        let rca = (resp.as_u32() >> 16) as u16;
+        // R6 carries status bits 23, 22, 19 in bits 15:13
+        if resp.as_u32() & 0xe000 != 0 {
+            return Err(Error::CardStatus(resp.as_u32() & 0xffff));
+        }
+
+        // SEND_CSD
+        let csd = host.command(
+            &Command::new(9, (rca as u32) << 16, ResponseType::R2),
+            Data::None,
+        )?;
+        let block_count = decode_block_count(&csd).ok_or(Error::UnsupportedCard)?;
+
+        host.set_clock(config.max_clock_hz)?;
+
+        let mut this = Self {
+            host,
+            rca,
+            high_capacity,
+            block_count,
+            cid,
+            csd,
+        };
+
+        // SELECT_CARD
+        this.command_r1(&Command::new(7, this.rca_arg(), ResponseType::R1b))?;
+
+        if config.bus_width == BusWidth::Four {
+            // SET_BUS_WIDTH
+            let rca_arg = this.rca_arg();
+            let status = app_command(
+                &mut this.host,
+                rca_arg,
+                &Command::new(6, 0b10, ResponseType::R1),
+                Data::None,
+            )?;
+            check_r1(status.as_u32())?;
+            this.host.set_bus_width(BusWidth::Four)?;
+        }
+
+        if !this.high_capacity {
+            // SET_BLOCKLEN
+            this.command_r1(&Command::new(16, BLOCK_SIZE as u32, ResponseType::R1))?;
+        }
+
+        Ok(this)
+    }
+
+    /// Get a reference to the host controller.
+    #[inline]
+    pub fn host(&self) -> &H {
+        &self.host
+    }
+
+    /// Get a mutable reference to the host controller.
+    #[inline]
+    pub fn host_mut(&mut self) -> &mut H {
+        &mut self.host
+    }
+
+    /// Release the host controller.
+    #[inline]
+    pub fn into_host(self) -> H {
+        self.host
+    }
+
+    /// Get the relative card address.
+    #[inline]
+    pub fn rca(&self) -> u16 {
+        self.rca
+    }
+
+    /// Check if the card is an SDHC/SDXC card (block-addressed).
+    #[inline]
+    pub fn is_high_capacity(&self) -> bool {
+        self.high_capacity
+    }
+
+    /// Get the contents of the CID register.
+    #[inline]
+    pub fn cid(&self) -> u128 {
+        self.cid.as_u128()
+    }
+
+    /// Get the contents of the CSD register.
+    #[inline]
+    pub fn csd(&self) -> u128 {
+        self.csd.as_u128()
+    }
+
+    /// Get the card status (CMD13).
+    pub fn status(&mut self) -> Result<u32, Error> {
+        self.command_r1(&Command::new(13, self.rca_arg(), ResponseType::R1))
+    }
+
+    #[inline]
+    fn rca_arg(&self) -> u32 {
+        (self.rca as u32) << 16
+    }
+
+    /// Issue a command with an R1 response and no data.
+    fn command_r1(&mut self, cmd: &Command) -> Result<u32, Error> {
+        let resp = self.host.command(cmd, Data::None)?;
+        check_r1(resp.as_u32())
+    }
+
+    /// Convert a block number to a command argument.
+    fn block_arg(&self, lba: u64, len: usize) -> Result<u32, Error> {
+        if len == 0 || len / BLOCK_SIZE * BLOCK_SIZE != len {
+            return Err(Error::BadBuffer);
+        }
+        let count = (len / BLOCK_SIZE) as u64;
+        match lba.checked_add(count) {
+            Some(end) if end <= self.block_count => {}
+            _ => return Err(Error::OutOfRange),
+        }
+        let addr = if self.high_capacity {
+            lba
+        } else {
+            lba * BLOCK_SIZE as u64
+        };
+        u32::try_from(addr).map_err(|_| Error::OutOfRange)
+    }
+
+    /// Wait until the card finishes programming and returns to the transfer
+    /// state.
+    fn wait_ready(&mut self) -> Result<(), Error> {
+        for _ in 0..BUSY_RETRIES {
+            let status = self.status()?;
+            if status & R1_READY_FOR_DATA != 0 && r1_current_state(status) == STATE_TRAN {
+                return Ok(());
+            }
+            self.host.delay_us(1);
+        }
+        Err(Error::DataTimeout)
+    }
+}
+
+impl<H: SdHost> BlockDevice for SdCard<H> {
+    type Error = Error;
+
+    #[inline]
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    #[inline]
+    fn block_count(&self) -> u64 {
+        self.block_count
+    }
+
+    fn read_blocks(&mut self, mut lba: u64, buf: &mut [u8]) -> Result<(), Error> {
+        self.block_arg(lba, buf.len())?;
+        for chunk in buf.chunks_mut(BLOCK_SIZE * MAX_BLOCKS_PER_COMMAND) {
+            let arg = self.block_arg(lba, chunk.len())?;
+            let count = chunk.len() / BLOCK_SIZE;
+            // READ_SINGLE_BLOCK or READ_MULTIPLE_BLOCK
+            let index = if count == 1 { 17 } else { 18 };
+            let resp = self.host.command(
+                &Command::new(index, arg, ResponseType::R1),
+                Data::Read(chunk),
+            )?;
+            check_r1(resp.as_u32())?;
+            lba += count as u64;
+        }
+        Ok(())
+    }
+
+    fn write_blocks(&mut self, mut lba: u64, buf: &[u8]) -> Result<(), Error> {
+        self.block_arg(lba, buf.len())?;
+        for chunk in buf.chunks(BLOCK_SIZE * MAX_BLOCKS_PER_COMMAND) {
+            let arg = self.block_arg(lba, chunk.len())?;
+            let count = chunk.len() / BLOCK_SIZE;
+            // WRITE_BLOCK or WRITE_MULTIPLE_BLOCK
+            let index = if count == 1 { 24 } else { 25 };
+            let resp = self.host.command(
+                &Command::new(index, arg, ResponseType::R1),
+                Data::Write(chunk),
+            )?;
+            check_r1(resp.as_u32())?;
+            self.wait_ready()?;
+            lba += count as u64;
+        }
+        Ok(())
+    }
+}
+
+/// Issue an application-specific command (CMD55 followed by `cmd`).
+fn app_command<H: SdHost>(
+    host: &mut H,
+    rca_arg: u32,
+    cmd: &Command,
+    data: Data<'_>,
+) -> Result<Response, Error> {
+    // APP_CMD
+    let status = host.command(&Command::new(55, rca_arg, ResponseType::R1), Data::None)?;
+    check_r1(status.as_u32())?;
+    host.command(cmd, data)
+}
+
+/// Check the card status (R1) for errors.
+fn check_r1(status: u32) -> Result<u32, Error> {
+    if status & R1_ERRORS != 0 {
+        Err(Error::CardStatus(status))
+    } else {
+        Ok(status)
+    }
+}
+
+/// Calculate the number of 512-byte blocks from the CSD register.
+fn decode_block_count(csd: &Response) -> Option<u64> {
+    match csd.bits(126, 2) {
+        // CSD version 1.0 (SDSC)
+        0 => {
+            let c_size = csd.bits(62, 12) as u64;
+            let c_size_mult = csd.bits(47, 3);
+            let read_bl_len = csd.bits(80, 4);
+            let bytes = (c_size + 1) << (c_size_mult + 2 + read_bl_len);
+            Some(bytes / BLOCK_SIZE as u64)
+        }
+        // CSD version 2.0 (SDHC/SDXC)
+        1 => Some((csd.bits(48, 22) as u64 + 1) * 1024),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+    use std::{vec, vec::Vec};
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    enum State {
+        Idle,
+        Ready,
+        Ident,
+        Stby,
+        Tran,
+    }
+
+    /// A simulated SD card connected to an ideal host controller
+    struct SimCard {
+        /// `true` for a physical layer version 2.00+ card
+        v2: bool,
+        high_capacity: bool,
+        /// The number of ACMD41 calls before the card reports power-up
+        busy_count: u32,
+        /// The number of CMD13 calls reporting the programming state after a
+        /// write
+        programming_count: u32,
+        programming_left: u32,
+        state: State,
+        app_cmd: bool,
+        bus_width: BusWidth,
+        card_bus_width: BusWidth,
+        clock_hz: u32,
+        block_len: usize,
+        storage: Vec<u8>,
+        log: Vec<u8>,
+    }
+
+    const RCA: u16 = 0xb368;
+    const SIM_BLOCKS: usize = 64;
+
+    impl SimCard {
+        fn new(v2: bool, high_capacity: bool) -> Self {
+            Self {
+                v2,
+                high_capacity,
+                busy_count: 3,
+                programming_count: 2,
+                programming_left: 0,
+                state: State::Idle,
+                app_cmd: false,
+                bus_width: BusWidth::One,
+                card_bus_width: BusWidth::One,
+                clock_hz: 0,
+                block_len: BLOCK_SIZE,
+                storage: (0..SIM_BLOCKS * BLOCK_SIZE)
+                    .map(|i| (i * 7) as u8)
+                    .collect(),
+                log: Vec::new(),
+            }
+        }
+
+        fn csd(&self) -> u128 {
+            if self.high_capacity {
+                // CSD_STRUCTURE = 1, C_SIZE = 0x3b37 (about 8 GB). Only the
+                // first `SIM_BLOCKS` blocks are backed by `storage`.
+                1 << 126 | 0x3b37_u128 << 48
+            } else {
+                // CSD_STRUCTURE = 0, READ_BL_LEN = 9, C_SIZE_MULT = 0,
+                // C_SIZE = SIM_BLOCKS / 4 - 1
+                9 << 80 | ((SIM_BLOCKS as u128 / 4 - 1) << 62)
+            }
+        }
+
+        fn status(&self) -> u32 {
+            let state = match self.state {
+                State::Idle => 0,
+                State::Ready => 1,
+                State::Ident => 2,
+                State::Stby => 3,
+                State::Tran if self.programming_left > 0 => 7,
+                State::Tran => 4,
+            };
+            let ready = if self.programming_left > 0 {
+                0
+            } else {
+                R1_READY_FOR_DATA
+            };
+            state << 9 | ready | (self.app_cmd as u32) << 5
+        }
+
+        fn addr_to_offset(&self, arg: u32, len: usize) -> Result<usize, Error> {
+            let offset = if self.high_capacity {
+                arg as usize * BLOCK_SIZE
+            } else {
+                arg as usize
+            };
+            if offset + len > self.storage.len() {
+                // OUT_OF_RANGE
+                return Err(Error::CardStatus(1 << 31));
+            }
+            Ok(offset)
+        }
+    }
+
+    impl SdHost for SimCard {
+        fn reset(&mut self) -> Result<(), Error> {
+            self.state = State::Idle;
+            self.bus_width = BusWidth::One;
+            self.clock_hz = 0;
+            Ok(())
+        }
+
+        fn set_clock(&mut self, hz: u32) -> Result<u32, Error> {
+            assert!(
+                hz <= IDENTIFICATION_CLOCK_HZ || matches!(self.state, State::Stby | State::Tran),
+                "the clock must stay at 400 kHz until identification completes"
+            );
+            self.clock_hz = hz;
+            Ok(hz)
+        }
+
+        fn set_bus_width(&mut self, width: BusWidth) -> Result<(), Error> {
+            self.bus_width = width;
+            Ok(())
+        }
+
+        fn command(&mut self, cmd: &Command, data: Data<'_>) -> Result<Response, Error> {
+            assert_ne!(self.clock_hz, 0, "the clock is stopped");
+            self.log.push(cmd.index);
+
+            let app_cmd = core::mem::replace(&mut self.app_cmd, false);
+            let arg_rca = (cmd.arg >> 16) as u16;
+
+            if self.state == State::Tran && !data.is_empty() {
+                assert_eq!(
+                    self.bus_width, self.card_bus_width,
+                    "host and card bus widths disagree"
+                );
+            }
+
+            let (expected, resp) = match (app_cmd, cmd.index, self.state) {
+                (_, 0, _) => {
+                    self.state = State::Idle;
+                    (ResponseType::None, Response::default())
+                }
+                (false, 8, State::Idle) => {
+                    if !self.v2 {
+                        return Err(Error::CommandTimeout);
+                    }
+                    (ResponseType::R7, Response::from_u32(cmd.arg & 0xfff))
+                }
+                (false, 55, _) => {
+                    if self.state != State::Idle && arg_rca != RCA {
+                        return Err(Error::CommandTimeout);
+                    }
+                    self.app_cmd = true;
+                    (ResponseType::R1, Response::from_u32(self.status() | 1 << 5))
+                }
+                (true, 41, State::Idle) => {
+                    assert_eq!(cmd.arg & OCR_VOLTAGE_WINDOW, OCR_VOLTAGE_WINDOW);
+                    if !self.v2 {
+                        assert_eq!(cmd.arg & OCR_CCS, 0, "HCS must be 0 for v1 cards");
+                    }
+                    let mut ocr = OCR_VOLTAGE_WINDOW;
+                    if self.busy_count > 0 {
+                        self.busy_count -= 1;
+                    } else {
+                        ocr |= OCR_BUSY;
+                        if self.high_capacity && cmd.arg & OCR_CCS != 0 {
+                            ocr |= OCR_CCS;
+                        }
+                        self.state = State::Ready;
+                    }
+                    (ResponseType::R3, Response::from_u32(ocr))
+                }
+                (false, 2, State::Ready) => {
+                    self.state = State::Ident;
+                    (ResponseType::R2, Response::from_u128(0x0353_4453_5530_3847))
+                }
+                (false, 3, State::Ident) => {
+                    self.state = State::Stby;
+                    (ResponseType::R6, Response::from_u32((RCA as u32) << 16))
+                }
+                (false, 9, State::Stby) if arg_rca == RCA => {
+                    (ResponseType::R2, Response::from_u128(self.csd()))
+                }
+                (false, 7, State::Stby) if arg_rca == RCA => {
+                    let status = self.status();
+                    self.state = State::Tran;
+                    (ResponseType::R1b, Response::from_u32(status))
+                }
+                (false, 13, State::Tran) if arg_rca == RCA => {
+                    let status = self.status();
+                    self.programming_left = self.programming_left.saturating_sub(1);
+                    (ResponseType::R1, Response::from_u32(status))
+                }
+                (true, 6, State::Tran) => {
+                    self.card_bus_width = match cmd.arg & 0b11 {
+                        0b00 => BusWidth::One,
+                        0b10 => BusWidth::Four,
+                        _ => panic!("invalid bus width"),
+                    };
+                    (ResponseType::R1, Response::from_u32(self.status()))
+                }
+                (false, 16, State::Tran) => {
+                    assert!(!self.high_capacity, "CMD16 is meaningless for SDHC");
+                    self.block_len = cmd.arg as usize;
+                    (ResponseType::R1, Response::from_u32(self.status()))
+                }
+                (false, 17 | 18 | 24 | 25, State::Tran) => {
+                    assert_eq!(self.block_len, BLOCK_SIZE);
+                    assert_eq!(self.programming_left, 0, "card is still programming");
+                    let status = self.status();
+                    let multi = matches!(cmd.index, 18 | 25);
+                    let offset = self.addr_to_offset(cmd.arg, data.len())?;
+                    match data {
+                        Data::Read(buf) => {
+                            assert!(matches!(cmd.index, 17 | 18));
+                            assert_eq!(multi, buf.len() > BLOCK_SIZE);
+                            buf.copy_from_slice(&self.storage[offset..][..buf.len()]);
+                        }
+                        Data::Write(buf) => {
+                            assert!(matches!(cmd.index, 24 | 25));
+                            assert_eq!(multi, buf.len() > BLOCK_SIZE);
+                            self.storage[offset..][..buf.len()].copy_from_slice(buf);
+                            self.programming_left = self.programming_count;
+                        }
+                        Data::None => panic!("missing data phase"),
+                    }
+                    if multi {
+                        // Auto CMD12
+                        self.log.push(12);
+                    }
+                    (ResponseType::R1, Response::from_u32(status))
+                }
+                (app_cmd, index, state) => {
+                    panic!("unexpected command (app_cmd = {app_cmd}, index = {index}) in state {state:?}")
+                }
+            };
+
+            assert_eq!(
+                cmd.response, expected,
+                "wrong response type for CMD{}",
+                cmd.index
+            );
+            Ok(resp)
+        }
+
+        fn delay_us(&mut self, _us: u32) {}
+    }
+
+    fn init(card: SimCard) -> SdCard<SimCard> {
+        SdCard::init(card, &Config::default()).unwrap()
+    }
+
+    #[test]
+    fn init_v2_high_capacity() {
+        let card = init(SimCard::new(true, true));
+        assert!(card.is_high_capacity());
+        assert_eq!(card.rca(), RCA);
+        assert_eq!(card.block_count(), (0x3b37 + 1) * 1024);
+        assert_eq!(card.cid(), 0x0353_4453_5530_3847);
+
+        let host = card.host();
+        assert_eq!(host.state, State::Tran);
+        assert_eq!(host.clock_hz, 25_000_000);
+        assert_eq!(host.bus_width, BusWidth::Four);
+        assert_eq!(host.card_bus_width, BusWidth::Four);
+        assert_eq!(
+            host.log,
+            [0, 8, 55, 41, 55, 41, 55, 41, 55, 41, 2, 3, 9, 7, 55, 6]
+        );
+    }
+
+    #[test]
+    fn init_v1() {
+        let card = init(SimCard::new(false, false));
+        assert!(!card.is_high_capacity());
+        assert_eq!(card.block_count(), SIM_BLOCKS as u64);
+        // CMD16 is issued for SDSC cards
+        assert_eq!(
+            card.host().log,
+            [0, 8, 55, 41, 55, 41, 55, 41, 55, 41, 2, 3, 9, 7, 55, 6, 16]
+        );
+    }
+
+    #[test]
+    fn init_v2_standard_capacity() {
+        let card = init(SimCard::new(true, false));
+        assert!(!card.is_high_capacity());
+        assert_eq!(*card.host().log.last().unwrap(), 16);
+    }
+
+    #[test]
+    fn init_one_bit() {
+        let config = Config {
+            max_clock_hz: 12_500_000,
+            bus_width: BusWidth::One,
+        };
+        let card = SdCard::init(SimCard::new(true, true), &config).unwrap();
+        let host = card.host();
+        assert_eq!(host.clock_hz, 12_500_000);
+        assert_eq!(host.bus_width, BusWidth::One);
+        assert!(!host.log.contains(&6));
+    }
+
+    #[test]
+    fn init_timeout() {
+        let mut sim = SimCard::new(true, true);
+        sim.busy_count = INIT_RETRIES;
+        assert_eq!(
+            SdCard::init(sim, &Config::default()).err(),
+            Some(Error::InitTimeout)
+        );
+    }
+
+    fn read_write(high_capacity: bool) {
+        let card = init(SimCard::new(true, high_capacity));
+        // Restrict the range to the simulated storage
+        let mut sim_card = SdCard {
+            block_count: SIM_BLOCKS as u64,
+            ..card
+        };
+        let card = &mut sim_card;
+
+        // Single block read
+        let mut block = [0u8; BLOCK_SIZE];
+        card.host_mut().log.clear();
+        card.read_blocks(3, &mut block).unwrap();
+        assert_eq!(card.host().log, [17]);
+        assert_eq!(
+            block[..],
+            card.host().storage[3 * BLOCK_SIZE..][..BLOCK_SIZE]
+        );
+
+        // Multi-block read
+        let mut blocks = vec![0u8; 4 * BLOCK_SIZE];
+        card.host_mut().log.clear();
+        card.read_blocks(10, &mut blocks).unwrap();
+        assert_eq!(card.host().log, [18, 12]);
+        assert_eq!(
+            blocks[..],
+            card.host().storage[10 * BLOCK_SIZE..][..4 * BLOCK_SIZE]
+        );
+
+        // Single block write; CMD13 is polled until programming finishes
+        card.host_mut().log.clear();
+        card.write_blocks(5, &[0x5a; BLOCK_SIZE]).unwrap();
+        assert_eq!(card.host().log, [24, 13, 13, 13]);
+        card.read_blocks(5, &mut block).unwrap();
+        assert_eq!(block, [0x5a; BLOCK_SIZE]);
+
+        // Multi-block write
+        let data: Vec<u8> = (0..3 * BLOCK_SIZE).map(|i| (i / 3) as u8).collect();
+        card.host_mut().log.clear();
+        card.write_blocks(SIM_BLOCKS as u64 - 3, &data).unwrap();
+        assert_eq!(card.host().log, [25, 12, 13, 13, 13]);
+        card.read_blocks(SIM_BLOCKS as u64 - 3, &mut blocks[..3 * BLOCK_SIZE])
+            .unwrap();
+        assert_eq!(blocks[..3 * BLOCK_SIZE], data[..]);
+
+        // Out-of-range and malformed requests are rejected without issuing
+        // commands
+        card.host_mut().log.clear();
+        assert_eq!(
+            card.read_blocks(SIM_BLOCKS as u64 - 1, &mut blocks[..2 * BLOCK_SIZE]),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(
+            card.read_blocks(0, &mut blocks[..100]),
+            Err(Error::BadBuffer)
+        );
+        assert_eq!(card.write_blocks(0, &[]), Err(Error::BadBuffer));
+        assert!(card.host().log.is_empty());
+    }
+
+    #[test]
+    fn read_write_high_capacity() {
+        read_write(true);
+    }
+
+    #[test]
+    fn read_write_standard_capacity() {
+        read_write(false);
+    }
+
+    #[test]
+    fn card_error() {
+        let mut card = init(SimCard::new(true, true));
+        // The card's capacity exceeds the simulated storage; the card
+        // reports OUT_OF_RANGE
+        let mut block = [0u8; BLOCK_SIZE];
+        assert_eq!(
+            card.read_blocks(SIM_BLOCKS as u64, &mut block),
+            Err(Error::CardStatus(1 << 31))
+        );
+    }
+
+    #[test]
+    fn data_geometry() {
+        let mut buf = [0u8; 3 * BLOCK_SIZE];
+        assert_eq!(Data::None.geometry(), Ok((0, 0)));
+        assert_eq!(Data::Read(&mut buf[..8]).geometry(), Ok((8, 1)));
+        assert_eq!(Data::Read(&mut buf).geometry(), Ok((BLOCK_SIZE, 3)));
+        assert_eq!(Data::Write(&buf[..600]).geometry(), Err(Error::BadBuffer));
+    }
+
+    #[test]
+    fn csd_v1() {
+        // 2 GB SDSC: C_SIZE = 0xf13, C_SIZE_MULT = 7, READ_BL_LEN = 10
+        let csd = Response::from_u128(10 << 80 | 0xf13 << 62 | 7 << 47);
+        assert_eq!(decode_block_count(&csd), Some((0xf13 + 1) * 512 * 2));
+        // Reserved CSD_STRUCTURE
+        assert_eq!(decode_block_count(&Response::from_u128(3 << 126)), None);
+    }
+}
@@ -0,0 +1,999 @@
+//! Flattened device tree (FDT/DTB) parser
+//!
+//! The firmware passes a device tree blob describing the board to the
+//! operating system. [`Fdt`] looks up peripherals in it by `compatible`
+//! strings and translates their `reg` addresses through the `ranges`
+//! properties of the parent buses to ARM physical addresses. This accounts for
+//! the peripheral address mode (`arm_peri_high`) and board variations that the
+//! hardcoded `BASE` constants can't.
+//!
+//! The parser does not allocate and validates every access against the blob,
+//! so malformed input results in `None` or an [`Error`] rather than a panic.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use bcm2711_pac::{fdt::Fdt, gpio};
+//!
+//! # let dtb_ptr: *const u8 = core::ptr::null();
+//! let fdt = unsafe { Fdt::from_ptr(dtb_ptr) }.unwrap();
+//!
+//! // Look up a peripheral by its `compatible` string
+//! let uart0 = fdt.resolve("arm,pl011").unwrap();
+//!
+//! // Translate a `BASE` constant according to the current address map
+//! let gpio_base = fdt.map_vpa(gpio::BASE).unwrap();
+//! ```
+//!
+//! See [the Devicetree Specification][1] for the format.
+//!
+//! [1]: https://www.devicetree.org/specifications/
+use core::{fmt, str};
+
+use crate::Vpa;
+
+/// The magic number at the beginning of a device tree blob
+pub const FDT_MAGIC: u32 = 0xd00d_feed;
+
+/// The maximum depth of the node tree supported by [`Fdt`]
+pub const MAX_DEPTH: usize = 16;
+
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+const FDT_END: u32 = 9;
+
+/// The size of the blob header
+const HEADER_LEN: usize = 40;
+
+/// The error type for [`Fdt::new`]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Error {
+    /// The blob does not start with [`FDT_MAGIC`].
+    BadMagic,
+    /// The blob is shorter than the size indicated by its header.
+    Truncated,
+    /// The blob's format version is not supported.
+    UnsupportedVersion,
+    /// The header refers to a block outside the blob.
+    BadLayout,
+}
+
+/// Read a big-endian `u32` at `offset`.
+#[inline]
+fn be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Round `x` up to a multiple of 4.
+#[inline]
+fn align4(x: usize) -> Option<usize> {
+    Some(x.checked_add(3)? & !3)
+}
+
+/// Read a NUL-terminated string at `offset`.
+fn c_str(data: &[u8], offset: usize) -> Option<&str> {
+    let tail = data.get(offset..)?;
+    let len = tail.iter().position(|&b| b == 0)?;
+    str::from_utf8(&tail[..len]).ok()
+}
+
+/// Decode `cells` big-endian cells as an integer. Only the last two cells
+/// are retained (e.g., the flags cell of a PCI address is discarded).
+fn read_cells(bytes: &[u8], cells: usize) -> Option<u64> {
+    let bytes = bytes.get(..cells.checked_mul(4)?)?;
+    Some(bytes.chunks(4).fold(0u64, |acc, c| {
+        acc << 32 | u32::from_be_bytes([c[0], c[1], c[2], c[3]]) as u64
+    }))
+}
+
+/// A parsed structure block token
+#[derive(Clone, Copy)]
+enum Token<'a> {
+    BeginNode(&'a str),
+    EndNode,
+    Prop(Property<'a>),
+    Nop,
+    End,
+}
+
+/// A flattened device tree blob
+#[derive(Clone, Copy)]
+pub struct Fdt<'a> {
+    data: &'a [u8],
+    structs: &'a [u8],
+    strings: &'a [u8],
+}
+
+impl fmt::Debug for Fdt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fdt")
+            .field("total_size", &self.total_size())
+            .field("model", &self.model())
+            .finish()
+    }
+}
+
+impl<'a> Fdt<'a> {
+    /// Parse the header of a device tree blob.
+    pub fn new(data: &'a [u8]) -> Result<Self, Error> {
+        let header = |i: usize| be_u32(data, i * 4).ok_or(Error::Truncated);
+        if header(0)? != FDT_MAGIC {
+            return Err(Error::BadMagic);
+        }
+        if data.len() < HEADER_LEN {
+            return Err(Error::Truncated);
+        }
+        let total_size = header(1)? as usize;
+        let off_dt_struct = header(2)? as usize;
+        let off_dt_strings = header(3)? as usize;
+        let version = header(5)?;
+        let last_comp_version = header(6)?;
+        let size_dt_strings = header(8)? as usize;
+        let size_dt_struct = header(9)? as usize;
+
+        // `size_dt_struct` was added in version 17
+        if version < 17 || last_comp_version > 17 {
+            return Err(Error::UnsupportedVersion);
+        }
+        let data = data.get(..total_size).ok_or(Error::Truncated)?;
+
+        let block = |offset: usize, size: usize| {
+            offset
+                .checked_add(size)
+                .and_then(|end| data.get(offset..end))
+                .ok_or(Error::BadLayout)
+        };
+        Ok(Self {
+            data,
+            structs: block(off_dt_struct, size_dt_struct)?,
+            strings: block(off_dt_strings, size_dt_strings)?,
+        })
+    }
+
+    /// Parse a device tree blob at the specified address. The size is taken
+    /// from the blob's header.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a readable memory region of at least 8 bytes. If
+    /// it starts with [`FDT_MAGIC`], the region must be as large as the size
+    /// indicated by the header and must remain valid and unmodified for `'a`.
+    pub unsafe fn from_ptr(ptr: *const u8) -> Result<Self, Error> {
+        let header = core::slice::from_raw_parts(ptr, 8);
+        if be_u32(header, 0) != Some(FDT_MAGIC) {
+            return Err(Error::BadMagic);
+        }
+        let total_size = be_u32(header, 4).unwrap() as usize;
+        Self::new(core::slice::from_raw_parts(ptr, total_size))
+    }
+
+    /// Get the size of the blob in bytes.
+    #[inline]
+    pub fn total_size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Get the underlying bytes.
+    #[inline]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Parse the token at `offset` in the structure block. Returns the token
+    /// and the offset of the next token.
+    fn token(&self, offset: usize) -> Option<(Token<'a>, usize)> {
+        let structs = self.structs;
+        let next = offset.checked_add(4)?;
+        match be_u32(structs, offset)? {
+            FDT_BEGIN_NODE => {
+                let name = c_str(structs, next)?;
+                Some((Token::BeginNode(name), align4(next + name.len() + 1)?))
+            }
+            FDT_END_NODE => Some((Token::EndNode, next)),
+            FDT_PROP => {
+                let len = be_u32(structs, next)? as usize;
+                let name_offset = be_u32(structs, next + 4)? as usize;
+                let value_start = next + 8;
+                let value = structs.get(value_start..value_start.checked_add(len)?)?;
+                let name = c_str(self.strings, name_offset)?;
+                Some((
+                    Token::Prop(Property { name, value }),
+                    align4(value_start + len)?,
+                ))
+            }
+            FDT_NOP => Some((Token::Nop, next)),
+            FDT_END => Some((Token::End, next)),
+            _ => None,
+        }
+    }
+
+    /// Construct a [`Node`] for the `FDT_BEGIN_NODE` token at `offset`.
+    fn node_at(
+        &self,
+        offset: usize,
+        ancestors: [u32; MAX_DEPTH],
+        depth: usize,
+    ) -> Option<Node<'a>> {
+        let (token, body) = self.token(offset)?;
+        match token {
+            Token::BeginNode(name) => Some(Node {
+                fdt: *self,
+                name,
+                offset: offset as u32,
+                body: body as u32,
+                ancestors,
+                depth: depth as u8,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Get the root node.
+    pub fn root(&self) -> Option<Node<'a>> {
+        let mut offset = 0;
+        loop {
+            match self.token(offset)? {
+                (Token::Nop, next) => offset = next,
+                (Token::BeginNode(_), _) => return self.node_at(offset, [0; MAX_DEPTH], 0),
+                _ => return None,
+            }
+        }
+    }
+
+    /// Iterate over all nodes in depth-first order.
+    pub fn nodes(&self) -> Nodes<'a> {
+        Nodes {
+            fdt: *self,
+            offset: 0,
+            ancestors: [0; MAX_DEPTH],
+            depth: 0,
+            min_depth: 0,
+            done: false,
+        }
+    }
+
+    /// Iterate over the nodes compatible with `compatible`.
+    pub fn find_compatible<'b>(&self, compatible: &'b str) -> impl Iterator<Item = Node<'a>> + 'b
+    where
+        'a: 'b,
+    {
+        self.nodes()
+            .filter(move |node| node.is_compatible(compatible))
+    }
+
+    /// Find a node by its full path (e.g., `/soc/gpio@7e200000`). A path
+    /// component without a unit address (e.g., `gpio`) matches a node name
+    /// with any unit address.
+    pub fn find_node(&self, path: &str) -> Option<Node<'a>> {
+        let mut node = self.root()?;
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            node = node.children().find(|child| {
+                let name = child.name();
+                name == component
+                    || (!component.contains('@') && name.split('@').next() == Some(component))
+            })?;
+        }
+        Some(node)
+    }
+
+    /// Get the root node's `model` property (e.g., `Raspberry Pi 4 Model B
+    /// Rev 1.4`).
+    pub fn model(&self) -> Option<&'a str> {
+        self.root()?.property("model")?.as_str()
+    }
+
+    /// Identify the board from the root node's `compatible` property.
+    pub fn board(&self) -> Option<Board> {
+        let root = self.root()?;
+        [
+            ("raspberrypi,4-model-b", Board::Pi4ModelB),
+            ("raspberrypi,400", Board::Pi400),
+            ("raspberrypi,4-compute-module", Board::ComputeModule4),
+            ("raspberrypi,4-compute-module-s", Board::ComputeModule4S),
+        ]
+        .into_iter()
+        .find(|(compatible, _)| root.is_compatible(compatible))
+        .map(|(_, board)| board)
+    }
+
+    /// Find the first enabled node compatible with `compatible` and get the
+    /// ARM physical address of its first `reg` entry.
+    pub fn resolve(&self, compatible: &str) -> Option<u64> {
+        self.find_compatible(compatible)
+            .filter(|node| node.is_enabled())
+            .find_map(|node| node.reg_arm_pa(0))
+            .map(|(addr, _)| addr)
+    }
+
+    /// Translate a VC peripheral address (e.g., a `BASE` constant) to an ARM
+    /// physical address through the `ranges` property of the `/soc` node.
+    /// The result reflects the peripheral address mode selected by the
+    /// firmware.
+    pub fn map_vpa(&self, vpa: Vpa) -> Option<u64> {
+        let soc = self.find_node("/soc")?;
+        translate(soc, vpa.to_legacy_bus()? as u64)
+    }
+}
+
+/// A board identified by [`Fdt::board`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Board {
+    /// Raspberry Pi 4 Model B
+    Pi4ModelB,
+    /// Raspberry Pi 400
+    Pi400,
+    /// Compute Module 4
+    ComputeModule4,
+    /// Compute Module 4S
+    ComputeModule4S,
+}
+
+/// A device tree node
+#[derive(Clone, Copy)]
+pub struct Node<'a> {
+    fdt: Fdt<'a>,
+    name: &'a str,
+    /// The offset of the `FDT_BEGIN_NODE` token
+    offset: u32,
+    /// The offset of the first token after the node name
+    body: u32,
+    /// The offsets of the ancestors' `FDT_BEGIN_NODE` tokens
+    ancestors: [u32; MAX_DEPTH],
+    depth: u8,
+}
+
+impl fmt::Debug for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Node")
+            .field("name", &self.name)
+            .field("depth", &self.depth)
+            .finish()
+    }
+}
+
+impl<'a> Node<'a> {
+    /// Get the node name including the unit address (e.g.,
+    /// `gpio@7e200000`). The root node's name is empty.
+    #[inline]
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Get the depth of the node. The root node's depth is zero.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.depth as usize
+    }
+
+    /// Get the parent node.
+    pub fn parent(&self) -> Option<Node<'a>> {
+        let depth = self.depth().checked_sub(1)?;
+        self.fdt
+            .node_at(self.ancestors[depth] as usize, self.ancestors, depth)
+    }
+
+    /// Iterate over the node's properties.
+    pub fn properties(&self) -> Properties<'a> {
+        Properties {
+            fdt: self.fdt,
+            offset: self.body as usize,
+        }
+    }
+
+    /// Get the property named `name`.
+    pub fn property(&self, name: &str) -> Option<Property<'a>> {
+        self.properties().find(|p| p.name == name)
+    }
+
+    /// Iterate over the node's descendants in depth-first order.
+    pub fn descendants(&self) -> Nodes<'a> {
+        let mut ancestors = self.ancestors;
+        let depth = self.depth() + 1;
+        let done = depth >= MAX_DEPTH;
+        if !done {
+            ancestors[self.depth()] = self.offset;
+        }
+        Nodes {
+            fdt: self.fdt,
+            offset: self.body as usize,
+            ancestors,
+            depth,
+            min_depth: depth,
+            done,
+        }
+    }
+
+    /// Iterate over the node's children.
+    pub fn children(&self) -> impl Iterator<Item = Node<'a>> {
+        let depth = self.depth() + 1;
+        self.descendants().filter(move |node| node.depth() == depth)
+    }
+
+    /// Iterate over the strings in the `compatible` property.
+    pub fn compatible(&self) -> impl Iterator<Item = &'a str> {
+        self.property("compatible")
+            .into_iter()
+            .flat_map(|p| p.as_str_list())
+    }
+
+    /// Check if `compatible` is included in the `compatible` property.
+    pub fn is_compatible(&self, compatible: &str) -> bool {
+        self.compatible().any(|c| c == compatible)
+    }
+
+    /// Check if the node is enabled, i.e., its `status` property is absent or
+    /// `okay`.
+    pub fn is_enabled(&self) -> bool {
+        match self.property("status").and_then(|p| p.as_str()) {
+            None | Some("okay") | Some("ok") => true,
+            Some(_) => false,
+        }
+    }
+
+    /// Get the `#address-cells` property, which specifies the number of cells
+    /// in the children's addresses.
+    pub fn address_cells(&self) -> usize {
+        self.property("#address-cells")
+            .and_then(|p| p.as_u32())
+            .unwrap_or(2) as usize
+    }
+
+    /// Get the `#size-cells` property, which specifies the number of cells in
+    /// the children's sizes.
+    pub fn size_cells(&self) -> usize {
+        self.property("#size-cells")
+            .and_then(|p| p.as_u32())
+            .unwrap_or(1) as usize
+    }
+
+    /// Iterate over the `(address, size)` pairs of the `reg` property. The
+    /// addresses are in the parent bus's address space.
+    pub fn reg(&self) -> impl Iterator<Item = (u64, u64)> + 'a {
+        let (address_cells, size_cells) = match self.parent() {
+            Some(parent) => (parent.address_cells(), parent.size_cells()),
+            None => (2, 1),
+        };
+        let value = self.property("reg").map_or(&[][..], |p| p.value);
+        let entry_len = (address_cells + size_cells) * 4;
+        value
+            .chunks_exact(entry_len.max(1))
+            .filter(move |_| entry_len != 0)
+            .filter_map(move |entry| {
+                Some((
+                    read_cells(entry, address_cells)?,
+                    read_cells(&entry[address_cells * 4..], size_cells)?,
+                ))
+            })
+    }
+
+    /// Get the `index`-th `(address, size)` pair of the `reg` property with
+    /// the address translated to an ARM physical address. Returns `None` if
+    /// the entry doesn't exist or can't be translated.
+    pub fn reg_arm_pa(&self, index: usize) -> Option<(u64, u64)> {
+        let (addr, size) = self.reg().nth(index)?;
+        Some((translate(self.parent()?, addr)?, size))
+    }
+}
+
+/// Translate an address in the address space of `bus`'s children to an ARM
+/// physical address (the root node's address space).
+fn translate(mut bus: Node<'_>, mut addr: u64) -> Option<u64> {
+    while let Some(parent) = bus.parent() {
+        // A bus without `ranges` is not memory-mapped
+        let ranges = bus.property("ranges")?.value;
+
+        if !ranges.is_empty() {
+            let child_cells = bus.address_cells();
+            let parent_cells = parent.address_cells();
+            let size_cells = bus.size_cells();
+            let entry_len = (child_cells + parent_cells + size_cells) * 4;
+            if entry_len == 0 {
+                return None;
+            }
+            addr = ranges.chunks_exact(entry_len).find_map(|entry| {
+                let child = read_cells(entry, child_cells)?;
+                let parent = read_cells(&entry[child_cells * 4..], parent_cells)?;
+                let size = read_cells(&entry[(child_cells + parent_cells) * 4..], size_cells)?;
+                let offset = addr.checked_sub(child)?;
+                if offset < size {
+                    parent.checked_add(offset)
+                } else {
+                    None
+                }
+            })?;
+        }
+
+        bus = parent;
+    }
+    Some(addr)
+}
+
+/// An iterator over device tree nodes in depth-first order
+#[derive(Clone)]
+pub struct Nodes<'a> {
+    fdt: Fdt<'a>,
+    offset: usize,
+    ancestors: [u32; MAX_DEPTH],
+    /// The depth of the next node
+    depth: usize,
+    /// Stop when a node at a depth lower than this ends
+    min_depth: usize,
+    done: bool,
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let Some((token, next)) = self.fdt.token(self.offset) else {
+                self.done = true;
+                break;
+            };
+            match token {
+                Token::BeginNode(_) => {
+                    if self.depth >= MAX_DEPTH {
+                        self.done = true;
+                        break;
+                    }
+                    let node = self.fdt.node_at(self.offset, self.ancestors, self.depth);
+                    self.ancestors[self.depth] = self.offset as u32;
+                    self.depth += 1;
+                    self.offset = next;
+                    return node;
+                }
+                Token::EndNode => {
+                    if self.depth <= self.min_depth {
+                        self.done = true;
+                        break;
+                    }
+                    self.depth -= 1;
+                }
+                Token::Prop(_) | Token::Nop => {}
+                Token::End => self.done = true,
+            }
+            self.offset = next;
+        }
+        None
+    }
+}
+
+/// An iterator over the properties of a node
+#[derive(Clone)]
+pub struct Properties<'a> {
+    fdt: Fdt<'a>,
+    offset: usize,
+}
+
+impl<'a> Iterator for Properties<'a> {
+    type Item = Property<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (token, next) = self.fdt.token(self.offset)?;
+            match token {
+                Token::Prop(prop) => {
+                    self.offset = next;
+                    return Some(prop);
+                }
+                Token::Nop => self.offset = next,
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// A device tree property
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Property<'a> {
+    /// The property name
+    pub name: &'a str,
+    /// The raw property value
+    pub value: &'a [u8],
+}
+
+impl<'a> Property<'a> {
+    /// Interpret the value as a single big-endian `u32`.
+    pub fn as_u32(&self) -> Option<u32> {
+        match self.value.len() {
+            4 => be_u32(self.value, 0),
+            _ => None,
+        }
+    }
+
+    /// Interpret the value as a NUL-terminated string.
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self.value.split_last() {
+            Some((0, s)) => str::from_utf8(s).ok(),
+            _ => None,
+        }
+    }
+
+    /// Interpret the value as a list of NUL-terminated strings.
+    pub fn as_str_list(&self) -> impl Iterator<Item = &'a str> {
+        let value = match self.value.split_last() {
+            Some((0, s)) => s,
+            _ => &[],
+        };
+        value
+            .split(|&b| b == 0)
+            .filter(move |_| !value.is_empty())
+            .filter_map(|s| str::from_utf8(s).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+    use std::{string::String, vec::Vec};
+
+    /// Builds a device tree blob
+    #[derive(Default)]
+    struct Builder {
+        structs: Vec<u8>,
+        strings: Vec<u8>,
+    }
+
+    impl Builder {
+        fn word(&mut self, x: u32) -> &mut Self {
+            self.structs.extend_from_slice(&x.to_be_bytes());
+            self
+        }
+
+        fn pad(&mut self) {
+            while self.structs.len() & 3 != 0 {
+                self.structs.push(0);
+            }
+        }
+
+        fn begin(&mut self, name: &str) -> &mut Self {
+            self.word(FDT_BEGIN_NODE);
+            self.structs.extend_from_slice(name.as_bytes());
+            self.structs.push(0);
+            self.pad();
+            self
+        }
+
+        fn end(&mut self) -> &mut Self {
+            self.word(FDT_END_NODE)
+        }
+
+        fn prop(&mut self, name: &str, value: &[u8]) -> &mut Self {
+            let name_offset = self.strings.len() as u32;
+            self.strings.extend_from_slice(name.as_bytes());
+            self.strings.push(0);
+            self.word(FDT_PROP)
+                .word(value.len() as u32)
+                .word(name_offset);
+            self.structs.extend_from_slice(value);
+            self.pad();
+            self
+        }
+
+        fn cells(&mut self, name: &str, cells: &[u32]) -> &mut Self {
+            let value: Vec<u8> = cells.iter().flat_map(|c| c.to_be_bytes()).collect();
+            self.prop(name, &value)
+        }
+
+        fn strs(&mut self, name: &str, strs: &[&str]) -> &mut Self {
+            let mut value = Vec::new();
+            for s in strs {
+                value.extend_from_slice(s.as_bytes());
+                value.push(0);
+            }
+            self.prop(name, &value)
+        }
+
+        fn finish(&mut self) -> Vec<u8> {
+            self.word(FDT_END);
+            let off_dt_struct = HEADER_LEN + 16; // empty memory reservation map
+            let off_dt_strings = off_dt_struct + self.structs.len();
+            let total_size = off_dt_strings + self.strings.len();
+            let mut out = Vec::new();
+            for word in [
+                FDT_MAGIC,
+                total_size as u32,
+                off_dt_struct as u32,
+                off_dt_strings as u32,
+                HEADER_LEN as u32,
+                17,
+                16,
+                0,
+                self.strings.len() as u32,
+                self.structs.len() as u32,
+            ] {
+                out.extend_from_slice(&word.to_be_bytes());
+            }
+            out.extend_from_slice(&[0; 16]);
+            out.extend_from_slice(&self.structs);
+            out.extend_from_slice(&self.strings);
+            out
+        }
+    }
+
+    /// Build a tree modeled after `bcm2711-rpi-4-b.dts`.
+    fn bcm2711(high_peripherals: bool, board: &str) -> Vec<u8> {
+        let mut b = Builder::default();
+        b.begin("")
+            .strs("compatible", &[board, "brcm,bcm2711"])
+            .strs("model", &["Raspberry Pi 4 Model B Rev 1.4"])
+            .cells("#address-cells", &[2])
+            .cells("#size-cells", &[1]);
+
+        b.begin("soc")
+            .strs("compatible", &["simple-bus"])
+            .cells("#address-cells", &[1])
+            .cells("#size-cells", &[1]);
+        if high_peripherals {
+            b.cells(
+                "ranges",
+                &[
+                    0x7e00_0000,
+                    0x4,
+                    0x7e00_0000,
+                    0x0180_0000, //
+                    0x7c00_0000,
+                    0x4,
+                    0x7c00_0000,
+                    0x0200_0000, //
+                    0x4000_0000,
+                    0x4,
+                    0xc000_0000,
+                    0x0080_0000,
+                ],
+            );
+        } else {
+            b.cells(
+                "ranges",
+                &[
+                    0x7e00_0000,
+                    0x0,
+                    0xfe00_0000,
+                    0x0180_0000, //
+                    0x7c00_0000,
+                    0x0,
+                    0xfc00_0000,
+                    0x0200_0000, //
+                    0x4000_0000,
+                    0x0,
+                    0xff80_0000,
+                    0x0080_0000,
+                ],
+            );
+        }
+        b.begin("gpio@7e200000")
+            .strs("compatible", &["brcm,bcm2711-gpio"])
+            .cells("reg", &[0x7e20_0000, 0xb4])
+            .end();
+        b.begin("serial@7e201000")
+            .strs("compatible", &["arm,pl011", "arm,primecell"])
+            .cells("reg", &[0x7e20_1000, 0x200])
+            .strs("status", &["okay"])
+            .end();
+        b.begin("serial@7e201400")
+            .strs("compatible", &["arm,pl011", "arm,primecell"])
+            .cells("reg", &[0x7e20_1400, 0x200])
+            .strs("status", &["disabled"])
+            .end();
+        b.begin("local_intc@40000000")
+            .strs("compatible", &["brcm,bcm2836-l1-intc"])
+            .cells("reg", &[0x4000_0000, 0x100])
+            .end();
+        b.end();
+
+        b.begin("emmc2bus")
+            .strs("compatible", &["simple-bus"])
+            .cells("#address-cells", &[2])
+            .cells("#size-cells", &[1])
+            .cells(
+                "ranges",
+                &if high_peripherals {
+                    [0x0, 0x7e00_0000, 0x4, 0x7e00_0000, 0x0180_0000]
+                } else {
+                    [0x0, 0x7e00_0000, 0x0, 0xfe00_0000, 0x0180_0000]
+                },
+            );
+        b.begin("mmc@7e340000")
+            .strs("compatible", &["brcm,bcm2711-emmc2"])
+            .cells("reg", &[0x0, 0x7e34_0000, 0x100])
+            .end();
+        b.end();
+
+        b.begin("i2c-mux")
+            .cells("#address-cells", &[1])
+            .cells("#size-cells", &[0]);
+        b.begin("i2c@0")
+            .strs("compatible", &["test,unmapped"])
+            .cells("reg", &[0])
+            .end();
+        b.end();
+
+        b.end();
+        b.finish()
+    }
+
+    #[test]
+    fn low_peripherals() {
+        let blob = bcm2711(false, "raspberrypi,4-model-b");
+        let fdt = Fdt::new(&blob).unwrap();
+        assert_eq!(fdt.total_size(), blob.len());
+        assert_eq!(fdt.model(), Some("Raspberry Pi 4 Model B Rev 1.4"));
+        assert_eq!(fdt.board(), Some(Board::Pi4ModelB));
+
+        assert_eq!(fdt.resolve("brcm,bcm2711-gpio"), Some(0xfe20_0000));
+        // The disabled UART is skipped
+        assert_eq!(fdt.resolve("arm,pl011"), Some(0xfe20_1000));
+        assert_eq!(fdt.find_compatible("arm,pl011").count(), 2);
+        assert_eq!(fdt.resolve("brcm,bcm2836-l1-intc"), Some(0xff80_0000));
+        assert_eq!(fdt.resolve("brcm,bcm2711-emmc2"), Some(0xfe34_0000));
+        assert_eq!(fdt.resolve("brcm,bcm2835-pwm"), None);
+
+        // `BASE` constants agree with the device tree
+        assert_eq!(
+            fdt.map_vpa(crate::gpio::BASE),
+            crate::gpio::BASE.to_arm_pa()
+        );
+        assert_eq!(fdt.map_vpa(crate::emmc2::BASE), Some(0xfe34_0000));
+        assert_eq!(fdt.map_vpa(Vpa(0x1234)), None);
+    }
+
+    #[test]
+    fn high_peripherals() {
+        let blob = bcm2711(true, "raspberrypi,4-compute-module");
+        let fdt = Fdt::new(&blob).unwrap();
+        assert_eq!(fdt.board(), Some(Board::ComputeModule4));
+        assert_eq!(fdt.resolve("brcm,bcm2711-gpio"), Some(0x4_7e20_0000));
+        assert_eq!(fdt.resolve("arm,pl011"), Some(0x4_7e20_1000));
+        assert_eq!(fdt.resolve("brcm,bcm2836-l1-intc"), Some(0x4_c000_0000));
+        assert_eq!(
+            fdt.map_vpa(crate::gpio::BASE),
+            crate::gpio::BASE.to_arm_pa_high()
+        );
+    }
+
+    #[test]
+    fn pi400() {
+        let blob = bcm2711(false, "raspberrypi,400");
+        assert_eq!(Fdt::new(&blob).unwrap().board(), Some(Board::Pi400));
+        let blob = bcm2711(false, "example,other");
+        assert_eq!(Fdt::new(&blob).unwrap().board(), None);
+    }
+
+    #[test]
+    fn structure() {
+        let blob = bcm2711(false, "raspberrypi,4-model-b");
+        let fdt = Fdt::new(&blob).unwrap();
+
+        let names: Vec<String> = fdt
+            .nodes()
+            .map(|n| std::format!("{}{}", "  ".repeat(n.depth()), n.name()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "",
+                "  soc",
+                "    gpio@7e200000",
+                "    serial@7e201000",
+                "    serial@7e201400",
+                "    local_intc@40000000",
+                "  emmc2bus",
+                "    mmc@7e340000",
+                "  i2c-mux",
+                "    i2c@0",
+            ]
+        );
+
+        let root = fdt.root().unwrap();
+        assert!(root.parent().is_none());
+        let children: Vec<&str> = root.children().map(|n| n.name()).collect();
+        assert_eq!(children, ["soc", "emmc2bus", "i2c-mux"]);
+
+        let soc = fdt.find_node("/soc").unwrap();
+        assert_eq!(soc.address_cells(), 1);
+        assert_eq!(soc.children().count(), 4);
+
+        let gpio = fdt.find_node("/soc/gpio").unwrap();
+        assert_eq!(gpio.name(), "gpio@7e200000");
+        assert_eq!(gpio.parent().unwrap().name(), "soc");
+        assert_eq!(gpio.reg().collect::<Vec<_>>(), [(0x7e20_0000, 0xb4)]);
+        assert_eq!(gpio.reg_arm_pa(0), Some((0xfe20_0000, 0xb4)));
+        assert_eq!(gpio.reg_arm_pa(1), None);
+        assert!(fdt.find_node("/soc/serial@7e201400").is_some());
+        assert!(fdt.find_node("/soc/serial@1").is_none());
+
+        let uart = fdt.find_node("/soc/serial@7e201400").unwrap();
+        assert!(!uart.is_enabled());
+        assert_eq!(
+            uart.compatible().collect::<Vec<_>>(),
+            ["arm,pl011", "arm,primecell"]
+        );
+
+        // A bus without `ranges` is not translatable
+        let i2c = fdt.find_node("/i2c-mux/i2c@0").unwrap();
+        assert_eq!(i2c.reg().collect::<Vec<_>>(), [(0, 0)]);
+        assert_eq!(i2c.reg_arm_pa(0), None);
+    }
+
+    #[test]
+    fn malformed() {
+        let blob = bcm2711(false, "raspberrypi,4-model-b");
+        assert_eq!(Fdt::new(&blob[..3]).err(), Some(Error::Truncated));
+        assert_eq!(
+            Fdt::new(&blob[..blob.len() - 1]).err(),
+            Some(Error::Truncated)
+        );
+        assert_eq!(Fdt::new(&[0; 64]).err(), Some(Error::BadMagic));
+
+        let mut bad = blob.clone();
+        bad[20..24].copy_from_slice(&16u32.to_be_bytes());
+        assert_eq!(Fdt::new(&bad).err(), Some(Error::UnsupportedVersion));
+
+        let mut bad = blob.clone();
+        bad[8..12].copy_from_slice(&0x1000_0000u32.to_be_bytes());
+        assert_eq!(Fdt::new(&bad).err(), Some(Error::BadLayout));
+
+        // Corrupting the structure block stops the iteration without panicking
+        for i in (HEADER_LEN + 16..blob.len()).step_by(3) {
+            let mut bad = blob.clone();
+            bad[i] ^= 0xa5;
+            if let Ok(fdt) = Fdt::new(&bad) {
+                let _ = fdt.nodes().count();
+                let _ = fdt.resolve("arm,pl011");
+                let _ = fdt.find_node("/soc/gpio").map(|n| n.reg_arm_pa(0));
+            }
+        }
+    }
+
+    /// Check that `blob` maps the main peripherals consistently with `Vpa`.
+    fn check_firmware_blob(name: &str, blob: &[u8]) {
+        let fdt = Fdt::new(blob).unwrap_or_else(|e| panic!("{name}: {e:?}"));
+        std::println!("{name}: {:?} {:?}", fdt.model(), fdt.board());
+
+        let gpio = ["brcm,bcm2711-gpio", "brcm,bcm2835-gpio"]
+            .into_iter()
+            .find_map(|c| fdt.resolve(c))
+            .unwrap_or_else(|| panic!("{name}: GPIO not found"));
+        assert_eq!(fdt.map_vpa(crate::gpio::BASE), Some(gpio), "{name}");
+        let uart0 = fdt.resolve("arm,pl011").unwrap();
+        assert_eq!(uart0 - gpio, 0x1000, "{name}");
+
+        if fdt.root().unwrap().is_compatible("brcm,bcm2711") {
+            assert!(fdt.board().is_some(), "{name}");
+            assert!(
+                gpio == crate::gpio::BASE.to_arm_pa().unwrap()
+                    || gpio == crate::gpio::BASE.to_arm_pa_high().unwrap(),
+                "{name}: {gpio:#x}"
+            );
+            assert_eq!(
+                fdt.resolve("brcm,bcm2711-emmc2"),
+                fdt.map_vpa(crate::emmc2::BASE),
+                "{name}"
+            );
+        }
+    }
+
+    /// Check the built-in trees and the blobs in `tests/dtb` (e.g.,
+    /// `bcm2711-rpi-4-b.dtb` from the `boot` directory of the Raspberry Pi
+    /// firmware). The blobs are not included in this repository.
+    #[test]
+    fn firmware_blobs() {
+        check_firmware_blob("rpi-4-b", &bcm2711(false, "raspberrypi,4-model-b"));
+        check_firmware_blob("rpi-400", &bcm2711(false, "raspberrypi,400"));
+        check_firmware_blob("rpi-cm4", &bcm2711(true, "raspberrypi,4-compute-module"));
+
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/dtb");
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension() != Some("dtb".as_ref()) {
+                continue;
+            }
+            let blob = std::fs::read(&path).unwrap();
+            check_firmware_blob(&std::format!("{path:?}"), &blob);
+        }
+    }
+}
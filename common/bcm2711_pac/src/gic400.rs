@@ -0,0 +1,594 @@
+//! [GIC-400 Generic Interrupt Controller][1]
+//!
+//! All interrupt lines specified by [`solid::interrupt::Number`][2] end up in
+//! this controller. The register blocks defined here can be used to inspect
+//! the configuration made by the OS (e.g., the target processors set by
+//! `HandlerOptions::with_target_processor_set`).
+//!
+//! [1]: https://developer.arm.com/documentation/ddi0471/b
+//! [2]: https://kyotomicrocomputer.github.io/solid-rapi4-examples/rustdoc/solid/interrupt/struct.Number.html
+use tock_registers::{
+    fields::{Field, FieldValue},
+    interfaces::Readable,
+    register_structs, RegisterLongName,
+};
+
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::{ReadOnly, ReadWrite, WriteOnly},
+    snapshot::{impl_snapshot, register_bitfields, FieldInfo, RegisterInfo},
+};
+
+/// The low-peripheral ARM physical address of [the GIC-400 distributor
+/// register block](DistributorRegisters).
+pub const BASE_GICD_ARM_PA: u64 = 0xff84_1000;
+
+/// The low-peripheral ARM physical address of [the GIC-400 CPU interface
+/// register block](CpuInterfaceRegisters).
+pub const BASE_GICC_ARM_PA: u64 = 0xff84_2000;
+
+/// The maximum number of interrupt IDs supported by GIC-400 (32 SGIs and PPIs
+/// plus 480 SPIs). The actual number is indicated by
+/// [`GICD_TYPER::ITLINES_NUMBER`](const@GICD_TYPER::ITLINES_NUMBER).
+pub const MAX_INTERRUPTS: usize = 512;
+
+/// The interrupt ID returned by [`GICC_IAR`] when there is no pending
+/// interrupt.
+pub const SPURIOUS_INTERRUPT_ID: u32 = 1023;
+
+register_structs! {
+    /// GIC-400 distributor register block
+    pub DistributorRegisters {
+        /// Distributor control
+        (0x000 => pub ctlr: ReadWrite<u32, GICD_CTLR::Register>),
+        /// Interrupt controller type (RO)
+        (0x004 => pub typer: ReadOnly<u32, GICD_TYPER::Register>),
+        /// Distributor implementer identification (RO)
+        (0x008 => pub iidr: ReadOnly<u32, GICD_IIDR::Register>),
+        (0x00c => _pad0),
+        /// Interrupt group
+        (0x080 => pub igroupr: [ReadWrite<u32, GICD_IGROUPR::Register>; 16]),
+        (0x0c0 => _pad1),
+        /// Interrupt set-enable
+        (0x100 => pub isenabler: [ReadWrite<u32, GICD_ISENABLER::Register>; 16]),
+        (0x140 => _pad2),
+        /// Interrupt clear-enable
+        (0x180 => pub icenabler: [ReadWrite<u32, GICD_ICENABLER::Register>; 16]),
+        (0x1c0 => _pad3),
+        /// Interrupt set-pending
+        (0x200 => pub ispendr: [ReadWrite<u32, GICD_ISPENDR::Register>; 16]),
+        (0x240 => _pad4),
+        /// Interrupt clear-pending
+        (0x280 => pub icpendr: [ReadWrite<u32, GICD_ICPENDR::Register>; 16]),
+        (0x2c0 => _pad5),
+        /// Interrupt set-active
+        (0x300 => pub isactiver: [ReadWrite<u32, GICD_ISACTIVER::Register>; 16]),
+        (0x340 => _pad6),
+        /// Interrupt clear-active
+        (0x380 => pub icactiver: [ReadWrite<u32, GICD_ICACTIVER::Register>; 16]),
+        (0x3c0 => _pad7),
+        /// Interrupt priority
+        (0x400 => pub ipriorityr: [ReadWrite<u32, GICD_IPRIORITYR::Register>; 128]),
+        (0x600 => _pad8),
+        /// Interrupt processor targets (RO for SGIs and PPIs)
+        (0x800 => pub itargetsr: [ReadWrite<u32, GICD_ITARGETSR::Register>; 128]),
+        (0xa00 => _pad9),
+        /// Interrupt configuration
+        (0xc00 => pub icfgr: [ReadWrite<u32, GICD_ICFGR::Register>; 32]),
+        (0xc80 => _pad10),
+        /// Private peripheral interrupt status (RO)
+        (0xd00 => pub ppisr: ReadOnly<u32, GICD_PPISR::Register>),
+        /// Shared peripheral interrupt status (RO). `spisr[i]` covers interrupt
+        /// IDs `32 * (i + 1)..32 * (i + 2)`.
+        (0xd04 => pub spisr: [ReadOnly<u32, GICD_SPISR::Register>; 15]),
+        (0xd40 => _pad11),
+        /// Software generated interrupt (WO)
+        (0xf00 => pub sgir: WriteOnly<u32, GICD_SGIR::Register>),
+        (0xf04 => _pad12),
+        /// SGI clear-pending
+        (0xf10 => pub cpendsgir: [ReadWrite<u32, GICD_CPENDSGIR::Register>; 4]),
+        /// SGI set-pending
+        (0xf20 => pub spendsgir: [ReadWrite<u32, GICD_SPENDSGIR::Register>; 4]),
+        (0xf30 => _pad13),
+        /// Peripheral ID 4-7 and 0-3 (in this order) (RO)
+        (0xfd0 => pub pidr: [ReadOnly<u32>; 8]),
+        /// Component ID 0-3 (RO)
+        (0xff0 => pub cidr: [ReadOnly<u32>; 4]),
+        (0x1000 => @END),
+    },
+
+    /// GIC-400 CPU interface register block
+    ///
+    /// Each processor sees its own instance of this register block at the same
+    /// address.
+    pub CpuInterfaceRegisters {
+        /// CPU interface control
+        (0x0000 => pub ctlr: ReadWrite<u32, GICC_CTLR::Register>),
+        /// Interrupt priority mask
+        (0x0004 => pub pmr: ReadWrite<u32, GICC_PMR::Register>),
+        /// Binary point
+        (0x0008 => pub bpr: ReadWrite<u32, GICC_BPR::Register>),
+        /// Interrupt acknowledge (RO)
+        (0x000c => pub iar: ReadOnly<u32, GICC_IAR::Register>),
+        /// End of interrupt (WO)
+        (0x0010 => pub eoir: WriteOnly<u32, GICC_EOIR::Register>),
+        /// Running priority (RO)
+        (0x0014 => pub rpr: ReadOnly<u32, GICC_RPR::Register>),
+        /// Highest priority pending interrupt (RO)
+        (0x0018 => pub hppir: ReadOnly<u32, GICC_HPPIR::Register>),
+        /// Aliased binary point
+        (0x001c => pub abpr: ReadWrite<u32, GICC_BPR::Register>),
+        /// Aliased interrupt acknowledge (RO)
+        (0x0020 => pub aiar: ReadOnly<u32, GICC_IAR::Register>),
+        /// Aliased end of interrupt (WO)
+        (0x0024 => pub aeoir: WriteOnly<u32, GICC_EOIR::Register>),
+        /// Aliased highest priority pending interrupt (RO)
+        (0x0028 => pub ahppir: ReadOnly<u32, GICC_HPPIR::Register>),
+        (0x002c => _pad0),
+        /// Active priority
+        (0x00d0 => pub apr: [ReadWrite<u32>; 4]),
+        /// Non-secure active priority
+        (0x00e0 => pub nsapr: [ReadWrite<u32>; 4]),
+        (0x00f0 => _pad1),
+        /// CPU interface identification (RO)
+        (0x00fc => pub iidr: ReadOnly<u32, GICC_IIDR::Register>),
+        (0x0100 => _pad2),
+        /// Deactivate interrupt (WO)
+        (0x1000 => pub dir: WriteOnly<u32, GICC_EOIR::Register>),
+        (0x1004 => @END),
+    }
+}
+
+impl_snapshot! {
+    DistributorRegisters {
+        ctlr,
+        typer,
+        iidr,
+        igroupr,
+        isenabler,
+        icenabler,
+        ispendr,
+        icpendr,
+        isactiver,
+        icactiver,
+        ipriorityr,
+        itargetsr,
+        icfgr,
+        ppisr,
+        spisr,
+        sgir,
+        cpendsgir,
+        spendsgir,
+        pidr,
+        cidr,
+    }
+    CpuInterfaceRegisters {
+        ctlr,
+        pmr,
+        bpr,
+        // Reading `GICC_IAR` or `GICC_AIAR` acknowledges an interrupt
+        iar: skip,
+        eoir,
+        rpr,
+        hppir,
+        abpr,
+        aiar: skip,
+        aeoir,
+        ahppir,
+        apr,
+        nsapr,
+        iidr,
+        dir,
+    }
+}
+
+/// Describe the GIC-400 register blocks. See [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[
+        Peripheral::new::<DistributorRegisters>(
+            "GICD",
+            "GIC-400 distributor",
+            &[Instance::new("GICD", BASE_GICD_ARM_PA)],
+        ),
+        Peripheral::new::<CpuInterfaceRegisters>(
+            "GICC",
+            "GIC-400 CPU interface",
+            &[Instance::new("GICC", BASE_GICC_ARM_PA)],
+        ),
+    ];
+    PERIPHERALS
+}
+
+/// Read-only views for checking the configuration of individual interrupt
+/// lines
+impl DistributorRegisters {
+    /// Get a flag indicating whether the specified interrupt line is enabled.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `intno` is outside the range `0..`[`MAX_INTERRUPTS`].
+    #[inline]
+    pub fn is_enabled(&self, intno: usize) -> bool {
+        let per_reg = GICD_ISENABLER::INTERRUPTS_PER_REGISTER;
+        self.isenabler[intno / per_reg].is_set(GICD_ISENABLER::int(intno % per_reg))
+    }
+
+    /// Get a flag indicating whether the specified interrupt line is pending.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `intno` is outside the range `0..`[`MAX_INTERRUPTS`].
+    #[inline]
+    pub fn is_pending(&self, intno: usize) -> bool {
+        let per_reg = GICD_ISPENDR::INTERRUPTS_PER_REGISTER;
+        self.ispendr[intno / per_reg].is_set(GICD_ISPENDR::int(intno % per_reg))
+    }
+
+    /// Get a flag indicating whether the specified interrupt line is active.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `intno` is outside the range `0..`[`MAX_INTERRUPTS`].
+    #[inline]
+    pub fn is_active(&self, intno: usize) -> bool {
+        let per_reg = GICD_ISACTIVER::INTERRUPTS_PER_REGISTER;
+        self.isactiver[intno / per_reg].is_set(GICD_ISACTIVER::int(intno % per_reg))
+    }
+
+    /// Get the priority of the specified interrupt line. Lower values
+    /// represent higher priorities.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `intno` is outside the range `0..`[`MAX_INTERRUPTS`].
+    #[inline]
+    pub fn priority(&self, intno: usize) -> u8 {
+        let per_reg = GICD_IPRIORITYR::INTERRUPTS_PER_REGISTER;
+        self.ipriorityr[intno / per_reg].read(GICD_IPRIORITYR::int(intno % per_reg)) as u8
+    }
+
+    /// Get the set of processors targeted by the specified interrupt line.
+    /// Bit `i` corresponds to CPU interface `i`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `intno` is outside the range `0..`[`MAX_INTERRUPTS`].
+    #[inline]
+    pub fn target_processors(&self, intno: usize) -> u8 {
+        let per_reg = GICD_ITARGETSR::INTERRUPTS_PER_REGISTER;
+        self.itargetsr[intno / per_reg].read(GICD_ITARGETSR::int(intno % per_reg)) as u8
+    }
+
+    /// Get a flag indicating whether the specified interrupt line is
+    /// configured as edge-triggered.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `intno` is outside the range `0..`[`MAX_INTERRUPTS`].
+    #[inline]
+    pub fn is_edge_triggered(&self, intno: usize) -> bool {
+        let per_reg = GICD_ICFGR::INTERRUPTS_PER_REGISTER;
+        self.icfgr[intno / per_reg].read(GICD_ICFGR::int(intno % per_reg))
+            == GICD_ICFGR::EDGE_TRIGGERED
+    }
+}
+
+#[macropol::macropol]
+macro_rules! register_int_field {
+    (
+        $( #[$meta:meta] )*
+        pub mod $NAME:ident [$width:literal] {
+            $(
+                #[doc = $const_doc:literal]
+                pub const $const_name:ident: u32 = $const_value:expr;
+            )*
+            $(
+                #[field_value($value:expr)]
+                $( #[$value_meta:meta] )*
+                pub const fn $value_name:ident();
+            )*
+        }
+    ) => {
+        $( #[$meta] )*
+        #[allow(non_snake_case)]
+        pub mod $NAME {
+            use super::*;
+            pub struct Register;
+            impl RegisterLongName for Register {}
+
+            impl RegisterInfo for Register {
+                const NAME: &'static str = stringify!($NAME);
+                const FIELDS: &'static [FieldInfo] = &[FieldInfo::array(
+                    "INT",
+                    0,
+                    $width,
+                    32 / $width,
+                    &[$( (stringify!($const_name), $const_value) ),*],
+                )];
+            }
+
+            /// The number of interrupt lines represented by each `$&NAME`
+            /// register.
+            pub const INTERRUPTS_PER_REGISTER: usize = 32 / $width;
+
+            $(
+                #[doc = $const_doc]
+                pub const $const_name: u32 = $const_value;
+            )*
+
+            /// Construct a [`Field`] corresponding to the specified interrupt
+            /// line.
+            ///
+            /// # Panic
+            ///
+            /// Panics if `i` is outside the range `0..`[`INTERRUPTS_PER_REGISTER`].
+            #[inline]
+            pub const fn int(i: usize) -> Field<u32, Register> {
+                assert!(i < INTERRUPTS_PER_REGISTER);
+                Field::new((1 << $width) - 1, $width * i)
+            }
+
+            $(
+                $( #[$value_meta] )*
+                ///
+                /// # Panic
+                ///
+                /// Panics if `i` is outside the range `0..`[`INTERRUPTS_PER_REGISTER`].
+                #[inline]
+                pub const fn $value_name(i: usize) -> FieldValue<u32, Register> {
+                    assert!(i < INTERRUPTS_PER_REGISTER);
+                    FieldValue::<u32, _>::new((1 << $width) - 1, $width * i, $value)
+                }
+            )*
+        }
+    };
+}
+
+register_bitfields! {u32,
+    pub GICD_CTLR [
+        /// Enable forwarding of group 0 interrupts
+        ENABLE_GRP0 OFFSET(0) NUMBITS(1) [],
+        /// Enable forwarding of group 1 interrupts
+        ENABLE_GRP1 OFFSET(1) NUMBITS(1) [],
+    ],
+    pub GICD_TYPER [
+        /// The number of implemented interrupt lines divided by 32, minus one
+        ITLINES_NUMBER OFFSET(0) NUMBITS(5) [],
+        /// The number of implemented CPU interfaces minus one
+        CPU_NUMBER OFFSET(5) NUMBITS(3) [],
+        /// The GIC implements the Security Extensions
+        SECURITY_EXTN OFFSET(10) NUMBITS(1) [],
+        /// The number of lockable SPIs
+        LSPI OFFSET(11) NUMBITS(5) [],
+    ],
+    pub GICD_IIDR [
+        /// Implementer (JEP106 code)
+        IMPLEMENTER OFFSET(0) NUMBITS(12) [],
+        /// Revision number
+        REVISION OFFSET(12) NUMBITS(4) [],
+        /// Variant number
+        VARIANT OFFSET(16) NUMBITS(4) [],
+        /// Product ID
+        PRODUCT_ID OFFSET(24) NUMBITS(8) [],
+    ],
+    pub GICD_PPISR [
+        /// Virtual maintenance interrupt (ID 25)
+        VMI OFFSET(9) NUMBITS(1) [],
+        /// Hypervisor timer event (ID 26)
+        HYP_TIMER OFFSET(10) NUMBITS(1) [],
+        /// Virtual timer event (ID 27)
+        VIRT_TIMER OFFSET(11) NUMBITS(1) [],
+        /// `nLEGACYFIQ` signal (ID 28)
+        LEGACY_FIQ OFFSET(12) NUMBITS(1) [],
+        /// Secure physical timer event (ID 29)
+        SECURE_PHYS_TIMER OFFSET(13) NUMBITS(1) [],
+        /// Non-secure physical timer event (ID 30)
+        NON_SECURE_PHYS_TIMER OFFSET(14) NUMBITS(1) [],
+        /// `nLEGACYIRQ` signal (ID 31)
+        LEGACY_IRQ OFFSET(15) NUMBITS(1) [],
+    ],
+    pub GICD_SGIR [
+        /// The interrupt ID of the SGI to forward to the target processors
+        SGIINTID OFFSET(0) NUMBITS(4) [],
+        /// Forward the SGI only if it's configured as group 1 (Secure accesses
+        /// only)
+        NSATT OFFSET(15) NUMBITS(1) [
+            Group0 = 0,
+            Group1 = 1,
+        ],
+        /// The set of target CPU interfaces. Valid only if
+        /// `TARGET_LIST_FILTER` is `TargetList`.
+        CPU_TARGET_LIST OFFSET(16) NUMBITS(8) [],
+        /// Target list filter
+        TARGET_LIST_FILTER OFFSET(24) NUMBITS(2) [
+            /// Forward the interrupt to the processors specified by
+            /// `CPU_TARGET_LIST`
+            TargetList = 0b00,
+            /// Forward the interrupt to all processors except the requesting
+            /// one
+            AllOthers = 0b01,
+            /// Forward the interrupt only to the requesting processor
+            Myself = 0b10,
+        ],
+    ],
+
+    pub GICC_CTLR [
+        /// Enable signaling of group 0 interrupts
+        ENABLE_GRP0 OFFSET(0) NUMBITS(1) [],
+        /// Enable signaling of group 1 interrupts
+        ENABLE_GRP1 OFFSET(1) NUMBITS(1) [],
+        /// Secure reads of `GICC_IAR` acknowledge group 1 interrupts
+        ACK_CTL OFFSET(2) NUMBITS(1) [],
+        /// Signal group 0 interrupts using the FIQ signal
+        FIQ_EN OFFSET(3) NUMBITS(1) [],
+        /// Use `GICC_BPR` for both group 0 and group 1 interrupts
+        CBPR OFFSET(4) NUMBITS(1) [],
+        /// Bypass FIQ is not signaled to the processor (group 0)
+        FIQ_BYP_DIS_GRP0 OFFSET(5) NUMBITS(1) [],
+        /// Bypass IRQ is not signaled to the processor (group 0)
+        IRQ_BYP_DIS_GRP0 OFFSET(6) NUMBITS(1) [],
+        /// Bypass FIQ is not signaled to the processor (group 1)
+        FIQ_BYP_DIS_GRP1 OFFSET(7) NUMBITS(1) [],
+        /// Bypass IRQ is not signaled to the processor (group 1)
+        IRQ_BYP_DIS_GRP1 OFFSET(8) NUMBITS(1) [],
+        /// Secure `GICC_EOIR` accesses only drop the priority; `GICC_DIR`
+        /// deactivates the interrupt
+        EOI_MODE_S OFFSET(9) NUMBITS(1) [],
+        /// Non-secure `GICC_EOIR` accesses only drop the priority; `GICC_DIR`
+        /// deactivates the interrupt
+        EOI_MODE_NS OFFSET(10) NUMBITS(1) [],
+    ],
+    pub GICC_PMR [
+        /// Priority mask. Only interrupts with a higher priority (lower value)
+        /// are signaled to the processor.
+        PRIORITY OFFSET(0) NUMBITS(8) [],
+    ],
+    pub GICC_BPR [
+        /// The split point between the group priority and the subpriority
+        BINARY_POINT OFFSET(0) NUMBITS(3) [],
+    ],
+    pub GICC_IAR [
+        /// Interrupt ID. [`SPURIOUS_INTERRUPT_ID`] indicates no pending
+        /// interrupt.
+        INTERRUPT_ID OFFSET(0) NUMBITS(10) [],
+        /// (SGI only) The processor that requested the interrupt
+        CPUID OFFSET(10) NUMBITS(3) [],
+    ],
+    pub GICC_RPR [
+        /// The running priority of the CPU interface
+        PRIORITY OFFSET(0) NUMBITS(8) [],
+    ],
+    pub GICC_IIDR [
+        /// Implementer (JEP106 code)
+        IMPLEMENTER OFFSET(0) NUMBITS(12) [],
+        /// Revision number
+        REVISION OFFSET(12) NUMBITS(4) [],
+        /// Architecture version
+        ARCHITECTURE_VERSION OFFSET(16) NUMBITS(4) [],
+        /// Product ID
+        PRODUCT_ID OFFSET(20) NUMBITS(12) [],
+    ],
+}
+
+pub use GICC_IAR as GICC_EOIR;
+pub use GICC_IAR as GICC_HPPIR;
+
+register_int_field! {
+    /// Interrupt group
+    pub mod GICD_IGROUPR [1] {
+        /// Field value: The interrupt line belongs to group 0
+        pub const GROUP0: u32 = 0;
+        /// Field value: The interrupt line belongs to group 1
+        pub const GROUP1: u32 = 1;
+    }
+}
+
+register_int_field! {
+    /// Interrupt set-enable
+    pub mod GICD_ISENABLER [1] {
+        #[field_value(1)]
+        /// Construct a [`FieldValue`] that can be used to enable the specified
+        /// interrupt line.
+        pub const fn set();
+    }
+}
+
+register_int_field! {
+    /// Interrupt clear-enable
+    pub mod GICD_ICENABLER [1] {
+        #[field_value(1)]
+        /// Construct a [`FieldValue`] that can be used to disable the specified
+        /// interrupt line.
+        pub const fn clear();
+    }
+}
+
+register_int_field! {
+    /// Interrupt set-pending
+    pub mod GICD_ISPENDR [1] {
+        #[field_value(1)]
+        /// Construct a [`FieldValue`] that can be used to make the specified
+        /// interrupt line pending.
+        pub const fn set();
+    }
+}
+
+register_int_field! {
+    /// Interrupt clear-pending
+    pub mod GICD_ICPENDR [1] {
+        #[field_value(1)]
+        /// Construct a [`FieldValue`] that can be used to clear the pending
+        /// state of the specified interrupt line.
+        pub const fn clear();
+    }
+}
+
+register_int_field! {
+    /// Interrupt set-active
+    pub mod GICD_ISACTIVER [1] {
+        #[field_value(1)]
+        /// Construct a [`FieldValue`] that can be used to activate the
+        /// specified interrupt line.
+        pub const fn set();
+    }
+}
+
+register_int_field! {
+    /// Interrupt clear-active
+    pub mod GICD_ICACTIVER [1] {
+        #[field_value(1)]
+        /// Construct a [`FieldValue`] that can be used to deactivate the
+        /// specified interrupt line.
+        pub const fn clear();
+    }
+}
+
+register_int_field! {
+    /// Interrupt priority. Only the upper bits of each field may be
+    /// implemented.
+    pub mod GICD_IPRIORITYR [8] {}
+}
+
+register_int_field! {
+    /// Interrupt processor targets. Bit `i` of each field corresponds to CPU
+    /// interface `i`.
+    pub mod GICD_ITARGETSR [8] {}
+}
+
+register_int_field! {
+    /// Interrupt configuration
+    pub mod GICD_ICFGR [2] {
+        /// Field value: The interrupt line is level-sensitive
+        pub const LEVEL_SENSITIVE: u32 = 0b00;
+        /// Field value: The interrupt line is edge-triggered
+        pub const EDGE_TRIGGERED: u32 = 0b10;
+
+        #[field_value(0b00)]
+        /// Construct a [`FieldValue`] that can be used to configure the
+        /// specified interrupt line as level-sensitive.
+        pub const fn level_sensitive();
+
+        #[field_value(0b10)]
+        /// Construct a [`FieldValue`] that can be used to configure the
+        /// specified interrupt line as edge-triggered.
+        pub const fn edge_triggered();
+    }
+}
+
+register_int_field! {
+    /// Shared peripheral interrupt status (the status of the input lines, not
+    /// the pending state)
+    pub mod GICD_SPISR [1] {}
+}
+
+register_int_field! {
+    /// SGI clear-pending. Each 8-bit field corresponds to an SGI, and bit `i`
+    /// of the field corresponds to the requesting processor `i`.
+    pub mod GICD_CPENDSGIR [8] {}
+}
+
+register_int_field! {
+    /// SGI set-pending. Each 8-bit field corresponds to an SGI, and bit `i`
+    /// of the field corresponds to the requesting processor `i`.
+    pub mod GICD_SPENDSGIR [8] {}
+}
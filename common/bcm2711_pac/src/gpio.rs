@@ -1,11 +1,17 @@
 use tock_registers::{
     fields::{Field, FieldValue},
-    register_structs,
+    interfaces::{ReadWriteable, Readable},
+    register_structs, RegisterLongName,
+};
+
+use crate::{
+    describe::{Instance, Peripheral},
     registers::{ReadOnly, ReadWrite, WriteOnly},
-    RegisterLongName,
+    snapshot::{impl_snapshot, FieldInfo, RegisterInfo},
+    Vpa,
 };
 
-use crate::Vpa;
+pub mod alt;
 
 /// The base address of [the GPIO register block](Registers).
 pub const BASE: Vpa = Vpa(0x4_7e20_0000);
@@ -46,11 +52,39 @@ register_structs! {
         (0x88 => pub gpafen: [ReadWrite<u32, GPAFEN::Register>; 2]),
         (0x90 => _reserved8),
         /// GPIO pull-up/pull-down register
-        (0xe4 => pub gpio_pup_pdn_cntrl_reg: [ReadWrite<u32>; 4]),
+        (0xe4 => pub gpio_pup_pdn_cntrl_reg: [ReadWrite<u32, GPIO_PUP_PDN_CNTRL::Register>; 4]),
         (0xf4 => @END),
     }
 }
 
+impl_snapshot! {
+    Registers {
+        gpfsel,
+        gpset,
+        gpclr,
+        gplev,
+        gpeds,
+        gpren,
+        gpfen,
+        gphen,
+        gplen,
+        gparen,
+        gpafen,
+        gpio_pup_pdn_cntrl_reg,
+    }
+}
+
+/// Describe [the GPIO register block](Registers) and its instance. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "GPIO",
+        "GPIO",
+        &[Instance::vpa("GPIO", BASE)],
+    )];
+    PERIPHERALS
+}
+
 /// GPIO function select
 #[allow(non_snake_case)]
 pub mod GPFSEL {
@@ -58,6 +92,26 @@ pub mod GPFSEL {
     pub struct Register;
     impl RegisterLongName for Register {}
 
+    impl RegisterInfo for Register {
+        const NAME: &'static str = "GPFSEL";
+        const FIELDS: &'static [FieldInfo] = &[FieldInfo::array(
+            "FSEL",
+            0,
+            3,
+            PINS_PER_REGISTER as u32,
+            &[
+                ("INPUT", INPUT),
+                ("OUTPUT", OUTPUT),
+                ("ALT0", ALT0),
+                ("ALT1", ALT1),
+                ("ALT2", ALT2),
+                ("ALT3", ALT3),
+                ("ALT4", ALT4),
+                ("ALT5", ALT5),
+            ],
+        )];
+    }
+
     /// The number of pins represented by each `GPFSEL` register.
     pub const PINS_PER_REGISTER: usize = 10;
 
@@ -90,6 +144,272 @@ pub mod GPFSEL {
     }
 }
 
+/// GPIO pull-up/pull-down
+#[allow(non_snake_case)]
+pub mod GPIO_PUP_PDN_CNTRL {
+    use super::*;
+    pub struct Register;
+    impl RegisterLongName for Register {}
+
+    impl RegisterInfo for Register {
+        const NAME: &'static str = "GPIO_PUP_PDN_CNTRL";
+        const FIELDS: &'static [FieldInfo] = &[FieldInfo::array(
+            "PUD",
+            0,
+            2,
+            PINS_PER_REGISTER as u32,
+            &[
+                ("NONE", NONE),
+                ("PULL_UP", PULL_UP),
+                ("PULL_DOWN", PULL_DOWN),
+            ],
+        )];
+    }
+
+    /// The number of pins represented by each `GPIO_PUP_PDN_CNTRL` register.
+    pub const PINS_PER_REGISTER: usize = 16;
+
+    /// Field value: No resistor is selected
+    pub const NONE: u32 = 0b00;
+    /// Field value: Pull up resistor is selected
+    pub const PULL_UP: u32 = 0b01;
+    /// Field value: Pull down resistor is selected
+    pub const PULL_DOWN: u32 = 0b10;
+
+    /// Construct a [`Field`] corresponding to the specified pin number.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `i` is outside the range `0..`[`PINS_PER_REGISTER`].
+    #[inline]
+    pub const fn pin(i: usize) -> Field<u32, Register> {
+        assert!(i < PINS_PER_REGISTER);
+        Field::new(0b11, 2 * i)
+    }
+
+    /// Construct a [`FieldValue`] that can be used to disable the pull
+    /// resistors of the specified pin.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `i` is outside the range `0..`[`PINS_PER_REGISTER`].
+    #[inline]
+    pub const fn none(i: usize) -> FieldValue<u32, Register> {
+        assert!(i < PINS_PER_REGISTER);
+        FieldValue::<u32, _>::new(0b11, 2 * i, NONE)
+    }
+
+    /// Construct a [`FieldValue`] that can be used to select the pull-up
+    /// resistor of the specified pin.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `i` is outside the range `0..`[`PINS_PER_REGISTER`].
+    #[inline]
+    pub const fn pull_up(i: usize) -> FieldValue<u32, Register> {
+        assert!(i < PINS_PER_REGISTER);
+        FieldValue::<u32, _>::new(0b11, 2 * i, PULL_UP)
+    }
+
+    /// Construct a [`FieldValue`] that can be used to select the pull-down
+    /// resistor of the specified pin.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `i` is outside the range `0..`[`PINS_PER_REGISTER`].
+    #[inline]
+    pub const fn pull_down(i: usize) -> FieldValue<u32, Register> {
+        assert!(i < PINS_PER_REGISTER);
+        FieldValue::<u32, _>::new(0b11, 2 * i, PULL_DOWN)
+    }
+}
+
+/// The number of GPIO pins
+pub const NUM_PINS: usize = 58;
+
+/// The pull resistor configuration of a GPIO pin
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Bias {
+    /// No resistor
+    None,
+    /// Pull-up resistor
+    PullUp,
+    /// Pull-down resistor
+    PullDown,
+}
+
+impl Bias {
+    /// Get the [`GPIO_PUP_PDN_CNTRL`] field value representing `self`.
+    #[inline]
+    pub const fn to_field_value(self) -> u32 {
+        match self {
+            Self::None => GPIO_PUP_PDN_CNTRL::NONE,
+            Self::PullUp => GPIO_PUP_PDN_CNTRL::PULL_UP,
+            Self::PullDown => GPIO_PUP_PDN_CNTRL::PULL_DOWN,
+        }
+    }
+
+    /// Decode a [`GPIO_PUP_PDN_CNTRL`] field value. Returns `None` for the
+    /// reserved value `0b11`.
+    #[inline]
+    pub const fn from_field_value(value: u32) -> Option<Self> {
+        match value {
+            GPIO_PUP_PDN_CNTRL::NONE => Some(Self::None),
+            GPIO_PUP_PDN_CNTRL::PULL_UP => Some(Self::PullUp),
+            GPIO_PUP_PDN_CNTRL::PULL_DOWN => Some(Self::PullDown),
+            _ => None,
+        }
+    }
+}
+
+/// The function of a GPIO pin
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Function {
+    /// Input
+    Input,
+    /// Output
+    Output,
+    /// Alternate function 0
+    Alt0,
+    /// Alternate function 1
+    Alt1,
+    /// Alternate function 2
+    Alt2,
+    /// Alternate function 3
+    Alt3,
+    /// Alternate function 4
+    Alt4,
+    /// Alternate function 5
+    Alt5,
+}
+
+impl Function {
+    /// Get the [`GPFSEL`] field value representing `self`.
+    #[inline]
+    pub const fn to_field_value(self) -> u32 {
+        match self {
+            Self::Input => GPFSEL::INPUT,
+            Self::Output => GPFSEL::OUTPUT,
+            Self::Alt0 => GPFSEL::ALT0,
+            Self::Alt1 => GPFSEL::ALT1,
+            Self::Alt2 => GPFSEL::ALT2,
+            Self::Alt3 => GPFSEL::ALT3,
+            Self::Alt4 => GPFSEL::ALT4,
+            Self::Alt5 => GPFSEL::ALT5,
+        }
+    }
+
+    /// Decode a [`GPFSEL`] field value. Returns `None` if `value` doesn't fit
+    /// in the field.
+    #[inline]
+    pub const fn from_field_value(value: u32) -> Option<Self> {
+        match value {
+            GPFSEL::INPUT => Some(Self::Input),
+            GPFSEL::OUTPUT => Some(Self::Output),
+            GPFSEL::ALT0 => Some(Self::Alt0),
+            GPFSEL::ALT1 => Some(Self::Alt1),
+            GPFSEL::ALT2 => Some(Self::Alt2),
+            GPFSEL::ALT3 => Some(Self::Alt3),
+            GPFSEL::ALT4 => Some(Self::Alt4),
+            GPFSEL::ALT5 => Some(Self::Alt5),
+            _ => None,
+        }
+    }
+
+    /// Get the alternate function `n`. Returns `None` if `n` is outside the
+    /// range `0..6`.
+    #[inline]
+    pub const fn alt(n: usize) -> Option<Self> {
+        match n {
+            0 => Some(Self::Alt0),
+            1 => Some(Self::Alt1),
+            2 => Some(Self::Alt2),
+            3 => Some(Self::Alt3),
+            4 => Some(Self::Alt4),
+            5 => Some(Self::Alt5),
+            _ => None,
+        }
+    }
+
+    /// Get the index of the alternate function. Returns `None` for
+    /// [`Self::Input`] and [`Self::Output`].
+    #[inline]
+    pub const fn alt_index(self) -> Option<usize> {
+        match self {
+            Self::Input | Self::Output => None,
+            Self::Alt0 => Some(0),
+            Self::Alt1 => Some(1),
+            Self::Alt2 => Some(2),
+            Self::Alt3 => Some(3),
+            Self::Alt4 => Some(4),
+            Self::Alt5 => Some(5),
+        }
+    }
+}
+
+impl Registers {
+    /// Set the function of the specified pin by a read-modify-write
+    /// operation on [`Self::gpfsel`].
+    ///
+    /// The operation is not atomic; callers must prevent concurrent updates
+    /// to the same register.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `pin` is outside the range `0..`[`NUM_PINS`].
+    #[inline]
+    pub fn set_function(&self, pin: usize, function: Function) {
+        assert!(pin < NUM_PINS);
+        const N: usize = GPFSEL::PINS_PER_REGISTER;
+        self.gpfsel[pin / N].modify(GPFSEL::pin(pin % N).val(function.to_field_value()));
+    }
+
+    /// Get the function of the specified pin.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `pin` is outside the range `0..`[`NUM_PINS`].
+    #[inline]
+    pub fn function(&self, pin: usize) -> Function {
+        assert!(pin < NUM_PINS);
+        const N: usize = GPFSEL::PINS_PER_REGISTER;
+        Function::from_field_value(self.gpfsel[pin / N].read(GPFSEL::pin(pin % N)))
+            .expect("3-bit field value")
+    }
+
+    /// Set the pull resistor configuration of the specified pin by a
+    /// read-modify-write operation on [`Self::gpio_pup_pdn_cntrl_reg`].
+    ///
+    /// The operation is not atomic; callers must prevent concurrent updates
+    /// to the same register.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `pin` is outside the range `0..`[`NUM_PINS`].
+    #[inline]
+    pub fn set_bias(&self, pin: usize, bias: Bias) {
+        assert!(pin < NUM_PINS);
+        const N: usize = GPIO_PUP_PDN_CNTRL::PINS_PER_REGISTER;
+        self.gpio_pup_pdn_cntrl_reg[pin / N]
+            .modify(GPIO_PUP_PDN_CNTRL::pin(pin % N).val(bias.to_field_value()));
+    }
+
+    /// Get the pull resistor configuration of the specified pin. Returns
+    /// `None` if the register contains the reserved value.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `pin` is outside the range `0..`[`NUM_PINS`].
+    #[inline]
+    pub fn bias(&self, pin: usize) -> Option<Bias> {
+        assert!(pin < NUM_PINS);
+        const N: usize = GPIO_PUP_PDN_CNTRL::PINS_PER_REGISTER;
+        Bias::from_field_value(
+            self.gpio_pup_pdn_cntrl_reg[pin / N].read(GPIO_PUP_PDN_CNTRL::pin(pin % N)),
+        )
+    }
+}
+
 #[macropol::macropol]
 macro_rules! register_pin_field {
     (
@@ -109,6 +429,12 @@ macro_rules! register_pin_field {
             pub struct Register;
             impl RegisterLongName for Register {}
 
+            impl RegisterInfo for Register {
+                const NAME: &'static str = stringify!($NAME);
+                const FIELDS: &'static [FieldInfo] =
+                    &[FieldInfo::array("PIN", 0, 1, PINS_PER_REGISTER as u32, &[])];
+            }
+
             /// The number of pins represented by each `$&NAME` register.
             pub const PINS_PER_REGISTER: usize = 32;
 
@@ -0,0 +1,768 @@
+//! Alternate functions of the GPIO pins
+//!
+//! This module maps each `(pin, function)` pair to the peripheral [`Signal`]
+//! it carries (e.g., GPIO14 `ALT0` is [`Signal::Uart0Txd`]) based on the
+//! alternate function assignments table in the BCM2711 ARM Peripherals
+//! datasheet. Pins 46–57 are wired to on-board functions and are not covered
+//! by the table.
+//!
+//! Pins can be configured by signal name at run time:
+//!
+//! ```rust,no_run
+//! use bcm2711_pac::gpio::{self, alt::Signal};
+//!
+//! let gpio = unsafe { &*(gpio::BASE.to_arm_pa().unwrap() as *const gpio::Registers) };
+//! gpio.set_signals(&[(14, Signal::Uart0Txd), (15, Signal::Uart0Rxd)])
+//!     .unwrap();
+//! ```
+//!
+//! Or checked at compile time through the marker types in [`signal`](mod@signal):
+//!
+//! ```rust,no_run
+//! use bcm2711_pac::gpio::{self, alt::{signal::*, AltPin}};
+//!
+//! let gpio = unsafe { &*(gpio::BASE.to_arm_pa().unwrap() as *const gpio::Registers) };
+//! gpio.set_alt_pins::<(AltPin<2, Sda1>, AltPin<3, Scl1>)>();
+//! ```
+//!
+//! An impossible combination fails to compile:
+//!
+//! ```rust,compile_fail
+//! use bcm2711_pac::gpio::{self, alt::{signal::*, AltPin}};
+//!
+//! let gpio = unsafe { &*(gpio::BASE.to_arm_pa().unwrap() as *const gpio::Registers) };
+//! gpio.set_alt_pins::<(AltPin<2, Sda1>, AltPin<4, Scl1>)>();
+//! ```
+use core::{fmt, marker::PhantomData};
+
+use super::{Function, Registers};
+
+/// The number of pins covered by the alternate function table
+pub const TABLE_LEN: usize = 46;
+
+macro_rules! signals {
+    (
+        $(
+            $( #[$meta:meta] )*
+            $name:ident = $datasheet_name:literal,
+        )*
+    ) => {
+        /// A peripheral signal that can be routed to a GPIO pin
+        #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+        pub enum Signal {
+            $(
+                $( #[$meta] )*
+                $name,
+            )*
+        }
+
+        impl Signal {
+            /// All signals
+            pub const ALL: &'static [Self] = &[$( Self::$name ),*];
+
+            /// Get the signal name used in the datasheet (e.g., `TXD0`).
+            pub const fn datasheet_name(self) -> &'static str {
+                match self {
+                    $( Self::$name => $datasheet_name, )*
+                }
+            }
+        }
+
+        /// Marker types representing [`Signal`]s, used with [`AltPin`]
+        pub mod signal {
+            $(
+                $( #[$meta] )*
+                pub struct $name;
+
+                impl super::SignalMarker for $name {
+                    const SIGNAL: super::Signal = super::Signal::$name;
+                }
+            )*
+        }
+    };
+}
+
+signals! {
+    /// ARM JTAG return clock
+    ArmRtck = "ARM_RTCK",
+    /// ARM JTAG clock
+    ArmTck = "ARM_TCK",
+    /// ARM JTAG data in
+    ArmTdi = "ARM_TDI",
+    /// ARM JTAG data out
+    ArmTdo = "ARM_TDO",
+    /// ARM JTAG mode select
+    ArmTms = "ARM_TMS",
+    /// ARM JTAG reset
+    ArmTrst = "ARM_TRST",
+    /// BSC/SPI slave chip select
+    BscSlCe = "BSCSL / CE_N",
+    /// BSC/SPI slave MISO
+    BscSlMiso = "BSCSL / MISO",
+    /// BSC/SPI slave SCL / SCLK
+    BscSlSclSclk = "BSCSL SCL / SCLK",
+    /// BSC/SPI slave SDA / MOSI
+    BscSlSdaMosi = "BSCSL SDA / MOSI",
+    /// DPI data bit 0
+    DpiD0 = "DPI_D0",
+    /// DPI data bit 1
+    DpiD1 = "DPI_D1",
+    /// DPI data bit 2
+    DpiD2 = "DPI_D2",
+    /// DPI data bit 3
+    DpiD3 = "DPI_D3",
+    /// DPI data bit 4
+    DpiD4 = "DPI_D4",
+    /// DPI data bit 5
+    DpiD5 = "DPI_D5",
+    /// DPI data bit 6
+    DpiD6 = "DPI_D6",
+    /// DPI data bit 7
+    DpiD7 = "DPI_D7",
+    /// DPI data bit 8
+    DpiD8 = "DPI_D8",
+    /// DPI data bit 9
+    DpiD9 = "DPI_D9",
+    /// DPI data bit 10
+    DpiD10 = "DPI_D10",
+    /// DPI data bit 11
+    DpiD11 = "DPI_D11",
+    /// DPI data bit 12
+    DpiD12 = "DPI_D12",
+    /// DPI data bit 13
+    DpiD13 = "DPI_D13",
+    /// DPI data bit 14
+    DpiD14 = "DPI_D14",
+    /// DPI data bit 15
+    DpiD15 = "DPI_D15",
+    /// DPI data bit 16
+    DpiD16 = "DPI_D16",
+    /// DPI data bit 17
+    DpiD17 = "DPI_D17",
+    /// DPI data bit 18
+    DpiD18 = "DPI_D18",
+    /// DPI data bit 19
+    DpiD19 = "DPI_D19",
+    /// DPI data bit 20
+    DpiD20 = "DPI_D20",
+    /// DPI data bit 21
+    DpiD21 = "DPI_D21",
+    /// DPI data bit 22
+    DpiD22 = "DPI_D22",
+    /// DPI data bit 23
+    DpiD23 = "DPI_D23",
+    /// DPI data enable
+    DpiDe = "DE",
+    /// DPI horizontal sync
+    DpiHsync = "LCD_HSYNC",
+    /// DPI pixel clock
+    DpiPclk = "PCLK",
+    /// DPI vertical sync
+    DpiVsync = "LCD_VSYNC",
+    /// General purpose clock 0
+    GpClk0 = "GPCLK0",
+    /// General purpose clock 1
+    GpClk1 = "GPCLK1",
+    /// General purpose clock 2
+    GpClk2 = "GPCLK2",
+    /// MII collision
+    MiiACol = "MII_A_COL",
+    /// MII carrier sense
+    MiiACrs = "MII_A_CRS",
+    /// MII receive error
+    MiiARxErr = "MII_A_RX_ERR",
+    /// MII transmit error
+    MiiATxErr = "MII_A_TX_ERR",
+    /// PCM/I2S CLK
+    PcmClk = "PCM_CLK",
+    /// PCM/I2S DIN
+    PcmDin = "PCM_DIN",
+    /// PCM/I2S DOUT
+    PcmDout = "PCM_DOUT",
+    /// PCM/I2S FS
+    PcmFs = "PCM_FS",
+    /// PWM0 channel 0
+    Pwm0Ch0 = "PWM0_0",
+    /// PWM0 channel 1
+    Pwm0Ch1 = "PWM0_1",
+    /// PWM1 channel 0
+    Pwm1Ch0 = "PWM1_0",
+    /// PWM1 channel 1
+    Pwm1Ch1 = "PWM1_1",
+    /// RGMII interrupt
+    RgmiiIrq = "RGMII_IRQ",
+    /// RGMII management clock
+    RgmiiMdc = "RGMII_MDC",
+    /// RGMII management data
+    RgmiiMdio = "RGMII_MDIO",
+    /// RGMII receive OK
+    RgmiiRxOk = "RGMII_RX_OK",
+    /// RGMII start/stop
+    RgmiiStartStop = "RGMII_START_STOP",
+    /// BSC0 (I2C0) clock
+    Scl0 = "SCL0",
+    /// BSC1 (I2C1) clock
+    Scl1 = "SCL1",
+    /// BSC3 (I2C3) clock
+    Scl3 = "SCL3",
+    /// BSC4 (I2C4) clock
+    Scl4 = "SCL4",
+    /// BSC5 (I2C5) clock
+    Scl5 = "SCL5",
+    /// BSC6 (I2C6) clock
+    Scl6 = "SCL6",
+    /// SD0 clk
+    Sd0Clk = "SD0_CLK",
+    /// SD0 cmd
+    Sd0Cmd = "SD0_CMD",
+    /// SD0 data 0
+    Sd0Dat0 = "SD0_DAT0",
+    /// SD0 data 1
+    Sd0Dat1 = "SD0_DAT1",
+    /// SD0 data 2
+    Sd0Dat2 = "SD0_DAT2",
+    /// SD0 data 3
+    Sd0Dat3 = "SD0_DAT3",
+    /// SD1 clk
+    Sd1Clk = "SD1_CLK",
+    /// SD1 cmd
+    Sd1Cmd = "SD1_CMD",
+    /// SD1 data 0
+    Sd1Dat0 = "SD1_DAT0",
+    /// SD1 data 1
+    Sd1Dat1 = "SD1_DAT1",
+    /// SD1 data 2
+    Sd1Dat2 = "SD1_DAT2",
+    /// SD1 data 3
+    Sd1Dat3 = "SD1_DAT3",
+    /// SD1 data 4
+    Sd1Dat4 = "SD1_DAT4",
+    /// SD1 data 5
+    Sd1Dat5 = "SD1_DAT5",
+    /// SD1 data 6
+    Sd1Dat6 = "SD1_DAT6",
+    /// SD1 data 7
+    Sd1Dat7 = "SD1_DAT7",
+    /// SD card LED
+    SdCardLed = "SD_CARD_LED",
+    /// SD card present
+    SdCardPres = "SD_CARD_PRES",
+    /// SD card power control
+    SdCardPwr0 = "SD_CARD_PWR0",
+    /// SD card voltage select
+    SdCardVolt = "SD_CARD_VOLT",
+    /// SD card write protect
+    SdCardWrprot = "SD_CARD_WRPROT",
+    /// BSC0 (I2C0) data
+    Sda0 = "SDA0",
+    /// BSC1 (I2C1) data
+    Sda1 = "SDA1",
+    /// BSC3 (I2C3) data
+    Sda3 = "SDA3",
+    /// BSC4 (I2C4) data
+    Sda4 = "SDA4",
+    /// BSC5 (I2C5) data
+    Sda5 = "SDA5",
+    /// BSC6 (I2C6) data
+    Sda6 = "SDA6",
+    /// SMI address bit 0
+    SmiSa0 = "SA0",
+    /// SMI address bit 1
+    SmiSa1 = "SA1",
+    /// SMI address bit 2
+    SmiSa2 = "SA2",
+    /// SMI address bit 3
+    SmiSa3 = "SA3",
+    /// SMI address bit 4
+    SmiSa4 = "SA4",
+    /// SMI address bit 5
+    SmiSa5 = "SA5",
+    /// SMI data bit 0
+    SmiSd0 = "SD0",
+    /// SMI data bit 1
+    SmiSd1 = "SD1",
+    /// SMI data bit 2
+    SmiSd2 = "SD2",
+    /// SMI data bit 3
+    SmiSd3 = "SD3",
+    /// SMI data bit 4
+    SmiSd4 = "SD4",
+    /// SMI data bit 5
+    SmiSd5 = "SD5",
+    /// SMI data bit 6
+    SmiSd6 = "SD6",
+    /// SMI data bit 7
+    SmiSd7 = "SD7",
+    /// SMI data bit 8
+    SmiSd8 = "SD8",
+    /// SMI data bit 9
+    SmiSd9 = "SD9",
+    /// SMI data bit 10
+    SmiSd10 = "SD10",
+    /// SMI data bit 11
+    SmiSd11 = "SD11",
+    /// SMI data bit 12
+    SmiSd12 = "SD12",
+    /// SMI data bit 13
+    SmiSd13 = "SD13",
+    /// SMI data bit 14
+    SmiSd14 = "SD14",
+    /// SMI data bit 15
+    SmiSd15 = "SD15",
+    /// SMI data bit 16
+    SmiSd16 = "SD16",
+    /// SMI data bit 17
+    SmiSd17 = "SD17",
+    /// SMI output enable / setup (SOE_N / SE)
+    SmiSoe = "SOE_N / SE",
+    /// SMI write enable / read-write (SWE_N / SRW_N)
+    SmiSwe = "SWE_N / SRW_N",
+    /// SPI0 chip select 0
+    Spi0Ce0 = "SPI0_CE0_N",
+    /// SPI0 chip select 1
+    Spi0Ce1 = "SPI0_CE1_N",
+    /// SPI0 chip select 2
+    Spi0Ce2 = "SPI0_CE2_N",
+    /// SPI0 MISO
+    Spi0Miso = "SPI0_MISO",
+    /// SPI0 MOSI
+    Spi0Mosi = "SPI0_MOSI",
+    /// SPI0 SCLK
+    Spi0Sclk = "SPI0_SCLK",
+    /// SPI1 chip select 0
+    Spi1Ce0 = "SPI1_CE0_N",
+    /// SPI1 chip select 1
+    Spi1Ce1 = "SPI1_CE1_N",
+    /// SPI1 chip select 2
+    Spi1Ce2 = "SPI1_CE2_N",
+    /// SPI1 MISO
+    Spi1Miso = "SPI1_MISO",
+    /// SPI1 MOSI
+    Spi1Mosi = "SPI1_MOSI",
+    /// SPI1 SCLK
+    Spi1Sclk = "SPI1_SCLK",
+    /// SPI3 chip select 0
+    Spi3Ce0 = "SPI3_CE0_N",
+    /// SPI3 chip select 1
+    Spi3Ce1 = "SPI3_CE1_N",
+    /// SPI3 MISO
+    Spi3Miso = "SPI3_MISO",
+    /// SPI3 MOSI
+    Spi3Mosi = "SPI3_MOSI",
+    /// SPI3 SCLK
+    Spi3Sclk = "SPI3_SCLK",
+    /// SPI4 chip select 0
+    Spi4Ce0 = "SPI4_CE0_N",
+    /// SPI4 chip select 1
+    Spi4Ce1 = "SPI4_CE1_N",
+    /// SPI4 MISO
+    Spi4Miso = "SPI4_MISO",
+    /// SPI4 MOSI
+    Spi4Mosi = "SPI4_MOSI",
+    /// SPI4 SCLK
+    Spi4Sclk = "SPI4_SCLK",
+    /// SPI5 chip select 0
+    Spi5Ce0 = "SPI5_CE0_N",
+    /// SPI5 chip select 1
+    Spi5Ce1 = "SPI5_CE1_N",
+    /// SPI5 MISO
+    Spi5Miso = "SPI5_MISO",
+    /// SPI5 MOSI
+    Spi5Mosi = "SPI5_MOSI",
+    /// SPI5 SCLK
+    Spi5Sclk = "SPI5_SCLK",
+    /// SPI6 chip select 0
+    Spi6Ce0 = "SPI6_CE0_N",
+    /// SPI6 chip select 1
+    Spi6Ce1 = "SPI6_CE1_N",
+    /// SPI6 MISO
+    Spi6Miso = "SPI6_MISO",
+    /// SPI6 MOSI
+    Spi6Mosi = "SPI6_MOSI",
+    /// SPI6 SCLK
+    Spi6Sclk = "SPI6_SCLK",
+    /// Display tearing effect 0
+    Te0 = "TE0",
+    /// Display tearing effect 1
+    Te1 = "TE1",
+    /// UART0 clear to send
+    Uart0Cts = "CTS0",
+    /// UART0 request to send
+    Uart0Rts = "RTS0",
+    /// UART0 receive data
+    Uart0Rxd = "RXD0",
+    /// UART0 transmit data
+    Uart0Txd = "TXD0",
+    /// Mini UART (UART1) clear to send
+    Uart1Cts = "CTS1",
+    /// Mini UART (UART1) request to send
+    Uart1Rts = "RTS1",
+    /// Mini UART (UART1) receive data
+    Uart1Rxd = "RXD1",
+    /// Mini UART (UART1) transmit data
+    Uart1Txd = "TXD1",
+    /// UART2 clear to send
+    Uart2Cts = "CTS2",
+    /// UART2 request to send
+    Uart2Rts = "RTS2",
+    /// UART2 receive data
+    Uart2Rxd = "RXD2",
+    /// UART2 transmit data
+    Uart2Txd = "TXD2",
+    /// UART3 clear to send
+    Uart3Cts = "CTS3",
+    /// UART3 request to send
+    Uart3Rts = "RTS3",
+    /// UART3 receive data
+    Uart3Rxd = "RXD3",
+    /// UART3 transmit data
+    Uart3Txd = "TXD3",
+    /// UART4 clear to send
+    Uart4Cts = "CTS4",
+    /// UART4 request to send
+    Uart4Rts = "RTS4",
+    /// UART4 receive data
+    Uart4Rxd = "RXD4",
+    /// UART4 transmit data
+    Uart4Txd = "TXD4",
+    /// UART5 clear to send
+    Uart5Cts = "CTS5",
+    /// UART5 request to send
+    Uart5Rts = "RTS5",
+    /// UART5 receive data
+    Uart5Rxd = "RXD5",
+    /// UART5 transmit data
+    Uart5Txd = "TXD5",
+}
+
+/// The signals assigned to `ALT0`–`ALT5` of each pin
+#[rustfmt::skip]
+const TABLE: [[Option<Signal>; 6]; TABLE_LEN] = {
+    use Signal::*;
+    [
+        /*  0 */ [Some(Sda0), Some(SmiSa5), Some(DpiPclk), Some(Spi3Ce0), Some(Uart2Txd), Some(Sda6)],
+        /*  1 */ [Some(Scl0), Some(SmiSa4), Some(DpiDe), Some(Spi3Miso), Some(Uart2Rxd), Some(Scl6)],
+        /*  2 */ [Some(Sda1), Some(SmiSa3), Some(DpiVsync), Some(Spi3Mosi), Some(Uart2Cts), Some(Sda3)],
+        /*  3 */ [Some(Scl1), Some(SmiSa2), Some(DpiHsync), Some(Spi3Sclk), Some(Uart2Rts), Some(Scl3)],
+        /*  4 */ [Some(GpClk0), Some(SmiSa1), Some(DpiD0), Some(Spi4Ce0), Some(Uart3Txd), Some(Sda3)],
+        /*  5 */ [Some(GpClk1), Some(SmiSa0), Some(DpiD1), Some(Spi4Miso), Some(Uart3Rxd), Some(Scl3)],
+        /*  6 */ [Some(GpClk2), Some(SmiSoe), Some(DpiD2), Some(Spi4Mosi), Some(Uart3Cts), Some(Sda4)],
+        /*  7 */ [Some(Spi0Ce1), Some(SmiSwe), Some(DpiD3), Some(Spi4Sclk), Some(Uart3Rts), Some(Scl4)],
+        /*  8 */ [Some(Spi0Ce0), Some(SmiSd0), Some(DpiD4), Some(BscSlCe), Some(Uart4Txd), Some(Sda4)],
+        /*  9 */ [Some(Spi0Miso), Some(SmiSd1), Some(DpiD5), Some(BscSlMiso), Some(Uart4Rxd), Some(Scl4)],
+        /* 10 */ [Some(Spi0Mosi), Some(SmiSd2), Some(DpiD6), Some(BscSlSdaMosi), Some(Uart4Cts), Some(Sda5)],
+        /* 11 */ [Some(Spi0Sclk), Some(SmiSd3), Some(DpiD7), Some(BscSlSclSclk), Some(Uart4Rts), Some(Scl5)],
+        /* 12 */ [Some(Pwm0Ch0), Some(SmiSd4), Some(DpiD8), Some(Spi5Ce0), Some(Uart5Txd), Some(Sda5)],
+        /* 13 */ [Some(Pwm0Ch1), Some(SmiSd5), Some(DpiD9), Some(Spi5Miso), Some(Uart5Rxd), Some(Scl5)],
+        /* 14 */ [Some(Uart0Txd), Some(SmiSd6), Some(DpiD10), Some(Spi5Mosi), Some(Uart5Cts), Some(Uart1Txd)],
+        /* 15 */ [Some(Uart0Rxd), Some(SmiSd7), Some(DpiD11), Some(Spi5Sclk), Some(Uart5Rts), Some(Uart1Rxd)],
+        /* 16 */ [None, Some(SmiSd8), Some(DpiD12), Some(Uart0Cts), Some(Spi1Ce2), Some(Uart1Cts)],
+        /* 17 */ [None, Some(SmiSd9), Some(DpiD13), Some(Uart0Rts), Some(Spi1Ce1), Some(Uart1Rts)],
+        /* 18 */ [Some(PcmClk), Some(SmiSd10), Some(DpiD14), Some(Spi6Ce0), Some(Spi1Ce0), Some(Pwm0Ch0)],
+        /* 19 */ [Some(PcmFs), Some(SmiSd11), Some(DpiD15), Some(Spi6Miso), Some(Spi1Miso), Some(Pwm0Ch1)],
+        /* 20 */ [Some(PcmDin), Some(SmiSd12), Some(DpiD16), Some(Spi6Mosi), Some(Spi1Mosi), Some(GpClk0)],
+        /* 21 */ [Some(PcmDout), Some(SmiSd13), Some(DpiD17), Some(Spi6Sclk), Some(Spi1Sclk), Some(GpClk1)],
+        /* 22 */ [Some(Sd0Clk), Some(SmiSd14), Some(DpiD18), Some(Sd1Clk), Some(ArmTrst), Some(Sda6)],
+        /* 23 */ [Some(Sd0Cmd), Some(SmiSd15), Some(DpiD19), Some(Sd1Cmd), Some(ArmRtck), Some(Scl6)],
+        /* 24 */ [Some(Sd0Dat0), Some(SmiSd16), Some(DpiD20), Some(Sd1Dat0), Some(ArmTdo), Some(Spi3Ce1)],
+        /* 25 */ [Some(Sd0Dat1), Some(SmiSd17), Some(DpiD21), Some(Sd1Dat1), Some(ArmTck), Some(Spi4Ce1)],
+        /* 26 */ [Some(Sd0Dat2), Some(Te0), Some(DpiD22), Some(Sd1Dat2), Some(ArmTdi), Some(Spi5Ce1)],
+        /* 27 */ [Some(Sd0Dat3), Some(Te1), Some(DpiD23), Some(Sd1Dat3), Some(ArmTms), Some(Spi6Ce1)],
+        /* 28 */ [Some(Sda0), Some(SmiSa5), Some(PcmClk), None, Some(MiiARxErr), Some(RgmiiMdio)],
+        /* 29 */ [Some(Scl0), Some(SmiSa4), Some(PcmFs), None, Some(MiiATxErr), Some(RgmiiMdc)],
+        /* 30 */ [None, Some(SmiSa3), Some(PcmDin), Some(Uart0Cts), Some(MiiACrs), Some(Uart1Cts)],
+        /* 31 */ [None, Some(SmiSa2), Some(PcmDout), Some(Uart0Rts), Some(MiiACol), Some(Uart1Rts)],
+        /* 32 */ [Some(GpClk0), Some(SmiSa1), None, Some(Uart0Txd), Some(SdCardPres), Some(Uart1Txd)],
+        /* 33 */ [None, Some(SmiSa0), None, Some(Uart0Rxd), Some(SdCardWrprot), Some(Uart1Rxd)],
+        /* 34 */ [Some(GpClk0), Some(SmiSoe), None, Some(Sd1Clk), Some(SdCardLed), Some(RgmiiIrq)],
+        /* 35 */ [Some(Spi0Ce1), Some(SmiSwe), None, Some(Sd1Cmd), Some(RgmiiStartStop), None],
+        /* 36 */ [Some(Spi0Ce0), Some(SmiSd0), Some(Uart0Txd), Some(Sd1Dat0), Some(RgmiiRxOk), Some(MiiARxErr)],
+        /* 37 */ [Some(Spi0Miso), Some(SmiSd1), Some(Uart0Rxd), Some(Sd1Dat1), Some(RgmiiMdio), Some(MiiATxErr)],
+        /* 38 */ [Some(Spi0Mosi), Some(SmiSd2), Some(Uart0Rts), Some(Sd1Dat2), Some(RgmiiMdc), Some(MiiACrs)],
+        /* 39 */ [Some(Spi0Sclk), Some(SmiSd3), Some(Uart0Cts), Some(Sd1Dat3), Some(RgmiiIrq), Some(MiiACol)],
+        /* 40 */ [Some(Pwm1Ch0), Some(SmiSd4), None, Some(Sd1Dat4), Some(Spi0Miso), Some(Uart1Txd)],
+        /* 41 */ [Some(Pwm1Ch1), Some(SmiSd5), None, Some(Sd1Dat5), Some(Spi0Mosi), Some(Uart1Rxd)],
+        /* 42 */ [Some(GpClk1), Some(SmiSd6), None, Some(Sd1Dat6), Some(Spi0Sclk), Some(Uart1Rts)],
+        /* 43 */ [Some(GpClk2), Some(SmiSd7), None, Some(Sd1Dat7), Some(Spi0Ce0), Some(Uart1Cts)],
+        /* 44 */ [Some(GpClk1), Some(Sda0), Some(Sda1), None, Some(Spi0Ce1), Some(SdCardVolt)],
+        /* 45 */ [Some(Pwm0Ch1), Some(Scl0), Some(Scl1), None, Some(Spi0Ce2), Some(SdCardPwr0)],
+    ]
+};
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.datasheet_name())
+    }
+}
+
+/// Get the signal carried by the specified pin when it's configured with the
+/// specified function. Returns `None` if `function` is not an alternate
+/// function or no signal is assigned.
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_pac::gpio::{alt::{self, Signal}, Function};
+/// assert_eq!(alt::signal(18, Function::Alt5), Some(Signal::Pwm0Ch0));
+/// assert_eq!(alt::signal(16, Function::Alt0), None);
+/// ```
+pub const fn signal(pin: usize, function: Function) -> Option<Signal> {
+    match function.alt_index() {
+        Some(i) if pin < TABLE_LEN => TABLE[pin][i],
+        _ => None,
+    }
+}
+
+/// Get the alternate function that routes `signal` to the specified pin.
+/// Returns `None` if the signal is not available on the pin.
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_pac::gpio::{alt::{self, Signal}, Function};
+/// assert_eq!(alt::function(14, Signal::Uart0Txd), Some(Function::Alt0));
+/// assert_eq!(alt::function(15, Signal::Uart0Txd), None);
+/// ```
+pub const fn function(pin: usize, signal: Signal) -> Option<Function> {
+    if pin >= TABLE_LEN {
+        return None;
+    }
+    let mut i = 0;
+    while i < 6 {
+        // `PartialEq::eq` is not usable in `const fn`
+        if let Some(s) = TABLE[pin][i] {
+            if s as u16 == signal as u16 {
+                return Function::alt(i);
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Iterate over the pins that can carry `signal` and the corresponding
+/// alternate functions.
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_pac::gpio::{alt::{self, Signal}, Function};
+/// assert!(alt::pins(Signal::Uart0Txd).eq([
+///     (14, Function::Alt0),
+///     (32, Function::Alt3),
+///     (36, Function::Alt2),
+/// ]));
+/// ```
+pub fn pins(signal: Signal) -> impl Iterator<Item = (usize, Function)> {
+    (0..TABLE_LEN).filter_map(move |pin| Some((pin, function(pin, signal)?)))
+}
+
+/// The error type for [`Registers::set_signal`] and
+/// [`Registers::set_signals`]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Error {
+    /// The signal is not available on the pin.
+    Unavailable {
+        /// The pin number
+        pin: usize,
+        /// The requested signal
+        signal: Signal,
+    },
+    /// The pin was assigned more than one signal.
+    Conflict {
+        /// The pin number
+        pin: usize,
+    },
+}
+
+impl Registers {
+    /// Route `signal` to the specified pin by selecting the appropriate
+    /// alternate function.
+    ///
+    /// The operation is not atomic; callers must prevent concurrent updates
+    /// to the same register.
+    pub fn set_signal(&self, pin: usize, signal: Signal) -> Result<(), Error> {
+        self.set_signals(&[(pin, signal)])
+    }
+
+    /// Route signals to pins. All assignments are validated before any of the
+    /// pins is reconfigured, so nothing is changed on failure.
+    ///
+    /// The operation is not atomic; callers must prevent concurrent updates
+    /// to the same registers.
+    pub fn set_signals(&self, assignments: &[(usize, Signal)]) -> Result<(), Error> {
+        for (i, &(pin, signal)) in assignments.iter().enumerate() {
+            function(pin, signal).ok_or(Error::Unavailable { pin, signal })?;
+            if assignments[..i]
+                .iter()
+                .any(|&(other_pin, other_signal)| other_pin == pin && other_signal != signal)
+            {
+                return Err(Error::Conflict { pin });
+            }
+        }
+        for &(pin, signal) in assignments {
+            self.set_function(pin, function(pin, signal).unwrap());
+        }
+        Ok(())
+    }
+
+    /// Get the signal currently routed to the specified pin. Returns `None`
+    /// if the pin is not configured with an alternate function or no signal
+    /// is assigned.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `pin` is outside the range `0..`[`NUM_PINS`](super::NUM_PINS).
+    pub fn signal(&self, pin: usize) -> Option<Signal> {
+        signal(pin, self.function(pin))
+    }
+
+    /// Apply the pin assignments represented by `P`, which is an [`AltPin`]
+    /// or a tuple of them. Impossible assignments are rejected at compile
+    /// time.
+    ///
+    /// The operation is not atomic; callers must prevent concurrent updates
+    /// to the same registers.
+    #[inline]
+    pub fn set_alt_pins<P: AltPins>(&self) {
+        P::apply(self);
+    }
+}
+
+/// A marker type representing a [`Signal`]
+pub trait SignalMarker {
+    /// The signal represented by `Self`
+    const SIGNAL: Signal;
+}
+
+/// A marker type representing the assignment of signal `S` to pin `PIN`.
+/// Referring to [`Self::FUNCTION`] (e.g., through
+/// [`Registers::set_alt_pins`]) fails to compile if the signal is not
+/// available on the pin.
+pub struct AltPin<const PIN: usize, S>(PhantomData<S>);
+
+impl<const PIN: usize, S: SignalMarker> AltPin<PIN, S> {
+    /// The alternate function that routes `S` to `PIN`
+    pub const FUNCTION: Function = match function(PIN, S::SIGNAL) {
+        Some(function) => function,
+        None => panic!("the signal is not available on the pin"),
+    };
+}
+
+/// A set of pin assignments. Implemented by [`AltPin`] and tuples of up to
+/// eight `AltPin`s.
+pub trait AltPins {
+    /// Apply the pin assignments.
+    fn apply(regs: &Registers);
+}
+
+impl<const PIN: usize, S: SignalMarker> AltPins for AltPin<PIN, S> {
+    #[inline]
+    fn apply(regs: &Registers) {
+        regs.set_function(PIN, Self::FUNCTION);
+    }
+}
+
+macro_rules! impl_alt_pins_for_tuple {
+    ( $( $T:ident ),* ) => {
+        impl<$( $T: AltPins ),*> AltPins for ( $( $T, )* ) {
+            #[inline]
+            fn apply(regs: &Registers) {
+                $( $T::apply(regs); )*
+            }
+        }
+    };
+}
+
+impl_alt_pins_for_tuple!(A);
+impl_alt_pins_for_tuple!(A, B);
+impl_alt_pins_for_tuple!(A, B, C);
+impl_alt_pins_for_tuple!(A, B, C, D);
+impl_alt_pins_for_tuple!(A, B, C, D, E);
+impl_alt_pins_for_tuple!(A, B, C, D, E, F);
+impl_alt_pins_for_tuple!(A, B, C, D, E, F, G);
+impl_alt_pins_for_tuple!(A, B, C, D, E, F, G, H);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim;
+    use tock_registers::interfaces::Readable;
+
+    #[test]
+    fn table() {
+        use Function::*;
+        assert_eq!(signal(14, Alt0), Some(Signal::Uart0Txd));
+        assert_eq!(signal(14, Alt5), Some(Signal::Uart1Txd));
+        assert_eq!(signal(18, Alt5), Some(Signal::Pwm0Ch0));
+        assert_eq!(signal(2, Alt0), Some(Signal::Sda1));
+        assert_eq!(signal(11, Alt0), Some(Signal::Spi0Sclk));
+        assert_eq!(signal(27, Alt5), Some(Signal::Spi6Ce1));
+        assert_eq!(signal(14, Input), None);
+        assert_eq!(signal(14, Output), None);
+        assert_eq!(signal(TABLE_LEN, Alt0), None);
+        assert_eq!(function(TABLE_LEN, Signal::Sda0), None);
+
+        // I2C3 is available on two pairs of pins
+        assert!(pins(Signal::Sda3).eq([(2, Alt5), (4, Alt5)]));
+
+        // The table and `function` are consistent
+        for (pin, row) in TABLE.iter().enumerate() {
+            for (i, s) in row.iter().enumerate() {
+                if let &Some(s) = s {
+                    assert_eq!(function(pin, s), Function::alt(i));
+                }
+            }
+        }
+
+        // Every signal is available somewhere
+        for &s in Signal::ALL {
+            assert!(pins(s).next().is_some(), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn function_field_value() {
+        for value in 0..8 {
+            let f = Function::from_field_value(value).unwrap();
+            assert_eq!(f.to_field_value(), value);
+            if let Some(i) = f.alt_index() {
+                assert_eq!(Function::alt(i), Some(f));
+            }
+        }
+        assert_eq!(Function::from_field_value(8), None);
+        assert_eq!(Function::alt(6), None);
+    }
+
+    #[test]
+    fn set_signals() {
+        let device = sim::Device::<Registers>::new();
+        let regs = device.regs();
+
+        regs.set_signals(&[(14, Signal::Uart0Txd), (15, Signal::Uart0Rxd)])
+            .unwrap();
+        assert_eq!(regs.function(14), Function::Alt0);
+        assert_eq!(regs.signal(15), Some(Signal::Uart0Rxd));
+        assert_eq!(regs.signal(16), None);
+        assert_eq!(regs.gpfsel[1].get(), 0b100 << 12 | 0b100 << 15);
+
+        // Nothing is changed on failure
+        assert_eq!(
+            regs.set_signals(&[(2, Signal::Sda1), (4, Signal::Scl1)]),
+            Err(Error::Unavailable {
+                pin: 4,
+                signal: Signal::Scl1
+            })
+        );
+        assert_eq!(
+            regs.set_signals(&[(2, Signal::Sda1), (2, Signal::Sda3)]),
+            Err(Error::Conflict { pin: 2 })
+        );
+        assert_eq!(regs.function(2), Function::Input);
+
+        regs.set_alt_pins::<(AltPin<18, signal::Pwm0Ch0>, AltPin<19, signal::Pwm0Ch1>)>();
+        assert_eq!(regs.signal(18), Some(Signal::Pwm0Ch0));
+        assert_eq!(regs.function(19), Function::Alt5);
+        assert_eq!(regs.function(14), Function::Alt0);
+    }
+}
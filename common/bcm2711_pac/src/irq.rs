@@ -0,0 +1,285 @@
+//! Interrupt IDs of the BCM2711 peripherals
+//!
+//! This module maps each peripheral instance to the GIC-400 interrupt ID
+//! (the value used by [`solid::interrupt::Number`][1]) its interrupt request
+//! signal is routed to. The VideoCore peripheral interrupts `0..64` are mapped
+//! to the GIC SPIs starting at ID [`VC_BASE`]. Some interrupt lines are shared
+//! by several instances; the handler must check the status registers of all of
+//! them.
+//!
+//! With the `solid` feature, `Irq::number` and `Irq::handler_options`
+//! produce values that can be passed to `solid::interrupt` directly:
+//!
+//! ```rust,ignore
+//! use bcm2711_pac::irq;
+//!
+//! handler.register_static(&irq::UART0.handler_options(10))?;
+//! irq::UART0.number().enable()?;
+//! ```
+//!
+//! The PWM controllers don't have interrupt outputs; use DMA to feed them.
+//!
+//! [1]: https://kyotomicrocomputer.github.io/solid-rapi4-examples/rustdoc/solid/interrupt/struct.Number.html
+
+/// The trigger type of an interrupt line
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Trigger {
+    /// The interrupt is asserted while the source condition holds and must be
+    /// cleared at the source.
+    Level,
+    /// The interrupt is signaled by a pulse.
+    Edge,
+}
+
+/// An interrupt line
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Irq {
+    /// The GIC interrupt ID
+    pub id: u32,
+    /// The trigger type the GIC should be configured with
+    pub trigger: Trigger,
+}
+
+impl Irq {
+    /// Construct a level-sensitive `Irq`.
+    #[inline]
+    pub const fn level(id: u32) -> Self {
+        Self {
+            id,
+            trigger: Trigger::Level,
+        }
+    }
+
+    /// Construct an edge-triggered `Irq`.
+    #[inline]
+    pub const fn edge(id: u32) -> Self {
+        Self {
+            id,
+            trigger: Trigger::Edge,
+        }
+    }
+
+    /// Get the `Irq` of the VideoCore peripheral interrupt `n`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `n` is outside the range `0..64`.
+    #[inline]
+    pub const fn vc(n: u32) -> Self {
+        assert!(n < 64);
+        Self::level(VC_BASE + n)
+    }
+
+    /// Check if this is a Private Peripheral Interrupt (banked per
+    /// processor).
+    #[inline]
+    pub const fn is_ppi(self) -> bool {
+        self.id >= 16 && self.id < 32
+    }
+
+    /// Get the interrupt number for `solid::interrupt`.
+    #[cfg(feature = "solid")]
+    #[inline]
+    pub const fn number(self) -> solid::interrupt::Number {
+        solid::interrupt::Number(self.id as i32)
+    }
+
+    /// Construct a `solid::interrupt::HandlerOptions` for this interrupt line
+    /// with the specified priority and the appropriate trigger type.
+    #[cfg(feature = "solid")]
+    #[inline]
+    pub const fn handler_options(self, priority: i32) -> solid::interrupt::HandlerOptions {
+        let options = solid::interrupt::HandlerOptions::new(self.number(), priority);
+        match self.trigger {
+            Trigger::Level => options.with_level_triggered(),
+            Trigger::Edge => options.with_edge_triggered(),
+        }
+    }
+}
+
+/// The GIC interrupt ID of the ARMC interrupt `0`
+pub const ARMC_BASE: u32 = 64;
+
+/// The GIC interrupt ID of the VideoCore peripheral interrupt `0`
+pub const VC_BASE: u32 = 96;
+
+// ARM core-local interrupts (PPIs)
+// ----------------------------------------------------------------------------
+
+/// ARM generic timer: hypervisor physical timer
+pub const CNTHP: Irq = Irq::level(26);
+/// ARM generic timer: virtual timer
+pub const CNTV: Irq = Irq::level(27);
+/// ARM generic timer: secure physical timer
+pub const CNTPS: Irq = Irq::level(29);
+/// ARM generic timer: non-secure physical timer
+pub const CNTPNS: Irq = Irq::level(30);
+
+/// Performance monitor unit of each core
+pub const PMU: [Irq; 4] = [
+    Irq::level(48),
+    Irq::level(49),
+    Irq::level(50),
+    Irq::level(51),
+];
+
+// ARMC interrupts
+// ----------------------------------------------------------------------------
+
+/// ARM timer ([AP804](crate::ap804))
+pub const ARM_TIMER: Irq = Irq::level(ARMC_BASE);
+/// ARM mailbox ([`mbox`](crate::mbox))
+pub const ARM_MAILBOX: Irq = Irq::level(ARMC_BASE + 1);
+/// ARM doorbell 0
+pub const ARM_DOORBELL_0: Irq = Irq::level(ARMC_BASE + 2);
+/// ARM doorbell 1
+pub const ARM_DOORBELL_1: Irq = Irq::level(ARMC_BASE + 3);
+/// VPU0 halted
+pub const VPU0_HALTED: Irq = Irq::level(ARMC_BASE + 4);
+/// VPU1 halted
+pub const VPU1_HALTED: Irq = Irq::level(ARMC_BASE + 5);
+/// ARM address error
+pub const ARM_ADDRESS_ERROR: Irq = Irq::level(ARMC_BASE + 6);
+/// ARM AXI error
+pub const ARM_AXI_ERROR: Irq = Irq::level(ARMC_BASE + 7);
+/// Software-triggered interrupts `0..8`
+pub const SWIRQ: [Irq; 8] = [
+    Irq::level(ARMC_BASE + 8),
+    Irq::level(ARMC_BASE + 9),
+    Irq::level(ARMC_BASE + 10),
+    Irq::level(ARMC_BASE + 11),
+    Irq::level(ARMC_BASE + 12),
+    Irq::level(ARMC_BASE + 13),
+    Irq::level(ARMC_BASE + 14),
+    Irq::level(ARMC_BASE + 15),
+];
+
+// VideoCore peripheral interrupts
+// ----------------------------------------------------------------------------
+
+/// [System timer](crate::sys_timer) compare channels `0..4`. Channels 0 and 2
+/// are used by the VideoCore firmware.
+pub const SYSTEM_TIMER: [Irq; 4] = [Irq::vc(0), Irq::vc(1), Irq::vc(2), Irq::vc(3)];
+
+/// USB (DWC OTG)
+pub const USB: Irq = Irq::vc(9);
+
+/// [DMA](crate::dmac) channels `0..16`. Channels 7 and 8 share a line, as do
+/// channels 9 and 10.
+pub const DMA: [Irq; 16] = [
+    Irq::vc(16),
+    Irq::vc(17),
+    Irq::vc(18),
+    Irq::vc(19),
+    Irq::vc(20),
+    Irq::vc(21),
+    Irq::vc(22),
+    Irq::vc(23),
+    Irq::vc(23),
+    Irq::vc(24),
+    Irq::vc(24),
+    Irq::vc(25),
+    Irq::vc(26),
+    Irq::vc(27),
+    Irq::vc(28),
+    Irq::vc(31),
+];
+
+/// [Auxiliary peripherals](crate::aux) (Mini UART, SPI1, and SPI2). Use
+/// [`AUX_IRQ`](crate::aux::Registers::aux_irq) to tell the sources apart.
+pub const AUX: Irq = Irq::vc(29);
+/// Mini UART (UART1). Shared with SPI1 and SPI2.
+pub const UART1: Irq = AUX;
+/// SPI1. Shared with the Mini UART and SPI2.
+pub const SPI1: Irq = AUX;
+/// SPI2. Shared with the Mini UART and SPI1.
+pub const SPI2: Irq = AUX;
+
+/// HDMI CEC
+pub const HDMI_CEC: Irq = Irq::vc(32);
+/// HVS (Hardware Video Scaler)
+pub const HVS: Irq = Irq::vc(33);
+/// DSI0
+pub const DSI0: Irq = Irq::vc(36);
+/// Pixel valve 2
+pub const PIXEL_VALVE_2: Irq = Irq::vc(37);
+/// CSI camera 0
+pub const CSI0: Irq = Irq::vc(38);
+/// CSI camera 1
+pub const CSI1: Irq = Irq::vc(39);
+/// HDMI0
+pub const HDMI0: Irq = Irq::vc(40);
+/// HDMI1
+pub const HDMI1: Irq = Irq::vc(41);
+/// Pixel valve 3
+pub const PIXEL_VALVE_3: Irq = Irq::vc(42);
+/// [BSC/SPI slave](crate::bsc_slave)
+pub const BSC_SLAVE: Irq = Irq::vc(43);
+/// DSI1
+pub const DSI1: Irq = Irq::vc(44);
+/// Pixel valve 0
+pub const PIXEL_VALVE_0: Irq = Irq::vc(45);
+/// Pixel valves 1 and 4
+pub const PIXEL_VALVE_1_4: Irq = Irq::vc(46);
+/// SMI
+pub const SMI: Irq = Irq::vc(48);
+
+/// [GPIO](crate::gpio) interrupts. The first three correspond to banks 0
+/// (pins 0–27), 1 (pins 28–45), and 2 (pins 46–57). The fourth one is
+/// asserted for an event on any pin.
+pub const GPIO: [Irq; 4] = [Irq::vc(49), Irq::vc(50), Irq::vc(51), Irq::vc(52)];
+
+/// [BSC](crate::bsc) master controllers. Shared by all instances.
+pub const I2C: Irq = Irq::vc(53);
+/// BSC0. Shared with the other BSC master controllers.
+pub const BSC0: Irq = I2C;
+/// BSC1. Shared with the other BSC master controllers.
+pub const BSC1: Irq = I2C;
+/// BSC3. Shared with the other BSC master controllers.
+pub const BSC3: Irq = I2C;
+/// BSC4. Shared with the other BSC master controllers.
+pub const BSC4: Irq = I2C;
+/// BSC5. Shared with the other BSC master controllers.
+pub const BSC5: Irq = I2C;
+/// BSC6. Shared with the other BSC master controllers.
+pub const BSC6: Irq = I2C;
+
+/// [SPI](crate::spi) master controllers. Shared by all instances.
+pub const SPI: Irq = Irq::vc(54);
+/// SPI0. Shared with the other SPI master controllers.
+pub const SPI0: Irq = SPI;
+/// SPI3. Shared with the other SPI master controllers.
+pub const SPI3: Irq = SPI;
+/// SPI4. Shared with the other SPI master controllers.
+pub const SPI4: Irq = SPI;
+/// SPI5. Shared with the other SPI master controllers.
+pub const SPI5: Irq = SPI;
+/// SPI6. Shared with the other SPI master controllers.
+pub const SPI6: Irq = SPI;
+
+/// [PCM/I2S](crate::pcm)
+pub const PCM: Irq = Irq::vc(55);
+/// SDHOST
+pub const SDHOST: Irq = Irq::vc(56);
+
+/// [PL011 UARTs](crate::pl011). Shared by all instances.
+pub const PL011: Irq = Irq::vc(57);
+/// UART0. Shared with the other PL011 UARTs.
+pub const UART0: Irq = PL011;
+/// UART2. Shared with the other PL011 UARTs.
+pub const UART2: Irq = PL011;
+/// UART3. Shared with the other PL011 UARTs.
+pub const UART3: Irq = PL011;
+/// UART4. Shared with the other PL011 UARTs.
+pub const UART4: Irq = PL011;
+/// UART5. Shared with the other PL011 UARTs.
+pub const UART5: Irq = PL011;
+
+/// VEC (composite video)
+pub const VEC: Irq = Irq::vc(59);
+/// [RNG200](crate::rng200)
+pub const RNG: Irq = Irq::vc(61);
+/// EMMC and [EMMC2](crate::emmc2). Shared by both instances.
+pub const EMMC: Irq = Irq::vc(62);
+/// EMMC2. Shared with EMMC.
+pub const EMMC2: Irq = EMMC;
@@ -2,19 +2,36 @@
 #![no_std]
 mod bus;
 mod field;
+mod registers;
 pub use {bus::*, field::*};
 
 pub mod ap804;
+pub mod arm_local;
+pub mod armc;
+pub mod avs;
 // `aux.rs` breaks some tools on Windows
 // https://msdn.microsoft.com/en-us/library/aa365247(v=vs.85).aspx#file_and_directory_names
 #[path = "aux_.rs"]
 pub mod aux;
 pub mod bsc;
+pub mod bsc_slave;
+pub mod cm;
+pub mod describe;
 pub mod dmac;
+pub mod emmc2;
+pub mod fdt;
+pub mod gic400;
 pub mod gpio;
+pub mod irq;
 pub mod mbox;
 pub mod pcm;
 pub mod pl011;
+pub mod pm;
 pub mod pwm;
+pub mod rng200;
+#[cfg(any(test, feature = "sim"))]
+pub mod sim;
+pub mod snapshot;
 pub mod spi;
 pub mod sys_timer;
+pub mod vcmbox;
@@ -1,13 +1,16 @@
 //! [BCM2711 ARM Mailboxes][1]
 //!
 //! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A166%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
-use tock_registers::{
-    register_structs,
+use tock_registers::register_structs;
+
+use crate::{
     registers::{ReadWrite, WriteOnly},
+    snapshot::impl_snapshot,
 };
 
 /// The low-peripheral ARM physical address of [the ARM Mailboxes register
-/// block](Registers).
+/// block](Registers). This register block is a part of [the ARM local
+/// peripheral register block](crate::arm_local::Registers).
 pub const BASE_ARM_PA: u64 = 0xff80_0080;
 
 register_structs! {
@@ -19,3 +22,10 @@ register_structs! {
         (0x80 => @END),
     }
 }
+
+impl_snapshot! {
+    Registers {
+        mbox_set,
+        mbox_clr,
+    }
+}
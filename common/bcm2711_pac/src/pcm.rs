@@ -1,9 +1,14 @@
 //! [BCM2711 PCM / I2S Audio][1]
 //!
 //! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A115%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
-use tock_registers::{register_bitfields, register_structs, registers::ReadWrite};
+use tock_registers::register_structs;
 
-use crate::Vpa;
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::ReadWrite,
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
+};
 
 /// The base address of [the PCM register block](Registers).
 pub const BASE: Vpa = Vpa(0x4_7e20_3000);
@@ -23,6 +28,32 @@ register_structs! {
     }
 }
 
+impl_snapshot! {
+    Registers {
+        cs_a,
+        // Reading `FIFO_A` pops the receive FIFO
+        fifo_a: skip,
+        mode_a,
+        rxc_a,
+        txc_a,
+        dreq_a,
+        inten_a,
+        intstc_a,
+        gray,
+    }
+}
+
+/// Describe [the PCM register block](Registers) and its instance. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "PCM",
+        "PCM / I2S audio",
+        &[Instance::vpa("PCM", BASE)],
+    )];
+    PERIPHERALS
+}
+
 register_bitfields! {u32,
     pub CS_A [
         /// Enable the PCM audio interface
@@ -1,13 +1,15 @@
 //! [PL011 UART][1]
 //!
 //! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A147%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
-use tock_registers::{
-    register_bitfields, register_structs,
+use tock_registers::register_structs;
+
+use crate::{
+    describe::{Instance, Peripheral},
     registers::{ReadOnly, ReadWrite, WriteOnly},
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
 };
 
-use crate::Vpa;
-
 /// The base address of the UART0 instance of [the PL011 register block](Registers).
 pub const BASE_UART0: Vpa = Vpa(0x4_7e20_1000);
 /// The base address of the UART2 instance of [the PL011 register block](Registers).
@@ -62,6 +64,46 @@ register_structs! {
     }
 }
 
+impl_snapshot! {
+    Registers {
+        // Reading `DR` pops the receive FIFO
+        dr: skip,
+        rsrecr,
+        fr,
+        ibrd,
+        fbrd,
+        lcrh,
+        cr,
+        ifls,
+        imsc,
+        ris,
+        mis,
+        icr,
+        dmacr,
+        itcr,
+        itip,
+        itop,
+        tdr,
+    }
+}
+
+/// Describe [the PL011 register block](Registers) and its instances. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "PL011",
+        "PL011 UART",
+        &[
+            Instance::vpa("UART0", BASE_UART0),
+            Instance::vpa("UART2", BASE_UART2),
+            Instance::vpa("UART3", BASE_UART3),
+            Instance::vpa("UART4", BASE_UART4),
+            Instance::vpa("UART5", BASE_UART5),
+        ],
+    )];
+    PERIPHERALS
+}
+
 register_bitfields! {u32,
     pub DR [
         /// Receive/transmit data
@@ -0,0 +1,459 @@
+//! BCM2711 Power Manager (watchdog and reset control)
+//!
+//! The Power Manager is not documented in the BCM2711 datasheet. The register
+//! definitions in this module are based on the Linux `bcm2835_wdt` driver.
+//!
+//! Every register in this block can only be written when the upper eight bits
+//! of the written value are [`PASSWD`]. [`Registers`] provides methods that
+//! take care of this. [`Watchdog`] is a driver built on top of them.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use bcm2711_pac::pm;
+//! use core::time::Duration;
+//!
+//! static WATCHDOG: pm::Watchdog =
+//!     unsafe { pm::Watchdog::new(pm::BASE_ARM_PA as usize as *const pm::Registers) };
+//! static HEARTBEAT: pm::Heartbeat = pm::Heartbeat::new();
+//!
+//! WATCHDOG.start(Duration::from_secs(5)).unwrap();
+//!
+//! // Call this from a timer handler periodically, e.g., by passing
+//! // `auto_feed` to `solid::timer::Timer::new` (requires the `solid`
+//! // feature)
+//! let mut auto_feed = pm::AutoFeed::new(&WATCHDOG, &HEARTBEAT, 10);
+//! auto_feed.tick();
+//!
+//! // Call this from the supervised task periodically
+//! HEARTBEAT.beat();
+//! ```
+use core::{
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
+use tock_registers::{
+    fields::FieldValue,
+    interfaces::{Readable, Writeable},
+    register_structs,
+};
+
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::ReadWrite,
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
+};
+
+/// The base address of [the Power Manager register block](Registers).
+pub const BASE: Vpa = Vpa(0x4_7e10_0000);
+
+/// The low-peripheral ARM physical address of [`BASE`], usable in constant
+/// contexts.
+pub const BASE_ARM_PA: u64 = 0xfe10_0000;
+
+/// The password that must be present in bits `24..32` of every value written
+/// to the Power Manager registers.
+pub const PASSWD: u32 = 0x5a << 24;
+
+/// The frequency of the watchdog counter
+/// ([`WDOG::TIME_SET`](const@WDOG::TIME_SET)).
+pub const WDOG_TICKS_PER_SEC: u32 = 1 << 16;
+
+/// The maximum value of [`WDOG::TIME_SET`](const@WDOG::TIME_SET). This
+/// corresponds to a time-out of almost 16 seconds.
+pub const WDOG_TICKS_MAX: u32 = 0xf_ffff;
+
+/// The boot partition number instructing the firmware to halt the system
+/// instead of booting it.
+pub const PARTITION_HALT: u8 = 63;
+
+register_structs! {
+    pub Registers {
+        (0x00 => _pad0),
+        /// Reset control
+        (0x1c => pub rstc: ReadWrite<u32, RSTC::Register>),
+        /// Reset status
+        (0x20 => pub rsts: ReadWrite<u32, RSTS::Register>),
+        /// Watchdog timer
+        (0x24 => pub wdog: ReadWrite<u32, WDOG::Register>),
+        (0x28 => @END),
+    }
+}
+
+impl_snapshot! {
+    Registers {
+        rstc,
+        rsts,
+        wdog,
+    }
+}
+
+/// Describe [the Power Manager register block](Registers) and its instance. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "PM",
+        "Power Manager (watchdog and reset control)",
+        &[Instance::vpa("PM", BASE)],
+    )];
+    PERIPHERALS
+}
+
+register_bitfields! {u32,
+    pub RSTC [
+        /// The action to perform when the watchdog timer expires
+        WRCFG OFFSET(4) NUMBITS(2) [
+            Disabled = 0b00,
+            FullReset = 0b10,
+        ],
+        /// Password
+        PASSWD OFFSET(24) NUMBITS(8) [
+            Passwd = 0x5a,
+        ],
+    ]
+}
+
+register_bitfields! {u32,
+    pub RSTS [
+        /// Reset status. The firmware uses the even-numbered bits of this
+        /// field to hold the boot partition number. See
+        /// [`encode_partition`](super::encode_partition).
+        RSTS OFFSET(0) NUMBITS(12) [],
+        /// Password
+        PASSWD OFFSET(24) NUMBITS(8) [
+            Passwd = 0x5a,
+        ],
+    ]
+}
+
+register_bitfields! {u32,
+    pub WDOG [
+        /// The remaining time until the watchdog timer expires, in the units
+        /// of 1/[`WDOG_TICKS_PER_SEC`](super::WDOG_TICKS_PER_SEC) seconds
+        TIME_SET OFFSET(0) NUMBITS(20) [],
+        /// Password
+        PASSWD OFFSET(24) NUMBITS(8) [
+            Passwd = 0x5a,
+        ],
+    ]
+}
+
+/// Replace the upper eight bits of `value` with [`PASSWD`].
+#[inline]
+const fn with_passwd(value: u32) -> u32 {
+    (value & !(0xff << 24)) | PASSWD
+}
+
+impl Registers {
+    /// Write `value` to [`Self::rstc`] with the password.
+    #[inline]
+    pub fn write_rstc(&self, value: FieldValue<u32, RSTC::Register>) {
+        self.rstc.set(with_passwd(value.value));
+    }
+
+    /// Modify [`Self::rstc`] with the password.
+    #[inline]
+    pub fn modify_rstc(&self, value: FieldValue<u32, RSTC::Register>) {
+        self.rstc.set(with_passwd(value.modify(self.rstc.get())));
+    }
+
+    /// Modify [`Self::rsts`] with the password.
+    #[inline]
+    pub fn modify_rsts(&self, value: FieldValue<u32, RSTS::Register>) {
+        self.rsts.set(with_passwd(value.modify(self.rsts.get())));
+    }
+
+    /// Write `value` to [`Self::wdog`] with the password.
+    #[inline]
+    pub fn write_wdog(&self, value: FieldValue<u32, WDOG::Register>) {
+        self.wdog.set(with_passwd(value.value));
+    }
+}
+
+/// Encode a boot partition number in the format stored in
+/// [`RSTS::RSTS`](const@RSTS::RSTS).
+///
+/// # Example
+///
+/// ```rust
+/// use bcm2711_pac::pm::{decode_partition, encode_partition, PARTITION_HALT};
+/// assert_eq!(encode_partition(PARTITION_HALT), 0x555);
+/// assert_eq!(decode_partition(encode_partition(42)), 42);
+/// ```
+#[inline]
+pub const fn encode_partition(partition: u8) -> u32 {
+    let mut out = 0;
+    let mut i = 0;
+    while i < 6 {
+        out |= ((partition as u32 >> i) & 1) << (i * 2);
+        i += 1;
+    }
+    out
+}
+
+/// Decode a boot partition number from the value of
+/// [`RSTS::RSTS`](const@RSTS::RSTS).
+#[inline]
+pub const fn decode_partition(rsts: u32) -> u8 {
+    let mut out = 0;
+    let mut i = 0;
+    while i < 6 {
+        out |= ((rsts >> (i * 2)) & 1) << i;
+        i += 1;
+    }
+    out as u8
+}
+
+/// The error type for [`Watchdog::start`].
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum StartError {
+    /// The time-out is zero or longer than the hardware supports
+    /// ([`WDOG_TICKS_MAX`]).
+    BadTimeout,
+}
+
+/// A driver for the watchdog timer of the Power Manager.
+///
+/// All methods take `&self` so that a `Watchdog` can be placed in a `static`
+/// and fed from both tasks and interrupt handlers.
+pub struct Watchdog {
+    regs: *const Registers,
+    /// The time-out in watchdog ticks, reloaded by [`Self::feed`]
+    timeout_ticks: AtomicU32,
+}
+
+// Safety: Every operation is a single read or write of a memory-mapped
+// register or an atomic access, so concurrent calls can't cause data races on
+// memory. (The hardware serializes concurrent register accesses.)
+unsafe impl Send for Watchdog {}
+unsafe impl Sync for Watchdog {}
+
+impl Watchdog {
+    /// Construct a `Watchdog`.
+    ///
+    /// # Safety
+    ///
+    /// `regs` must point to [the Power Manager register block](Registers)
+    /// and remain valid for the lifetime of the constructed `Watchdog`.
+    #[inline]
+    pub const unsafe fn new(regs: *const Registers) -> Self {
+        Self {
+            regs,
+            timeout_ticks: AtomicU32::new(0),
+        }
+    }
+
+    #[inline]
+    fn regs(&self) -> &Registers {
+        // Safety: Upheld by the caller of `Self::new`
+        unsafe { &*self.regs }
+    }
+
+    /// Start the watchdog timer with the specified time-out. The system is
+    /// reset unless [`Self::feed`] is called within `timeout`.
+    pub fn start(&self, timeout: Duration) -> Result<(), StartError> {
+        let ticks = timeout.as_micros() * WDOG_TICKS_PER_SEC as u128 / 1_000_000;
+        if ticks == 0 || ticks > WDOG_TICKS_MAX as u128 {
+            return Err(StartError::BadTimeout);
+        }
+        let ticks = ticks as u32;
+        self.timeout_ticks.store(ticks, Ordering::Relaxed);
+
+        let regs = self.regs();
+        regs.write_wdog(WDOG::TIME_SET.val(ticks));
+        regs.modify_rstc(RSTC::WRCFG::FullReset);
+        Ok(())
+    }
+
+    /// Reload the watchdog timer with the time-out given to the last call to
+    /// [`Self::start`]. Does nothing if the watchdog has never been started.
+    #[inline]
+    pub fn feed(&self) {
+        let ticks = self.timeout_ticks.load(Ordering::Relaxed);
+        if ticks != 0 {
+            self.regs().write_wdog(WDOG::TIME_SET.val(ticks));
+        }
+    }
+
+    /// Stop the watchdog timer.
+    #[inline]
+    pub fn stop(&self) {
+        self.regs().modify_rstc(RSTC::WRCFG::Disabled);
+    }
+
+    /// Check if the watchdog timer is running.
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.regs().rstc.matches_all(RSTC::WRCFG::FullReset)
+    }
+
+    /// Get the remaining time until the watchdog timer expires.
+    #[inline]
+    pub fn remaining(&self) -> Duration {
+        let ticks = self.regs().wdog.read(WDOG::TIME_SET);
+        Duration::from_micros(ticks as u64 * 1_000_000 / WDOG_TICKS_PER_SEC as u64)
+    }
+
+    /// Get the boot partition number the system was booted from (or will
+    /// be booted from after a reset).
+    #[inline]
+    pub fn partition(&self) -> u8 {
+        decode_partition(self.regs().rsts.read(RSTS::RSTS))
+    }
+
+    /// Reset the system immediately and instruct the firmware to boot from
+    /// the specified partition. Pass [`PARTITION_HALT`] to halt the system
+    /// instead.
+    pub fn reboot(&self, partition: u8) -> ! {
+        let regs = self.regs();
+        // Replace the partition number (the even-numbered bits) and preserve
+        // the rest of the reset status
+        let rsts = regs.rsts.get() & !encode_partition(PARTITION_HALT);
+        regs.rsts
+            .set(with_passwd(rsts | encode_partition(partition & 0x3f)));
+        regs.write_wdog(WDOG::TIME_SET.val(10));
+        regs.modify_rstc(RSTC::WRCFG::FullReset);
+        loop {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// A progress counter updated by a supervised task and monitored by
+/// [`AutoFeed`].
+pub struct Heartbeat {
+    count: AtomicU32,
+}
+
+impl Heartbeat {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            count: AtomicU32::new(0),
+        }
+    }
+
+    /// Indicate that the supervised task is making progress.
+    #[inline]
+    pub fn beat(&self) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get the number of calls to [`Self::beat`] so far (wrapping).
+    #[inline]
+    pub fn count(&self) -> u32 {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for Heartbeat {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The result of [`AutoFeed::tick`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum FeedStatus {
+    /// The supervised task made progress since the last tick. The watchdog
+    /// was fed.
+    Fed,
+    /// The supervised task hasn't made progress for the specified number of
+    /// ticks, which is still within the allowance. The watchdog was fed.
+    Late(u32),
+    /// The supervised task hasn't made progress for longer than the
+    /// allowance. The watchdog was not fed and will reset the system.
+    Stalled,
+}
+
+/// Feeds a [`Watchdog`] from a periodic timer handler as long as a
+/// supervised task keeps calling [`Heartbeat::beat`].
+///
+/// This distinguishes two failure modes:
+///
+///  - **The timer interrupt is dead** (e.g., interrupts are disabled
+///    forever): nobody calls [`Self::tick`], and the watchdog resets the
+///    system silently.
+///
+///  - **The supervised task is stalled** (e.g., deadlocked): [`Self::tick`]
+///    keeps running, notices the lack of progress, reports
+///    [`FeedStatus::Stalled`] (and calls the stall hook, if any), and stops
+///    feeding the watchdog, which then resets the system.
+///
+/// With the `solid` feature, `AutoFeed<'static>` can be used directly as the
+/// handler of a `solid::timer::Timer`.
+pub struct AutoFeed<'a> {
+    watchdog: &'a Watchdog,
+    heartbeat: &'a Heartbeat,
+    /// The number of ticks without progress tolerated
+    allowance: u32,
+    last_count: u32,
+    missed: u32,
+    on_stall: Option<fn()>,
+}
+
+impl<'a> AutoFeed<'a> {
+    /// Construct an `AutoFeed`. The supervised task is considered stalled if
+    /// it doesn't call [`Heartbeat::beat`] for `allowance` consecutive ticks.
+    #[inline]
+    pub fn new(watchdog: &'a Watchdog, heartbeat: &'a Heartbeat, allowance: u32) -> Self {
+        Self {
+            watchdog,
+            heartbeat,
+            allowance,
+            last_count: heartbeat.count(),
+            missed: 0,
+            on_stall: None,
+        }
+    }
+
+    /// Set a function to be called (from the timer handler) once when the
+    /// supervised task is found stalled, e.g., to log the incident before
+    /// the watchdog resets the system.
+    #[inline]
+    pub fn with_stall_hook(self, on_stall: fn()) -> Self {
+        Self {
+            on_stall: Some(on_stall),
+            ..self
+        }
+    }
+
+    /// Check the heartbeat and feed the watchdog if the supervised task is
+    /// alive. Call this periodically with an interval shorter than the
+    /// watchdog time-out.
+    pub fn tick(&mut self) -> FeedStatus {
+        let count = self.heartbeat.count();
+        if count != self.last_count {
+            self.last_count = count;
+            self.missed = 0;
+            self.watchdog.feed();
+            return FeedStatus::Fed;
+        }
+
+        if self.missed < self.allowance {
+            self.missed += 1;
+            self.watchdog.feed();
+            return FeedStatus::Late(self.missed);
+        }
+
+        if self.missed == self.allowance {
+            self.missed += 1;
+            if let Some(on_stall) = self.on_stall {
+                on_stall();
+            }
+        }
+        FeedStatus::Stalled
+    }
+}
+
+#[cfg(feature = "solid")]
+impl<'a> solid::closure::FuncMut<(solid::thread::CpuCx<'a>,)> for AutoFeed<'static> {
+    type Output = ();
+
+    #[inline]
+    fn call(&mut self, _: (solid::thread::CpuCx<'a>,)) {
+        self.tick();
+    }
+}
@@ -1,13 +1,15 @@
 //! [BCM2711 PWM][1]
 //!
 //! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A130%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
-use tock_registers::{
-    register_bitfields, register_structs,
+use tock_registers::register_structs;
+
+use crate::{
+    describe::{Instance, Peripheral},
     registers::{ReadWrite, WriteOnly},
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
 };
 
-use crate::Vpa;
-
 /// The base address of the PWM0 instance of [the PWM register block](Registers).
 pub const BASE_PWM0: Vpa = Vpa(0x4_7e20_c000);
 /// The base address of the PWM1 instance of [the PWM register block](Registers).
@@ -37,6 +39,33 @@ register_structs! {
     }
 }
 
+impl_snapshot! {
+    Registers {
+        ctl,
+        sta,
+        dmac,
+        rng1,
+        dat1,
+        fif1,
+        rng2,
+        dat2,
+    }
+}
+
+/// Describe [the PWM register block](Registers) and its instances. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "PWM",
+        "PWM",
+        &[
+            Instance::vpa("PWM0", BASE_PWM0),
+            Instance::vpa("PWM1", BASE_PWM1),
+        ],
+    )];
+    PERIPHERALS
+}
+
 register_bitfields! {u32,
     pub CTL [
         /// Channel 1 enable
@@ -0,0 +1,11 @@
+//! The register types used by the register blocks
+//!
+//! These are the types of `tock_registers`, which access the memory directly.
+//! With the `sim` feature (and in the unit tests of this crate), they are
+//! replaced with [drop-in replacements](crate::sim::registers) that redirect
+//! accesses to [simulated devices](crate::sim).
+#[cfg(not(any(test, feature = "sim")))]
+pub use tock_registers::registers::{ReadOnly, ReadWrite, WriteOnly};
+
+#[cfg(any(test, feature = "sim"))]
+pub use crate::sim::registers::{ReadOnly, ReadWrite, WriteOnly};
@@ -0,0 +1,293 @@
+//! BCM2711 RNG200 hardware random number generator
+//!
+//! The RNG200 is not documented in the BCM2711 datasheet. The register
+//! definitions in this module are based on the Linux `iproc-rng200` driver.
+//!
+//! [`Rng200`] is a driver implementing [`rand_core::RngCore`] and
+//! [`rand_core::CryptoRng`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use bcm2711_pac::rng200;
+//! use rand_core::RngCore;
+//!
+//! let regs = unsafe {
+//!     &*(rng200::BASE.to_arm_pa().unwrap() as usize as *const rng200::Registers)
+//! };
+//! let mut rng = rng200::Rng200::new(regs);
+//! rng.init();
+//!
+//! let mut seed = [0u8; 32];
+//! rng.try_fill_bytes(&mut seed).unwrap();
+//! ```
+use rand_core::{CryptoRng, RngCore};
+use tock_registers::{
+    interfaces::{ReadWriteable, Readable, Writeable},
+    register_structs,
+};
+
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::{ReadOnly, ReadWrite},
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
+};
+
+/// The base address of [the RNG200 register block](Registers).
+pub const BASE: Vpa = Vpa(0x4_7e10_4000);
+
+/// The number of bits discarded by the hardware after it's enabled because
+/// the initial output has low entropy.
+pub const WARM_UP_BITS: u32 = 0x40000;
+
+register_structs! {
+    pub Registers {
+        /// Control
+        (0x00 => pub ctrl: ReadWrite<u32, CTRL::Register>),
+        /// RNG soft reset
+        (0x04 => pub rng_soft_reset: ReadWrite<u32, SOFT_RESET::Register>),
+        /// RBG soft reset
+        (0x08 => pub rbg_soft_reset: ReadWrite<u32, SOFT_RESET::Register>),
+        /// The total number of bits generated since the RNG was enabled (RO)
+        (0x0c => pub rng_total_bit_count: ReadOnly<u32>),
+        /// The number of bits to discard after the RNG is enabled
+        (0x10 => pub rng_total_bit_count_threshold: ReadWrite<u32>),
+        (0x14 => _pad0),
+        /// Interrupt status (W1C)
+        (0x18 => pub int_status: ReadWrite<u32, INT::Register>),
+        /// Interrupt enable
+        (0x1c => pub int_enable: ReadWrite<u32, INT::Register>),
+        /// FIFO data (RO). Reading this register pops a word from the FIFO.
+        (0x20 => pub fifo_data: ReadOnly<u32>),
+        /// FIFO count
+        (0x24 => pub fifo_count: ReadWrite<u32, FIFO_COUNT::Register>),
+        (0x28 => @END),
+    }
+}
+
+impl_snapshot! {
+    Registers {
+        ctrl,
+        rng_soft_reset,
+        rbg_soft_reset,
+        rng_total_bit_count,
+        rng_total_bit_count_threshold,
+        int_status,
+        int_enable,
+        // Reading `FIFO_DATA` consumes random numbers
+        fifo_data: skip,
+        fifo_count,
+    }
+}
+
+/// Describe [the RNG200 register block](Registers) and its instance. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "RNG200",
+        "RNG200 hardware random number generator",
+        &[Instance::vpa("RNG200", BASE)],
+    )];
+    PERIPHERALS
+}
+
+register_bitfields! {u32,
+    pub CTRL [
+        /// Random bit generator enable. All bits must be set to enable the
+        /// generator.
+        RBGEN OFFSET(0) NUMBITS(13) [
+            Disable = 0,
+            Enable = 0x1fff,
+        ],
+        /// Sample clock divider
+        DIV_CTRL OFFSET(13) NUMBITS(4) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub SOFT_RESET [
+        /// Hold the block in reset
+        RESET OFFSET(0) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub INT [
+        /// [`Registers::rng_total_bit_count`] reached
+        /// [`Registers::rng_total_bit_count_threshold`]
+        TOTAL_BITS_COUNT OFFSET(0) NUMBITS(1) [],
+        /// The NIST statistical test failed
+        NIST_FAIL OFFSET(5) NUMBITS(1) [],
+        /// The start-up transition count was met
+        STARTUP_TRANSITIONS_MET OFFSET(17) NUMBITS(1) [],
+        /// The generator is locked up and must be reset
+        MASTER_FAIL_LOCKUP OFFSET(31) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub FIFO_COUNT [
+        /// The number of words in the FIFO (RO)
+        COUNT OFFSET(0) NUMBITS(8) [],
+        /// The FIFO level at which the FIFO full interrupt is raised
+        THRESHOLD OFFSET(8) NUMBITS(8) [],
+    ]
+}
+
+/// The error code reported through [`rand_core::Error`] when the generator
+/// failed and had to be reset.
+pub const ERROR_CODE_FAILED: u32 = rand_core::Error::CUSTOM_START;
+
+/// A driver for the RNG200 hardware random number generator.
+pub struct Rng200<'a> {
+    regs: &'a Registers,
+}
+
+impl<'a> Rng200<'a> {
+    /// Construct a `Rng200`. Call [`Self::init`] before using it.
+    #[inline]
+    pub const fn new(regs: &'a Registers) -> Self {
+        Self { regs }
+    }
+
+    /// Get the underlying register block.
+    #[inline]
+    pub const fn regs(&self) -> &'a Registers {
+        self.regs
+    }
+
+    /// Enable the generator if it's not enabled yet (the firmware may have
+    /// done so already).
+    pub fn init(&mut self) {
+        let regs = self.regs;
+        if regs.ctrl.read(CTRL::RBGEN) != 0 {
+            return;
+        }
+
+        // Discard the initial low-entropy output
+        regs.rng_total_bit_count_threshold.set(WARM_UP_BITS);
+        regs.fifo_count.write(FIFO_COUNT::THRESHOLD.val(2));
+        // Enable the generator (1 MHz sample rate)
+        regs.ctrl.write(CTRL::RBGEN::Enable + CTRL::DIV_CTRL.val(3));
+    }
+
+    /// Check if the warm-up period has elapsed.
+    #[inline]
+    pub fn is_warmed_up(&self) -> bool {
+        self.regs.rng_total_bit_count.get() > 16
+    }
+
+    /// Get the number of words currently available in the FIFO.
+    #[inline]
+    pub fn available(&self) -> usize {
+        self.regs.fifo_count.read(FIFO_COUNT::COUNT) as usize
+    }
+
+    /// Discard the contents of the FIFO.
+    pub fn drain(&mut self) {
+        for _ in 0..self.available() {
+            self.regs.fifo_data.get();
+        }
+    }
+
+    /// Check the generator's health, resetting it if it has failed.
+    /// Returns `false` if a failure was detected.
+    pub fn check(&mut self) -> bool {
+        let status = self.regs.int_status.extract();
+        if !status.is_set(INT::MASTER_FAIL_LOCKUP) && !status.is_set(INT::NIST_FAIL) {
+            return true;
+        }
+
+        self.reset();
+        false
+    }
+
+    /// Reset the generator and discard its output.
+    pub fn reset(&mut self) {
+        let regs = self.regs;
+        regs.ctrl.modify(CTRL::RBGEN::Disable);
+
+        regs.rbg_soft_reset.write(SOFT_RESET::RESET::SET);
+        regs.rng_soft_reset.write(SOFT_RESET::RESET::SET);
+        regs.rng_soft_reset.write(SOFT_RESET::RESET::CLEAR);
+        regs.rbg_soft_reset.write(SOFT_RESET::RESET::CLEAR);
+
+        // Clear the interrupt status (W1C)
+        regs.int_status.set(u32::MAX);
+
+        regs.ctrl.modify(CTRL::RBGEN::Enable);
+    }
+
+    /// Read words into `out` without blocking. Returns the number of words
+    /// read.
+    pub fn read_words(&mut self, out: &mut [u32]) -> Result<usize, rand_core::Error> {
+        if !self.check() {
+            return Err(failed());
+        }
+        if !self.is_warmed_up() {
+            return Ok(0);
+        }
+
+        let count = self.available().min(out.len());
+        for word in &mut out[..count] {
+            *word = self.regs.fifo_data.get();
+        }
+        Ok(count)
+    }
+
+    /// Read words into `out`, blocking until all of them are read.
+    pub fn read_words_blocking(&mut self, mut out: &mut [u32]) -> Result<(), rand_core::Error> {
+        while !out.is_empty() {
+            let count = self.read_words(out)?;
+            out = &mut out[count..];
+            if count == 0 {
+                core::hint::spin_loop();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[inline]
+fn failed() -> rand_core::Error {
+    core::num::NonZeroU32::new(ERROR_CODE_FAILED)
+        .unwrap()
+        .into()
+}
+
+impl RngCore for Rng200<'_> {
+    fn next_u32(&mut self) -> u32 {
+        let mut word = [0];
+        self.read_words_blocking(&mut word)
+            .expect("hardware RNG failure");
+        word[0]
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut words = [0; 2];
+        self.read_words_blocking(&mut words)
+            .expect("hardware RNG failure");
+        words[0] as u64 | (words[1] as u64) << 32
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.try_fill_bytes(dest).expect("hardware RNG failure");
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        const CHUNK_WORDS: usize = 16;
+        for chunk in dest.chunks_mut(4 * CHUNK_WORDS) {
+            let mut words = [0u32; CHUNK_WORDS];
+            let words = &mut words[..chunk.chunks(4).len()];
+            self.read_words_blocking(words)?;
+            for (bytes, word) in chunk.chunks_mut(4).zip(words.iter()) {
+                bytes.copy_from_slice(&word.to_le_bytes()[..bytes.len()]);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The RNG200 is a true random number generator with on-chip health tests.
+impl CryptoRng for Rng200<'_> {}
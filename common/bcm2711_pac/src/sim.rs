@@ -0,0 +1,572 @@
+//! Simulated register blocks for host-side unit tests
+//!
+//! This module is available with the `sim` feature, which replaces the
+//! register types of all register blocks with [drop-in
+//! replacements](registers) that can be redirected to a simulated device. A
+//! [`Device<B>`] allocates a register block `B` whose accesses are handled by
+//! a device model instead of the memory, so driver code taking `&B` can run
+//! under `cargo test` on the host.
+//!
+//! The model is made up of the following parts:
+//!
+//!  - The [`Semantics`] of each register, which define what reads and writes
+//!    do (e.g., [`Semantics::W1c`] for write-1-to-clear status bits and
+//!    [`Semantics::Fifo`] for data registers popping a receive queue). All
+//!    registers are [`Semantics::Plain`] by default.
+//!
+//!  - Read and write hooks ([`Device::on_read`], [`Device::on_write`]), which
+//!    are called before the semantics are applied and can update the
+//!    [`State`] of the device to script its behavior.
+//!
+//! Every access is recorded in a trace ([`Device::trace`]).
+//!
+//! [`aux`], [`bsc`], [`gpio`], [`pl011`], [`spi`], and [`sys_timer`] provide
+//! ready-made models of the corresponding peripherals.
+//!
+//! ```rust,ignore
+//! // Cargo.toml: [dev-dependencies]
+//! // bcm2711_pac = { path = "...", features = ["sim"] }
+//! use bcm2711_pac::{gpio, sim};
+//! use tock_registers::interfaces::{Readable, Writeable};
+//!
+//! let device = sim::gpio::Gpio::new();
+//! let regs: &gpio::Registers = device.regs();
+//! regs.set_function(5, gpio::Function::Output);
+//! regs.gpset[0].write(gpio::GPSET::pin(5).val(1));
+//! assert!(regs.gplev[0].is_set(gpio::GPLEV::pin(5)));
+//! ```
+//!
+//! Register blocks not allocated by a `Device` are accessed directly as
+//! before, so enabling the feature doesn't affect code using register blocks
+//! in ordinary memory. Don't enable this feature for the target build; every
+//! register access goes through a global device table.
+extern crate std;
+
+use core::{fmt, marker::PhantomData, mem::size_of};
+use std::{
+    boxed::Box,
+    collections::VecDeque,
+    sync::{Arc, Mutex, MutexGuard},
+    vec::Vec,
+};
+
+// `aux.rs` breaks some tools on Windows
+// https://msdn.microsoft.com/en-us/library/aa365247(v=vs.85).aspx#file_and_directory_names
+#[path = "sim/aux_.rs"]
+pub mod aux;
+pub mod bsc;
+pub mod gpio;
+pub mod pl011;
+pub mod registers;
+pub mod spi;
+pub mod sys_timer;
+
+/// A register access recorded in a [trace](Device::trace)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Access {
+    /// A read returning `value`
+    Read { offset: usize, value: u32 },
+    /// A write of `value`
+    Write { offset: usize, value: u32 },
+}
+
+/// The behavior of a simulated register
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Semantics {
+    /// Reads return the last written value.
+    Plain,
+    /// Writes are ignored. The value can be changed through [`State::set`]
+    /// (e.g., by a hook).
+    ReadOnly,
+    /// Writes are only seen by the write hooks. Reads return zero.
+    WriteOnly,
+    /// Writing one to a bit in the mask clears it. Writing zero to a bit in
+    /// the mask has no effect. The other bits are [`Self::Plain`].
+    W1c(u32),
+    /// Writing one to a bit sets the corresponding bit of the register at the
+    /// specified offset (e.g., `gpset` → `gplev`). Reads return zero.
+    SetBits(usize),
+    /// Writing one to a bit clears the corresponding bit of the register at
+    /// the specified offset (e.g., `gpclr` → `gplev`). Reads return zero.
+    ClearBits(usize),
+    /// Reads pop a value from the receive queue ([`State::push_rx`]) and
+    /// return zero if it's empty. Writes push a value to the transmit queue
+    /// ([`State::pop_tx`]).
+    Fifo,
+}
+
+/// The state of a simulated device
+pub struct State {
+    values: Vec<u32>,
+    semantics: Vec<Semantics>,
+    rx: Vec<VecDeque<u32>>,
+    tx: Vec<VecDeque<u32>>,
+    trace: Vec<Access>,
+    tracing: bool,
+}
+
+impl State {
+    fn new(len: usize) -> Self {
+        Self {
+            values: std::vec![0; len],
+            semantics: std::vec![Semantics::Plain; len],
+            rx: (0..len).map(|_| VecDeque::new()).collect(),
+            tx: (0..len).map(|_| VecDeque::new()).collect(),
+            trace: Vec::new(),
+            tracing: true,
+        }
+    }
+
+    /// Get the stored value of the register at `offset`. Unlike a read
+    /// through the register block, this doesn't have side effects.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `offset` is out of range or not aligned.
+    #[inline]
+    pub fn get(&self, offset: usize) -> u32 {
+        self.values[index(offset)]
+    }
+
+    /// Set the stored value of the register at `offset` regardless of its
+    /// semantics.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `offset` is out of range or not aligned.
+    #[inline]
+    pub fn set(&mut self, offset: usize, value: u32) {
+        self.values[index(offset)] = value;
+    }
+
+    /// Set the specified bits of the stored value of the register at
+    /// `offset`.
+    #[inline]
+    pub fn set_bits(&mut self, offset: usize, mask: u32) {
+        self.values[index(offset)] |= mask;
+    }
+
+    /// Clear the specified bits of the stored value of the register at
+    /// `offset`.
+    #[inline]
+    pub fn clear_bits(&mut self, offset: usize, mask: u32) {
+        self.values[index(offset)] &= !mask;
+    }
+
+    /// Get the semantics of the register at `offset`.
+    #[inline]
+    pub fn semantics(&self, offset: usize) -> Semantics {
+        self.semantics[index(offset)]
+    }
+
+    /// Set the semantics of the register at `offset`.
+    #[inline]
+    pub fn set_semantics(&mut self, offset: usize, semantics: Semantics) {
+        self.semantics[index(offset)] = semantics;
+    }
+
+    /// Push a value to the receive queue of the register at `offset`.
+    #[inline]
+    pub fn push_rx(&mut self, offset: usize, value: u32) {
+        self.rx[index(offset)].push_back(value);
+    }
+
+    /// Get the number of values in the receive queue of the register at
+    /// `offset`.
+    #[inline]
+    pub fn rx_len(&self, offset: usize) -> usize {
+        self.rx[index(offset)].len()
+    }
+
+    /// Pop a value from the transmit queue of the register at `offset`.
+    #[inline]
+    pub fn pop_tx(&mut self, offset: usize) -> Option<u32> {
+        self.tx[index(offset)].pop_front()
+    }
+
+    /// Get the number of values in the transmit queue of the register at
+    /// `offset`.
+    #[inline]
+    pub fn tx_len(&self, offset: usize) -> usize {
+        self.tx[index(offset)].len()
+    }
+
+    fn read(&mut self, offset: usize) -> u32 {
+        let i = index(offset);
+        let value = match self.semantics[i] {
+            Semantics::Plain | Semantics::ReadOnly | Semantics::W1c(_) => self.values[i],
+            Semantics::WriteOnly | Semantics::SetBits(_) | Semantics::ClearBits(_) => 0,
+            Semantics::Fifo => self.rx[i].pop_front().unwrap_or(0),
+        };
+        if self.tracing {
+            self.trace.push(Access::Read { offset, value });
+        }
+        value
+    }
+
+    fn write(&mut self, offset: usize, value: u32) {
+        let i = index(offset);
+        match self.semantics[i] {
+            Semantics::Plain => self.values[i] = value,
+            Semantics::ReadOnly | Semantics::WriteOnly => {}
+            Semantics::W1c(mask) => {
+                self.values[i] = (self.values[i] & mask & !value) | (value & !mask);
+            }
+            Semantics::SetBits(target) => self.set_bits(target, value),
+            Semantics::ClearBits(target) => self.clear_bits(target, value),
+            Semantics::Fifo => self.tx[i].push_back(value),
+        }
+        if self.tracing {
+            self.trace.push(Access::Write { offset, value });
+        }
+    }
+}
+
+#[inline]
+fn index(offset: usize) -> usize {
+    assert_eq!(offset % 4, 0, "unaligned register offset");
+    offset / 4
+}
+
+type ReadHook = Box<dyn FnMut(&mut State) + Send>;
+type WriteHook = Box<dyn FnMut(&mut State, u32) + Send>;
+
+struct Inner {
+    state: State,
+    read_hooks: Vec<(usize, ReadHook)>,
+    write_hooks: Vec<(usize, WriteHook)>,
+}
+
+impl Inner {
+    fn read(&mut self, offset: usize) -> u32 {
+        for (_, hook) in self.read_hooks.iter_mut().filter(|(o, _)| *o == offset) {
+            hook(&mut self.state);
+        }
+        self.state.read(offset)
+    }
+
+    fn write(&mut self, offset: usize, value: u32) {
+        for (_, hook) in self.write_hooks.iter_mut().filter(|(o, _)| *o == offset) {
+            hook(&mut self.state, value);
+        }
+        self.state.write(offset, value)
+    }
+}
+
+struct Shared {
+    base: usize,
+    size: usize,
+    inner: Mutex<Inner>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        // A panicking test shouldn't affect others
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The devices currently alive
+static DEVICES: Mutex<Vec<Arc<Shared>>> = Mutex::new(Vec::new());
+
+fn devices() -> MutexGuard<'static, Vec<Arc<Shared>>> {
+    DEVICES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn find(addr: usize) -> Option<Arc<Shared>> {
+    devices()
+        .iter()
+        .find(|dev| (dev.base..dev.base + dev.size).contains(&addr))
+        .cloned()
+}
+
+pub(crate) fn read(ptr: *const u32) -> u32 {
+    match find(ptr as usize) {
+        Some(dev) => dev.lock().read(ptr as usize - dev.base),
+        // Safety: Not a simulated register; the caller (a register type)
+        // guarantees `ptr` is valid for reads
+        None => unsafe { ptr.read_volatile() },
+    }
+}
+
+pub(crate) fn write(ptr: *mut u32, value: u32) {
+    match find(ptr as usize) {
+        Some(dev) => dev.lock().write(ptr as usize - dev.base, value),
+        // Safety: Not a simulated register; the caller (a register type)
+        // guarantees `ptr` is valid for writes
+        None => unsafe { ptr.write_volatile(value) },
+    }
+}
+
+/// A simulated register block `B`
+///
+/// The register block returned by [`Self::regs`] is valid as long as the
+/// `Device` is alive. Hooks must not access the registers of the same device
+/// (use the provided [`State`] instead), or they will deadlock.
+pub struct Device<B> {
+    shared: Arc<Shared>,
+    /// The storage providing unique addresses for the registers. Never
+    /// accessed.
+    anchor: Box<[u32]>,
+    _block: PhantomData<fn() -> B>,
+}
+
+impl<B> Device<B> {
+    /// Construct a `Device` with all registers [`Semantics::Plain`] and zero.
+    pub fn new() -> Self {
+        assert!(core::mem::align_of::<B>() <= 4);
+        assert_eq!(size_of::<B>() % 4, 0, "not a block of 32-bit registers");
+        let len = size_of::<B>() / 4;
+        let anchor = std::vec![0u32; len.max(1)].into_boxed_slice();
+        let shared = Arc::new(Shared {
+            base: anchor.as_ptr() as usize,
+            size: size_of::<B>(),
+            inner: Mutex::new(Inner {
+                state: State::new(len),
+                read_hooks: Vec::new(),
+                write_hooks: Vec::new(),
+            }),
+        });
+        devices().push(shared.clone());
+        Self {
+            shared,
+            anchor,
+            _block: PhantomData,
+        }
+    }
+
+    /// Get the simulated register block.
+    #[inline]
+    pub fn regs(&self) -> &B {
+        // Safety: `anchor` is large enough and suitably aligned, and all
+        // register blocks are valid when zero-initialized. The registers
+        // only access it through `read` and `write`, which redirect the
+        // accesses to `shared`.
+        unsafe { &*(self.anchor.as_ptr() as *const B) }
+    }
+
+    /// Get the offset of `register`, which must be a part of
+    /// [`Self::regs`].
+    ///
+    /// ```rust,ignore
+    /// let offset = device.offset(&device.regs().gplev[1]);
+    /// assert_eq!(offset, 0x38);
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// Panics if `register` is outside the register block.
+    pub fn offset<T>(&self, register: &T) -> usize {
+        let addr = register as *const T as usize;
+        assert!(
+            (self.shared.base..self.shared.base + self.shared.size).contains(&addr),
+            "the register doesn't belong to this device"
+        );
+        addr - self.shared.base
+    }
+
+    /// Call `f` with the state of the device.
+    ///
+    /// `f` must not access the registers of this device, or it will
+    /// deadlock.
+    pub fn with_state<T>(&self, f: impl FnOnce(&mut State) -> T) -> T {
+        f(&mut self.shared.lock().state)
+    }
+
+    /// Get the stored value of the register at `offset`. See [`State::get`].
+    pub fn get(&self, offset: usize) -> u32 {
+        self.with_state(|state| state.get(offset))
+    }
+
+    /// Set the stored value of the register at `offset`. See [`State::set`].
+    pub fn set(&self, offset: usize, value: u32) {
+        self.with_state(|state| state.set(offset, value))
+    }
+
+    /// Set the semantics of the register at `offset`.
+    pub fn set_semantics(&self, offset: usize, semantics: Semantics) {
+        self.with_state(|state| state.set_semantics(offset, semantics))
+    }
+
+    /// Register a hook called before each read of the register at `offset`.
+    pub fn on_read(&self, offset: usize, hook: impl FnMut(&mut State) + Send + 'static) {
+        index(offset);
+        self.shared.lock().read_hooks.push((offset, Box::new(hook)));
+    }
+
+    /// Register a hook called before each write to the register at `offset`.
+    /// The hook receives the written value.
+    pub fn on_write(&self, offset: usize, hook: impl FnMut(&mut State, u32) + Send + 'static) {
+        index(offset);
+        self.shared
+            .lock()
+            .write_hooks
+            .push((offset, Box::new(hook)));
+    }
+
+    /// Get the accesses recorded so far.
+    pub fn trace(&self) -> Vec<Access> {
+        self.with_state(|state| state.trace.clone())
+    }
+
+    /// Get and clear the accesses recorded so far.
+    pub fn take_trace(&self) -> Vec<Access> {
+        self.with_state(|state| core::mem::take(&mut state.trace))
+    }
+
+    /// Enable or disable recording accesses. Enabled by default.
+    pub fn set_tracing(&self, enable: bool) {
+        self.with_state(|state| state.tracing = enable)
+    }
+}
+
+impl<B> Default for Device<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B> Drop for Device<B> {
+    fn drop(&mut self) {
+        devices().retain(|dev| !Arc::ptr_eq(dev, &self.shared));
+    }
+}
+
+impl<B> fmt::Debug for Device<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Device")
+            .field("base", &(self.shared.base as *const B))
+            .field("size", &self.shared.size)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pl011;
+    use std::format;
+    use tock_registers::interfaces::{Readable, Writeable};
+
+    #[test]
+    fn semantics() {
+        let device = Device::<pl011::Registers>::new();
+        let regs = device.regs();
+        let ibrd = device.offset(&regs.ibrd);
+        let icr = device.offset(&regs.icr);
+        let ris = device.offset(&regs.ris);
+        let dr = device.offset(&regs.dr);
+        assert_eq!((ibrd, icr, ris, dr), (0x24, 0x44, 0x3c, 0x00));
+
+        // Plain
+        regs.ibrd.set(26);
+        assert_eq!(regs.ibrd.get(), 26);
+        assert_eq!(device.get(ibrd), 26);
+
+        // ReadOnly
+        device.set_semantics(ibrd, Semantics::ReadOnly);
+        regs.ibrd.set(1);
+        assert_eq!(regs.ibrd.get(), 26);
+
+        // W1c
+        device.set_semantics(ibrd, Semantics::W1c(0xff));
+        device.set(ibrd, 0x0f0f);
+        regs.ibrd.set(0x3003);
+        assert_eq!(regs.ibrd.get(), 0x300c);
+
+        // ClearBits
+        device.set_semantics(icr, Semantics::ClearBits(ris));
+        device.set(ris, 0b111);
+        regs.icr.set(0b010);
+        assert_eq!(regs.ris.get(), 0b101);
+
+        // Fifo
+        device.set_semantics(dr, Semantics::Fifo);
+        device.with_state(|state| {
+            state.push_rx(dr, 0x41);
+            state.push_rx(dr, 0x42);
+        });
+        assert_eq!(regs.dr.get(), 0x41);
+        assert_eq!(regs.dr.get(), 0x42);
+        assert_eq!(regs.dr.get(), 0);
+        regs.dr.set(0x43);
+        assert_eq!(device.with_state(|state| state.pop_tx(dr)), Some(0x43));
+    }
+
+    #[test]
+    fn hooks_and_trace() {
+        let device = Device::<pl011::Registers>::new();
+        let regs = device.regs();
+        let fr = device.offset(&regs.fr);
+        let ibrd = device.offset(&regs.ibrd);
+        let fbrd = device.offset(&regs.fbrd);
+
+        // Report `BUSY` for the first two reads
+        let mut count = 0;
+        device.on_read(fr, move |state| {
+            count += 1;
+            state.set(fr, if count <= 2 { 1 << 3 } else { 0 });
+        });
+        // Mirror `IBRD` to `FBRD`
+        device.on_write(ibrd, move |state, value| state.set(fbrd, value));
+
+        while regs.fr.is_set(pl011::FR::BUSY) {}
+        regs.ibrd.set(3);
+        assert_eq!(regs.fbrd.get(), 3);
+
+        assert_eq!(
+            device.take_trace(),
+            [
+                Access::Read {
+                    offset: fr,
+                    value: 8
+                },
+                Access::Read {
+                    offset: fr,
+                    value: 8
+                },
+                Access::Read {
+                    offset: fr,
+                    value: 0
+                },
+                Access::Write {
+                    offset: ibrd,
+                    value: 3
+                },
+                Access::Read {
+                    offset: fbrd,
+                    value: 3
+                },
+            ]
+        );
+        assert!(device.trace().is_empty());
+
+        device.set_tracing(false);
+        regs.ibrd.get();
+        assert!(device.trace().is_empty());
+    }
+
+    #[test]
+    fn snapshot() {
+        let device = Device::<pl011::Registers>::new();
+        let regs = device.regs();
+        let dr = device.offset(&regs.dr);
+        device.set_semantics(dr, Semantics::Fifo);
+        device.with_state(|state| state.push_rx(dr, 0x41));
+        regs.cr.write(pl011::CR::UARTEN::SET);
+
+        // The snapshot doesn't pop the FIFO, and it's not simulated
+        let snapshot = regs.snapshot();
+        assert!(format!("{:?}", snapshot).contains("cr: CR { UARTEN: 1, "));
+        assert_eq!(snapshot.cr.get(), 1);
+        assert_eq!(device.with_state(|state| state.rx_len(dr)), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "the register doesn't belong to this device")]
+    fn offset_of_foreign_register() {
+        let device1 = Device::<pl011::Registers>::new();
+        let device2 = Device::<pl011::Registers>::new();
+        device1.offset(&device2.regs().cr);
+    }
+}
@@ -0,0 +1,512 @@
+//! A model of [the auxiliary peripheral register block](crate::aux::Registers)
+//!
+//! The Mini UART:
+//!
+//!  - Writing to `aux_mu.io_reg` transmits a character, which can be
+//!    retrieved by [`Aux::take_tx`]. The transmit FIFO drains instantly
+//!    unless `aux_mu.cntl_reg.CTS_AFC` is set and `CTS` is deasserted by
+//!    [`Aux::set_cts`], in which case up to eight characters are held in the
+//!    FIFO.
+//!  - Reading `aux_mu.io_reg` pops a character pushed by [`Aux::push_rx`].
+//!    Characters pushed to the full receive FIFO (eight characters) are
+//!    dropped and set `aux_mu.lsr_reg.RX_OVERRUN`, which is cleared by
+//!    reading `aux_mu.lsr_reg`.
+//!  - `aux_mu.lsr_reg`, `aux_mu.stat_reg`, `aux_mu.iir_reg`, and
+//!    `aux_irq.MINI_UART_IRQ` reflect the FIFOs and `aux_mu.ier_reg`. The
+//!    receive interrupt is pending while the receive FIFO isn't empty, and the
+//!    transmit interrupt is pending while the transmit FIFO is empty.
+//!  - Writes to `aux_mu.iir_reg` (clearing the FIFOs) are ignored. The other
+//!    registers don't affect the behavior.
+//!
+//! The SPI masters (SPI1 and SPI2):
+//!
+//!  - While `cntl0_reg.ENABLE` is set, writing to `io_reg[a-d]` or
+//!    `txhold_reg[a-d]` shifts out a word instantly. The words can be
+//!    retrieved by [`Aux::take_spi_tx`]. The word shifted in at the same time
+//!    (see [`Aux::set_spi_response`]) is pushed to the receive FIFO, which is
+//!    popped by reading `io_reg[a-d]` or `txhold_reg[a-d]` and peeked by
+//!    `peek_reg`. Words pushed to the full receive FIFO (four words) are
+//!    dropped. Setting `cntl0_reg.CLEAR_FIFO` clears the receive FIFO.
+//!  - `stat_reg` reflects the receive FIFO. The transmit FIFO is always empty
+//!    and the shifter idle, so `aux_irq.SPI1_IRQ` (`SPI2_IRQ`) is set while
+//!    `cntl1_reg.TX_EMPTY_IRQ` or `cntl1_reg.DONE_IRQ` is set.
+extern crate std;
+
+use core::ops::Deref;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    vec::Vec,
+};
+
+use super::{Device, Semantics, State};
+use crate::aux::{
+    Registers, AUX_IRQ, AUX_MU_CNTL_REG, AUX_MU_IER_REG, AUX_MU_LSR_REG, AUX_MU_MSR_REG,
+    AUX_MU_STAT_REG, AUX_SPI_CNTL0_REG, AUX_SPI_CNTL1_REG, AUX_SPI_STAT_REG,
+};
+
+const AUX_IRQ: usize = 0x00;
+const MU_IO: usize = 0x40;
+const MU_IER: usize = 0x44;
+const MU_IIR: usize = 0x48;
+const MU_LSR: usize = 0x54;
+const MU_MSR: usize = 0x58;
+const MU_CNTL: usize = 0x60;
+const MU_STAT: usize = 0x64;
+
+/// The base offsets of SPI1 and SPI2
+const SPI_BASE: [usize; 2] = [0x80, 0xc0];
+const SPI_CNTL0: usize = 0x00;
+const SPI_CNTL1: usize = 0x04;
+const SPI_STAT: usize = 0x08;
+const SPI_PEEK: usize = 0x0c;
+const SPI_IO: usize = 0x20;
+const SPI_TXHOLD: usize = 0x30;
+
+/// The depth of the Mini UART FIFOs
+const FIFO_LEN: usize = 8;
+
+/// The depth of the SPI receive FIFO
+const SPI_FIFO_LEN: usize = 4;
+
+/// A word shifted out by SPI1 or SPI2
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct SpiWord {
+    /// The value written to the data register
+    pub value: u32,
+    /// The word was written to `txhold_reg[a-d]`, i.e., CS was held asserted
+    /// after shifting it out.
+    pub hold: bool,
+    /// The value of `cntl0_reg` when the word was written
+    pub cntl0: u32,
+}
+
+/// The state of SPI1 or SPI2 not stored in the registers
+#[derive(Debug, Default)]
+struct SpiModel {
+    rx: VecDeque<u32>,
+    response: VecDeque<u32>,
+    tx: Vec<SpiWord>,
+}
+
+/// A simulated auxiliary peripheral register block. Dereferences to
+/// [`Device`].
+#[derive(Debug)]
+pub struct Aux {
+    device: Device<Registers>,
+    overrun: Arc<AtomicBool>,
+    spis: [Arc<Mutex<SpiModel>>; 2],
+}
+
+impl Aux {
+    /// Construct an `Aux` with empty FIFOs and `CTS` asserted.
+    pub fn new() -> Self {
+        let device = Device::new();
+        device.set_semantics(AUX_IRQ, Semantics::ReadOnly);
+        device.set_semantics(MU_IO, Semantics::Fifo);
+        device.set_semantics(MU_IIR, Semantics::ReadOnly);
+        device.set_semantics(MU_LSR, Semantics::ReadOnly);
+        device.set_semantics(MU_MSR, Semantics::ReadOnly);
+        device.set_semantics(MU_STAT, Semantics::ReadOnly);
+        device.set(MU_MSR, AUX_MU_MSR_REG::CTS::Low.value);
+
+        let overrun = Arc::new(AtomicBool::new(false));
+        let overrun2 = Arc::clone(&overrun);
+        device.on_read(MU_LSR, move |state| {
+            let mut value = 0;
+            if state.rx_len(MU_IO) > 0 {
+                value |= AUX_MU_LSR_REG::DATA_READY::SET.value;
+            }
+            if overrun2.swap(false, Ordering::Relaxed) {
+                value |= AUX_MU_LSR_REG::RX_OVERRUN::SET.value;
+            }
+            if tx_len(state) < FIFO_LEN {
+                value |= AUX_MU_LSR_REG::TX_EMPTY::SET.value;
+            }
+            if tx_len(state) == 0 {
+                value |= AUX_MU_LSR_REG::TX_IDLE::SET.value;
+            }
+            state.set(MU_LSR, value);
+        });
+        device.on_read(MU_STAT, |state| {
+            let (rx_len, tx_len) = (state.rx_len(MU_IO), tx_len(state));
+            let mut value = AUX_MU_STAT_REG::RX_FIFO_LEVEL.val(rx_len as u32).value
+                | AUX_MU_STAT_REG::TX_FIFO_LEVEL.val(tx_len as u32).value;
+            if rx_len > 0 {
+                value |= AUX_MU_STAT_REG::RX_NOT_EMPTY::SET.value;
+            }
+            if tx_len < FIFO_LEN {
+                value |= AUX_MU_STAT_REG::TX_NOT_FULL::SET.value;
+            } else {
+                value |= AUX_MU_STAT_REG::TX_FULL::SET.value;
+            }
+            if tx_len == 0 {
+                value |= AUX_MU_STAT_REG::TX_EMPTY::SET.value
+                    | AUX_MU_STAT_REG::TX_IDLE::SET.value
+                    | AUX_MU_STAT_REG::TX_DONE::SET.value;
+            }
+            if state.get(MU_MSR) & AUX_MU_MSR_REG::CTS::Low.value != 0 {
+                value |= AUX_MU_STAT_REG::CTS::SET.value;
+            }
+            state.set(MU_STAT, value);
+        });
+        device.on_read(MU_IIR, |state| {
+            // Bit 0 is clear while an interrupt is pending. Bits 1–2 identify
+            // the interrupt.
+            let value = match pending_interrupt(state) {
+                Some(Interrupt::Rx) => 0b100,
+                Some(Interrupt::Tx) => 0b010,
+                None => 0b001,
+            };
+            state.set(MU_IIR, value);
+        });
+        device.on_read(AUX_IRQ, |state| {
+            let mut value = 0;
+            if pending_interrupt(state).is_some() {
+                value |= AUX_IRQ::MINI_UART_IRQ::SET.value;
+            }
+            let spi_irq =
+                AUX_SPI_CNTL1_REG::TX_EMPTY_IRQ::SET.value | AUX_SPI_CNTL1_REG::DONE_IRQ::SET.value;
+            if state.get(SPI_BASE[0] + SPI_CNTL1) & spi_irq != 0 {
+                value |= AUX_IRQ::SPI1_IRQ::SET.value;
+            }
+            if state.get(SPI_BASE[1] + SPI_CNTL1) & spi_irq != 0 {
+                value |= AUX_IRQ::SPI2_IRQ::SET.value;
+            }
+            state.set(AUX_IRQ, value);
+        });
+
+        let spis = SPI_BASE.map(|base| spi_model(&device, base));
+
+        Self {
+            device,
+            overrun,
+            spis,
+        }
+    }
+
+    /// Receive `bytes`. Characters that don't fit in the receive FIFO are
+    /// dropped, causing an overrun.
+    pub fn push_rx(&self, bytes: &[u8]) {
+        self.device.with_state(|state| {
+            for &b in bytes {
+                if state.rx_len(MU_IO) < FIFO_LEN {
+                    state.push_rx(MU_IO, b.into());
+                } else {
+                    self.overrun.store(true, Ordering::Relaxed);
+                }
+            }
+        })
+    }
+
+    /// Get the number of characters in the receive FIFO.
+    pub fn rx_len(&self) -> usize {
+        self.device.with_state(|state| state.rx_len(MU_IO))
+    }
+
+    /// Take the characters transmitted so far. Returns nothing while the
+    /// transmit FIFO is held by the flow control.
+    pub fn take_tx(&self) -> Vec<u8> {
+        self.device.with_state(|state| {
+            if is_held(state) {
+                return Vec::new();
+            }
+            core::iter::from_fn(|| state.pop_tx(MU_IO))
+                .map(|value| value as u8)
+                .collect()
+        })
+    }
+
+    /// Assert or deassert `CTS` (`aux_mu.msr_reg.CTS`).
+    pub fn set_cts(&self, asserted: bool) {
+        self.device.with_state(|state| {
+            let value = if asserted {
+                AUX_MU_MSR_REG::CTS::Low
+            } else {
+                AUX_MU_MSR_REG::CTS::High
+            };
+            state.set(MU_MSR, value.value);
+        })
+    }
+
+    /// Set the words shifted in by the following transfers of SPI`n` (`1` or
+    /// `2`), given as read from the data registers. Zero is shifted in after
+    /// they are exhausted.
+    pub fn set_spi_response(&self, n: usize, words: &[u32]) {
+        self.spi(n).lock().unwrap().response = words.iter().copied().collect();
+    }
+
+    /// Take the words shifted out by SPI`n` (`1` or `2`) so far.
+    pub fn take_spi_tx(&self, n: usize) -> Vec<SpiWord> {
+        core::mem::take(&mut self.spi(n).lock().unwrap().tx)
+    }
+
+    /// Get the number of words in the receive FIFO of SPI`n` (`1` or `2`).
+    pub fn spi_rx_len(&self, n: usize) -> usize {
+        self.spi(n).lock().unwrap().rx.len()
+    }
+
+    fn spi(&self, n: usize) -> &Mutex<SpiModel> {
+        assert!(n == 1 || n == 2, "no such SPI: {n}");
+        &self.spis[n - 1]
+    }
+}
+
+impl Default for Aux {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for Aux {
+    type Target = Device<Registers>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.device
+    }
+}
+
+/// Check if the transmit FIFO is held by the CTS flow control.
+fn is_held(state: &State) -> bool {
+    state.get(MU_CNTL) & AUX_MU_CNTL_REG::CTS_AFC::SET.value != 0
+        && state.get(MU_MSR) & AUX_MU_MSR_REG::CTS::Low.value == 0
+}
+
+/// Get the number of characters in the transmit FIFO.
+fn tx_len(state: &State) -> usize {
+    if is_held(state) {
+        state.tx_len(MU_IO)
+    } else {
+        0
+    }
+}
+
+/// Install the hooks modeling the SPI master at `base`.
+fn spi_model(device: &Device<Registers>, base: usize) -> Arc<Mutex<SpiModel>> {
+    let model = Arc::new(Mutex::new(SpiModel::default()));
+    device.set_semantics(base + SPI_STAT, Semantics::ReadOnly);
+    device.set_semantics(base + SPI_PEEK, Semantics::ReadOnly);
+
+    let model2 = Arc::clone(&model);
+    device.on_write(base + SPI_CNTL0, move |_, value| {
+        if value & AUX_SPI_CNTL0_REG::CLEAR_FIFO::SET.value != 0 {
+            model2.lock().unwrap().rx.clear();
+        }
+    });
+    let model2 = Arc::clone(&model);
+    device.on_read(base + SPI_STAT, move |state| {
+        let rx_len = model2.lock().unwrap().rx.len();
+        let mut value = AUX_SPI_STAT_REG::TX_EMPTY::SET.value
+            | AUX_SPI_STAT_REG::RX_FIFO_LEVEL.val(rx_len as u32).value;
+        if rx_len == 0 {
+            value |= AUX_SPI_STAT_REG::RX_EMPTY::SET.value;
+        }
+        if rx_len >= SPI_FIFO_LEN {
+            value |= AUX_SPI_STAT_REG::RX_FULL::SET.value;
+        }
+        state.set(base + SPI_STAT, value);
+    });
+    let model2 = Arc::clone(&model);
+    device.on_read(base + SPI_PEEK, move |state| {
+        let value = model2.lock().unwrap().rx.front().copied().unwrap_or(0);
+        state.set(base + SPI_PEEK, value);
+    });
+
+    for i in 0..8 {
+        let offset = base + if i < 4 { SPI_IO } else { SPI_TXHOLD } + i % 4 * 4;
+        device.set_semantics(offset, Semantics::ReadOnly);
+
+        let model2 = Arc::clone(&model);
+        device.on_read(offset, move |state| {
+            let value = model2.lock().unwrap().rx.pop_front().unwrap_or(0);
+            state.set(offset, value);
+        });
+        let model2 = Arc::clone(&model);
+        device.on_write(offset, move |state, value| {
+            let cntl0 = state.get(base + SPI_CNTL0);
+            if cntl0 & AUX_SPI_CNTL0_REG::ENABLE::SET.value == 0 {
+                return;
+            }
+            let mut model = model2.lock().unwrap();
+            model.tx.push(SpiWord {
+                value,
+                hold: i >= 4,
+                cntl0,
+            });
+            let response = model.response.pop_front().unwrap_or(0);
+            if model.rx.len() < SPI_FIFO_LEN {
+                model.rx.push_back(response);
+            }
+        });
+    }
+    model
+}
+
+enum Interrupt {
+    Rx,
+    Tx,
+}
+
+/// Get the pending Mini UART interrupt with the highest priority.
+fn pending_interrupt(state: &State) -> Option<Interrupt> {
+    let ier = state.get(MU_IER);
+    if ier & AUX_MU_IER_REG::RX_INT_ENABLE::SET.value != 0 && state.rx_len(MU_IO) > 0 {
+        Some(Interrupt::Rx)
+    } else if ier & AUX_MU_IER_REG::TX_INT_ENABLE::SET.value != 0 && tx_len(state) == 0 {
+        Some(Interrupt::Tx)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aux::AUX_MU_IO_REG;
+    use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
+
+    #[test]
+    fn tx() {
+        let device = Aux::new();
+        let regs = &device.regs().aux_mu;
+        assert!(regs.stat_reg.is_set(AUX_MU_STAT_REG::TX_EMPTY));
+        for &b in b"hi" {
+            regs.io_reg.write(AUX_MU_IO_REG::TX_DATA.val(b.into()));
+        }
+        assert_eq!(device.take_tx(), b"hi");
+
+        // Flow control holds the transmit FIFO
+        regs.cntl_reg.write(AUX_MU_CNTL_REG::CTS_AFC::SET);
+        device.set_cts(false);
+        assert!(!regs.stat_reg.is_set(AUX_MU_STAT_REG::CTS));
+        for i in 0..FIFO_LEN {
+            assert!(regs.lsr_reg.is_set(AUX_MU_LSR_REG::TX_EMPTY));
+            regs.io_reg.set(i as u32);
+        }
+        assert!(regs.stat_reg.is_set(AUX_MU_STAT_REG::TX_FULL));
+        assert!(!regs.lsr_reg.is_set(AUX_MU_LSR_REG::TX_EMPTY));
+        assert!(device.take_tx().is_empty());
+
+        device.set_cts(true);
+        assert_eq!(regs.stat_reg.read(AUX_MU_STAT_REG::TX_FIFO_LEVEL), 0);
+        assert_eq!(device.take_tx().len(), FIFO_LEN);
+    }
+
+    #[test]
+    fn rx() {
+        let device = Aux::new();
+        let regs = &device.regs().aux_mu;
+        assert!(!regs.lsr_reg.is_set(AUX_MU_LSR_REG::DATA_READY));
+
+        device.push_rx(b"0123456789");
+        assert_eq!(device.rx_len(), FIFO_LEN);
+        assert_eq!(regs.stat_reg.read(AUX_MU_STAT_REG::RX_FIFO_LEVEL), 8);
+        let lsr = regs.lsr_reg.extract();
+        assert!(lsr.is_set(AUX_MU_LSR_REG::DATA_READY));
+        assert!(lsr.is_set(AUX_MU_LSR_REG::RX_OVERRUN));
+        assert!(!regs.lsr_reg.is_set(AUX_MU_LSR_REG::RX_OVERRUN));
+
+        let received: Vec<u8> = (0..FIFO_LEN)
+            .map(|_| regs.io_reg.read(AUX_MU_IO_REG::RX_DATA) as u8)
+            .collect();
+        assert_eq!(received, b"01234567");
+        assert!(!regs.lsr_reg.is_set(AUX_MU_LSR_REG::DATA_READY));
+    }
+
+    #[test]
+    fn interrupts() {
+        let device = Aux::new();
+        let aux = device.regs();
+        assert_eq!(aux.aux_irq.get(), 0);
+        assert_eq!(aux.aux_mu.iir_reg.get(), 0b001);
+
+        aux.aux_mu
+            .ier_reg
+            .write(AUX_MU_IER_REG::RX_INT_ENABLE::SET + AUX_MU_IER_REG::TX_INT_ENABLE::SET);
+        assert!(aux.aux_irq.is_set(AUX_IRQ::MINI_UART_IRQ));
+        assert_eq!(aux.aux_mu.iir_reg.get(), 0b010);
+
+        device.push_rx(b"a");
+        assert_eq!(aux.aux_mu.iir_reg.get(), 0b100);
+
+        aux.aux_mu.ier_reg.set(0);
+        assert_eq!(aux.aux_irq.get(), 0);
+    }
+
+    #[test]
+    fn spi() {
+        let device = Aux::new();
+        let regs = &device.regs().aux_spi2;
+
+        // Disabled
+        regs.io_rega.set(1);
+        assert!(device.take_spi_tx(2).is_empty());
+
+        regs.cntl0_reg
+            .write(AUX_SPI_CNTL0_REG::ENABLE::SET + AUX_SPI_CNTL0_REG::SHIFT_LEN.val(8));
+        device.set_spi_response(2, &[0x11, 0x22]);
+        regs.txhold_regc.set(0xa0);
+        regs.io_regb.set(0xb0);
+        regs.io_rega.set(0xc0);
+        let cntl0 = regs.cntl0_reg.get();
+        assert_eq!(
+            device.take_spi_tx(2),
+            [
+                SpiWord {
+                    value: 0xa0,
+                    hold: true,
+                    cntl0
+                },
+                SpiWord {
+                    value: 0xb0,
+                    hold: false,
+                    cntl0
+                },
+                SpiWord {
+                    value: 0xc0,
+                    hold: false,
+                    cntl0
+                },
+            ]
+        );
+        assert!(device.take_spi_tx(1).is_empty());
+
+        let stat = regs.stat_reg.extract();
+        assert!(!stat.is_set(AUX_SPI_STAT_REG::RX_EMPTY));
+        assert_eq!(stat.read(AUX_SPI_STAT_REG::RX_FIFO_LEVEL), 3);
+        assert_eq!(regs.peek_reg.get(), 0x11);
+        assert_eq!(regs.io_regd.get(), 0x11);
+        assert_eq!(regs.txhold_rega.get(), 0x22);
+        assert_eq!(regs.io_rega.get(), 0);
+        assert!(regs.stat_reg.is_set(AUX_SPI_STAT_REG::RX_EMPTY));
+
+        // The receive FIFO overflows
+        for i in 0..6 {
+            regs.io_rega.set(i);
+        }
+        assert!(regs.stat_reg.is_set(AUX_SPI_STAT_REG::RX_FULL));
+        assert_eq!(device.spi_rx_len(2), SPI_FIFO_LEN);
+        regs.cntl0_reg.modify(AUX_SPI_CNTL0_REG::CLEAR_FIFO::SET);
+        assert_eq!(device.spi_rx_len(2), 0);
+    }
+
+    #[test]
+    fn spi_interrupts() {
+        let device = Aux::new();
+        let aux = device.regs();
+        assert_eq!(aux.aux_irq.get(), 0);
+        aux.aux_spi1
+            .cntl1_reg
+            .write(AUX_SPI_CNTL1_REG::DONE_IRQ::SET);
+        assert_eq!(aux.aux_irq.get(), AUX_IRQ::SPI1_IRQ::SET.value);
+        aux.aux_spi2
+            .cntl1_reg
+            .write(AUX_SPI_CNTL1_REG::TX_EMPTY_IRQ::SET);
+        aux.aux_spi1.cntl1_reg.set(0);
+        assert_eq!(aux.aux_irq.get(), AUX_IRQ::SPI2_IRQ::SET.value);
+    }
+}
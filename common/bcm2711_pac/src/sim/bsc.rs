@@ -0,0 +1,391 @@
+//! A model of [the BSC register block](crate::bsc::Registers) and the slaves
+//! on its bus
+//!
+//!  - Writing `c` with `ST` and `I2CEN` set starts a transfer to the address
+//!    in `a` of `dlen` bytes. Writing it while a transfer is active queues the
+//!    next transfer, which starts with a repeated start when the current one
+//!    finishes. Writing `c` with `I2CEN` clear aborts the transfers.
+//!    `c.CLEAR` clears the FIFOs.
+//!  - Time advances on every read of `s`: each read reflects the current
+//!    state and then advances the active transfer by one step (the address or
+//!    a byte). A write transfer stalls while the FIFO is empty, and a read
+//!    transfer stalls while it's full (16 bytes).
+//!  - Slaves are attached by [`Bsc::attach`]. Transfers to other addresses
+//!    fail with `s.ERR`. Transfers to slaves made to stretch the clock by
+//!    [`Bsc::set_stretching`] fail with `s.CLKT`. Both end the transfer and
+//!    set `s.DONE`, as does the completion of every transfer.
+//!  - Read transfers receive the bytes set by [`Bsc::set_response`], then
+//!    `0xff`. The completed transfers can be retrieved by
+//!    [`Bsc::take_transfers`].
+//!  - `s.TXW` and `s.RXR` read as zero. `div`, `del`, and `clkt` don't affect
+//!    the behavior.
+extern crate std;
+
+use core::ops::Deref;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+    vec::Vec,
+};
+
+use super::{Device, Semantics, State};
+use crate::bsc::{Registers, A, C, DLEN, S};
+
+const C: usize = 0x00;
+const S: usize = 0x04;
+const DLEN: usize = 0x08;
+const A: usize = 0x0c;
+const FIFO: usize = 0x10;
+
+/// The depth of the FIFO
+const FIFO_LEN: usize = 16;
+
+/// A transfer completed by a slave
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Transfer {
+    /// The 7-bit slave address
+    pub addr: u8,
+    /// The transfer is a read.
+    pub read: bool,
+    /// The bytes written or read
+    pub data: Vec<u8>,
+    /// The transfer began with a repeated start (rather than following a stop
+    /// condition).
+    pub repeated_start: bool,
+}
+
+/// A slave attached to the bus
+#[derive(Debug, Default)]
+struct Slave {
+    response: VecDeque<u8>,
+    stretching: bool,
+}
+
+/// A transfer in progress
+#[derive(Debug)]
+struct Active {
+    transfer: Transfer,
+    len: usize,
+    /// The address was acknowledged.
+    addressed: bool,
+}
+
+/// The state of the bus not stored in the registers
+#[derive(Debug, Default)]
+struct Bus {
+    slaves: BTreeMap<u8, Slave>,
+    tx: VecDeque<u8>,
+    rx: VecDeque<u8>,
+    active: Option<Active>,
+    /// `(a, dlen, c)` of the transfer queued while `active` is in progress
+    queued: Option<(u32, u32, u32)>,
+    transfers: Vec<Transfer>,
+}
+
+/// A simulated BSC master register block. Dereferences to [`Device`].
+#[derive(Debug)]
+pub struct Bsc {
+    device: Device<Registers>,
+    bus: Arc<Mutex<Bus>>,
+}
+
+impl Bsc {
+    /// Construct a `Bsc` with no slaves.
+    pub fn new() -> Self {
+        let device = Device::new();
+        device.set_semantics(
+            S,
+            Semantics::W1c(S::DONE::SET.value | S::ERR::SET.value | S::CLKT::SET.value),
+        );
+        device.set_semantics(FIFO, Semantics::ReadOnly);
+
+        let bus = Arc::new(Mutex::new(Bus::default()));
+        let bus2 = Arc::clone(&bus);
+        device.on_write(C, move |state, value| {
+            let mut bus = bus2.lock().unwrap();
+            if value & C::CLEAR::Clear.value != 0 {
+                bus.tx.clear();
+                bus.rx.clear();
+            }
+            if value & C::I2CEN::SET.value == 0 {
+                bus.active = None;
+                bus.queued = None;
+            } else if value & C::ST::SET.value != 0 {
+                let transfer = (state.get(A), state.get(DLEN), value);
+                if bus.active.is_some() {
+                    bus.queued = Some(transfer);
+                } else {
+                    bus.start(transfer, false);
+                }
+            }
+        });
+        device.on_read(C, |state| {
+            state.clear_bits(C, C::CLEAR::Clear.value | C::ST::SET.value);
+        });
+        let bus2 = Arc::clone(&bus);
+        device.on_read(S, move |state| {
+            let mut bus = bus2.lock().unwrap();
+            let mut value = state.get(S) & (S::DONE::SET + S::ERR::SET + S::CLKT::SET).value;
+            if bus.active.is_some() {
+                value |= S::TA::SET.value;
+            }
+            if bus.tx.len() < FIFO_LEN {
+                value |= S::TXD::SET.value;
+            }
+            if bus.tx.is_empty() {
+                value |= S::TXE::SET.value;
+            }
+            if !bus.rx.is_empty() {
+                value |= S::RXD::SET.value;
+            }
+            if bus.rx.len() >= FIFO_LEN {
+                value |= S::RXF::SET.value;
+            }
+            state.set(S, value);
+            bus.step(state);
+        });
+        let bus2 = Arc::clone(&bus);
+        device.on_write(FIFO, move |_, value| {
+            let mut bus = bus2.lock().unwrap();
+            if bus.tx.len() < FIFO_LEN {
+                bus.tx.push_back(value as u8);
+            }
+        });
+        let bus2 = Arc::clone(&bus);
+        device.on_read(FIFO, move |state| {
+            let byte = bus2.lock().unwrap().rx.pop_front().unwrap_or(0);
+            state.set(FIFO, byte.into());
+        });
+
+        Self { device, bus }
+    }
+
+    /// Attach a slave acknowledging the 7-bit address `addr`.
+    pub fn attach(&self, addr: u8) {
+        self.bus.lock().unwrap().slaves.entry(addr).or_default();
+    }
+
+    /// Set the bytes returned by the following reads from the slave at
+    /// `addr`, attaching it if necessary.
+    pub fn set_response(&self, addr: u8, bytes: &[u8]) {
+        self.bus
+            .lock()
+            .unwrap()
+            .slaves
+            .entry(addr)
+            .or_default()
+            .response = bytes.iter().copied().collect();
+    }
+
+    /// Make the slave at `addr` stretch the clock indefinitely after
+    /// acknowledging its address, attaching it if necessary.
+    pub fn set_stretching(&self, addr: u8, stretching: bool) {
+        self.bus
+            .lock()
+            .unwrap()
+            .slaves
+            .entry(addr)
+            .or_default()
+            .stretching = stretching;
+    }
+
+    /// Take the transfers completed so far.
+    pub fn take_transfers(&self) -> Vec<Transfer> {
+        core::mem::take(&mut self.bus.lock().unwrap().transfers)
+    }
+
+    /// Get the number of bytes in the FIFO.
+    pub fn fifo_len(&self) -> usize {
+        let bus = self.bus.lock().unwrap();
+        bus.tx.len() + bus.rx.len()
+    }
+}
+
+impl Default for Bsc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for Bsc {
+    type Target = Device<Registers>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.device
+    }
+}
+
+impl Bus {
+    /// Start the transfer described by `(a, dlen, c)`.
+    fn start(&mut self, (a, dlen, c): (u32, u32, u32), repeated_start: bool) {
+        self.active = Some(Active {
+            transfer: Transfer {
+                addr: (a & A::ADDR.mask) as u8,
+                read: c & C::READ::Read.value != 0,
+                data: Vec::new(),
+                repeated_start,
+            },
+            len: (dlen & DLEN::DLEN.mask) as usize,
+            addressed: false,
+        });
+    }
+
+    /// Advance the active transfer by one step.
+    fn step(&mut self, state: &mut State) {
+        let active = match &mut self.active {
+            Some(active) => active,
+            None => return,
+        };
+        let addr = active.transfer.addr;
+        if !active.addressed {
+            match self.slaves.get(&addr) {
+                None => return self.fail(state, S::ERR::SET.value),
+                Some(slave) if slave.stretching => return self.fail(state, S::CLKT::SET.value),
+                Some(_) => active.addressed = true,
+            }
+        } else if active.transfer.read {
+            if self.rx.len() < FIFO_LEN {
+                let slave = self.slaves.get_mut(&addr).unwrap();
+                let byte = slave.response.pop_front().unwrap_or(0xff);
+                self.rx.push_back(byte);
+                active.transfer.data.push(byte);
+            }
+        } else if let Some(byte) = self.tx.pop_front() {
+            active.transfer.data.push(byte);
+        }
+
+        if active.transfer.data.len() == active.len {
+            let active = self.active.take().unwrap();
+            self.transfers.push(active.transfer);
+            state.set_bits(S, S::DONE::SET.value);
+            if let Some(queued) = self.queued.take() {
+                self.start(queued, true);
+            }
+        }
+    }
+
+    /// End the transfers with the error flag `flag`.
+    fn fail(&mut self, state: &mut State, flag: u32) {
+        self.active = None;
+        self.queued = None;
+        state.set_bits(S, flag | S::DONE::SET.value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tock_registers::interfaces::{Readable, Writeable};
+
+    /// Read `s` until `s.TA` is clear.
+    fn run(regs: &Registers) {
+        while regs.s.is_set(S::TA) {}
+    }
+
+    #[test]
+    fn write() {
+        let device = Bsc::new();
+        let regs = device.regs();
+        device.attach(0x50);
+        regs.a.write(A::ADDR.val(0x50));
+        regs.dlen.write(DLEN::DLEN.val(3));
+        regs.fifo.set(1);
+        regs.fifo.set(2);
+        regs.c.write(C::I2CEN::SET + C::ST::SET);
+        assert_eq!(regs.c.get(), C::I2CEN::SET.value);
+
+        // Stalls on the empty FIFO
+        for _ in 0..10 {
+            assert!(regs.s.is_set(S::TA));
+        }
+        regs.fifo.set(3);
+        run(regs);
+        let s = regs.s.extract();
+        assert!(s.is_set(S::DONE));
+        assert!(s.is_set(S::TXE));
+        assert!(!s.is_set(S::ERR));
+        assert_eq!(
+            device.take_transfers(),
+            [Transfer {
+                addr: 0x50,
+                read: false,
+                data: std::vec![1, 2, 3],
+                repeated_start: false,
+            }]
+        );
+
+        regs.s.write(S::DONE::SET);
+        assert!(!regs.s.is_set(S::DONE));
+    }
+
+    #[test]
+    fn repeated_start() {
+        let device = Bsc::new();
+        let regs = device.regs();
+        device.set_response(0x20, &[0xaa]);
+        regs.a.write(A::ADDR.val(0x20));
+        regs.dlen.write(DLEN::DLEN.val(1));
+        regs.fifo.set(0x10);
+        regs.c.write(C::I2CEN::SET + C::ST::SET);
+        assert!(regs.s.is_set(S::TA));
+        regs.dlen.write(DLEN::DLEN.val(2));
+        regs.c.write(C::I2CEN::SET + C::ST::SET + C::READ::Read);
+        run(regs);
+        assert_eq!(regs.fifo.get(), 0xaa);
+        assert_eq!(regs.fifo.get(), 0xff);
+        assert!(!regs.s.is_set(S::RXD));
+        let transfers = device.take_transfers();
+        assert_eq!(transfers.len(), 2);
+        assert!(!transfers[0].repeated_start);
+        assert_eq!(
+            transfers[1],
+            Transfer {
+                addr: 0x20,
+                read: true,
+                data: std::vec![0xaa, 0xff],
+                repeated_start: true,
+            }
+        );
+    }
+
+    #[test]
+    fn rx_full() {
+        let device = Bsc::new();
+        let regs = device.regs();
+        device.attach(0x20);
+        regs.a.write(A::ADDR.val(0x20));
+        regs.dlen.write(DLEN::DLEN.val(20));
+        regs.c.write(C::I2CEN::SET + C::ST::SET + C::READ::Read);
+        for _ in 0..40 {
+            regs.s.get();
+        }
+        assert!(regs.s.is_set(S::RXF));
+        assert!(regs.s.is_set(S::TA));
+        assert_eq!(device.fifo_len(), FIFO_LEN);
+
+        regs.c.write(C::CLEAR::Clear);
+        assert_eq!(device.fifo_len(), 0);
+        assert!(!regs.s.is_set(S::TA));
+    }
+
+    #[test]
+    fn errors() {
+        let device = Bsc::new();
+        let regs = device.regs();
+        regs.a.write(A::ADDR.val(0x20));
+        regs.c.write(C::I2CEN::SET + C::ST::SET);
+        run(regs);
+        let s = regs.s.extract();
+        assert!(s.is_set(S::ERR));
+        assert!(s.is_set(S::DONE));
+        regs.s.write(S::ERR::SET + S::DONE::SET);
+
+        device.set_stretching(0x20, true);
+        regs.c.write(C::I2CEN::SET + C::ST::SET);
+        run(regs);
+        assert!(regs.s.is_set(S::CLKT));
+        assert!(!regs.s.is_set(S::ERR));
+        assert!(device.take_transfers().is_empty());
+    }
+}
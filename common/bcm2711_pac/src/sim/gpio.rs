@@ -0,0 +1,219 @@
+//! A model of [the GPIO register block](crate::gpio::Registers)
+//!
+//!  - Writing to `gpset`/`gpclr` changes the output latches, which drive the
+//!    levels (`gplev`) of the pins configured as outputs. The latches are
+//!    kept in the stored values of `gpset`. The levels of the other pins are
+//!    set by [`Gpio::set_input`].
+//!  - A level change sets the pin's bit in `gpeds` if the corresponding edge
+//!    detection (`gpren`/`gpfen`/`gparen`/`gpafen`) is enabled. The
+//!    synchronous and asynchronous edge detection behave identically.
+//!  - `gpeds` is write-1-to-clear. The bits of the pins whose high/low
+//!    detection (`gphen`/`gplen`) is enabled are set again while the level
+//!    persists.
+use core::ops::Deref;
+
+use super::{Device, Semantics, State};
+use crate::gpio::{Function, Registers, GPFSEL, NUM_PINS};
+
+const GPFSEL: usize = 0x00;
+const GPSET: usize = 0x1c;
+const GPCLR: usize = 0x28;
+const GPLEV: usize = 0x34;
+const GPEDS: usize = 0x40;
+const GPREN: usize = 0x4c;
+const GPFEN: usize = 0x58;
+const GPHEN: usize = 0x64;
+const GPLEN: usize = 0x70;
+const GPAREN: usize = 0x7c;
+const GPAFEN: usize = 0x88;
+
+/// A simulated GPIO register block. Dereferences to [`Device`].
+#[derive(Debug)]
+pub struct Gpio {
+    device: Device<Registers>,
+}
+
+impl Gpio {
+    /// Construct a `Gpio` with all pins configured as inputs and low.
+    pub fn new() -> Self {
+        let device = Device::new();
+        for bank in 0..2 {
+            let o = bank * 4;
+            device.set_semantics(GPSET + o, Semantics::WriteOnly);
+            device.set_semantics(GPCLR + o, Semantics::WriteOnly);
+            device.set_semantics(GPLEV + o, Semantics::ReadOnly);
+            device.set_semantics(GPEDS + o, Semantics::W1c(u32::MAX));
+
+            device.on_write(GPSET + o, move |state, value| {
+                state.set_bits(GPSET + o, value);
+                update_outputs(state, bank);
+            });
+            device.on_write(GPCLR + o, move |state, value| {
+                state.clear_bits(GPSET + o, value);
+                update_outputs(state, bank);
+            });
+            device.on_read(GPEDS + o, move |state| {
+                let level = state.get(GPLEV + o);
+                let detected = (state.get(GPHEN + o) & level) | (state.get(GPLEN + o) & !level);
+                state.set_bits(GPEDS + o, detected);
+            });
+        }
+        for i in 0..6 {
+            // Hooks run before the value is stored, so store it here
+            device.on_write(GPFSEL + i * 4, move |state, value| {
+                state.set(GPFSEL + i * 4, value);
+                update_outputs(state, 0);
+                update_outputs(state, 1);
+            });
+        }
+        Self { device }
+    }
+
+    /// Drive the input pin `pin` high or low. This has no effect on the pins
+    /// configured as outputs.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `pin` is outside the range `0..`[`NUM_PINS`].
+    pub fn set_input(&self, pin: usize, high: bool) {
+        assert!(pin < NUM_PINS);
+        let (bank, bit) = (pin / 32, 1 << (pin % 32));
+        self.device.with_state(|state| {
+            if output_mask(state, bank) & bit != 0 {
+                return;
+            }
+            let level = state.get(GPLEV + bank * 4);
+            set_level(state, bank, if high { level | bit } else { level & !bit });
+        });
+    }
+
+    /// Get the level of the pin `pin`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `pin` is outside the range `0..`[`NUM_PINS`].
+    pub fn level(&self, pin: usize) -> bool {
+        assert!(pin < NUM_PINS);
+        self.device.get(GPLEV + pin / 32 * 4) & (1 << (pin % 32)) != 0
+    }
+}
+
+impl Default for Gpio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for Gpio {
+    type Target = Device<Registers>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.device
+    }
+}
+
+/// Get the bit mask of the pins configured as outputs in `bank`.
+fn output_mask(state: &State, bank: usize) -> u32 {
+    let per_reg = GPFSEL::PINS_PER_REGISTER;
+    (0..32)
+        .map(|i| bank * 32 + i)
+        .take_while(|&pin| pin < NUM_PINS)
+        .filter(|&pin| {
+            let fsel = state.get(GPFSEL + pin / per_reg * 4) >> (pin % per_reg * 3) & 0b111;
+            Function::from_field_value(fsel) == Some(Function::Output)
+        })
+        .fold(0, |mask, pin| mask | 1 << (pin % 32))
+}
+
+/// Drive the outputs of `bank` with the output latches.
+fn update_outputs(state: &mut State, bank: usize) {
+    let o = bank * 4;
+    let mask = output_mask(state, bank);
+    let level = (state.get(GPLEV + o) & !mask) | (state.get(GPSET + o) & mask);
+    set_level(state, bank, level);
+}
+
+/// Update the levels of `bank` and detect edges.
+fn set_level(state: &mut State, bank: usize, level: u32) {
+    let o = bank * 4;
+    let old = state.get(GPLEV + o);
+    let rising = level & !old;
+    let falling = old & !level;
+    let detected = (rising & (state.get(GPREN + o) | state.get(GPAREN + o)))
+        | (falling & (state.get(GPFEN + o) | state.get(GPAFEN + o)));
+    state.set(GPLEV + o, level);
+    state.set_bits(GPEDS + o, detected);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpio::{GPEDS, GPLEV};
+    use tock_registers::interfaces::{Readable, Writeable};
+
+    #[test]
+    fn set_clear() {
+        let device = Gpio::new();
+        let regs = device.regs();
+        regs.set_function(5, Function::Output);
+        regs.set_function(40, Function::Output);
+
+        regs.gpset[0].set(1 << 5 | 1 << 6);
+        regs.gpset[1].set(1 << (40 - 32));
+        assert!(regs.gplev[0].is_set(GPLEV::pin(5)));
+        assert!(device.level(40));
+        // Not an output
+        assert!(!regs.gplev[0].is_set(GPLEV::pin(6)));
+
+        regs.gpclr[0].set(1 << 5);
+        assert!(!device.level(5));
+        assert!(device.level(40));
+
+        // Inputs follow `set_input`, outputs don't
+        regs.gpset[0].set(1 << 5);
+        device.set_input(6, true);
+        device.set_input(5, false);
+        assert_eq!(regs.gplev[0].get(), 1 << 5 | 1 << 6);
+
+        // The latched level appears when the pin becomes an output
+        device.set_input(6, false);
+        regs.gpclr[0].set(1 << 5);
+        regs.set_function(6, Function::Output);
+        assert!(device.level(6));
+        regs.set_function(5, Function::Input);
+        regs.set_function(5, Function::Output);
+        assert!(!device.level(5));
+    }
+
+    #[test]
+    fn event_detect() {
+        let device = Gpio::new();
+        let regs = device.regs();
+        regs.gpren[0].set(1 << 2);
+        regs.gpafen[0].set(1 << 3);
+        regs.gphen[0].set(1 << 4);
+
+        device.set_input(2, true);
+        device.set_input(3, true);
+        assert_eq!(regs.gpeds[0].get(), 1 << 2);
+
+        device.set_input(2, false);
+        device.set_input(3, false);
+        assert_eq!(regs.gpeds[0].get(), 1 << 2 | 1 << 3);
+
+        // Write-1-to-clear
+        regs.gpeds[0].write(GPEDS::pin(2).val(1));
+        assert_eq!(regs.gpeds[0].get(), 1 << 3);
+        regs.gpeds[0].set(1 << 3);
+        assert_eq!(regs.gpeds[0].get(), 0);
+
+        // The high detection persists while the level is high
+        device.set_input(4, true);
+        regs.gpeds[0].set(1 << 4);
+        assert_eq!(regs.gpeds[0].get(), 1 << 4);
+        device.set_input(4, false);
+        regs.gpeds[0].set(1 << 4);
+        assert_eq!(regs.gpeds[0].get(), 0);
+    }
+}
@@ -0,0 +1,264 @@
+//! A model of [the PL011 UART register block](crate::pl011::Registers)
+//!
+//!  - Writing to `dr` transmits a character, which can be retrieved by
+//!    [`Uart::take_tx`]. The transmit FIFO drains instantly, so `fr.TXFE` is
+//!    always set, and each write sets `ris.TXRIS`.
+//!  - If `cr.CTSEN` is set and `CTS` is deasserted by [`Uart::set_cts`],
+//!    written characters are held in the transmit FIFO instead. `fr.TXFF` and
+//!    `fr.TXFE` reflect the held characters, and [`Uart::take_tx`] returns
+//!    nothing. Asserting `CTS` releases them and sets `ris.TXRIS`.
+//!  - Reading `dr` pops a character pushed by [`Uart::push_rx`] or
+//!    [`Uart::push_rx_raw`]. `fr.RXFE`, `fr.RXFF`, `ris.RXRIS` (according to
+//!    `ifls.RXIFLSEL`), and `ris.RTRIS` reflect the receive FIFO. The receive
+//!    timeout is considered elapsed whenever the FIFO isn't empty.
+//!  - `mis` is `ris & imsc`. Writing to `icr` clears the `ris` bits.
+//!  - `lcrh` and the other bits of `cr` don't affect the behavior.
+extern crate std;
+
+use core::ops::Deref;
+use std::vec::Vec;
+
+use super::{Device, Semantics, State};
+use crate::pl011::{Registers, CR, FR, IFLS, RIS};
+
+const DR: usize = 0x00;
+const FR: usize = 0x18;
+const CR: usize = 0x30;
+const IFLS: usize = 0x34;
+const IMSC: usize = 0x38;
+const RIS: usize = 0x3c;
+const MIS: usize = 0x40;
+const ICR: usize = 0x44;
+
+/// The depth of the receive and transmit FIFOs
+const FIFO_LEN: usize = 32;
+
+/// A simulated PL011 UART register block. Dereferences to [`Device`].
+#[derive(Debug)]
+pub struct Uart {
+    device: Device<Registers>,
+}
+
+impl Uart {
+    /// Construct a `Uart` with empty FIFOs and `CTS` asserted.
+    pub fn new() -> Self {
+        let device = Device::new();
+        device.set_semantics(DR, Semantics::Fifo);
+        device.set_semantics(FR, Semantics::ReadOnly);
+        device.set_semantics(RIS, Semantics::ReadOnly);
+        device.set_semantics(MIS, Semantics::ReadOnly);
+        device.set_semantics(ICR, Semantics::ClearBits(RIS));
+        device.set(FR, FR::CTS::SET.value);
+
+        device.on_read(FR, |state| {
+            let rx_len = state.rx_len(DR);
+            let mut value = state.get(FR) & FR::CTS::SET.value;
+            if !is_held(state) || state.tx_len(DR) == 0 {
+                value |= FR::TXFE::SET.value;
+            }
+            if is_held(state) && state.tx_len(DR) >= FIFO_LEN {
+                value |= FR::TXFF::SET.value;
+            }
+            if rx_len == 0 {
+                value |= FR::RXFE::SET.value;
+            }
+            if rx_len >= FIFO_LEN {
+                value |= FR::RXFF::SET.value;
+            }
+            state.set(FR, value);
+        });
+        device.on_read(RIS, update_ris);
+        device.on_read(MIS, |state| {
+            update_ris(state);
+            state.set(MIS, state.get(RIS) & state.get(IMSC));
+        });
+        device.on_write(DR, |state, _| {
+            if !is_held(state) {
+                state.set_bits(RIS, RIS::TXRIS::SET.value);
+            }
+        });
+
+        Self { device }
+    }
+
+    /// Receive `bytes`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the receive FIFO overflows.
+    pub fn push_rx(&self, bytes: &[u8]) {
+        for &b in bytes {
+            self.push_rx_raw(b.into());
+        }
+    }
+
+    /// Receive a raw `dr` value, which may include the error bits
+    /// (`FE`, `PE`, `BE`, and `OE`). The error bits are also reflected to
+    /// the corresponding bits of `ris`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the receive FIFO overflows.
+    pub fn push_rx_raw(&self, value: u32) {
+        self.device.with_state(|state| {
+            assert!(state.rx_len(DR) < FIFO_LEN, "receive FIFO overflow");
+            state.push_rx(DR, value);
+            // `DR::{FE, PE, BE, OE}` → `RIS::{FERIS, PERIS, BERIS, OERIS}`
+            state.set_bits(RIS, (value >> 8 & 0b1111) << 7);
+        })
+    }
+
+    /// Get the number of characters in the receive FIFO.
+    pub fn rx_len(&self) -> usize {
+        self.device.with_state(|state| state.rx_len(DR))
+    }
+
+    /// Take the characters transmitted so far. Returns nothing while the
+    /// transmit FIFO is held by the flow control.
+    pub fn take_tx(&self) -> Vec<u8> {
+        self.device.with_state(|state| {
+            if is_held(state) {
+                return Vec::new();
+            }
+            core::iter::from_fn(|| state.pop_tx(DR))
+                .map(|value| value as u8)
+                .collect()
+        })
+    }
+
+    /// Assert or deassert `CTS` (`fr.CTS`).
+    pub fn set_cts(&self, asserted: bool) {
+        self.device.with_state(|state| {
+            if asserted {
+                let released = is_held(state) && state.tx_len(DR) > 0;
+                state.set_bits(FR, FR::CTS::SET.value);
+                if released {
+                    state.set_bits(RIS, RIS::TXRIS::SET.value);
+                }
+            } else {
+                state.clear_bits(FR, FR::CTS::SET.value);
+            }
+        })
+    }
+}
+
+impl Default for Uart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for Uart {
+    type Target = Device<Registers>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.device
+    }
+}
+
+/// Check if the transmit FIFO is held by the CTS flow control.
+fn is_held(state: &State) -> bool {
+    state.get(CR) & CR::CTSEN::SET.value != 0 && state.get(FR) & FR::CTS::SET.value == 0
+}
+
+/// Update the receive interrupt bits of `ris` according to the receive FIFO.
+fn update_ris(state: &mut State) {
+    let rx_len = state.rx_len(DR);
+    let threshold = match IFLS::RXIFLSEL.read(state.get(IFLS)) {
+        0b000 => FIFO_LEN / 8,
+        0b001 => FIFO_LEN / 4,
+        0b010 => FIFO_LEN / 2,
+        0b011 => FIFO_LEN * 3 / 4,
+        _ => FIFO_LEN * 7 / 8,
+    };
+    let mut value = state.get(RIS) & !(RIS::RXRIS::SET.value | RIS::RTRIS::SET.value);
+    if rx_len >= threshold {
+        value |= RIS::RXRIS::SET.value;
+    }
+    if rx_len > 0 {
+        value |= RIS::RTRIS::SET.value;
+    }
+    state.set(RIS, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pl011::{DR, ICR, IMSC, MIS};
+    use tock_registers::interfaces::{Readable, Writeable};
+
+    #[test]
+    fn tx() {
+        let device = Uart::new();
+        let regs = device.regs();
+        assert!(regs.fr.is_set(FR::TXFE));
+        for &b in b"hi" {
+            regs.dr.set(b.into());
+        }
+        assert_eq!(device.take_tx(), b"hi");
+        assert!(device.take_tx().is_empty());
+
+        assert!(regs.ris.is_set(RIS::TXRIS));
+        assert!(!regs.mis.is_set(MIS::TXMIS));
+        regs.imsc.write(IMSC::TXIM::SET);
+        assert!(regs.mis.is_set(MIS::TXMIS));
+        regs.icr.write(ICR::TXIC::SET);
+        assert!(!regs.mis.is_set(MIS::TXMIS));
+    }
+
+    #[test]
+    fn rx() {
+        let device = Uart::new();
+        let regs = device.regs();
+        regs.ifls.write(IFLS::RXIFLSEL::OneEighth);
+        assert!(regs.fr.is_set(FR::RXFE));
+        assert!(!regs.ris.is_set(RIS::RTRIS));
+
+        device.push_rx(b"abc");
+        assert!(!regs.fr.is_set(FR::RXFE));
+        assert!(regs.ris.is_set(RIS::RTRIS));
+        assert!(!regs.ris.is_set(RIS::RXRIS));
+        device.push_rx(b"d");
+        assert!(regs.ris.is_set(RIS::RXRIS));
+
+        let received: Vec<u8> = (0..4).map(|_| regs.dr.read(DR::DATA) as u8).collect();
+        assert_eq!(received, b"abcd");
+        assert!(regs.fr.is_set(FR::RXFE));
+        assert_eq!(regs.ris.get(), 0);
+
+        // Errors
+        device.push_rx_raw(0x41 | DR::FE::SET.value);
+        assert!(regs.ris.is_set(RIS::FERIS));
+        assert!(regs.dr.is_set(DR::FE));
+        regs.icr.write(ICR::FEIC::SET);
+        assert!(!regs.ris.is_set(RIS::FERIS));
+    }
+
+    #[test]
+    fn cts() {
+        let device = Uart::new();
+        let regs = device.regs();
+        assert!(regs.fr.is_set(FR::CTS));
+        device.set_cts(false);
+        assert!(!regs.fr.is_set(FR::CTS));
+        assert!(regs.fr.is_set(FR::TXFE));
+
+        // Flow control holds the transmit FIFO
+        regs.cr.write(CR::CTSEN::SET);
+        regs.icr.write(ICR::TXIC::SET);
+        for i in 0..FIFO_LEN {
+            assert!(!regs.fr.is_set(FR::TXFF));
+            regs.dr.set(i as u32);
+        }
+        assert!(regs.fr.is_set(FR::TXFF));
+        assert!(!regs.fr.is_set(FR::TXFE));
+        assert!(!regs.ris.is_set(RIS::TXRIS));
+        assert!(device.take_tx().is_empty());
+
+        device.set_cts(true);
+        assert!(regs.ris.is_set(RIS::TXRIS));
+        assert!(!regs.fr.is_set(FR::TXFF));
+        assert_eq!(device.take_tx().len(), FIFO_LEN);
+    }
+}
@@ -0,0 +1,75 @@
+//! Drop-in replacements for the register types of `tock_registers`
+//!
+//! The types have the same layout and interfaces as their `tock_registers`
+//! counterparts. An access to a register located in a [`Device`] is
+//! redirected to the device model. Other accesses go to the memory as usual,
+//! so register blocks in ordinary memory (e.g., [`Snapshot`]s) keep working.
+//!
+//! [`Device`]: super::Device
+//! [`Snapshot`]: crate::snapshot::Snapshot
+use core::{cell::UnsafeCell, marker::PhantomData};
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    RegisterLongName, UIntLike,
+};
+
+/// Read-write register
+#[repr(transparent)]
+pub struct ReadWrite<T: UIntLike, R: RegisterLongName = ()> {
+    value: UnsafeCell<T>,
+    associated_register: PhantomData<R>,
+}
+
+impl<R: RegisterLongName> Readable for ReadWrite<u32, R> {
+    type T = u32;
+    type R = R;
+
+    #[inline]
+    fn get(&self) -> u32 {
+        super::read(self.value.get())
+    }
+}
+
+impl<R: RegisterLongName> Writeable for ReadWrite<u32, R> {
+    type T = u32;
+    type R = R;
+
+    #[inline]
+    fn set(&self, value: u32) {
+        super::write(self.value.get(), value)
+    }
+}
+
+/// Read-only register
+#[repr(transparent)]
+pub struct ReadOnly<T: UIntLike, R: RegisterLongName = ()> {
+    value: T,
+    associated_register: PhantomData<R>,
+}
+
+impl<R: RegisterLongName> Readable for ReadOnly<u32, R> {
+    type T = u32;
+    type R = R;
+
+    #[inline]
+    fn get(&self) -> u32 {
+        super::read(&self.value)
+    }
+}
+
+/// Write-only register
+#[repr(transparent)]
+pub struct WriteOnly<T: UIntLike, R: RegisterLongName = ()> {
+    value: UnsafeCell<T>,
+    associated_register: PhantomData<R>,
+}
+
+impl<R: RegisterLongName> Writeable for WriteOnly<u32, R> {
+    type T = u32;
+    type R = R;
+
+    #[inline]
+    fn set(&self, value: u32) {
+        super::write(self.value.get(), value)
+    }
+}
@@ -0,0 +1,179 @@
+//! A model of [the SPI master register block](crate::spi::Registers)
+//!
+//!  - While `cs.TA` is set and `cs.DMAEN` is clear, writing a byte to `fifo`
+//!    shifts it out instantly. The byte can be retrieved by [`Spi::take_tx`],
+//!    and the byte shifted in at the same time (see [`Spi::set_response`]) is
+//!    pushed to the receive FIFO, which is read from `fifo`.
+//!  - `cs.DONE`, `cs.RXD`, `cs.TXD`, `cs.RXR`, and `cs.RXF` reflect the
+//!    receive FIFO (64 bytes deep). `cs.TXD` is clear while the receive FIFO
+//!    is full, so no bytes are lost. `cs.CLEAR_TX` and `cs.CLEAR_RX` read as
+//!    zero and don't clear anything.
+//!  - In the DMA mode (`cs.DMAEN` set), writes to `fifo` are only recorded.
+//!    The DMA engines are not modeled.
+extern crate std;
+
+use core::ops::Deref;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    vec::Vec,
+};
+
+use super::{Device, Semantics, State};
+use crate::spi::{Registers, CS};
+
+const CS: usize = 0x00;
+const FIFO: usize = 0x04;
+
+/// The depth of the receive FIFO
+const FIFO_LEN: usize = 64;
+
+/// A simulated SPI master register block. Dereferences to [`Device`].
+#[derive(Debug)]
+pub struct Spi {
+    device: Device<Registers>,
+    response: Arc<Mutex<VecDeque<u8>>>,
+}
+
+impl Spi {
+    /// Construct a `Spi` with empty FIFOs.
+    pub fn new() -> Self {
+        let device = Device::new();
+        device.set_semantics(FIFO, Semantics::Fifo);
+
+        device.on_read(CS, |state| {
+            let rx_len = state.rx_len(FIFO);
+            let mut value =
+                state.get(CS) & !(CS::CLEAR_TX::SET.value | CS::CLEAR_RX::SET.value | STATUS);
+            if value & CS::TA::SET.value != 0 {
+                value |= CS::DONE::SET.value;
+            }
+            if rx_len > 0 {
+                value |= CS::RXD::SET.value;
+            }
+            if rx_len < FIFO_LEN {
+                value |= CS::TXD::SET.value;
+            }
+            if rx_len >= FIFO_LEN * 3 / 4 {
+                value |= CS::RXR::SET.value;
+            }
+            if rx_len >= FIFO_LEN {
+                value |= CS::RXF::SET.value;
+            }
+            state.set(CS, value);
+        });
+
+        let response = Arc::new(Mutex::new(VecDeque::new()));
+        let response2 = Arc::clone(&response);
+        device.on_write(FIFO, move |state, _| {
+            if is_shifting(state) {
+                let byte = response2.lock().unwrap().pop_front().unwrap_or(0);
+                state.push_rx(FIFO, byte.into());
+            }
+        });
+
+        Self { device, response }
+    }
+
+    /// Set the bytes shifted in by the following transfers. Zero is shifted
+    /// in after they are exhausted.
+    pub fn set_response(&self, bytes: &[u8]) {
+        *self.response.lock().unwrap() = bytes.iter().copied().collect();
+    }
+
+    /// Take the values written to `fifo` so far, truncated to bytes.
+    pub fn take_tx(&self) -> Vec<u8> {
+        self.device.with_state(|state| {
+            core::iter::from_fn(|| state.pop_tx(FIFO))
+                .map(|value| value as u8)
+                .collect()
+        })
+    }
+
+    /// Get the number of bytes in the receive FIFO.
+    pub fn rx_len(&self) -> usize {
+        self.device.with_state(|state| state.rx_len(FIFO))
+    }
+}
+
+impl Default for Spi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for Spi {
+    type Target = Device<Registers>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.device
+    }
+}
+
+/// The read-only status bits of `cs`
+const STATUS: u32 = CS::DONE::SET.value
+    | CS::RXD::SET.value
+    | CS::TXD::SET.value
+    | CS::RXR::SET.value
+    | CS::RXF::SET.value;
+
+/// Check if writes to `fifo` are shifted out.
+fn is_shifting(state: &State) -> bool {
+    let cs = state.get(CS);
+    cs & CS::TA::SET.value != 0 && cs & CS::DMAEN::SET.value == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tock_registers::interfaces::{Readable, Writeable};
+
+    #[test]
+    fn transfer() {
+        let device = Spi::new();
+        let regs = device.regs();
+        assert!(!regs.cs.is_set(CS::DONE));
+
+        regs.cs.write(CS::TA::SET + CS::CLEAR_RX::SET);
+        assert!(regs.cs.is_set(CS::DONE));
+        assert!(regs.cs.is_set(CS::TXD));
+        assert!(!regs.cs.is_set(CS::RXD));
+        assert!(!regs.cs.is_set(CS::CLEAR_RX));
+
+        device.set_response(b"ok");
+        for &b in b"hey" {
+            regs.fifo.set(b.into());
+        }
+        assert_eq!(device.take_tx(), b"hey");
+        assert!(regs.cs.is_set(CS::RXD));
+        let received: Vec<u8> = (0..3).map(|_| regs.fifo.get() as u8).collect();
+        assert_eq!(received, b"ok\0");
+        assert!(!regs.cs.is_set(CS::RXD));
+    }
+
+    #[test]
+    fn rx_full() {
+        let device = Spi::new();
+        let regs = device.regs();
+        regs.cs.write(CS::TA::SET);
+        for i in 0..FIFO_LEN {
+            assert!(regs.cs.is_set(CS::TXD));
+            regs.fifo.set(i as u32);
+        }
+        assert!(!regs.cs.is_set(CS::TXD));
+        assert!(regs.cs.is_set(CS::RXR));
+        assert!(regs.cs.is_set(CS::RXF));
+        assert_eq!(device.rx_len(), FIFO_LEN);
+    }
+
+    #[test]
+    fn dma() {
+        let device = Spi::new();
+        let regs = device.regs();
+        regs.cs.write(CS::TA::SET + CS::DMAEN::SET);
+        regs.fifo.set(0x0403_0201);
+        assert_eq!(device.rx_len(), 0);
+        assert_eq!(device.take_tx(), [0x01]);
+    }
+}
@@ -0,0 +1,164 @@
+//! A model of [the System Timer register block](crate::sys_timer::Registers)
+//!
+//!  - `clo` and `chi` are read-only. The counter only advances when
+//!    [`SysTimer::advance`] is called or, if [`SysTimer::auto_advance`] is
+//!    set, each time `clo` is read.
+//!  - When the lower 32 bits of the counter reach a compare register `c[i]`,
+//!    `cs.M[i]` is set. `cs.M[i]` is write-1-to-clear.
+extern crate std;
+
+use core::ops::Deref;
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+
+use super::{Device, Semantics, State};
+use crate::sys_timer::Registers;
+
+const CS: usize = 0x00;
+const CLO: usize = 0x04;
+const CHI: usize = 0x08;
+const C: usize = 0x0c;
+
+/// A simulated System Timer register block. Dereferences to [`Device`].
+#[derive(Debug)]
+pub struct SysTimer {
+    device: Device<Registers>,
+    step: Arc<AtomicU32>,
+}
+
+impl SysTimer {
+    /// Construct a `SysTimer` with the counter and all compare registers
+    /// zero.
+    pub fn new() -> Self {
+        let device = Device::new();
+        device.set_semantics(CS, Semantics::W1c(0b1111));
+        device.set_semantics(CLO, Semantics::ReadOnly);
+        device.set_semantics(CHI, Semantics::ReadOnly);
+
+        let step = Arc::new(AtomicU32::new(0));
+        let step2 = Arc::clone(&step);
+        device.on_read(CLO, move |state| {
+            advance(state, step2.load(Ordering::Relaxed).into())
+        });
+
+        Self { device, step }
+    }
+
+    /// Get the current value of the counter.
+    pub fn counter(&self) -> u64 {
+        self.device.with_state(counter)
+    }
+
+    /// Set the counter to `value` without matching the compare registers.
+    pub fn set_counter(&self, value: u64) {
+        self.device.with_state(|state| set_counter(state, value))
+    }
+
+    /// Advance the counter by `us` microseconds, setting `cs.M[i]` for each
+    /// compare register `c[i]` passed on the way.
+    pub fn advance(&self, us: u64) {
+        self.device.with_state(|state| advance(state, us))
+    }
+
+    /// Make each read of `clo` advance the counter by `us` microseconds
+    /// before returning it, so that code polling the counter makes progress.
+    /// `0` (the default) disables this.
+    pub fn auto_advance(&self, us: u32) {
+        self.step.store(us, Ordering::Relaxed);
+    }
+}
+
+impl Default for SysTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for SysTimer {
+    type Target = Device<Registers>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.device
+    }
+}
+
+fn counter(state: &mut State) -> u64 {
+    u64::from(state.get(CLO)) | u64::from(state.get(CHI)) << 32
+}
+
+fn set_counter(state: &mut State, value: u64) {
+    state.set(CLO, value as u32);
+    state.set(CHI, (value >> 32) as u32);
+}
+
+fn advance(state: &mut State, us: u64) {
+    if us == 0 {
+        return;
+    }
+    let old = counter(state);
+    for i in 0..4 {
+        // The compare registers are matched against the lower 32 bits
+        let distance = state
+            .get(C + i * 4)
+            .wrapping_sub(old as u32)
+            .wrapping_sub(1);
+        if u64::from(distance) < us {
+            state.set_bits(CS, 1 << i);
+        }
+    }
+    set_counter(state, old.wrapping_add(us));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tock_registers::interfaces::{Readable, Writeable};
+
+    #[test]
+    fn counter_and_match() {
+        let device = SysTimer::new();
+        let regs = device.regs();
+        device.set_counter(0x1_ffff_fff0);
+        regs.c[0].set(0x8000_0000);
+        regs.c[1].set(0);
+        regs.c[2].set(0x8000_0000);
+        regs.c[3].set(0x10);
+
+        // Read-only
+        regs.cs.set(0);
+        assert_eq!(regs.clo.get(), 0xffff_fff0);
+        assert_eq!(regs.chi.get(), 1);
+
+        device.advance(0x10);
+        assert_eq!(device.counter(), 0x2_0000_0000);
+        assert_eq!(regs.cs.get(), 0b0010);
+        device.advance(0x10);
+        assert_eq!(regs.cs.get(), 0b1010);
+
+        // Write-1-to-clear
+        regs.cs.write(crate::sys_timer::CS::M(1).val(1));
+        assert_eq!(regs.cs.get(), 0b1000);
+
+        // Matches aren't repeated until the counter wraps around
+        device.advance(0x1000);
+        assert_eq!(regs.cs.get(), 0b1000);
+    }
+
+    #[test]
+    fn auto_advance() {
+        let device = SysTimer::new();
+        let regs = device.regs();
+        regs.c[0].set(100);
+        device.auto_advance(30);
+        let mut reads = 0;
+        while !regs.cs.is_set(crate::sys_timer::CS::M(0)) {
+            regs.clo.get();
+            reads += 1;
+        }
+        assert_eq!(reads, 4);
+        assert_eq!(device.counter(), 120);
+    }
+}
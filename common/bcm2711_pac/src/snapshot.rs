@@ -0,0 +1,782 @@
+//! Register snapshots
+//!
+//! [`Snapshot`] captures the state of a whole register block for debugging.
+//! Every register is read once, except write-only registers and registers
+//! whose reads have side effects (e.g., popping a FIFO or acknowledging an
+//! interrupt), which are left out. The captured values are stored in a copy of
+//! the register block in RAM, so the usual `tock_registers` accessors work on
+//! it, and its `Debug` and `Display` implementations decode every field by
+//! name.
+//!
+//! ```rust,no_run
+//! use bcm2711_pac::pl011;
+//! use tock_registers::interfaces::Readable;
+//!
+//! let uart0 = unsafe {
+//!     &*(pl011::BASE_UART0.to_arm_pa().unwrap() as *const pl011::Registers)
+//! };
+//! let snapshot = uart0.snapshot();
+//!
+//! // Access the captured values like the real registers
+//! let enabled = snapshot.cr.is_set(pl011::CR::UARTEN);
+//!
+//! // `Registers { rsrecr: RSRECR { FE: 0, PE: 0, BE: 0, OE: 0 }, fr: FR { … }, … }`
+//! println!("{snapshot:?}");
+//!
+//! // One line per register:
+//! // `0x030 cr = 0x00000301 CR { UARTEN: 1, SIREN: 0, …, TXE: 1, RXE: 1, … }`
+//! println!("{snapshot}");
+//! ```
+//!
+//! The registers are read one by one, so the snapshot is not atomic.
+use core::{fmt, marker::PhantomData, mem::MaybeUninit, ops::Deref};
+use tock_registers::{interfaces::Readable, RegisterLongName};
+
+use crate::{
+    describe::{Access, RegisterDesc},
+    registers::{ReadOnly, ReadWrite, WriteOnly},
+};
+
+/// Describes a bit field of a register
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldInfo {
+    /// The field name
+    pub name: &'static str,
+    /// The bit position of the first instance
+    pub offset: u32,
+    /// The number of bits
+    pub width: u32,
+    /// The number of instances. Instance `i` is located at bit position
+    /// `offset + width * i`.
+    pub count: u32,
+    /// The named values of the field
+    pub values: &'static [(&'static str, u32)],
+}
+
+impl FieldInfo {
+    /// Construct a `FieldInfo` describing a single field.
+    pub const fn new(
+        name: &'static str,
+        offset: u32,
+        width: u32,
+        values: &'static [(&'static str, u32)],
+    ) -> Self {
+        Self::array(name, offset, width, 1, values)
+    }
+
+    /// Construct a `FieldInfo` describing `count` adjacent instances of a
+    /// field.
+    pub const fn array(
+        name: &'static str,
+        offset: u32,
+        width: u32,
+        count: u32,
+        values: &'static [(&'static str, u32)],
+    ) -> Self {
+        Self {
+            name,
+            offset,
+            width,
+            count,
+            values,
+        }
+    }
+
+    /// Extract instance `i` of the field from a register value.
+    #[inline]
+    pub const fn extract(&self, register_value: u32, i: u32) -> u32 {
+        let mask = if self.width >= 32 {
+            u32::MAX
+        } else {
+            (1 << self.width) - 1
+        };
+        (register_value >> (self.offset + self.width * i)) & mask
+    }
+
+    /// Get the name of a field value.
+    pub fn value_name(&self, value: u32) -> Option<&'static str> {
+        self.values
+            .iter()
+            .find(|&&(_, v)| v == value)
+            .map(|&(name, _)| name)
+    }
+
+    fn fmt_value(&self, value: u32, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value_name(value) {
+            Some(name) => f.write_str(name),
+            None if value < 10 => write!(f, "{}", value),
+            None => write!(f, "{:#x}", value),
+        }
+    }
+}
+
+/// Describes the bit fields of a register. Implemented by the `Register`
+/// types of the bit field modules (e.g., [`pl011::CR::Register`]).
+///
+/// [`pl011::CR::Register`]: crate::pl011::CR::Register
+pub trait RegisterInfo: RegisterLongName {
+    /// The register name
+    const NAME: &'static str;
+    /// The bit fields
+    const FIELDS: &'static [FieldInfo];
+}
+
+/// A register without bit field definitions
+impl RegisterInfo for () {
+    const NAME: &'static str = "";
+    const FIELDS: &'static [FieldInfo] = &[];
+}
+
+/// A register value with `Debug` and `Display` implementations decoding the
+/// bit fields of `R`
+pub struct Decoded<R> {
+    value: u32,
+    _register: PhantomData<R>,
+}
+
+impl<R> Clone for Decoded<R> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<R> Copy for Decoded<R> {}
+
+impl<R: RegisterInfo> Decoded<R> {
+    /// Construct a `Decoded`.
+    #[inline]
+    pub const fn new(value: u32) -> Self {
+        Self {
+            value,
+            _register: PhantomData,
+        }
+    }
+
+    /// Get the raw register value.
+    #[inline]
+    pub const fn get(&self) -> u32 {
+        self.value
+    }
+}
+
+impl<R: RegisterInfo> fmt::Debug for Decoded<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if R::FIELDS.is_empty() {
+            return write!(f, "{:#010x}", self.value);
+        }
+
+        struct FieldValue<'a>(&'a FieldInfo, u32);
+        impl fmt::Debug for FieldValue<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt_value(self.1, f)
+            }
+        }
+
+        struct FieldArray<'a>(&'a FieldInfo, u32);
+        impl fmt::Debug for FieldArray<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_list()
+                    .entries(
+                        (0..self.0.count).map(|i| FieldValue(self.0, self.0.extract(self.1, i))),
+                    )
+                    .finish()
+            }
+        }
+
+        let mut st = f.debug_struct(R::NAME);
+        for field in R::FIELDS {
+            if field.count == 1 {
+                st.field(field.name, &FieldValue(field, field.extract(self.value, 0)));
+            } else {
+                st.field(field.name, &FieldArray(field, self.value));
+            }
+        }
+        st.finish()
+    }
+}
+
+impl<R: RegisterInfo> fmt::Display for Decoded<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if R::FIELDS.is_empty() {
+            write!(f, "{:#010x}", self.value)
+        } else {
+            write!(f, "{:#010x} {:?}", self.value, self)
+        }
+    }
+}
+
+/// A register, an array of registers, or a register block that can be
+/// captured by [`Snapshot`] and described by [`describe`](mod@crate::describe)
+pub trait Snap: Sized {
+    /// Read the registers and write their values to the corresponding
+    /// locations of `dst`.
+    ///
+    /// # Safety
+    ///
+    /// `dst` must be valid for writes.
+    unsafe fn snap(&self, dst: *mut Self);
+
+    /// Format the values in a `Debug`-like format.
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// Format the values with one line per register. `offset` is the offset
+    /// of `self` in the outermost register block, and `path` is the path to
+    /// `self` from there (`None` for the outermost register block itself).
+    fn fmt_lines(
+        &self,
+        offset: usize,
+        path: Option<&dyn fmt::Display>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result;
+
+    /// Call `f` for each register in offset order. `offset` and `path` are
+    /// as in [`Self::fmt_lines`].
+    fn describe(
+        offset: usize,
+        path: Option<&dyn fmt::Display>,
+        f: &mut dyn FnMut(&RegisterDesc<'_>),
+    );
+}
+
+impl<R: RegisterInfo> Snap for ReadWrite<u32, R> {
+    #[inline]
+    unsafe fn snap(&self, dst: *mut Self) {
+        dst.cast::<u32>().write(self.get());
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&Decoded::<R>::new(self.get()), f)
+    }
+
+    fn fmt_lines(
+        &self,
+        offset: usize,
+        path: Option<&dyn fmt::Display>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        writeln!(
+            f,
+            "{:#05x} {} = {}",
+            offset,
+            path.unwrap_or(&""),
+            Decoded::<R>::new(self.get())
+        )
+    }
+
+    fn describe(
+        offset: usize,
+        path: Option<&dyn fmt::Display>,
+        f: &mut dyn FnMut(&RegisterDesc<'_>),
+    ) {
+        f(&RegisterDesc::new::<R>(
+            offset,
+            path.unwrap_or(&""),
+            Access::ReadWrite,
+        ));
+    }
+}
+
+impl<R: RegisterInfo> Snap for ReadOnly<u32, R> {
+    #[inline]
+    unsafe fn snap(&self, dst: *mut Self) {
+        dst.cast::<u32>().write(self.get());
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&Decoded::<R>::new(self.get()), f)
+    }
+
+    fn fmt_lines(
+        &self,
+        offset: usize,
+        path: Option<&dyn fmt::Display>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        writeln!(
+            f,
+            "{:#05x} {} = {}",
+            offset,
+            path.unwrap_or(&""),
+            Decoded::<R>::new(self.get())
+        )
+    }
+
+    fn describe(
+        offset: usize,
+        path: Option<&dyn fmt::Display>,
+        f: &mut dyn FnMut(&RegisterDesc<'_>),
+    ) {
+        f(&RegisterDesc::new::<R>(
+            offset,
+            path.unwrap_or(&""),
+            Access::ReadOnly,
+        ));
+    }
+}
+
+impl<R: RegisterInfo> Snap for WriteOnly<u32, R> {
+    #[inline]
+    unsafe fn snap(&self, _dst: *mut Self) {}
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<write-only>")
+    }
+
+    fn fmt_lines(
+        &self,
+        offset: usize,
+        path: Option<&dyn fmt::Display>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        writeln!(f, "{:#05x} {} <write-only>", offset, path.unwrap_or(&""))
+    }
+
+    fn describe(
+        offset: usize,
+        path: Option<&dyn fmt::Display>,
+        f: &mut dyn FnMut(&RegisterDesc<'_>),
+    ) {
+        f(&RegisterDesc::new::<R>(
+            offset,
+            path.unwrap_or(&""),
+            Access::WriteOnly,
+        ));
+    }
+}
+
+impl<T: Snap, const N: usize> Snap for [T; N] {
+    unsafe fn snap(&self, dst: *mut Self) {
+        for (i, item) in self.iter().enumerate() {
+            item.snap(dst.cast::<T>().add(i));
+        }
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter().map(DebugSnap)).finish()
+    }
+
+    fn fmt_lines(
+        &self,
+        offset: usize,
+        path: Option<&dyn fmt::Display>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        for (i, item) in self.iter().enumerate() {
+            item.fmt_lines(
+                offset + i * core::mem::size_of::<T>(),
+                Some(&format_args!("{}[{}]", path.unwrap_or(&""), i)),
+                f,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn describe(
+        offset: usize,
+        path: Option<&dyn fmt::Display>,
+        f: &mut dyn FnMut(&RegisterDesc<'_>),
+    ) {
+        for i in 0..N {
+            T::describe(
+                offset + i * core::mem::size_of::<T>(),
+                Some(&format_args!("{}[{}]", path.unwrap_or(&""), i)),
+                f,
+            );
+        }
+    }
+}
+
+/// Adapts [`Snap::fmt_debug`] to `Debug`
+#[doc(hidden)]
+pub struct DebugSnap<'a, T>(pub &'a T);
+
+impl<T: Snap> fmt::Debug for DebugSnap<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_debug(f)
+    }
+}
+
+/// The placeholder for a register left out of a snapshot because reading it
+/// has side effects
+#[doc(hidden)]
+pub struct NotRead;
+
+impl fmt::Debug for NotRead {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<not read>")
+    }
+}
+
+/// The path to a register
+#[doc(hidden)]
+pub struct Path<'a> {
+    pub parent: Option<&'a dyn fmt::Display>,
+    pub name: &'static str,
+}
+
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.parent {
+            Some(parent) => write!(f, "{}.{}", parent, self.name),
+            None => f.write_str(self.name),
+        }
+    }
+}
+
+/// Call [`Snap::describe`] for a field of a register block. `block` doesn't
+/// have to point to an initialized register block.
+#[doc(hidden)]
+#[inline]
+pub fn describe_field<B, T: Snap>(
+    block: *const B,
+    field: *const T,
+    offset: usize,
+    path: &Path<'_>,
+    f: &mut dyn FnMut(&RegisterDesc<'_>),
+) {
+    T::describe(offset + (field as usize - block as usize), Some(path), f);
+}
+
+/// Get the offset of `field` in `block`.
+#[doc(hidden)]
+#[inline]
+pub fn offset_of<B, F>(block: &B, field: *const F) -> usize {
+    field as usize - block as *const B as usize
+}
+
+/// The captured state of a register block `B`. Created by the `snapshot`
+/// method of register blocks (e.g., [`pl011::Registers::snapshot`]).
+///
+/// The registers not read are zero in the copy.
+///
+/// [`pl011::Registers::snapshot`]: crate::pl011::Registers::snapshot
+pub struct Snapshot<B> {
+    copy: MaybeUninit<B>,
+}
+
+impl<B: Snap> Snapshot<B> {
+    /// Read the registers of `regs`.
+    pub fn new(regs: &B) -> Self {
+        // All register blocks are valid when zero-initialized
+        let mut copy = MaybeUninit::<B>::zeroed();
+        // Safety: `copy.as_mut_ptr()` is valid for writes
+        unsafe { regs.snap(copy.as_mut_ptr()) };
+        Self { copy }
+    }
+}
+
+impl<B> Deref for Snapshot<B> {
+    type Target = B;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // Safety: Initialized by `Snapshot::new`
+        unsafe { &*self.copy.as_ptr() }
+    }
+}
+
+impl<B: Snap> fmt::Debug for Snapshot<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt_debug(f)
+    }
+}
+
+impl<B: Snap> fmt::Display for Snapshot<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt_lines(0, None, f)
+    }
+}
+
+/// Define register bit fields by `tock_registers::register_bitfields!` and
+/// implement [`RegisterInfo`] for them.
+macro_rules! register_bitfields {
+    {
+        $valtype:ident,
+        $(
+            $( #[$meta:meta] )*
+            $vis:vis $reg:ident [
+                $(
+                    $( #[$field_meta:meta] )*
+                    $field:ident OFFSET($offset:expr) NUMBITS($numbits:expr) [
+                        $(
+                            $( #[$value_meta:meta] )*
+                            $value_name:ident = $value:expr
+                        ),* $(,)?
+                    ]
+                ),* $(,)?
+            ]
+        ),* $(,)?
+    } => {
+        tock_registers::register_bitfields! {
+            $valtype,
+            $(
+                $( #[$meta] )*
+                $vis $reg [
+                    $(
+                        $( #[$field_meta] )*
+                        $field OFFSET($offset) NUMBITS($numbits) [
+                            $(
+                                $( #[$value_meta] )*
+                                $value_name = $value
+                            ),*
+                        ]
+                    ),*
+                ]
+            ),*
+        }
+
+        $(
+            impl $crate::snapshot::RegisterInfo for $reg::Register {
+                const NAME: &'static str = stringify!($reg);
+                const FIELDS: &'static [$crate::snapshot::FieldInfo] = &[
+                    $(
+                        $crate::snapshot::FieldInfo::new(
+                            stringify!($field),
+                            $offset,
+                            $numbits,
+                            &[$( (stringify!($value_name), $value) ),*],
+                        ),
+                    )*
+                ];
+            }
+        )*
+    };
+}
+
+/// Implement [`Snap`] and the `snapshot` method for register blocks. The
+/// registers whose reads have side effects must be marked with `skip`.
+macro_rules! impl_snapshot {
+    (
+        $(
+            $name:ident {
+                $( $field:ident $(: $skip:ident)? ),* $(,)?
+            }
+        )*
+    ) => {
+        $(
+            impl $crate::snapshot::Snap for $name {
+                unsafe fn snap(&self, dst: *mut Self) {
+                    $(
+                        $crate::snapshot::impl_snapshot!(
+                            @snap self, dst, $field $(, $skip)?
+                        );
+                    )*
+                }
+
+                fn fmt_debug(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    f.debug_struct(stringify!($name))
+                        $(
+                            .field(
+                                stringify!($field),
+                                $crate::snapshot::impl_snapshot!(
+                                    @debug self, $field $(, $skip)?
+                                ),
+                            )
+                        )*
+                        .finish()
+                }
+
+                fn fmt_lines(
+                    &self,
+                    offset: usize,
+                    path: Option<&dyn core::fmt::Display>,
+                    f: &mut core::fmt::Formatter<'_>,
+                ) -> core::fmt::Result {
+                    $(
+                        $crate::snapshot::impl_snapshot!(
+                            @lines self, offset, path, f, $field $(, $skip)?
+                        );
+                    )*
+                    Ok(())
+                }
+
+                fn describe(
+                    offset: usize,
+                    path: Option<&dyn core::fmt::Display>,
+                    f: &mut dyn FnMut(&$crate::describe::RegisterDesc<'_>),
+                ) {
+                    let block = core::mem::MaybeUninit::<Self>::uninit();
+                    let block = block.as_ptr();
+                    $(
+                        $crate::snapshot::impl_snapshot!(
+                            @describe block, offset, path, f, $field $(, $skip)?
+                        );
+                    )*
+                }
+            }
+
+            impl $name {
+                /// Read the registers and capture their values in a
+                /// [`Snapshot`](crate::snapshot::Snapshot). The registers
+                /// whose reads have side effects are not read.
+                pub fn snapshot(&self) -> $crate::snapshot::Snapshot<Self> {
+                    $crate::snapshot::Snapshot::new(self)
+                }
+            }
+        )*
+    };
+
+    (@snap $this:ident, $dst:ident, $field:ident) => {
+        $crate::snapshot::Snap::snap(
+            &$this.$field,
+            core::ptr::addr_of_mut!((*$dst).$field),
+        )
+    };
+    (@snap $this:ident, $dst:ident, $field:ident, skip) => {};
+
+    (@debug $this:ident, $field:ident) => {
+        &$crate::snapshot::DebugSnap(&$this.$field)
+    };
+    (@debug $this:ident, $field:ident, skip) => {
+        &$crate::snapshot::NotRead
+    };
+
+    (@describe $block:ident, $offset:ident, $path:ident, $f:ident, $field:ident) => {
+        $crate::snapshot::describe_field(
+            $block,
+            // Safety: Only the address is taken
+            unsafe { core::ptr::addr_of!((*$block).$field) },
+            $offset,
+            &$crate::snapshot::Path {
+                parent: $path,
+                name: stringify!($field),
+            },
+            $f,
+        )
+    };
+    (@describe $block:ident, $offset:ident, $path:ident, $f:ident, $field:ident, skip) => {
+        $crate::snapshot::describe_field(
+            $block,
+            // Safety: Only the address is taken
+            unsafe { core::ptr::addr_of!((*$block).$field) },
+            $offset,
+            &$crate::snapshot::Path {
+                parent: $path,
+                name: stringify!($field),
+            },
+            &mut |desc: &$crate::describe::RegisterDesc<'_>| {
+                $f(&$crate::describe::RegisterDesc {
+                    read_side_effects: true,
+                    ..*desc
+                })
+            },
+        )
+    };
+
+    (@lines $this:ident, $offset:ident, $path:ident, $f:ident, $field:ident) => {
+        $crate::snapshot::Snap::fmt_lines(
+            &$this.$field,
+            $offset + $crate::snapshot::offset_of($this, core::ptr::addr_of!($this.$field)),
+            Some(&$crate::snapshot::Path {
+                parent: $path,
+                name: stringify!($field),
+            }),
+            $f,
+        )?
+    };
+    (@lines $this:ident, $offset:ident, $path:ident, $f:ident, $field:ident, skip) => {
+        writeln!(
+            $f,
+            "{:#05x} {} <not read>",
+            $offset + $crate::snapshot::offset_of($this, core::ptr::addr_of!($this.$field)),
+            $crate::snapshot::Path {
+                parent: $path,
+                name: stringify!($field),
+            },
+        )?
+    };
+}
+
+pub(crate) use {impl_snapshot, register_bitfields};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+    use crate::{aux, gpio, pl011, sim};
+    use std::{format, string::ToString, vec::Vec};
+    use tock_registers::interfaces::Writeable;
+
+    #[test]
+    fn decoded() {
+        let lcrh = Decoded::<pl011::LCRH::Register>::new(0x70);
+        assert_eq!(
+            format!("{:?}", lcrh),
+            "LCRH { BRK: 0, PEN: 0, EPS: Odd, STP2: 0, FEN: 1, WLEN: EightBits, SPS: 0 }"
+        );
+        assert_eq!(
+            lcrh.to_string(),
+            "0x00000070 LCRH { BRK: 0, PEN: 0, EPS: Odd, STP2: 0, FEN: 1, WLEN: EightBits, SPS: 0 }"
+        );
+
+        let fsel = Decoded::<gpio::GPFSEL::Register>::new(0b100_001);
+        assert_eq!(
+            format!("{:?}", fsel),
+            "GPFSEL { FSEL: [OUTPUT, ALT0, INPUT, INPUT, INPUT, \
+                INPUT, INPUT, INPUT, INPUT, INPUT] }"
+        );
+
+        assert_eq!(format!("{:?}", Decoded::<()>::new(0x1234)), "0x00001234");
+    }
+
+    #[test]
+    fn pl011() {
+        let device = sim::Device::<pl011::Registers>::new();
+        device.set(0x00, 0x41); // DR
+        device.set(0x18, 0x90); // FR: TXFE | RXFE
+        let regs = device.regs();
+        regs.cr.write(pl011::CR::UARTEN::SET + pl011::CR::TXE::SET);
+
+        let snapshot = regs.snapshot();
+        assert_eq!(snapshot.cr.get(), 0x101);
+        assert_eq!(snapshot.fr.get(), 0x90);
+        // `DR` is not read
+        assert_eq!(snapshot.dr.get(), 0);
+
+        let debug = format!("{:?}", snapshot);
+        assert!(debug.starts_with("Registers { dr: <not read>, rsrecr: RSRECR { "));
+        assert!(debug.contains(", cr: CR { UARTEN: 1, SIREN: 0, SIRLP: 0, LBE: 0, TXE: 1, "));
+        assert!(debug.contains(", icr: <write-only>, "));
+
+        let display = snapshot.to_string();
+        let lines: Vec<_> = display.lines().collect();
+        assert_eq!(lines[0], "0x000 dr <not read>");
+        assert!(lines.contains(&"0x024 ibrd = 0x00000000 IBRD { IBRD: 0 }"));
+        assert!(lines
+            .iter()
+            .any(|l| l.starts_with("0x030 cr = 0x00000101 CR { UARTEN: 1, ")));
+        assert!(lines.contains(&"0x044 icr <write-only>"));
+    }
+
+    #[test]
+    fn nested() {
+        let device = sim::Device::<aux::Registers>::new();
+        device.set(0x40, 0x55); // AUX_MU_IO_REG
+        device.set(0x68, 270); // AUX_MU_BAUD_REG
+        let snapshot = device.regs().snapshot();
+        assert_eq!(snapshot.aux_mu.io_reg.get(), 0);
+        assert_eq!(snapshot.aux_mu.baud_reg.get(), 270);
+
+        let display = snapshot.to_string();
+        let lines: Vec<_> = display.lines().collect();
+        assert!(lines.contains(&"0x040 aux_mu.io_reg <not read>"));
+        assert!(lines.contains(&"0x054 aux_mu.lsr_reg <not read>"));
+        assert!(lines
+            .iter()
+            .any(|l| l.starts_with("0x068 aux_mu.baud_reg = 0x0000010e ")));
+        assert!(lines.contains(&"0x0a0 aux_spi1.io_rega <not read>"));
+    }
+
+    #[test]
+    fn array() {
+        let device = sim::Device::<gpio::Registers>::new();
+        device.set(0x04, 0b010 << 12); // GPFSEL1: pin 14 = ALT5
+        let display = device.regs().snapshot().to_string();
+        assert!(display.contains(
+            "0x004 gpfsel[1] = 0x00002000 GPFSEL { FSEL: [INPUT, INPUT, INPUT, INPUT, \
+                ALT5, INPUT, INPUT, INPUT, INPUT, INPUT] }\n"
+        ));
+    }
+}
@@ -1,9 +1,14 @@
 //! [BCM2711 SPI][1]
 //!
 //! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A136%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
-use tock_registers::{register_bitfields, register_structs, registers::ReadWrite};
+use tock_registers::register_structs;
 
-use crate::Vpa;
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::ReadWrite,
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
+};
 
 /// The base address of the SPI0 instance of [the SPI register block](Registers).
 pub const BASE_SPI0: Vpa = Vpa(0x4_7e20_4000);
@@ -34,6 +39,35 @@ register_structs! {
     }
 }
 
+impl_snapshot! {
+    Registers {
+        cs,
+        // Reading `FIFO` pops the receive FIFO
+        fifo: skip,
+        clk,
+        dlen,
+        ltoh,
+        dc,
+    }
+}
+
+/// Describe [the SPI register block](Registers) and its instances. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "SPI",
+        "SPI master",
+        &[
+            Instance::vpa("SPI0", BASE_SPI0),
+            Instance::vpa("SPI3", BASE_SPI3),
+            Instance::vpa("SPI4", BASE_SPI4),
+            Instance::vpa("SPI5", BASE_SPI5),
+            Instance::vpa("SPI6", BASE_SPI6),
+        ],
+    )];
+    PERIPHERALS
+}
+
 register_bitfields! {u32,
     pub CS [
         /// Chip select
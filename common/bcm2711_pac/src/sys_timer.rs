@@ -3,12 +3,15 @@
 //! [1]: https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf#%5B%7B%22num%22%3A145%2C%22gen%22%3A0%7D%2C%7B%22name%22%3A%22XYZ%22%7D%2C115%2C841.89%2Cnull%5D
 use tock_registers::{
     fields::{Field, FieldValue},
-    register_structs,
-    registers::{ReadOnly, ReadWrite},
-    RegisterLongName,
+    register_structs, RegisterLongName,
 };
 
-use crate::Vpa;
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::{ReadOnly, ReadWrite},
+    snapshot::{impl_snapshot, FieldInfo, RegisterInfo},
+    Vpa,
+};
 
 /// The base address of [the System Timer peripheral register block](Registers).
 pub const BASE: Vpa = Vpa(0x4_7e00_3000);
@@ -27,12 +30,37 @@ register_structs! {
     }
 }
 
+impl_snapshot! {
+    Registers {
+        cs,
+        clo,
+        chi,
+        c,
+    }
+}
+
+/// Describe [the System Timer register block](Registers) and its instance. See
+/// [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "SYSTIMER",
+        "System Timer",
+        &[Instance::vpa("SYSTIMER", BASE)],
+    )];
+    PERIPHERALS
+}
+
 #[allow(non_snake_case)]
 pub mod CS {
     use super::*;
     pub struct Register;
     impl RegisterLongName for Register {}
 
+    impl RegisterInfo for Register {
+        const NAME: &'static str = "CS";
+        const FIELDS: &'static [FieldInfo] = &[FieldInfo::array("M", 0, 1, 4, &[])];
+    }
+
     /// Construct a [`Field`] representing the `M` bit (System Timer Match, W1C)
     /// corresponding to the specified comparator number.
     ///
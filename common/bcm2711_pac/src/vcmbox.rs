@@ -0,0 +1,144 @@
+//! [VideoCore mailboxes][1]
+//!
+//! The mailboxes are used to exchange messages with the VideoCore firmware.
+//! Mailbox 0 carries messages from VideoCore to ARM, and mailbox 1 carries
+//! messages in the opposite direction. A message is a 32-bit word comprising
+//! a channel number ([`MBOX_DATA::CHANNEL`](const@MBOX_DATA::CHANNEL)) and a 28-bit payload
+//! ([`MBOX_DATA::DATA`](const@MBOX_DATA::DATA)), which is usually the upper 28 bits of the VC bus
+//! address of a 16-byte aligned buffer. See [`property`] for the
+//! property-tag protocol carried by [`MBOX_DATA::CHANNEL::PropertyArmToVc`].
+//!
+//! [1]: https://github.com/raspberrypi/firmware/wiki/Mailboxes
+use tock_registers::register_structs;
+
+use crate::{
+    describe::{Instance, Peripheral},
+    registers::{ReadOnly, ReadWrite, WriteOnly},
+    snapshot::{impl_snapshot, register_bitfields},
+    Vpa,
+};
+
+pub mod property;
+
+/// The base address of [the VideoCore mailbox register block](Registers).
+pub const BASE: Vpa = Vpa(0x4_7e00_b880);
+
+register_structs! {
+    pub Registers {
+        /// Mailbox 0 (VC to ARM) read (RO). Reading this register pops a
+        /// message from the mailbox.
+        (0x00 => pub read: ReadOnly<u32, MBOX_DATA::Register>),
+        (0x04 => _pad0),
+        /// Mailbox 0 (VC to ARM) peek (RO)
+        (0x10 => pub peek0: ReadOnly<u32, MBOX_DATA::Register>),
+        /// Mailbox 0 (VC to ARM) sender (RO)
+        (0x14 => pub sender0: ReadOnly<u32, SENDER::Register>),
+        /// Mailbox 0 (VC to ARM) status (RO)
+        (0x18 => pub status0: ReadOnly<u32, STATUS::Register>),
+        /// Mailbox 0 (VC to ARM) configuration
+        (0x1c => pub config0: ReadWrite<u32, CONFIG::Register>),
+        /// Mailbox 1 (ARM to VC) write (WO). Writing this register pushes a
+        /// message to the mailbox.
+        (0x20 => pub write: WriteOnly<u32, MBOX_DATA::Register>),
+        (0x24 => _pad1),
+        /// Mailbox 1 (ARM to VC) peek (RO)
+        (0x30 => pub peek1: ReadOnly<u32, MBOX_DATA::Register>),
+        /// Mailbox 1 (ARM to VC) sender (RO)
+        (0x34 => pub sender1: ReadOnly<u32, SENDER::Register>),
+        /// Mailbox 1 (ARM to VC) status (RO)
+        (0x38 => pub status1: ReadOnly<u32, STATUS::Register>),
+        /// Mailbox 1 (ARM to VC) configuration
+        (0x3c => pub config1: ReadWrite<u32, CONFIG::Register>),
+        (0x40 => @END),
+    }
+}
+
+impl_snapshot! {
+    Registers {
+        // Reading `READ` pops mailbox 0
+        read: skip,
+        peek0,
+        sender0,
+        status0,
+        config0,
+        write,
+        peek1,
+        sender1,
+        status1,
+        config1,
+    }
+}
+
+/// Describe [the VideoCore mailbox register block](Registers) and its instance.
+/// See [`describe`](mod@crate::describe).
+pub fn describe() -> &'static [Peripheral] {
+    const PERIPHERALS: &[Peripheral] = &[Peripheral::new::<Registers>(
+        "VCMBOX",
+        "VideoCore mailboxes",
+        &[Instance::vpa("VCMBOX", BASE)],
+    )];
+    PERIPHERALS
+}
+
+register_bitfields! {u32,
+    pub MBOX_DATA [
+        /// Channel
+        CHANNEL OFFSET(0) NUMBITS(4) [
+            PowerManagement = 0,
+            Framebuffer = 1,
+            VirtualUart = 2,
+            Vchiq = 3,
+            Leds = 4,
+            Buttons = 5,
+            TouchScreen = 6,
+            PropertyArmToVc = 8,
+            PropertyVcToArm = 9,
+        ],
+        /// Payload. For the property channels, this is the VC bus address of
+        /// a 16-byte aligned buffer shifted right by 4 bits.
+        DATA OFFSET(4) NUMBITS(28) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub SENDER [
+        /// The ID of the sender of the last message
+        SENDER OFFSET(0) NUMBITS(2) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub STATUS [
+        /// The number of messages in the mailbox
+        LEVEL OFFSET(0) NUMBITS(8) [],
+        /// The mailbox is empty
+        EMPTY OFFSET(30) NUMBITS(1) [],
+        /// The mailbox is full
+        FULL OFFSET(31) NUMBITS(1) [],
+    ]
+}
+
+register_bitfields! {u32,
+    pub CONFIG [
+        /// Interrupt when data is available
+        IHAVEDATAIRQEN OFFSET(0) NUMBITS(1) [],
+        /// Interrupt when space is available
+        IHAVESPACEIRQEN OFFSET(1) NUMBITS(1) [],
+        /// Interrupt when the opposite mailbox becomes empty
+        OPPEMPTYIRQEN OFFSET(2) NUMBITS(1) [],
+        /// Clear the mailbox (W1SC)
+        MAIL_CLEAR OFFSET(3) NUMBITS(1) [],
+        /// Data available interrupt pending (RO)
+        IHAVEDATAIRQPEND OFFSET(4) NUMBITS(1) [],
+        /// Space available interrupt pending (RO)
+        IHAVESPACEIRQPEND OFFSET(5) NUMBITS(1) [],
+        /// Opposite mailbox empty interrupt pending (RO)
+        OPPEMPTYIRQPEND OFFSET(6) NUMBITS(1) [],
+        /// Non-owner access error (W1C)
+        ERRNOOWN OFFSET(8) NUMBITS(1) [],
+        /// Write to a full mailbox (W1C)
+        ERROVERFLW OFFSET(9) NUMBITS(1) [],
+        /// Read from an empty mailbox (W1C)
+        ERRUNDRFLW OFFSET(10) NUMBITS(1) [],
+    ]
+}
@@ -0,0 +1,1063 @@
+//! [The mailbox property interface][1]
+//!
+//! A property request is a 16-byte aligned buffer of 32-bit words laid out as
+//! follows:
+//!
+//! | Word            | Content                                              |
+//! | --------------- | ---------------------------------------------------- |
+//! | `0`             | Buffer size in bytes                                 |
+//! | `1`             | Request/response code ([`ResponseCode`])             |
+//! | `2..`           | Tags: tag ID, value buffer size in bytes, request/response code, value buffer |
+//! | last            | End tag (`0`)                                        |
+//!
+//! [`PropertyBuffer`] builds this layout, and each type implementing [`Tag`]
+//! knows how to encode its request and decode its response. Neither of them
+//! touches hardware, so they can be used and tested on any host.
+//!
+//! # Example
+//!
+//! ```rust
+//! use bcm2711_pac::vcmbox::property::{
+//!     ClockId, GetBoardRevision, GetClockRate, PropertyBuffer, ResponseCode,
+//! };
+//!
+//! let mut buf = PropertyBuffer::<32>::new();
+//! let revision = buf.push(&GetBoardRevision).unwrap();
+//! let arm_clock = buf.push(&GetClockRate { clock: ClockId::ARM }).unwrap();
+//! buf.finish().unwrap();
+//!
+//! assert_eq!(
+//!     buf.words(),
+//!     [
+//!         48, 0, // size, request
+//!         0x0001_0002, 4, 0, 0, // GetBoardRevision
+//!         0x0003_0002, 8, 0, 3, 0, // GetClockRate
+//!         0, // end tag
+//!     ]
+//! );
+//!
+//! // Pass the buffer to the firmware. The firmware updates it in place:
+//! let words = buf.words_mut();
+//! words[1] = 0x8000_0000;
+//! words[4] = 0x8000_0004;
+//! words[5] = 0x00c0_3111;
+//! words[8] = 0x8000_0008;
+//! words[10] = 1_500_000_000;
+//!
+//! assert_eq!(buf.response_code(), ResponseCode::Success);
+//! assert_eq!(buf.get(revision), Ok(0x00c0_3111));
+//! assert_eq!(buf.get(arm_clock).unwrap().rate_hz, 1_500_000_000);
+//! ```
+//!
+//! [1]: https://github.com/raspberrypi/firmware/wiki/Mailbox-property-interface
+use core::{fmt, marker::PhantomData};
+
+/// The request code placed in a request buffer
+pub const REQUEST_CODE: u32 = 0;
+
+/// The bit set by the firmware in a tag's request/response code to indicate a
+/// response
+pub const TAG_RESPONSE_BIT: u32 = 1 << 31;
+
+/// The response code of a [`PropertyBuffer`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum ResponseCode {
+    /// The buffer hasn't been processed by the firmware yet.
+    Request,
+    /// The request was processed successfully.
+    Success,
+    /// The firmware failed to parse the request buffer.
+    ParseError,
+    /// An unrecognized response code.
+    Unknown(u32),
+}
+
+impl From<u32> for ResponseCode {
+    #[inline]
+    fn from(x: u32) -> Self {
+        match x {
+            REQUEST_CODE => Self::Request,
+            0x8000_0000 => Self::Success,
+            0x8000_0001 => Self::ParseError,
+            x => Self::Unknown(x),
+        }
+    }
+}
+
+/// The error type for [`PropertyBuffer::push`] and [`PropertyBuffer::finish`]
+/// indicating that the buffer is too small.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct CapacityError;
+
+/// The error type for [`PropertyBuffer::get`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum TagError {
+    /// The firmware did not process the tag. This usually means that the tag
+    /// is not supported by the firmware.
+    NotProcessed,
+    /// The response is shorter than expected. Holds the response length (in
+    /// bytes) reported by the firmware.
+    Truncated(usize),
+}
+
+/// A property tag.
+pub trait Tag {
+    /// The tag identifier
+    const ID: u32;
+    /// The size of the value buffer in 32-bit words. This must be large enough
+    /// to hold both the request and the response.
+    const VALUE_LEN: usize;
+    /// The minimum length of a valid response in bytes
+    const RESPONSE_LEN: usize;
+    /// The decoded response
+    type Response;
+
+    /// Encode the request into the value buffer `value`, whose length is
+    /// [`Self::VALUE_LEN`].
+    fn encode_request(&self, value: &mut [u32]);
+
+    /// Decode the response from the value buffer `value`, whose length is
+    /// [`Self::VALUE_LEN`].
+    fn decode_response(value: &[u32]) -> Self::Response;
+}
+
+/// A reference to a tag pushed to a [`PropertyBuffer`], used to retrieve the
+/// response by [`PropertyBuffer::get`].
+pub struct TagRef<T> {
+    /// The index of the tag header in [`PropertyBuffer::words`]
+    offset: usize,
+    _tag: PhantomData<fn() -> T>,
+}
+
+impl<T> TagRef<T> {
+    /// Get the index of the tag header in [`PropertyBuffer::words`].
+    #[inline]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<T> Clone for TagRef<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TagRef<T> {}
+
+impl<T> fmt::Debug for TagRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TagRef").field(&self.offset).finish()
+    }
+}
+
+/// The size of a tag header in words (tag ID, value buffer size,
+/// request/response code)
+const TAG_HEADER_LEN: usize = 3;
+
+/// A property request/response buffer with the capacity of `N` 32-bit words.
+///
+/// The buffer is aligned to 16 bytes as required by the mailbox interface.
+/// The firmware accesses the buffer by DMA; the caller must perform cache
+/// maintenance on it before and after a mailbox transaction.
+#[derive(Clone)]
+#[repr(C, align(16))]
+pub struct PropertyBuffer<const N: usize> {
+    /// The buffer seen by the firmware. Must be the first field.
+    words: [u32; N],
+    /// The number of words used in `words`
+    len: usize,
+}
+
+impl<const N: usize> Default for PropertyBuffer<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for PropertyBuffer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.words()).finish()
+    }
+}
+
+impl<const N: usize> PropertyBuffer<N> {
+    /// Construct an empty request buffer.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `N` is smaller than `4`.
+    #[inline]
+    pub const fn new() -> Self {
+        assert!(N >= 4, "buffer too small");
+        let mut words = [0; N];
+        words[1] = REQUEST_CODE;
+        Self { words, len: 2 }
+    }
+
+    /// Append a tag to the request.
+    pub fn push<T: Tag>(&mut self, tag: &T) -> Result<TagRef<T>, CapacityError> {
+        let offset = self.len;
+        // Reserve one word for the end tag
+        let end = offset + TAG_HEADER_LEN + T::VALUE_LEN;
+        if end + 1 > N {
+            return Err(CapacityError);
+        }
+
+        self.words[offset] = T::ID;
+        self.words[offset + 1] = (T::VALUE_LEN * 4) as u32;
+        self.words[offset + 2] = REQUEST_CODE;
+        let value = &mut self.words[offset + TAG_HEADER_LEN..end];
+        value.fill(0);
+        tag.encode_request(value);
+        self.len = end;
+
+        Ok(TagRef {
+            offset,
+            _tag: PhantomData,
+        })
+    }
+
+    /// Append the end tag and padding and fill the buffer size field. The
+    /// buffer is ready to be passed to the firmware after this.
+    pub fn finish(&mut self) -> Result<(), CapacityError> {
+        // End tag + padding to a 16-byte boundary
+        let end = (self.len + 1 + 3) & !3;
+        if end > N {
+            return Err(CapacityError);
+        }
+        self.words[self.len..end].fill(0);
+        self.len = end;
+        self.words[0] = (end * 4) as u32;
+        Ok(())
+    }
+
+    /// Get the request/response code of the buffer.
+    #[inline]
+    pub fn response_code(&self) -> ResponseCode {
+        self.words[1].into()
+    }
+
+    /// Decode the response of a tag previously pushed by [`Self::push`].
+    pub fn get<T: Tag>(&self, tag: TagRef<T>) -> Result<T::Response, TagError> {
+        let code = self.words[tag.offset + 2];
+        if code & TAG_RESPONSE_BIT == 0 {
+            return Err(TagError::NotProcessed);
+        }
+
+        let len = (code & !TAG_RESPONSE_BIT) as usize;
+        if len < T::RESPONSE_LEN {
+            return Err(TagError::Truncated(len));
+        }
+
+        let start = tag.offset + TAG_HEADER_LEN;
+        Ok(T::decode_response(&self.words[start..start + T::VALUE_LEN]))
+    }
+
+    /// Get the used portion of the buffer.
+    #[inline]
+    pub fn words(&self) -> &[u32] {
+        &self.words[..self.len]
+    }
+
+    /// Get the used portion of the buffer mutably.
+    #[inline]
+    pub fn words_mut(&mut self) -> &mut [u32] {
+        &mut self.words[..self.len]
+    }
+
+    /// Get the used portion of the buffer as bytes in the native byte order
+    /// (which is little endian for both ARM and VideoCore).
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        let words = self.words();
+        // Safety: `u8` has no invalid bit patterns and a weaker alignment
+        // requirement than `u32`
+        unsafe { core::slice::from_raw_parts(words.as_ptr().cast(), words.len() * 4) }
+    }
+
+    /// Get a raw pointer to the buffer, which is the value to pass to the
+    /// firmware (after translated to a VC bus address).
+    #[inline]
+    pub fn as_ptr(&self) -> *const u32 {
+        self.words.as_ptr()
+    }
+
+    /// Get a raw mutable pointer to the buffer.
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut u32 {
+        self.words.as_mut_ptr()
+    }
+}
+
+/// A value that occupies one word in a value buffer.
+trait Word: Sized {
+    fn from_word(x: u32) -> Self;
+    fn into_word(self) -> u32;
+}
+
+impl Word for u32 {
+    #[inline]
+    fn from_word(x: u32) -> Self {
+        x
+    }
+
+    #[inline]
+    fn into_word(self) -> u32 {
+        self
+    }
+}
+
+impl Word for i32 {
+    #[inline]
+    fn from_word(x: u32) -> Self {
+        x as i32
+    }
+
+    #[inline]
+    fn into_word(self) -> u32 {
+        self as u32
+    }
+}
+
+impl Word for bool {
+    #[inline]
+    fn from_word(x: u32) -> Self {
+        x != 0
+    }
+
+    #[inline]
+    fn into_word(self) -> u32 {
+        self as u32
+    }
+}
+
+#[macropol::macropol]
+macro_rules! define_id {
+    (
+        $( #[$meta:meta] )*
+        pub struct $Name:ident {
+            $(
+                $( #[$const_meta:meta] )*
+                $CONST:ident = $value:literal,
+            )*
+        }
+    ) => {
+        $( #[$meta] )*
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $Name(pub u32);
+
+        impl $Name {
+            $(
+                $( #[$const_meta] )*
+                pub const $CONST: Self = Self($value);
+            )*
+        }
+
+        impl fmt::Debug for $Name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match *self {
+                    $( Self::$CONST => f.write_str("$&CONST"), )*
+                    Self(x) => write!(f, "$&Name({})", x),
+                }
+            }
+        }
+
+        impl Word for $Name {
+            #[inline]
+            fn from_word(x: u32) -> Self {
+                Self(x)
+            }
+
+            #[inline]
+            fn into_word(self) -> u32 {
+                self.0
+            }
+        }
+    };
+}
+
+define_id! {
+    /// A clock identifier
+    pub struct ClockId {
+        EMMC = 1,
+        UART = 2,
+        ARM = 3,
+        CORE = 4,
+        V3D = 5,
+        H264 = 6,
+        ISP = 7,
+        SDRAM = 8,
+        PIXEL = 9,
+        PWM = 10,
+        HEVC = 11,
+        EMMC2 = 12,
+        M2MC = 13,
+        PIXEL_BVB = 14,
+    }
+}
+
+define_id! {
+    /// A voltage identifier
+    pub struct VoltageId {
+        CORE = 1,
+        SDRAM_C = 2,
+        SDRAM_P = 3,
+        SDRAM_I = 4,
+    }
+}
+
+define_id! {
+    /// A power domain identifier
+    pub struct DeviceId {
+        SD_CARD = 0,
+        UART0 = 1,
+        UART1 = 2,
+        USB_HCD = 3,
+        I2C0 = 4,
+        I2C1 = 5,
+        I2C2 = 6,
+        SPI = 7,
+        CCP2TX = 8,
+    }
+}
+
+/// The response of a clock rate tag
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct ClockRate {
+    pub clock: ClockId,
+    /// The clock rate in hertz. Zero if the clock doesn't exist.
+    pub rate_hz: u32,
+}
+
+/// The response of a clock state tag
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct ClockState {
+    pub clock: ClockId,
+    /// Bit 0: on, bit 1: the clock doesn't exist
+    pub state: u32,
+}
+
+/// The response of a power state tag
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct PowerState {
+    pub device: DeviceId,
+    /// Bit 0: on, bit 1: the device doesn't exist
+    pub state: u32,
+}
+
+/// The response of a voltage tag
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Voltage {
+    pub id: VoltageId,
+    /// The voltage in microvolts. `0x8000_0000` if the voltage doesn't exist.
+    pub microvolts: u32,
+}
+
+/// The response of a temperature tag
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Temperature {
+    /// The temperature sensor ID (always `0`)
+    pub id: u32,
+    /// The temperature in thousandths of a degree Celsius
+    pub millicelsius: u32,
+}
+
+/// A memory region
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct MemoryRegion {
+    /// The base address
+    pub base: u32,
+    /// The size in bytes
+    pub size: u32,
+}
+
+/// A two-dimensional size in pixels
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Size2d {
+    pub width: u32,
+    pub height: u32,
+}
+
+#[macropol::macropol]
+macro_rules! define_tag {
+    // No response
+    (
+        $( #[$meta:meta] )*
+        pub struct $Name:ident = $id:literal {
+            $( $( #[$field_meta:meta] )* pub $field:ident: $FieldTy:ty, )*
+        } -> ();
+    ) => {
+        define_tag! {
+            @struct
+            $( #[$meta] )*
+            pub struct $Name = $id {
+                $( $( #[$field_meta] )* pub $field: $FieldTy, )*
+            }
+        }
+
+        impl Tag for $Name {
+            const ID: u32 = $id;
+            const VALUE_LEN: usize = define_tag!(@count $($field)*);
+            const RESPONSE_LEN: usize = 0;
+            type Response = ();
+
+            #[inline]
+            fn encode_request(&self, value: &mut [u32]) {
+                let words: &[u32] = &[$( Word::into_word(self.$field) ),*];
+                value[..words.len()].copy_from_slice(words);
+            }
+
+            #[inline]
+            fn decode_response(_value: &[u32]) -> Self::Response {}
+        }
+    };
+
+    // `Response` is a single word
+    (
+        $( #[$meta:meta] )*
+        pub struct $Name:ident = $id:literal {
+            $( $( #[$field_meta:meta] )* pub $field:ident: $FieldTy:ty, )*
+        } -> $Response:ty [$response_len:literal];
+    ) => {
+        define_tag! {
+            @struct
+            $( #[$meta] )*
+            pub struct $Name = $id {
+                $( $( #[$field_meta] )* pub $field: $FieldTy, )*
+            }
+        }
+
+        impl Tag for $Name {
+            const ID: u32 = $id;
+            const VALUE_LEN: usize = max($response_len, define_tag!(@count $($field)*));
+            const RESPONSE_LEN: usize = $response_len * 4;
+            type Response = $Response;
+
+            #[inline]
+            fn encode_request(&self, value: &mut [u32]) {
+                let words: &[u32] = &[$( Word::into_word(self.$field) ),*];
+                value[..words.len()].copy_from_slice(words);
+            }
+
+            #[inline]
+            fn decode_response(value: &[u32]) -> Self::Response {
+                Word::from_word(value[0])
+            }
+        }
+    };
+
+    // `Response` is a struct
+    (
+        $( #[$meta:meta] )*
+        pub struct $Name:ident = $id:literal {
+            $( $( #[$field_meta:meta] )* pub $field:ident: $FieldTy:ty, )*
+        } -> $Response:ident { $( $response_field:ident ),* $(,)? };
+    ) => {
+        define_tag! {
+            @struct
+            $( #[$meta] )*
+            pub struct $Name = $id {
+                $( $( #[$field_meta] )* pub $field: $FieldTy, )*
+            }
+        }
+
+        impl Tag for $Name {
+            const ID: u32 = $id;
+            const VALUE_LEN: usize = max(
+                define_tag!(@count $($response_field)*),
+                define_tag!(@count $($field)*),
+            );
+            const RESPONSE_LEN: usize = define_tag!(@count $($response_field)*) * 4;
+            type Response = $Response;
+
+            #[inline]
+            fn encode_request(&self, value: &mut [u32]) {
+                let words: &[u32] = &[$( Word::into_word(self.$field) ),*];
+                value[..words.len()].copy_from_slice(words);
+            }
+
+            #[inline]
+            fn decode_response(value: &[u32]) -> Self::Response {
+                let mut words = value.iter().copied();
+                $Response {
+                    $( $response_field: Word::from_word(words.next().unwrap()), )*
+                }
+            }
+        }
+    };
+
+    (
+        @struct
+        $( #[$meta:meta] )*
+        pub struct $Name:ident = $id:literal {}
+    ) => {
+        $( #[$meta] )*
+        ///
+        /// Tag ID: `$&id`
+        #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+        pub struct $Name;
+    };
+
+    (
+        @struct
+        $( #[$meta:meta] )*
+        pub struct $Name:ident = $id:literal {
+            $( $( #[$field_meta:meta] )* pub $field:ident: $FieldTy:ty, )+
+        }
+    ) => {
+        $( #[$meta] )*
+        ///
+        /// Tag ID: `$&id`
+        #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+        pub struct $Name {
+            $( $( #[$field_meta] )* pub $field: $FieldTy, )*
+        }
+    };
+
+    (@count $($x:ident)*) => { 0 $( + define_tag!(@one $x) )* };
+    (@one $x:ident) => { 1 };
+}
+
+const fn max(x: usize, y: usize) -> usize {
+    if x > y {
+        x
+    } else {
+        y
+    }
+}
+
+// Hardware information
+// ----------------------------------------------------------------------------
+
+define_tag! {
+    /// Get the VideoCore firmware revision.
+    pub struct GetFirmwareRevision = 0x0000_0001 {} -> u32 [1];
+}
+
+define_tag! {
+    /// Get the board model.
+    pub struct GetBoardModel = 0x0001_0001 {} -> u32 [1];
+}
+
+define_tag! {
+    /// Get the board revision code.
+    pub struct GetBoardRevision = 0x0001_0002 {} -> u32 [1];
+}
+
+/// Get the board's MAC address.
+///
+/// Tag ID: `0x0001_0003`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct GetBoardMacAddress;
+
+impl Tag for GetBoardMacAddress {
+    const ID: u32 = 0x0001_0003;
+    const VALUE_LEN: usize = 2;
+    const RESPONSE_LEN: usize = 6;
+    type Response = [u8; 6];
+
+    #[inline]
+    fn encode_request(&self, _value: &mut [u32]) {}
+
+    #[inline]
+    fn decode_response(value: &[u32]) -> Self::Response {
+        let [a, b, c, d] = value[0].to_le_bytes();
+        let [e, f, _, _] = value[1].to_le_bytes();
+        [a, b, c, d, e, f]
+    }
+}
+
+/// Get the board's serial number.
+///
+/// Tag ID: `0x0001_0004`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct GetBoardSerial;
+
+impl Tag for GetBoardSerial {
+    const ID: u32 = 0x0001_0004;
+    const VALUE_LEN: usize = 2;
+    const RESPONSE_LEN: usize = 8;
+    type Response = u64;
+
+    #[inline]
+    fn encode_request(&self, _value: &mut [u32]) {}
+
+    #[inline]
+    fn decode_response(value: &[u32]) -> Self::Response {
+        value[0] as u64 | (value[1] as u64) << 32
+    }
+}
+
+define_tag! {
+    /// Get the memory region assigned to ARM.
+    pub struct GetArmMemory = 0x0001_0005 {} -> MemoryRegion { base, size };
+}
+
+define_tag! {
+    /// Get the memory region assigned to VideoCore.
+    pub struct GetVcMemory = 0x0001_0006 {} -> MemoryRegion { base, size };
+}
+
+// Power
+// ----------------------------------------------------------------------------
+
+define_tag! {
+    /// Get the power state of a device.
+    pub struct GetPowerState = 0x0002_0001 {
+        pub device: DeviceId,
+    } -> PowerState { device, state };
+}
+
+define_tag! {
+    /// Set the power state of a device.
+    pub struct SetPowerState = 0x0002_8001 {
+        pub device: DeviceId,
+        /// Bit 0: on, bit 1: wait for the power to become stable
+        pub state: u32,
+    } -> PowerState { device, state };
+}
+
+// Clocks
+// ----------------------------------------------------------------------------
+
+define_tag! {
+    /// Get the state of a clock.
+    pub struct GetClockState = 0x0003_0001 {
+        pub clock: ClockId,
+    } -> ClockState { clock, state };
+}
+
+define_tag! {
+    /// Set the state of a clock.
+    pub struct SetClockState = 0x0003_8001 {
+        pub clock: ClockId,
+        /// Bit 0: on
+        pub state: u32,
+    } -> ClockState { clock, state };
+}
+
+define_tag! {
+    /// Get the configured rate of a clock.
+    pub struct GetClockRate = 0x0003_0002 {
+        pub clock: ClockId,
+    } -> ClockRate { clock, rate_hz };
+}
+
+define_tag! {
+    /// Get the measured rate of a clock.
+    pub struct GetClockRateMeasured = 0x0003_0047 {
+        pub clock: ClockId,
+    } -> ClockRate { clock, rate_hz };
+}
+
+define_tag! {
+    /// Set the rate of a clock. The response contains the rate actually set.
+    pub struct SetClockRate = 0x0003_8002 {
+        pub clock: ClockId,
+        pub rate_hz: u32,
+        /// Don't apply the turbo settings when setting the ARM clock rate
+        pub skip_setting_turbo: bool,
+    } -> ClockRate { clock, rate_hz };
+}
+
+define_tag! {
+    /// Get the maximum supported rate of a clock.
+    pub struct GetMaxClockRate = 0x0003_0004 {
+        pub clock: ClockId,
+    } -> ClockRate { clock, rate_hz };
+}
+
+define_tag! {
+    /// Get the minimum supported rate of a clock.
+    pub struct GetMinClockRate = 0x0003_0007 {
+        pub clock: ClockId,
+    } -> ClockRate { clock, rate_hz };
+}
+
+// Voltages and temperatures
+// ----------------------------------------------------------------------------
+
+define_tag! {
+    /// Get a voltage.
+    pub struct GetVoltage = 0x0003_0003 {
+        pub id: VoltageId,
+    } -> Voltage { id, microvolts };
+}
+
+define_tag! {
+    /// Get the maximum supported value of a voltage.
+    pub struct GetMaxVoltage = 0x0003_0005 {
+        pub id: VoltageId,
+    } -> Voltage { id, microvolts };
+}
+
+define_tag! {
+    /// Get the minimum supported value of a voltage.
+    pub struct GetMinVoltage = 0x0003_0008 {
+        pub id: VoltageId,
+    } -> Voltage { id, microvolts };
+}
+
+define_tag! {
+    /// Get the SoC temperature.
+    pub struct GetTemperature = 0x0003_0006 {
+        /// Must be `0`
+        pub id: u32,
+    } -> Temperature { id, millicelsius };
+}
+
+define_tag! {
+    /// Get the maximum safe SoC temperature, above which the firmware
+    /// throttles the clocks.
+    pub struct GetMaxTemperature = 0x0003_000a {
+        /// Must be `0`
+        pub id: u32,
+    } -> Temperature { id, millicelsius };
+}
+
+define_tag! {
+    /// Get the throttling state.
+    ///
+    /// Bit 0: under-voltage detected, bit 1: ARM frequency capped, bit 2:
+    /// currently throttled, bit 3: soft temperature limit active. Bits
+    /// `16..20` indicate the same conditions have occurred since the last
+    /// reset.
+    pub struct GetThrottled = 0x0003_0046 {} -> u32 [1];
+}
+
+// GPU memory
+// ----------------------------------------------------------------------------
+
+define_tag! {
+    /// Allocate contiguous memory on the GPU. The response is a handle.
+    pub struct AllocateMemory = 0x0003_000c {
+        /// The size in bytes
+        pub size: u32,
+        /// The alignment in bytes
+        pub alignment: u32,
+        /// Allocation flags
+        pub flags: u32,
+    } -> u32 [1];
+}
+
+define_tag! {
+    /// Lock a memory allocation in place. The response is the VC bus address.
+    pub struct LockMemory = 0x0003_000d {
+        pub handle: u32,
+    } -> u32 [1];
+}
+
+define_tag! {
+    /// Unlock a memory allocation. The response is the status (`0` = success).
+    pub struct UnlockMemory = 0x0003_000e {
+        pub handle: u32,
+    } -> u32 [1];
+}
+
+define_tag! {
+    /// Release a memory allocation. The response is the status (`0` =
+    /// success).
+    pub struct ReleaseMemory = 0x0003_000f {
+        pub handle: u32,
+    } -> u32 [1];
+}
+
+// Frame buffer
+// ----------------------------------------------------------------------------
+
+define_tag! {
+    /// Allocate a frame buffer. The response is the VC bus address and the size
+    /// of the frame buffer.
+    pub struct AllocateBuffer = 0x0004_0001 {
+        /// The alignment in bytes
+        pub alignment: u32,
+    } -> MemoryRegion { base, size };
+}
+
+define_tag! {
+    /// Release the frame buffer.
+    pub struct ReleaseBuffer = 0x0004_8001 {} -> ();
+}
+
+define_tag! {
+    /// Get the physical (display) size.
+    pub struct GetPhysicalSize = 0x0004_0003 {} -> Size2d { width, height };
+}
+
+define_tag! {
+    /// Set the physical (display) size.
+    pub struct SetPhysicalSize = 0x0004_8003 {
+        pub width: u32,
+        pub height: u32,
+    } -> Size2d { width, height };
+}
+
+define_tag! {
+    /// Get the virtual (buffer) size.
+    pub struct GetVirtualSize = 0x0004_0004 {} -> Size2d { width, height };
+}
+
+define_tag! {
+    /// Set the virtual (buffer) size.
+    pub struct SetVirtualSize = 0x0004_8004 {
+        pub width: u32,
+        pub height: u32,
+    } -> Size2d { width, height };
+}
+
+define_tag! {
+    /// Get the depth in bits per pixel.
+    pub struct GetDepth = 0x0004_0005 {} -> u32 [1];
+}
+
+define_tag! {
+    /// Set the depth in bits per pixel.
+    pub struct SetDepth = 0x0004_8005 {
+        pub bits_per_pixel: u32,
+    } -> u32 [1];
+}
+
+define_tag! {
+    /// Get the number of bytes per line.
+    pub struct GetPitch = 0x0004_0008 {} -> u32 [1];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_set_clock_rate() {
+        let mut buf = PropertyBuffer::<16>::new();
+        buf.push(&SetClockRate {
+            clock: ClockId::ARM,
+            rate_hz: 600_000_000,
+            skip_setting_turbo: true,
+        })
+        .unwrap();
+        buf.finish().unwrap();
+
+        #[rustfmt::skip]
+        let expected: [u8; 48] = [
+            0x30, 0, 0, 0, // size = 48
+            0, 0, 0, 0, // request
+            0x02, 0x80, 0x03, 0x00, // tag ID
+            12, 0, 0, 0, // value buffer size
+            0, 0, 0, 0, // request
+            3, 0, 0, 0, // clock ID
+            0x00, 0x46, 0xc3, 0x23, // 600 MHz
+            1, 0, 0, 0, // skip turbo
+            0, 0, 0, 0, // end tag
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // padding
+        ];
+        assert_eq!(buf.as_bytes(), expected);
+    }
+
+    #[test]
+    fn value_buffer_fits_response() {
+        let mut buf = PropertyBuffer::<16>::new();
+        buf.push(&GetBoardSerial).unwrap();
+        buf.push(&GetTemperature { id: 0 }).unwrap();
+        buf.finish().unwrap();
+        assert_eq!(
+            buf.words(),
+            [
+                64,
+                0, //
+                0x0001_0004,
+                8,
+                0,
+                0,
+                0, //
+                0x0003_0006,
+                8,
+                0,
+                0,
+                0, //
+                0,
+                0,
+                0,
+                0,
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_responses() {
+        let mut buf = PropertyBuffer::<32>::new();
+        let serial = buf.push(&GetBoardSerial).unwrap();
+        let mac = buf.push(&GetBoardMacAddress).unwrap();
+        let temp = buf.push(&GetTemperature { id: 0 }).unwrap();
+        let unsupported = buf.push(&GetMaxTemperature { id: 0 }).unwrap();
+        buf.finish().unwrap();
+
+        let words = buf.words_mut();
+        words[1] = 0x8000_0000;
+        words[serial.offset() + 2] = 0x8000_0008;
+        words[serial.offset() + 3] = 0x89ab_cdef;
+        words[serial.offset() + 4] = 0x1000_0000;
+        words[mac.offset() + 2] = 0x8000_0006;
+        words[mac.offset() + 3] = 0x3aa6_32dc;
+        words[mac.offset() + 4] = 0x0000_b8e1;
+        words[temp.offset() + 2] = 0x8000_0004;
+        words[temp.offset() + 4] = 48_686;
+
+        assert_eq!(buf.response_code(), ResponseCode::Success);
+        assert_eq!(buf.get(serial), Ok(0x1000_0000_89ab_cdef));
+        assert_eq!(buf.get(mac), Ok([0xdc, 0x32, 0xa6, 0x3a, 0xe1, 0xb8]));
+        assert_eq!(buf.get(temp), Err(TagError::Truncated(4)));
+        assert_eq!(buf.get(unsupported), Err(TagError::NotProcessed));
+
+        buf.words_mut()[temp.offset() + 2] = 0x8000_0008;
+        assert_eq!(
+            buf.get(temp),
+            Ok(Temperature {
+                id: 0,
+                millicelsius: 48_686
+            })
+        );
+    }
+
+    #[test]
+    fn decode_empty_response() {
+        let mut buf = PropertyBuffer::<8>::new();
+        let release = buf.push(&ReleaseBuffer).unwrap();
+        buf.finish().unwrap();
+        assert_eq!(buf.words()[release.offset() + 1], 0);
+        assert_eq!(buf.get(release), Err(TagError::NotProcessed));
+
+        buf.words_mut()[release.offset() + 2] = 0x8000_0000;
+        assert_eq!(buf.get(release), Ok(()));
+    }
+
+    #[test]
+    fn capacity() {
+        let mut buf = PropertyBuffer::<8>::new();
+        buf.push(&GetBoardRevision).unwrap();
+        assert_eq!(buf.push(&GetBoardRevision).unwrap_err(), CapacityError);
+        buf.finish().unwrap();
+        assert_eq!(buf.words().len(), 8);
+
+        let mut buf = PropertyBuffer::<7>::new();
+        buf.push(&GetBoardRevision).unwrap();
+        assert_eq!(buf.finish(), Err(CapacityError));
+    }
+
+    #[test]
+    fn alignment() {
+        assert_eq!(core::mem::align_of::<PropertyBuffer<4>>(), 16);
+        let buf = PropertyBuffer::<4>::new();
+        assert_eq!(buf.as_ptr() as usize % 16, 0);
+    }
+
+    #[test]
+    fn id_debug() {
+        extern crate std;
+        use std::format;
+        assert_eq!(format!("{:?}", ClockId::EMMC2), "EMMC2");
+        assert_eq!(format!("{:?}", ClockId(42)), "ClockId(42)");
+    }
+}
@@ -1,4 +1,5 @@
 ﻿#![feature(type_alias_impl_trait)]
+use bcm2711_pac::irq;
 use solid::{interrupt, singleton::pin_singleton, thread::CpuCx};
 
 #[no_mangle]
@@ -34,8 +35,8 @@ pub extern "C" fn slo_main() {
     assert!(
         handler
             .register_static(
-                &interrupt::HandlerOptions::new(ap804::INTNO, 10)
-                    .with_level_triggered()
+                &irq::ARM_TIMER
+                    .handler_options(10)
                     .with_target_processor(1)
             )
             .expect("unable to register interrupt handler"),
@@ -86,7 +87,7 @@ mod ap804 {
     use bcm2711_pac::ap804;
     use tock_registers::interfaces::Writeable;
 
-    pub const INTNO: solid::interrupt::Number = solid::interrupt::Number(64);
+    pub const INTNO: solid::interrupt::Number = bcm2711_pac::irq::ARM_TIMER.number();
 
     fn ap804_regs() -> &'static ap804::Registers {
         // Safety: SOLID for RaPi4B provides an identity mapping in this area, and we don't alter
@@ -4,6 +4,8 @@
 
 #[cfg(target_os = "solid_asp3")]
 mod cpumon;
+#[cfg(target_os = "solid_asp3")]
+mod thermal;
 
 #[cfg(target_os = "solid_asp3")]
 solid::staticenv! {
@@ -24,6 +26,10 @@ extern "C" fn slo_main() {
     // Start CPU usage monitor
     #[cfg(target_os = "solid_asp3")]
     cpumon::init();
+
+    // Start SoC temperature monitor
+    #[cfg(target_os = "solid_asp3")]
+    thermal::init();
     
     #[cfg(target_os = "solid_asp3")]
     let num_processors = solid::abi::SOLID_CORE_MAX;
@@ -169,7 +175,15 @@ fn handle_sensors(st: State) -> (State, impl IntoResponse) {
     #[cfg(not(target_os = "solid_asp3"))]
     let [cpu0] = [0.5f32];
 
-    let body = format!(r#"{{"cpu":[{cpu0}]}}"#);
+    #[cfg(target_os = "solid_asp3")]
+    let temperature = thermal::current_temperature();
+    #[cfg(not(target_os = "solid_asp3"))]
+    let temperature = Some(45.0f32);
+
+    // `null` if the sensor has no valid reading yet
+    let temperature = temperature.map_or_else(|| "null".to_owned(), |x| x.to_string());
+
+    let body = format!(r#"{{"cpu":[{cpu0}],"temperature":{temperature}}}"#);
     (st, (mime::APPLICATION_JSON, body))
 }
 
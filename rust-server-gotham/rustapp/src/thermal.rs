@@ -0,0 +1,32 @@
+﻿//! SoC temperature monitor
+use bcm2711_pac::avs;
+use solid::{singleton::pin_singleton, timer};
+
+/// Tracks the SoC temperature. Sampled by the timer handler.
+static THERMOMETER: avs::Thermometer = unsafe {
+    // Safety: SOLID for RaPi4B provides an identity mapping in this area, and we don't alter
+    // the mapping
+    avs::Thermometer::new(avs::BASE_ARM_PA as usize as *const avs::Registers, 3)
+};
+
+pub fn init() {
+    // Construct a timer object in a global variable.
+    let Ok(handler) = pin_singleton!(: Timer<_> = timer::Timer::new(
+        timer::Schedule::Interval(timer::Usecs32(100_000)),
+        &THERMOMETER,
+    )) else {
+        // The timer is already running; there's nothing to do here.
+        return;
+    };
+
+    // Start the timer.
+    assert!(
+        handler.start().expect("unable to start timer"),
+        "timer was already running"
+    );
+}
+
+/// Get the recent SoC temperature in degrees Celsius.
+pub fn current_temperature() -> Option<f32> {
+    THERMOMETER.millicelsius().map(|x| x as f32 / 1000.0)
+}